@@ -7,6 +7,7 @@ use starknet_api::contract_class::EntryPointType;
 use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, Nonce};
 use starknet_api::data_availability::DataAvailabilityMode;
 use starknet_api::executable_transaction::AccountTransaction as Transaction;
+use starknet_api::execution_resources::GasAmount;
 use starknet_api::transaction::fields::Resource::{L1DataGas, L1Gas, L2Gas};
 use starknet_api::transaction::fields::{
     AccountDeploymentData,
@@ -82,11 +83,14 @@ pub struct ExecutionFlags {
     pub only_query: bool,
     pub charge_fee: bool,
     pub validate: bool,
+    /// Caps the Sierra gas available to validation and execution, regardless of the
+    /// transaction's resource bounds. Used to probe gas usage without modifying the transaction.
+    pub sierra_gas_limit: Option<GasAmount>,
 }
 
 impl Default for ExecutionFlags {
     fn default() -> Self {
-        Self { only_query: false, charge_fee: true, validate: true }
+        Self { only_query: false, charge_fee: true, validate: true, sierra_gas_limit: None }
     }
 }
 
@@ -137,6 +141,7 @@ impl AccountTransaction {
             only_query: false,
             charge_fee: enforce_fee(&tx, false),
             validate: true,
+            sierra_gas_limit: None,
         };
         AccountTransaction { tx, execution_flags }
     }
@@ -760,7 +765,10 @@ impl<U: UpdatableState> ExecutableTransaction<U> for AccountTransaction {
         self.perform_pre_validation_stage(state, &tx_context, strict_nonce_check)?;
 
         // Run validation and execution.
-        let initial_gas = tx_context.initial_sierra_gas();
+        let mut initial_gas = tx_context.initial_sierra_gas();
+        if let Some(sierra_gas_limit) = self.execution_flags.sierra_gas_limit {
+            initial_gas = initial_gas.min(sierra_gas_limit);
+        }
         let ValidateExecuteCallInfo {
             validate_call_info,
             execute_call_info,
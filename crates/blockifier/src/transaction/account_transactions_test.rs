@@ -1825,6 +1825,54 @@ fn test_revert_in_execute(
     );
 }
 
+#[rstest]
+fn test_sierra_gas_limit_override(
+    block_context: BlockContext,
+    default_all_resource_bounds: ValidResourceBounds,
+) {
+    let cairo_version = CairoVersion::Cairo1(RunnableCairo1::Casm);
+    let account = FeatureContract::AccountWithoutValidations(cairo_version);
+    let test_contract = FeatureContract::TestContract(cairo_version);
+    let chain_info = &block_context.chain_info;
+    let tx = executable_invoke_tx(invoke_tx_args! {
+        sender_address: account.get_instance_address(0),
+        calldata: build_recurse_calldata(&[CompilerBasedVersion::CairoVersion(cairo_version)]),
+        resource_bounds: default_all_resource_bounds,
+        version: TransactionVersion::THREE
+    });
+    // Each execution runs on a fresh state, so the same transaction can be executed repeatedly.
+    let execute_with_limit = |sierra_gas_limit: Option<GasAmount>| {
+        let state = &mut test_state(chain_info, BALANCE, &[(account, 1), (test_contract, 1)]);
+        let execution_flags =
+            AccountExecutionFlags { sierra_gas_limit, ..AccountExecutionFlags::default() };
+        AccountTransaction { tx: tx.clone(), execution_flags }
+            .execute(state, &block_context)
+            .unwrap()
+    };
+
+    let unlimited_execution_info = execute_with_limit(None);
+    assert!(!unlimited_execution_info.is_reverted());
+    let validate_gas_consumed =
+        unlimited_execution_info.validate_call_info.unwrap().execution.gas_consumed;
+    let execute_gas_consumed =
+        unlimited_execution_info.execute_call_info.unwrap().execution.gas_consumed;
+    assert!(execute_gas_consumed > 0, "New Cairo1 contract should consume gas.");
+
+    // A limit that covers the validation but not the execution reverts the transaction.
+    let low_limit = GasAmount(validate_gas_consumed + execute_gas_consumed / 2);
+    let low_limit_execution_info = execute_with_limit(Some(low_limit));
+    assert!(low_limit_execution_info.is_reverted());
+
+    // A limit above the consumed gas does not affect the execution.
+    let high_limit = GasAmount(validate_gas_consumed + 2 * execute_gas_consumed);
+    let high_limit_execution_info = execute_with_limit(Some(high_limit));
+    assert!(!high_limit_execution_info.is_reverted());
+    assert_eq!(
+        high_limit_execution_info.execute_call_info.unwrap().execution.gas_consumed,
+        execute_gas_consumed
+    );
+}
+
 #[rstest]
 #[cfg_attr(feature = "cairo_native", case::native(CairoVersion::Cairo1(RunnableCairo1::Native)))]
 #[case::vm(CairoVersion::Cairo1(RunnableCairo1::Casm))]
@@ -34,6 +34,67 @@ impl CustomLogger {
     }
 }
 
+/// Counts occurrences of an event and logs every `n`-th one, starting with the first. When dropped,
+/// the last occurrence is logged if it wasn't logged already, so the final partial window isn't
+/// silently swallowed. Use it through [`log_every_n_or_on_drop`].
+pub struct LogEveryNGuard {
+    logger: CustomLogger,
+    n: usize,
+    num_occurrences: usize,
+    unlogged_message: Option<String>,
+}
+
+impl LogEveryNGuard {
+    /// Creates a guard that logs every `n`-th occurrence at the given level. Panics if `n` is 0.
+    pub fn new(level: TraceLevel, n: usize) -> Self {
+        assert!(n > 0, "LogEveryNGuard expects a positive n.");
+        Self {
+            logger: CustomLogger::new(level, None),
+            n,
+            num_occurrences: 0,
+            unlogged_message: None,
+        }
+    }
+
+    /// Records an occurrence, logging its message if it starts a new window.
+    pub fn log_message(&mut self, message: String) {
+        if self.num_occurrences % self.n == 0 {
+            self.logger.log_message(&message);
+            self.unlogged_message = None;
+        } else {
+            self.unlogged_message = Some(message);
+        }
+        self.num_occurrences += 1;
+    }
+}
+
+impl Drop for LogEveryNGuard {
+    fn drop(&mut self) {
+        if let Some(message) = self.unlogged_message.take() {
+            self.logger.log_message(&message);
+        }
+    }
+}
+
+/// Logs the formatted message on the given [`LogEveryNGuard`], i.e. once every `n` occurrences and
+/// once more when the guard is dropped if the last occurrence wasn't logged.
+///
+/// # Example
+/// ```rust,ignore
+/// let mut guard = LogEveryNGuard::new(TraceLevel::Info, 100);
+/// for block in blocks {
+///     log_every_n_or_on_drop!(guard, "Processed block {}.", block.number);
+/// }
+/// // The last block is logged here unless it was already logged inside the loop.
+/// drop(guard);
+/// ```
+#[macro_export]
+macro_rules! log_every_n_or_on_drop {
+    ($guard:expr, $($arg:tt)+) => {
+        $guard.log_message(format!($($arg)+))
+    };
+}
+
 #[derive(Clone, Copy)]
 pub enum TraceLevel {
     Trace,
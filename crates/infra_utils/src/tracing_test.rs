@@ -6,7 +6,8 @@ use tracing::span::{Attributes, Id, Record};
 use tracing::subscriber::with_default;
 use tracing::{Event, Metadata, Subscriber};
 
-use crate::tracing::{CustomLogger, TraceLevel};
+use crate::log_every_n_or_on_drop;
+use crate::tracing::{CustomLogger, LogEveryNGuard, TraceLevel};
 
 #[test]
 fn test_dynamic_logger_without_base_message() {
@@ -94,6 +95,44 @@ fn test_empty_message() {
     assert!(messages[0].contains("WARN"));
 }
 
+#[test]
+fn test_log_every_n_or_on_drop() {
+    let subscriber = TestSubscriber::new();
+
+    with_default(subscriber.clone(), || {
+        let mut guard = LogEveryNGuard::new(TraceLevel::Info, 3);
+        for i in 0..5 {
+            log_every_n_or_on_drop!(guard, "Occurrence {}", i);
+        }
+        assert_eq!(subscriber.messages().len(), 2);
+        drop(guard);
+    });
+
+    let messages = subscriber.messages();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].contains("Occurrence 0"));
+    assert!(messages[1].contains("Occurrence 3"));
+    // Logged when the guard was dropped.
+    assert!(messages[2].contains("Occurrence 4"));
+}
+
+#[test]
+fn test_log_every_n_or_on_drop_full_window() {
+    let subscriber = TestSubscriber::new();
+
+    with_default(subscriber.clone(), || {
+        let mut guard = LogEveryNGuard::new(TraceLevel::Info, 3);
+        for i in 0..4 {
+            log_every_n_or_on_drop!(guard, "Occurrence {}", i);
+        }
+    });
+
+    // The last occurrence was already logged, so nothing is logged on drop.
+    let messages = subscriber.messages();
+    assert_eq!(messages.len(), 2);
+    assert!(messages[1].contains("Occurrence 3"));
+}
+
 // Custom visitor to capture event fields
 struct MessageVisitor<'a> {
     message: &'a mut String,
@@ -47,6 +47,7 @@ impl PapyrusStorage {
             min_size: 1 << 20, // 1MB.
             max_size: config.max_size,
             growth_step: 1 << 26, // 64MB.
+            max_readers: 1 << 13, // 8K readers
         };
         let storage_config = papyrus_storage::StorageConfig {
             db_config,
@@ -57,6 +58,7 @@ impl PapyrusStorage {
                 max_size: 1 << 40,        // 1TB
                 growth_step: 2 << 30,     // 2GB
                 max_object_size: 1 << 30, // 1GB
+                compression_levels: Default::default(),
             },
         };
         let (reader, writer) = papyrus_storage::open_storage(storage_config)?;
@@ -75,6 +77,7 @@ impl PapyrusStorage {
             min_size: 1 << 20,    // 1MB
             max_size: 1 << 35,    // 32GB
             growth_step: 1 << 26, // 64MB
+            max_readers: 1 << 13, // 8K readers
         };
         let storage_config = papyrus_storage::StorageConfig { db_config, ..Default::default() };
         let (reader, writer) = papyrus_storage::open_storage(storage_config).unwrap();
@@ -20,6 +20,17 @@ pub const PAPYRUS_COMPILED_CLASS_MARKER: &str = "papyrus_compiled_class_marker";
 /// finality.
 pub const PAPYRUS_BASE_LAYER_MARKER: &str = "papyrus_base_layer_marker";
 
+/// The number of blocks the node is behind central, i.e. the central block marker minus the header
+/// marker.
+pub const PAPYRUS_BLOCKS_BEHIND_CENTRAL: &str = "papyrus_blocks_behind_central";
+
+/// Whether the central sync is paused (1) or running (0).
+pub const PAPYRUS_SYNC_PAUSED: &str = "papyrus_sync_paused";
+
+/// The latest checkpoint the central sync reached. All the synced data up to and including this
+/// block is flushed to the disk. Reported only if the sync's checkpoint_interval is set.
+pub const PAPYRUS_SYNC_CHECKPOINT: &str = "papyrus_sync_checkpoint";
+
 /// The latency, in seconds, between a block timestamp (as state in its header) and the time the
 /// node stores the header.
 pub const PAPYRUS_HEADER_LATENCY_SEC: &str = "papyrus_header_latency";
@@ -33,6 +44,30 @@ pub const PAPYRUS_NUM_ACTIVE_INBOUND_SESSIONS: &str = "papyrus_num_active_inboun
 /// The number of active sessions this peer has in which it requests data.
 pub const PAPYRUS_NUM_ACTIVE_OUTBOUND_SESSIONS: &str = "papyrus_num_active_outbound_sessions";
 
+/// The number of inbound queries rejected because the querying peer exceeded the rate limit.
+pub const PAPYRUS_NUM_REJECTED_INBOUND_QUERIES: &str = "papyrus_num_rejected_inbound_queries";
+
+/// The number of peers found through discovery.
+pub const PAPYRUS_NUM_DISCOVERED_PEERS: &str = "papyrus_num_discovered_peers";
+
+/// The number of peers found through discovery that we failed to dial before ever connecting to
+/// them. A high ratio to the discovered peers usually means many peers are behind a NAT.
+pub const PAPYRUS_NUM_UNREACHABLE_DISCOVERED_PEERS: &str =
+    "papyrus_num_unreachable_discovered_peers";
+
+/// Whether AutoNAT found that other peers can dial this node (1) or not (0). Not reported before
+/// AutoNAT reached a conclusion, or if it's disabled.
+pub const PAPYRUS_IS_PUBLICLY_REACHABLE: &str = "papyrus_is_publicly_reachable";
+
+/// The number of peers in this peer's gossipsub mesh for a broadcast topic, labeled by the topic
+/// hash. A low value for a critical topic indicates poor connectivity.
+pub const PAPYRUS_NUM_TOPIC_MESH_PEERS: &str = "papyrus_num_topic_mesh_peers";
+
+/// The number of executions that failed because a compiled class was missing. A spike usually means
+/// the compiled class marker is lagging.
+pub const PAPYRUS_EXECUTION_MISSING_COMPILED_CLASS: &str =
+    "papyrus_execution_missing_compiled_class";
+
 // TODO: consider making this value non static and add a way to change this while the app is
 // running. e.g via a monitoring endpoint.
 /// Global variable set by the main config to enable collecting profiling metrics.
@@ -1,4 +1,4 @@
-//! Contains only the [StorageQuery] struct.
+//! Contains the [StorageQuery] struct and functions for saving and loading sets of queries.
 //!
 //! The struct is used in the storage_benchmark binary and in the document_calls feature of the
 //! [papyrus_storage] library. It is not part of the latter because it is not in
@@ -9,6 +9,12 @@
 // TODO(dvir): add links to the document for the storage_benchmark binary and the
 // document_calls feature after they will be publish.
 
+#[cfg(test)]
+#[path = "storage_query_test.rs"]
+mod storage_query_test;
+
+use std::io::{BufRead, BufReader, Read, Write};
+
 use serde::{Deserialize, Serialize};
 use starknet_api::core::ContractAddress;
 use starknet_api::state::{StateNumber, StorageKey};
@@ -26,3 +32,62 @@ pub enum StorageQuery {
     /// Get the storage at a given state number.
     GetStorageAt(StateNumber, ContractAddress, StorageKey),
 }
+
+/// The format of a file of storage queries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StorageQueriesFormat {
+    /// A JSON object per line.
+    #[default]
+    Json,
+    /// The queries serialized with bincode. Loads much faster than JSON for large query sets.
+    Binary,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StorageQueriesFormatError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Binary(#[from] bincode::Error),
+}
+
+/// Reads storage queries written in the given format.
+pub fn load_storage_queries(
+    reader: impl Read,
+    format: StorageQueriesFormat,
+) -> Result<Vec<StorageQuery>, StorageQueriesFormatError> {
+    match format {
+        StorageQueriesFormat::Json => {
+            let mut queries = Vec::new();
+            for line in BufReader::new(reader).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                queries.push(serde_json::from_str(&line)?);
+            }
+            Ok(queries)
+        }
+        StorageQueriesFormat::Binary => Ok(bincode::deserialize_from(reader)?),
+    }
+}
+
+/// Writes storage queries in the given format, such that [`load_storage_queries`] can read them.
+pub fn save_storage_queries(
+    mut writer: impl Write,
+    queries: &[StorageQuery],
+    format: StorageQueriesFormat,
+) -> Result<(), StorageQueriesFormatError> {
+    match format {
+        StorageQueriesFormat::Json => {
+            for query in queries {
+                serde_json::to_writer(&mut writer, query)?;
+                writeln!(writer)?;
+            }
+        }
+        StorageQueriesFormat::Binary => bincode::serialize_into(&mut writer, queries)?,
+    }
+    Ok(writer.flush()?)
+}
@@ -0,0 +1,31 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_api::state::{StateNumber, StorageKey};
+
+use super::{load_storage_queries, save_storage_queries, StorageQueriesFormat, StorageQuery};
+
+#[test]
+fn storage_queries_round_trip() {
+    let queries = vec![
+        StorageQuery::GetClassHashAt(
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            ContractAddress::from(1_u64),
+        ),
+        StorageQuery::GetNonceAt(
+            StateNumber::unchecked_right_after_block(BlockNumber(1)),
+            ContractAddress::from(2_u64),
+        ),
+        StorageQuery::GetStorageAt(
+            StateNumber::unchecked_right_after_block(BlockNumber(2)),
+            ContractAddress::from(3_u64),
+            StorageKey::from(4_u64),
+        ),
+    ];
+
+    for format in [StorageQueriesFormat::Json, StorageQueriesFormat::Binary] {
+        let mut bytes = Vec::new();
+        save_storage_queries(&mut bytes, &queries, format).unwrap();
+        assert_eq!(load_storage_queries(bytes.as_slice(), format).unwrap(), queries);
+    }
+}
@@ -48,6 +48,15 @@ where
     Ok(Duration::from_secs(secs))
 }
 
+/// Deserializes optional seconds to an optional duration object.
+pub fn deserialize_optional_seconds_to_duration<'de, D>(de: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let secs: Option<u64> = Deserialize::deserialize(de)?;
+    Ok(secs.map(Duration::from_secs))
+}
+
 /// Deserializes float seconds to duration object.
 pub fn deserialize_float_seconds_to_duration<'de, D>(de: D) -> Result<Duration, D::Error>
 where
@@ -1,42 +1,88 @@
 // TODO(shahak): Add a test for executing when there's a missing casm that's not required and when
 // there's a missing casm that is required.
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use assert_matches::assert_matches;
-use blockifier::execution::call_info::Retdata;
+use blockifier::execution::call_info::{CallInfo, Retdata};
+use blockifier::execution::contract_class::{
+    CompiledClassV0,
+    CompiledClassV1,
+    RunnableCompiledClass,
+};
+use blockifier::execution::entry_point::CallEntryPoint;
 use blockifier::execution::errors::ConstructorEntryPointExecutionError;
 use blockifier::execution::stack_trace::gen_tx_execution_error_trace;
+use blockifier::state::cached_state::CachedState;
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
+use blockifier::transaction::objects::TransactionExecutionInfo;
 use blockifier::versioned_constants::VersionedConstants;
 use indexmap::indexmap;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_common::metrics as papyrus_metrics;
+use papyrus_common::state::DeployedContract;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::test_utils::get_test_storage;
+use papyrus_test_utils::prometheus_is_contained;
 use pretty_assertions::assert_eq;
+use prometheus_parse::Value::Counter;
 use starknet_api::abi::abi_utils::get_storage_var_address;
-use starknet_api::block::{BlockNumber, StarknetVersion};
-use starknet_api::core::{ChainId, CompiledClassHash, EntryPointSelector};
+use starknet_api::block::{BlockBody, BlockNumber, StarknetVersion};
+use starknet_api::contract_class::SierraVersion;
+use starknet_api::core::{
+    ChainId,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    EthAddress,
+};
+use starknet_api::execution_resources::GasAmount;
 use starknet_api::state::{StateNumber, ThinStateDiff};
 use starknet_api::transaction::fields::{Calldata, Fee};
-use starknet_api::{calldata, class_hash, contract_address, felt, nonce};
+use starknet_api::transaction::{
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    L1HandlerTransaction,
+    Transaction,
+    TransactionHash,
+    TransactionOutput,
+};
+use starknet_api::{calldata, class_hash, contract_address, felt, nonce, storage_key};
 use starknet_types_core::felt::Felt;
 
-use crate::execution_utils::selector_from_name;
+use crate::execution_utils::{
+    get_trace_constructor,
+    induced_state_diff,
+    selector_from_name,
+    TraceDetailLevel,
+};
 use crate::objects::{
     DeclareTransactionTrace,
     DeployAccountTransactionTrace,
     FeeEstimation,
     FunctionInvocationResult,
     InvokeTransactionTrace,
+    PendingData,
     PriceUnit,
+    RevertedCall,
     TransactionSimulationOutput,
     TransactionTrace,
+    TransactionTracingOutput,
 };
+use crate::state_reader::ExecutionStateReader;
 use crate::test_utils::{
     execute_simulate_transactions,
+    execute_trace_transactions,
+    get_test_cairo1_account_casm,
+    get_test_deprecated_contract_class,
     prepare_storage,
+    prepare_storage_with_block_1,
     TxsScenarioBuilder,
     ACCOUNT_ADDRESS,
     ACCOUNT_CLASS_HASH,
     ACCOUNT_INITIAL_BALANCE,
+    BLOCK_TIMESTAMP,
     CHAIN_ID,
     CONTRACT_ADDRESS,
     DEPRECATED_CONTRACT_ADDRESS,
@@ -47,14 +93,28 @@ use crate::test_utils::{
 };
 use crate::testing_instances::get_test_execution_config;
 use crate::{
+    create_cached_state,
     estimate_fee,
+    estimate_message_fee,
     execute_call,
+    execute_call_with_cached_state,
+    execute_call_with_state_diff,
+    replay_block,
+    simulate_transactions,
     ExecutableTransactionInput,
+    ExecutionBackend,
+    ExecutionConfig,
     ExecutionError,
     FeeEstimationResult,
+    MessageFromL1,
     RevertedTransaction,
+    StateOverrides,
 };
 
+// Held by the tests that fail on a missing compiled class, so that they don't change the missing
+// compiled class counter while another test measures it.
+static MISSING_COMPILED_CLASS_LOCK: Mutex<()> = Mutex::new(());
+
 // Test calling entry points of a deprecated class.
 #[test]
 fn execute_call_cairo0() {
@@ -76,6 +136,7 @@ fn execute_call_cairo0() {
         Calldata::default(),
         &get_test_execution_config(),
         true,
+        StateOverrides::default(),
     )
     .unwrap()
     .retdata;
@@ -93,6 +154,7 @@ fn execute_call_cairo0() {
         Calldata(Arc::new(vec![Felt::from(25u128)])),
         &get_test_execution_config(),
         true,
+        StateOverrides::default(),
     )
     .unwrap()
     .retdata;
@@ -110,6 +172,7 @@ fn execute_call_cairo0() {
         Calldata(Arc::new(vec![Felt::from(123u128)])),
         &get_test_execution_config(),
         true,
+        StateOverrides::default(),
     )
     .unwrap()
     .retdata;
@@ -127,12 +190,69 @@ fn execute_call_cairo0() {
         Calldata(Arc::new(vec![Felt::from(123u128), Felt::from(456u128)])),
         &get_test_execution_config(),
         true,
+        StateOverrides::default(),
     )
     .unwrap()
     .retdata;
     assert_eq!(retdata, Retdata(vec![Felt::from(456u128)]));
 }
 
+// Test calling a contract deployed in a block that is treated as pending.
+#[test]
+fn execute_call_on_pending_data_from_block() {
+    let pending_contract_address = contract_address!("0x1234");
+    let class_hash = class_hash!("0x2");
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage_with_block_1(
+        storage_writer,
+        BlockBody::default(),
+        ThinStateDiff {
+            deployed_contracts: indexmap!(pending_contract_address => class_hash),
+            ..Default::default()
+        },
+    );
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let pending_data = PendingData::from_block_header_and_diff(
+        txn.get_block_header(BlockNumber(1)).unwrap().unwrap(),
+        txn.get_state_diff(BlockNumber(1)).unwrap().unwrap(),
+    );
+    assert_eq!(pending_data.timestamp, *BLOCK_TIMESTAMP);
+    assert_eq!(pending_data.l1_gas_price, *GAS_PRICE);
+    assert_eq!(pending_data.sequencer, *SEQUENCER_ADDRESS);
+    assert_eq!(
+        pending_data.deployed_contracts,
+        vec![DeployedContract { address: pending_contract_address, class_hash }]
+    );
+
+    let key = felt!(1234_u16);
+    let value = felt!(18_u8);
+    let execute_call_before_block_1 = |maybe_pending_data| {
+        execute_call(
+            storage_reader.clone(),
+            maybe_pending_data,
+            &CHAIN_ID,
+            StateNumber::right_before_block(BlockNumber(1)),
+            BlockNumber(1),
+            &pending_contract_address,
+            selector_from_name("test_storage_read_write"),
+            calldata![key, value],
+            &get_test_execution_config(),
+            true,
+            StateOverrides::default(),
+        )
+    };
+
+    // The contract is deployed only in the pending data.
+    assert_matches!(
+        execute_call_before_block_1(None),
+        Err(ExecutionError::ContractNotFound { contract_address, .. })
+        if contract_address == pending_contract_address
+    );
+    let retdata = execute_call_before_block_1(Some(pending_data)).unwrap().retdata;
+    assert_eq!(retdata, Retdata(vec![value]));
+}
+
 // Test calling entry points of a cairo 1 class.
 #[test]
 fn execute_call_cairo1() {
@@ -155,6 +275,7 @@ fn execute_call_cairo1() {
         calldata,
         &get_test_execution_config(),
         true,
+        StateOverrides::default(),
     )
     .unwrap()
     .retdata;
@@ -162,6 +283,123 @@ fn execute_call_cairo1() {
     assert_eq!(retdata, Retdata(vec![value]));
 }
 
+// Test that the state diff of a call contains its storage writes.
+#[test]
+fn execute_call_with_state_diff_contains_storage_write() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let key = felt!(1234_u16);
+    let value = felt!(18_u8);
+    let (call_execution, state_diff) = execute_call_with_state_diff(
+        storage_reader,
+        None,
+        &CHAIN_ID,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &CONTRACT_ADDRESS,
+        selector_from_name("test_storage_read_write"),
+        calldata![key, value],
+        &get_test_execution_config(),
+        true,
+        StateOverrides::default(),
+    )
+    .unwrap();
+
+    assert_eq!(call_execution.retdata, Retdata(vec![value]));
+    assert_eq!(
+        state_diff,
+        ThinStateDiff {
+            storage_diffs: indexmap! {*CONTRACT_ADDRESS => indexmap! {storage_key!(1234_u16) => value}},
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn execute_call_with_state_overrides() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let account_balance_key =
+        get_storage_var_address("ERC20_balances", &[*ACCOUNT_ADDRESS.0.key()]);
+    let overridden_balance = felt!(1234_u16);
+    let get_account_balance = |state_overrides| {
+        execute_call(
+            storage_reader.clone(),
+            None,
+            &CHAIN_ID,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(0),
+            &TEST_ERC20_CONTRACT_ADDRESS,
+            selector_from_name("balanceOf"),
+            calldata![*ACCOUNT_ADDRESS.0.key()],
+            &get_test_execution_config(),
+            true,
+            state_overrides,
+        )
+        .unwrap()
+        .retdata
+    };
+
+    // The balance is returned as a u256 (low, high).
+    let retdata = get_account_balance(StateOverrides {
+        storage: vec![(*TEST_ERC20_CONTRACT_ADDRESS, account_balance_key, overridden_balance)],
+        ..Default::default()
+    });
+    assert_eq!(retdata, Retdata(vec![overridden_balance, Felt::ZERO]));
+
+    // The override doesn't persist beyond the call.
+    let retdata = get_account_balance(StateOverrides::default());
+    assert_eq!(retdata, Retdata(vec![*ACCOUNT_INITIAL_BALANCE, Felt::ZERO]));
+}
+
+#[test]
+fn execute_call_with_class_overrides() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    // Neither the contract nor its class exist in the state.
+    let undeployed_contract_address = contract_address!("0x9999");
+    let undeclared_class_hash = class_hash!("0x9999");
+    let call_undeployed_contract = |state_overrides| {
+        execute_call(
+            storage_reader.clone(),
+            None,
+            &CHAIN_ID,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(0),
+            &undeployed_contract_address,
+            selector_from_name("return_result"),
+            calldata![felt!(123_u8)],
+            &get_test_execution_config(),
+            true,
+            state_overrides,
+        )
+    };
+
+    let retdata = call_undeployed_contract(StateOverrides {
+        class_hashes: vec![(undeployed_contract_address, undeclared_class_hash)],
+        declared_classes: vec![(
+            undeclared_class_hash,
+            RunnableCompiledClass::V0(
+                CompiledClassV0::try_from(get_test_deprecated_contract_class()).unwrap(),
+            ),
+        )],
+        ..Default::default()
+    })
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![felt!(123_u8)]));
+
+    // The overrides don't persist beyond the call.
+    assert_matches!(
+        call_undeployed_contract(StateOverrides::default()),
+        Err(ExecutionError::ContractNotFound { contract_address, .. })
+        if contract_address == undeployed_contract_address
+    );
+}
+
 // TODO(yair): Compare to the expected fee instead of asserting that it is not zero (all
 // estimate_fee tests).
 #[test]
@@ -256,6 +494,143 @@ fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
     .unwrap()
 }
 
+#[test]
+fn too_many_transactions() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let execution_config =
+        ExecutionConfig { max_txs_per_request: 1, ..get_test_execution_config() };
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+
+    let fee_result = estimate_fee(
+        txs.clone(),
+        &CHAIN_ID,
+        storage_reader.clone(),
+        None,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &execution_config,
+        false,
+        true,
+    );
+    assert_matches!(fee_result, Err(ExecutionError::TooManyTransactions { count: 2, limit: 1 }));
+
+    let simulation_result = simulate_transactions(
+        txs,
+        None,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &execution_config,
+        false,
+        false,
+        true,
+        None,
+        StateOverrides::default(),
+    );
+    assert_matches!(
+        simulation_result,
+        Err(ExecutionError::TooManyTransactions { count: 2, limit: 1 })
+    );
+}
+
+#[test]
+fn state_number_beyond_synced_state() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let future_state_number = StateNumber::unchecked_right_after_block(BlockNumber(5));
+    let synced_state_number = StateNumber::unchecked_right_after_block(BlockNumber(1));
+
+    let call_result = execute_call(
+        storage_reader.clone(),
+        None,
+        &CHAIN_ID,
+        future_state_number,
+        BlockNumber(1),
+        &DEPRECATED_CONTRACT_ADDRESS,
+        selector_from_name("without_arg"),
+        Calldata::default(),
+        &get_test_execution_config(),
+        false,
+        StateOverrides::default(),
+    );
+    assert_matches!(
+        call_result,
+        Err(ExecutionError::StateNumberOutOfRange { requested, synced })
+        if requested == future_state_number && synced == synced_state_number
+    );
+
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let fee_result = estimate_fee(
+        txs,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        future_state_number,
+        BlockNumber(1),
+        &get_test_execution_config(),
+        false,
+        true,
+    );
+    assert_matches!(
+        fee_result,
+        Err(ExecutionError::StateNumberOutOfRange { requested, synced })
+        if requested == future_state_number && synced == synced_state_number
+    );
+}
+
+#[test]
+fn message_from_l1_to_l1_handler_transaction() {
+    let from_address = EthAddress::try_from(felt!("0x987")).unwrap();
+    let message = MessageFromL1 {
+        from_address,
+        to_address: *DEPRECATED_CONTRACT_ADDRESS,
+        entry_point_selector: selector_from_name("l1_handle"),
+        payload: calldata![felt!("0x123"), felt!("0x456")],
+    };
+
+    let tx = L1HandlerTransaction::from(message);
+    assert_eq!(tx.version, L1HandlerTransaction::VERSION);
+    assert_eq!(tx.contract_address, *DEPRECATED_CONTRACT_ADDRESS);
+    assert_eq!(tx.entry_point_selector, selector_from_name("l1_handle"));
+    // The sender address is prepended to the payload.
+    assert_eq!(tx.calldata, calldata![Felt::from(from_address), felt!("0x123"), felt!("0x456")]);
+}
+
+#[test]
+fn estimate_message_fee_of_l1_handler() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let message = MessageFromL1 {
+        from_address: EthAddress::try_from(felt!("0x987")).unwrap(),
+        to_address: *CONTRACT_ADDRESS,
+        entry_point_selector: selector_from_name("l1_handle"),
+        payload: calldata![felt!("0x123")],
+    };
+
+    let fee = estimate_message_fee(
+        message,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &get_test_execution_config(),
+        true,
+    )
+    .unwrap()
+    .expect("Fee estimation should succeed.");
+    assert_ne!(fee.overall_fee, Fee(0));
+    assert_eq!(fee.l1_gas_price, GAS_PRICE.price_in_wei);
+}
+
 #[test]
 fn serialization_precision() {
     let input =
@@ -344,6 +719,102 @@ fn simulate_invoke() {
     }
 }
 
+#[test]
+fn trace_transactions_matches_simulate_transactions() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let tx = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .declare_deprecated_class(*ACCOUNT_ADDRESS)
+        .collect();
+    let simulation_results =
+        execute_simulate_transactions(storage_reader.clone(), None, tx.clone(), None, true, true);
+    let tracing_results = execute_trace_transactions(storage_reader, None, tx, None, true, true);
+
+    let expected_tracing_results = simulation_results
+        .into_iter()
+        .map(|simulation_output| TransactionTracingOutput {
+            transaction_trace: simulation_output.transaction_trace,
+            induced_state_diff: simulation_output.induced_state_diff,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tracing_results, expected_tracing_results);
+}
+
+#[test]
+fn replay_block_reconstructs_state_diff() {
+    let tx_hashes = vec![TransactionHash(felt!(1_u8)), TransactionHash(felt!(2_u8))];
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+
+    // Execute the transactions to get the state diff of block 1.
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let simulation_results = execute_simulate_transactions(
+        storage_reader,
+        None,
+        txs.clone(),
+        Some(tx_hashes.clone()),
+        true,
+        true,
+    );
+    let block_1_state_diff = merge_state_diffs(
+        simulation_results
+            .into_iter()
+            .map(|simulation_output| simulation_output.induced_state_diff),
+    );
+
+    // Store the transactions and their state diff as block 1.
+    let transactions = txs
+        .into_iter()
+        .map(|tx| match tx {
+            ExecutableTransactionInput::Invoke(tx, _) => Transaction::Invoke(tx),
+            _ => panic!("Expected only invoke transactions."),
+        })
+        .collect::<Vec<_>>();
+    let transaction_outputs =
+        vec![TransactionOutput::Invoke(InvokeTransactionOutput::default()); transactions.len()];
+    let block_1_body =
+        BlockBody { transactions, transaction_outputs, transaction_hashes: tx_hashes };
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage_with_block_1(storage_writer, block_1_body, block_1_state_diff);
+
+    let replay_results = replay_block(
+        storage_reader.clone(),
+        BlockNumber(1),
+        &ChainId::Other(CHAIN_ID.to_string()),
+        &get_test_execution_config(),
+    )
+    .unwrap();
+    let replayed_state_diff = merge_state_diffs(
+        replay_results.into_iter().map(|execution_output| execution_output.induced_state_diff),
+    );
+    let stored_state_diff =
+        storage_reader.begin_ro_txn().unwrap().get_state_diff(BlockNumber(1)).unwrap().unwrap();
+    assert_eq!(replayed_state_diff, stored_state_diff);
+}
+
+// Merges consecutive state diffs into a single state diff.
+fn merge_state_diffs(state_diffs: impl IntoIterator<Item = ThinStateDiff>) -> ThinStateDiff {
+    let mut merged_state_diff = ThinStateDiff::default();
+    for state_diff in state_diffs {
+        merged_state_diff.deployed_contracts.extend(state_diff.deployed_contracts);
+        for (address, storage_diff) in state_diff.storage_diffs {
+            merged_state_diff.storage_diffs.entry(address).or_default().extend(storage_diff);
+        }
+        merged_state_diff.declared_classes.extend(state_diff.declared_classes);
+        merged_state_diff
+            .deprecated_declared_classes
+            .extend(state_diff.deprecated_declared_classes);
+        merged_state_diff.nonces.extend(state_diff.nonces);
+        merged_state_diff.replaced_classes.extend(state_diff.replaced_classes);
+    }
+    merged_state_diff
+}
+
 #[test]
 fn simulate_declare_deprecated() {
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
@@ -586,6 +1057,262 @@ fn simulate_invoke_from_new_account() {
     assert_matches!(invoke_trace.execute_invocation, FunctionInvocationResult::Ok(_));
 }
 
+#[test]
+fn simulate_invoke_with_state_overrides() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let account_balance_key =
+        get_storage_var_address("ERC20_balances", &[*ACCOUNT_ADDRESS.0.key()]);
+    let initial_balance: u128 = ACCOUNT_INITIAL_BALANCE.to_biguint().try_into().unwrap();
+    let overridden_balance = 3 * initial_balance;
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let simulate_and_get_account_balance = |state_overrides| {
+        let mut simulation_results = simulate_transactions(
+            txs.clone(),
+            None,
+            &CHAIN_ID,
+            storage_reader.clone(),
+            None,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(1),
+            &get_test_execution_config(),
+            true,
+            true,
+            true,
+            None,
+            state_overrides,
+        )
+        .unwrap();
+        let simulation_result = simulation_results.pop().unwrap();
+        let account_balance = *simulation_result
+            .induced_state_diff
+            .storage_diffs
+            .get(&*TEST_ERC20_CONTRACT_ADDRESS)
+            .and_then(|storage_diff| storage_diff.get(&account_balance_key))
+            .expect("The fee transfer should change the balance of the account.");
+        (account_balance, simulation_result.fee_estimation.overall_fee.0)
+    };
+
+    // The fee is charged from the overridden balance.
+    let (account_balance, fee) = simulate_and_get_account_balance(StateOverrides {
+        storage: vec![(
+            *TEST_ERC20_CONTRACT_ADDRESS,
+            account_balance_key,
+            felt!(overridden_balance),
+        )],
+        ..Default::default()
+    });
+    assert_eq!(account_balance, felt!(overridden_balance - fee));
+
+    // The override doesn't persist beyond the simulation.
+    let (account_balance, fee) = simulate_and_get_account_balance(StateOverrides::default());
+    assert_eq!(account_balance, felt!(initial_balance - fee));
+}
+
+#[test]
+fn simulate_invoke_with_sierra_gas_limit() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    // The Sierra gas limit only affects Cairo 1 code, so the transaction is sent from a Cairo 1
+    // account.
+    let cairo1_account_address = contract_address!("0x9999");
+    let cairo1_account_class_hash = class_hash!("0x9999");
+    let state_overrides = StateOverrides {
+        class_hashes: vec![(cairo1_account_address, cairo1_account_class_hash)],
+        declared_classes: vec![(
+            cairo1_account_class_hash,
+            RunnableCompiledClass::V1(
+                CompiledClassV1::try_from((get_test_cairo1_account_casm(), SierraVersion::LATEST))
+                    .unwrap(),
+            ),
+        )],
+        ..Default::default()
+    };
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(cairo1_account_address, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let simulate_with_sierra_gas_limits = |sierra_gas_limits| {
+        simulate_transactions(
+            txs.clone(),
+            None,
+            &CHAIN_ID,
+            storage_reader.clone(),
+            None,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(1),
+            &get_test_execution_config(),
+            false,
+            false,
+            true,
+            sierra_gas_limits,
+            state_overrides.clone(),
+        )
+    };
+
+    let mut unlimited_result = simulate_with_sierra_gas_limits(None).unwrap();
+    let TransactionSimulationOutput {
+        transaction_trace: TransactionTrace::Invoke(unlimited_trace),
+        sierra_gas_consumed: unlimited_gas_consumed,
+        ..
+    } = unlimited_result.pop().unwrap()
+    else {
+        panic!("Wrong trace type, expected InvokeTransactionTrace.")
+    };
+    assert_matches!(unlimited_trace.execute_invocation, FunctionInvocationResult::Ok(_));
+    assert!(unlimited_gas_consumed > GasAmount(0), "A Cairo 1 account should consume gas.");
+
+    // A limit below the consumed gas reverts the transaction.
+    let low_limit = GasAmount(unlimited_gas_consumed.0 / 2);
+    let mut low_limit_result =
+        simulate_with_sierra_gas_limits(Some(vec![Some(low_limit)])).unwrap();
+    let TransactionSimulationOutput {
+        transaction_trace: TransactionTrace::Invoke(low_limit_trace),
+        sierra_gas_consumed: low_limit_gas_consumed,
+        ..
+    } = low_limit_result.pop().unwrap()
+    else {
+        panic!("Wrong trace type, expected InvokeTransactionTrace.")
+    };
+    assert_matches!(low_limit_trace.execute_invocation, FunctionInvocationResult::Err(_));
+    assert!(low_limit_gas_consumed <= low_limit);
+
+    // A missing limit for a transaction leaves its gas unlimited.
+    let mut no_limit_result = simulate_with_sierra_gas_limits(Some(vec![None])).unwrap();
+    assert_eq!(no_limit_result.pop().unwrap().sierra_gas_consumed, unlimited_gas_consumed);
+
+    // The limits must match the transactions.
+    assert_matches!(
+        simulate_with_sierra_gas_limits(Some(vec![])),
+        Err(ExecutionError::SierraGasLimitsCountMismatch { limits: 0, transactions: 1 })
+    );
+}
+
+#[test]
+fn simulate_reverted_invoke_returns_revert_call_tree() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let non_existing_contract = contract_address!("0x987");
+
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .invoke_deprecated(*ACCOUNT_ADDRESS, non_existing_contract, None, false)
+        .collect();
+    let result = execute_simulate_transactions(storage_reader, None, txs, None, false, false);
+    assert_eq!(result.len(), 2);
+
+    // The successful transaction has no revert call tree.
+    assert_eq!(result[0].revert_call_tree, None);
+
+    // The account is a Cairo 0 contract, so the tree holds the calls that were active when the
+    // transaction reverted, starting from the account's __execute__.
+    let TransactionTrace::Invoke(invoke_trace) = &result[1].transaction_trace else {
+        panic!("Wrong trace type, expected InvokeTransactionTrace.")
+    };
+    assert_matches!(invoke_trace.execute_invocation, FunctionInvocationResult::Err(_));
+    let outermost_call =
+        result[1].revert_call_tree.as_ref().expect("Transaction should be reverted.");
+    assert_eq!(outermost_call.contract_address, *ACCOUNT_ADDRESS);
+    assert_eq!(outermost_call.class_hash, *ACCOUNT_CLASS_HASH);
+    assert_eq!(outermost_call.entry_point_selector, Some(selector_from_name("__execute__")));
+    assert!(outermost_call.failed);
+}
+
+#[test]
+fn simulate_reverted_invoke_keeps_finished_inner_calls() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    // Failures in Cairo 1 code are caught by the caller, so the transaction is sent from a Cairo 1
+    // account.
+    let cairo1_account_address = contract_address!("0x9999");
+    let cairo1_account_class_hash = class_hash!("0x9999");
+    let state_overrides = StateOverrides {
+        class_hashes: vec![(cairo1_account_address, cairo1_account_class_hash)],
+        declared_classes: vec![(
+            cairo1_account_class_hash,
+            RunnableCompiledClass::V1(
+                CompiledClassV1::try_from((get_test_cairo1_account_casm(), SierraVersion::LATEST))
+                    .unwrap(),
+            ),
+        )],
+        ..Default::default()
+    };
+
+    // test_nested_library_call first library calls test_library_call, which library calls
+    // test_storage_read_write(a + 1, b + 1), and then library calls test_storage_read_write(a, b).
+    // With a = -1, the first write succeeds and the second fails since a isn't a storage address.
+    let contract_class_hash = class_hash!("0x2");
+    let calldata = calldata![
+        *CONTRACT_ADDRESS.0.key(),
+        selector_from_name("test_nested_library_call").0,
+        felt!(5_u8),
+        contract_class_hash.0,
+        selector_from_name("test_library_call").0,
+        selector_from_name("test_storage_read_write").0,
+        Felt::MAX,
+        felt!(0_u8)
+    ];
+    let txs = vec![ExecutableTransactionInput::Invoke(
+        InvokeTransaction::V1(InvokeTransactionV1 {
+            calldata,
+            sender_address: cairo1_account_address,
+            ..Default::default()
+        }),
+        false,
+    )];
+    let mut result = simulate_transactions(
+        txs,
+        None,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &get_test_execution_config(),
+        false,
+        false,
+        true,
+        None,
+        state_overrides,
+    )
+    .unwrap();
+    let revert_call_tree =
+        result.pop().unwrap().revert_call_tree.expect("Transaction should be reverted.");
+
+    let test_contract_call =
+        |selector_name: &str, failed: bool, inner_calls: Vec<RevertedCall>| RevertedCall {
+            contract_address: *CONTRACT_ADDRESS,
+            class_hash: contract_class_hash,
+            entry_point_selector: Some(selector_from_name(selector_name)),
+            failed,
+            inner_calls,
+        };
+    let expected_revert_call_tree = RevertedCall {
+        contract_address: cairo1_account_address,
+        class_hash: cairo1_account_class_hash,
+        entry_point_selector: Some(selector_from_name("__execute__")),
+        failed: true,
+        inner_calls: vec![test_contract_call(
+            "test_nested_library_call",
+            true,
+            vec![
+                // The call that finished before the failing one is kept.
+                test_contract_call(
+                    "test_library_call",
+                    false,
+                    vec![test_contract_call("test_storage_read_write", false, vec![])],
+                ),
+                test_contract_call("test_storage_read_write", true, vec![]),
+            ],
+        )],
+    };
+    assert_eq!(revert_call_tree, expected_revert_call_tree);
+}
+
 #[test]
 fn simulate_invoke_from_new_account_validate_and_charge() {
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
@@ -790,11 +1517,10 @@ fn blockifier_error_mapping() {
         },
     );
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
-    else {
+    let ExecutionError::ExecutionFailed { transaction_index, reason } = err else {
         panic!("unexpected variant")
     };
-    assert_eq!(execution_error, expected);
+    assert_eq!(reason, expected);
     assert_eq!(transaction_index, 0);
 
     let child = blockifier::execution::errors::EntryPointExecutionError::RecursionDepthExceeded;
@@ -807,11 +1533,10 @@ fn blockifier_error_mapping() {
     };
     let expected = format!("{}", gen_tx_execution_error_trace(&blockifier_err));
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
-    else {
+    let ExecutionError::ExecutionFailed { transaction_index, reason } = err else {
         panic!("unexpected variant")
     };
-    assert_eq!(execution_error, expected);
+    assert_eq!(reason, expected);
     assert_eq!(transaction_index, 0);
 
     let child = blockifier::execution::errors::EntryPointExecutionError::RecursionDepthExceeded;
@@ -823,14 +1548,47 @@ fn blockifier_error_mapping() {
     };
     let expected = format!("{}", gen_tx_execution_error_trace(&blockifier_err));
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
-    else {
+    let ExecutionError::ValidationFailed { transaction_index, reason } = err else {
         panic!("unexpected variant")
     };
-    assert_eq!(execution_error, expected);
+    assert_eq!(reason, expected);
     assert_eq!(transaction_index, 0);
 }
 
+#[test]
+fn simulate_with_bad_nonce_fails_validation() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let txs = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .invoke_deprecated(
+            *ACCOUNT_ADDRESS,
+            *DEPRECATED_CONTRACT_ADDRESS,
+            // The account's nonce is 1 after the first transaction.
+            Some(nonce!(5_u128)),
+            false,
+        )
+        .collect();
+    let err = simulate_transactions(
+        txs,
+        None,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &get_test_execution_config(),
+        false,
+        false,
+        false,
+        None,
+        StateOverrides::default(),
+    )
+    .expect_err("Simulation should fail.");
+    assert_matches!(err, ExecutionError::ValidationFailed { transaction_index: 1, .. });
+}
+
 // Test that we retrieve the correct versioned constants.
 #[test]
 fn test_get_versioned_constants() {
@@ -844,3 +1602,224 @@ fn test_get_versioned_constants() {
     let versioned_constants = VersionedConstants::get(&starknet_version_13_2).unwrap();
     assert_eq!(versioned_constants.invoke_tx_max_n_steps, 10_000_000);
 }
+
+// Test that calls executed on a shared cached state reuse the values read by previous calls, and
+// that the changes of all the calls are accumulated in the cached state.
+#[test]
+fn execute_calls_with_shared_cached_state() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let chain_id = ChainId::Other(CHAIN_ID.to_string());
+    let mut cached_state = create_cached_state(
+        storage_reader,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        None,
+        ExecutionBackend::Vm,
+    );
+    let call = |cached_state: &mut CachedState<ExecutionStateReader>,
+                entry_point_name: &str,
+                calldata: Vec<Felt>| {
+        execute_call_with_cached_state(
+            cached_state,
+            &chain_id,
+            BlockNumber(0),
+            &DEPRECATED_CONTRACT_ADDRESS,
+            selector_from_name(entry_point_name),
+            Calldata(Arc::new(calldata)),
+            &get_test_execution_config(),
+            true,
+        )
+        .unwrap()
+        .retdata
+    };
+
+    let retdata = call(
+        &mut cached_state,
+        "test_storage_read_write",
+        vec![Felt::from(123u128), Felt::from(456u128)],
+    );
+    assert_eq!(retdata, Retdata(vec![Felt::from(456u128)]));
+
+    // Point the underlying reader to the state before the contract was deployed. The next call
+    // succeeds only if it uses the class hash and class cached by the first call.
+    cached_state.state.state_number = StateNumber(BlockNumber(0));
+    let retdata = call(&mut cached_state, "return_result", vec![Felt::from(123u128)]);
+    assert_eq!(retdata, Retdata(vec![Felt::from(123u128)]));
+
+    let state_diff = induced_state_diff(&mut cached_state, None).unwrap();
+    assert_eq!(
+        state_diff.storage_diffs,
+        indexmap! {*DEPRECATED_CONTRACT_ADDRESS => indexmap! {storage_key!(123_u8) => felt!(456_u16)}}
+    );
+}
+
+// Test that a missing compiled class found by a call on a shared cached state doesn't affect the
+// errors of the following calls.
+#[test]
+fn missing_compiled_class_is_reset_between_calls_with_shared_cached_state() {
+    let _missing_compiled_class_guard = MISSING_COMPILED_CLASS_LOCK.lock().unwrap();
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = class_hash!("0x1234");
+    let contract_address = contract_address!("0x1234");
+    // Declare and deploy a class in block 1 without storing its compiled class.
+    prepare_storage_with_block_1(
+        storage_writer,
+        BlockBody::default(),
+        ThinStateDiff {
+            deployed_contracts: indexmap! {contract_address => class_hash},
+            declared_classes: indexmap! {class_hash => CompiledClassHash::default()},
+            ..Default::default()
+        },
+    );
+    let chain_id = ChainId::Other(CHAIN_ID.to_string());
+    let mut cached_state = create_cached_state(
+        storage_reader,
+        StateNumber::unchecked_right_after_block(BlockNumber(1)),
+        None,
+        ExecutionBackend::Vm,
+    );
+    let mut call = |contract_address: &ContractAddress, entry_point_name: &str| {
+        execute_call_with_cached_state(
+            &mut cached_state,
+            &chain_id,
+            BlockNumber(1),
+            contract_address,
+            selector_from_name(entry_point_name),
+            Calldata::default(),
+            &get_test_execution_config(),
+            true,
+        )
+    };
+
+    assert_matches!(
+        call(&contract_address, "without_arg"),
+        Err(ExecutionError::MissingCompiledClass { class_hash: missing_class_hash })
+        if missing_class_hash == class_hash
+    );
+    // The class of this contract exists, so the failure is reported as a contract error.
+    assert_matches!(
+        call(&DEPRECATED_CONTRACT_ADDRESS, "non_existing_entry_point"),
+        Err(ExecutionError::ContractError(_))
+    );
+}
+
+// Test that a missing compiled class fails the execution and is counted in the metrics.
+#[test]
+fn missing_compiled_class_metric() {
+    let _missing_compiled_class_guard = MISSING_COMPILED_CLASS_LOCK.lock().unwrap();
+    let handle = PrometheusBuilder::new().install_recorder().unwrap();
+    let missing_compiled_class_count = || match prometheus_is_contained(
+        handle.render(),
+        papyrus_metrics::PAPYRUS_EXECUTION_MISSING_COMPILED_CLASS,
+        &[],
+    ) {
+        Some(Counter(count)) => count,
+        None => 0f64,
+        Some(value) => panic!("Expected a counter, got {value:?}"),
+    };
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = class_hash!("0x1234");
+    let contract_address = contract_address!("0x1234");
+    // Declare and deploy a class in block 1 without storing its compiled class.
+    prepare_storage_with_block_1(
+        storage_writer,
+        BlockBody::default(),
+        ThinStateDiff {
+            deployed_contracts: indexmap! {contract_address => class_hash},
+            declared_classes: indexmap! {class_hash => CompiledClassHash::default()},
+            ..Default::default()
+        },
+    );
+    let count_before_call = missing_compiled_class_count();
+
+    let result = execute_call(
+        storage_reader,
+        None,
+        &ChainId::Other(CHAIN_ID.to_string()),
+        StateNumber::unchecked_right_after_block(BlockNumber(1)),
+        BlockNumber(1),
+        &contract_address,
+        selector_from_name("without_arg"),
+        Calldata::default(),
+        &get_test_execution_config(),
+        true,
+        StateOverrides::default(),
+    );
+    assert_matches!(
+        result,
+        Err(ExecutionError::MissingCompiledClass { class_hash: missing_class_hash })
+        if missing_class_hash == class_hash
+    );
+    assert_eq!(missing_compiled_class_count() - count_before_call, 1f64);
+}
+
+// Test that a class that can't be compiled to native is executed on the VM when falling back to it
+// is allowed, and fails the execution otherwise. The Cairo 1 class in the test storage has an empty
+// Sierra program, so it can't be compiled to native.
+#[cfg(feature = "cairo_native")]
+#[test]
+fn native_backend_falls_back_to_vm() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let key = felt!(1234_u16);
+    let value = felt!(18_u8);
+    let execute_with_backend = |backend| {
+        execute_call(
+            storage_reader.clone(),
+            None,
+            &CHAIN_ID,
+            StateNumber::unchecked_right_after_block(BlockNumber(0)),
+            BlockNumber(0),
+            &CONTRACT_ADDRESS,
+            selector_from_name("test_storage_read_write"),
+            calldata![key, value],
+            &ExecutionConfig { backend, ..get_test_execution_config() },
+            true,
+            StateOverrides::default(),
+        )
+    };
+
+    let retdata = execute_with_backend(ExecutionBackend::NativeWithVmFallback).unwrap().retdata;
+    assert_eq!(retdata, Retdata(vec![value]));
+
+    assert_matches!(
+        execute_with_backend(ExecutionBackend::Native),
+        Err(ExecutionError::NativeCompilationFailed { class_hash }) if class_hash == class_hash!("0x2")
+    );
+}
+
+#[test]
+fn pruned_trace_omits_deep_calls() {
+    // A call chain of depth 2 below the top-level invocation.
+    fn get_execution_info() -> TransactionExecutionInfo {
+        let call_info = |inner_calls| CallInfo {
+            call: CallEntryPoint { class_hash: Some(class_hash!("0x1")), ..Default::default() },
+            inner_calls,
+            ..Default::default()
+        };
+        TransactionExecutionInfo {
+            execute_call_info: Some(call_info(vec![call_info(vec![call_info(vec![])])])),
+            ..Default::default()
+        }
+    }
+    let tx = ExecutableTransactionInput::L1Handler(L1HandlerTransaction::default(), Fee(1), false);
+    let get_top_level_invocation = |detail_level| {
+        let trace = get_trace_constructor(&tx, detail_level)(get_execution_info()).unwrap();
+        let TransactionTrace::L1Handler(trace) = trace else {
+            panic!("Expected an L1 handler trace, got {trace:?}");
+        };
+        trace.function_invocation
+    };
+
+    let full_invocation = get_top_level_invocation(TraceDetailLevel::Full);
+    assert_eq!(full_invocation.calls.len(), 1);
+    assert_eq!(full_invocation.calls[0].calls.len(), 1);
+
+    let pruned_invocation = get_top_level_invocation(TraceDetailLevel::MaxCallDepth(1));
+    assert_eq!(pruned_invocation.calls.len(), 1);
+    assert!(pruned_invocation.calls[0].calls.is_empty());
+
+    let pruned_invocation = get_top_level_invocation(TraceDetailLevel::MaxCallDepth(0));
+    assert!(pruned_invocation.calls.is_empty());
+}
@@ -7,7 +7,7 @@ use blockifier::execution::contract_class::{
     CompiledClassV1,
     RunnableCompiledClass,
 };
-use blockifier::state::cached_state::{CachedState, CommitmentStateDiff, MutRefState};
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff};
 use blockifier::state::state_api::StateReader;
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use cairo_vm::types::errors::program_errors::ProgramError;
@@ -26,7 +26,6 @@ use starknet_types_core::felt::Felt;
 use thiserror::Error;
 
 use crate::objects::TransactionTrace;
-use crate::state_reader::ExecutionStateReader;
 use crate::{
     BlockifierError,
     ExecutableTransactionInput,
@@ -89,12 +88,25 @@ pub(crate) fn get_contract_class(
     )))
 }
 
+/// The amount of detail included in a transaction trace. Pruned traces are cheaper to hold and to
+/// serialize when tracing many transactions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TraceDetailLevel {
+    /// The full trace.
+    #[default]
+    Full,
+    /// Omits the calls nested deeper than the given depth below the top-level invocations. A
+    /// depth of 0 keeps only the top-level invocations.
+    MaxCallDepth(usize),
+}
+
 /// Given an ExecutableTransactionInput, returns a function that will convert the corresponding
-/// TransactionExecutionInfo into the right TransactionTrace variant.
+/// TransactionExecutionInfo into the right TransactionTrace variant, with the given detail level.
 pub fn get_trace_constructor(
     tx: &ExecutableTransactionInput,
-) -> fn(TransactionExecutionInfo) -> ExecutionResult<TransactionTrace> {
-    match tx {
+    detail_level: TraceDetailLevel,
+) -> impl Fn(TransactionExecutionInfo) -> ExecutionResult<TransactionTrace> {
+    let constructor: fn(TransactionExecutionInfo) -> ExecutionResult<TransactionTrace> = match tx {
         ExecutableTransactionInput::Invoke(..) => {
             |execution_info| Ok(TransactionTrace::Invoke(execution_info.try_into()?))
         }
@@ -116,17 +128,25 @@ pub fn get_trace_constructor(
         ExecutableTransactionInput::L1Handler(..) => {
             |execution_info| Ok(TransactionTrace::L1Handler(execution_info.try_into()?))
         }
+    };
+    move |execution_info| {
+        let mut trace = constructor(execution_info)?;
+        if let TraceDetailLevel::MaxCallDepth(max_depth) = detail_level {
+            trace.prune_calls(max_depth);
+        }
+        Ok(trace)
     }
 }
 
-/// Returns the state diff induced by a single transaction. If the transaction
-/// is a deprecated Declare, the user is required to pass the class hash of the deprecated class as
-/// it is not provided by the blockifier API.
+/// Returns the state diff induced by a single transaction, or the diff accumulated in a cached
+/// state shared between several calls and transactions. If the transaction is a deprecated
+/// Declare, the user is required to pass the class hash of the deprecated class as it is not
+/// provided by the blockifier API.
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
 // instead.
 #[allow(clippy::result_large_err)]
-pub fn induced_state_diff(
-    transactional_state: &mut CachedState<MutRefState<'_, CachedState<ExecutionStateReader>>>,
+pub fn induced_state_diff<S: StateReader>(
+    transactional_state: &mut CachedState<S>,
     deprecated_declared_class_hash: Option<ClassHash>,
 ) -> ExecutionResult<ThinStateDiff> {
     let blockifier_state_diff =
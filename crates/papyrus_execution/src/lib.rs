@@ -11,7 +11,7 @@
 #[cfg(test)]
 mod execution_test;
 pub mod execution_utils;
-mod state_reader;
+pub mod state_reader;
 #[cfg(test)]
 mod test_utils;
 #[cfg(any(feature = "testing", test))]
@@ -19,20 +19,25 @@ pub mod testing_instances;
 
 pub mod objects;
 use std::cell::Cell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, LazyLock};
 
 use blockifier::blockifier::block::{pre_process_block, validated_gas_prices};
 use blockifier::bouncer::BouncerConfig;
 use blockifier::context::{BlockContext, ChainInfo, FeeTokenAddresses, TransactionContext};
-use blockifier::execution::call_info::CallExecution;
+use blockifier::execution::call_info::{CallExecution, CallInfo};
+use blockifier::execution::contract_class::RunnableCompiledClass;
 use blockifier::execution::entry_point::{
     CallEntryPoint,
     CallType as BlockifierCallType,
     EntryPointExecutionContext,
 };
 use blockifier::state::cached_state::CachedState;
-use blockifier::transaction::account_transaction::ExecutionFlags;
+use blockifier::state::state_api::StateReader;
+use blockifier::transaction::account_transaction::{
+    AccountTransaction as BlockifierAccountTransaction,
+    ExecutionFlags,
+};
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
 use blockifier::transaction::objects::{
     DeprecatedTransactionInfo,
@@ -44,12 +49,24 @@ use blockifier::transaction::transactions::ExecutableTransaction;
 use blockifier::versioned_constants::{VersionedConstants, VersionedConstantsError};
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use cairo_vm::types::builtin_name::BuiltinName;
-use execution_utils::{get_trace_constructor, induced_state_diff};
-use objects::{PriceUnit, TransactionSimulationOutput};
+use execution_utils::{
+    get_trace_constructor,
+    induced_state_diff,
+    selector_from_name,
+    TraceDetailLevel,
+};
+use metrics::increment_counter;
+use objects::{PriceUnit, TransactionSimulationOutput, TransactionTracingOutput};
+use papyrus_common::deprecated_class_abi::calculate_deprecated_class_abi_length;
+use papyrus_common::metrics as papyrus_metrics;
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::db::RO;
 use papyrus_storage::header::HeaderStorageReader;
-use papyrus_storage::{StorageError, StorageReader};
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     BlockHashAndNumber,
@@ -59,10 +76,13 @@ use starknet_api::block::{
     StarknetVersion,
 };
 use starknet_api::contract_class::{ClassInfo, EntryPointType, SierraVersion};
-use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector};
+use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector, EthAddress};
 use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::{StateNumber, ThinStateDiff};
+use starknet_api::executable_transaction::AccountTransaction as ApiExecutableAccountTransaction;
+use starknet_api::execution_resources::GasAmount;
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::transaction::constants::EXECUTE_ENTRY_POINT_NAME;
 use starknet_api::transaction::fields::{Calldata, Fee};
 use starknet_api::transaction::{
     DeclareTransaction,
@@ -83,7 +103,13 @@ use starknet_types_core::felt::Felt;
 use state_reader::ExecutionStateReader;
 use tracing::trace;
 
-use crate::objects::{tx_execution_output_to_fee_estimation, FeeEstimation, PendingData};
+use crate::objects::{
+    revert_call_tree,
+    sierra_gas_consumed,
+    tx_execution_output_to_fee_estimation,
+    FeeEstimation,
+    PendingData,
+};
 
 /// The address of the STRK fee contract on Starknet.
 const STRK_FEE_CONTRACT_ADDRESS_STR: &str =
@@ -92,6 +118,7 @@ const STRK_FEE_CONTRACT_ADDRESS_STR: &str =
 const ETH_FEE_CONTRACT_ADDRESS_STR: &str =
     "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
 const DEFAULT_INITIAL_GAS_COST: u64 = 10000000000;
+const DEFAULT_MAX_TXS_PER_REQUEST: usize = 100;
 
 /// Result type for execution functions.
 pub type ExecutionResult<T> = Result<T, ExecutionError>;
@@ -123,6 +150,24 @@ pub struct ExecutionConfig {
     pub eth_fee_contract_address: ContractAddress,
     /// The initial gas cost for a transaction
     pub default_initial_gas_cost: u64,
+    /// The engine used to execute Cairo 1 contracts
+    pub backend: ExecutionBackend,
+    /// The maximal number of transactions in a single simulation or fee estimation request
+    pub max_txs_per_request: usize,
+}
+
+/// The engine used to execute Cairo 1 contracts. Cairo 0 contracts always run on the VM.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+pub enum ExecutionBackend {
+    /// Execute with the Cairo VM.
+    #[default]
+    Vm,
+    /// Execute with Cairo native. Fails if a class can't be compiled to native.
+    #[cfg(feature = "cairo_native")]
+    Native,
+    /// Execute with Cairo native, and with the VM for classes that can't be compiled to native.
+    #[cfg(feature = "cairo_native")]
+    NativeWithVmFallback,
 }
 
 impl Default for ExecutionConfig {
@@ -131,6 +176,8 @@ impl Default for ExecutionConfig {
             strk_fee_contract_address: *STRK_FEE_CONTRACT_ADDRESS,
             eth_fee_contract_address: *ETH_FEE_CONTRACT_ADDRESS,
             default_initial_gas_cost: DEFAULT_INITIAL_GAS_COST,
+            backend: ExecutionBackend::default(),
+            max_txs_per_request: DEFAULT_MAX_TXS_PER_REQUEST,
         }
     }
 }
@@ -156,6 +203,19 @@ impl SerializeConfig for ExecutionConfig {
                 "The initial gas cost for a transaction",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "backend",
+                &self.backend,
+                "The engine used to execute Cairo 1 contracts: Vm, Native or NativeWithVmFallback",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_txs_per_request",
+                &self.max_txs_per_request,
+                "The maximal number of transactions in a single simulation or fee estimation \
+                 request",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -164,6 +224,8 @@ impl SerializeConfig for ExecutionConfig {
 /// The error type for the execution module.
 #[derive(thiserror::Error, Debug)]
 pub enum ExecutionError {
+    #[error("Block {block_number} is not found in the storage.")]
+    BlockNotFound { block_number: BlockNumber },
     #[error("Bad declare tx: {tx:?}. error: {err:?}")]
     BadDeclareTransaction {
         tx: DeclareTransaction,
@@ -185,35 +247,84 @@ pub enum ExecutionError {
     ContractNotFound { contract_address: ContractAddress, state_number: StateNumber },
     #[error("Gas consumed should fit into u64")]
     GasConsumedOutOfRange,
+    #[error(
+        "State number {requested:?} is beyond the synced state. The latest synced state number is \
+         {synced:?}."
+    )]
+    StateNumberOutOfRange { requested: StateNumber, synced: StateNumber },
     #[error("Missing class hash in call info")]
     MissingClassHash,
     #[error("Missing compiled class with hash {class_hash} (The CASM table isn't synced)")]
     MissingCompiledClass { class_hash: ClassHash },
+    #[error("Failed to compile class {class_hash} to native.")]
+    NativeCompilationFailed { class_hash: ClassHash },
     #[error(transparent)]
     StateError(#[from] blockifier::state::errors::StateError),
     #[error(transparent)]
     StorageError(#[from] StorageError),
-    #[error(transparent)]
-    TransactionFeeError(#[from] blockifier::transaction::errors::TransactionFeeError),
     #[error(
-        "Execution failed at transaction {transaction_index:?} with error: {execution_error:?}"
+        "Got {count} transactions, while at most {limit} transactions are allowed per request."
     )]
-    TransactionExecutionError { transaction_index: usize, execution_error: String },
+    TooManyTransactions { count: usize, limit: usize },
+    #[error("Got {limits} Sierra gas limits for {transactions} transactions.")]
+    SierraGasLimitsCountMismatch { limits: usize, transactions: usize },
+    #[error(transparent)]
+    TransactionFeeError(#[from] blockifier::transaction::errors::TransactionFeeError),
+    #[error("Execution failed at transaction {transaction_index:?} with error: {reason:?}")]
+    ExecutionFailed { transaction_index: usize, reason: String },
     #[error("Failed to calculate transaction hash.")]
     TransactionHashCalculationFailed(StarknetApiError),
     #[error("Unknown builtin name: {builtin_name}")]
     UnknownBuiltin { builtin_name: BuiltinName },
+    #[error("Deploy transactions are not supported in execution.")]
+    UnsupportedDeployTransaction,
+    #[error("Validation failed at transaction {transaction_index:?} with error: {reason:?}")]
+    ValidationFailed { transaction_index: usize, reason: String },
     #[error(transparent)]
     VersionedConstants(#[from] VersionedConstantsError),
 }
 
+// Returns a MissingCompiledClass error and counts it, as it usually means the compiled classes
+// aren't synced yet.
+fn missing_compiled_class_error(class_hash: ClassHash) -> ExecutionError {
+    increment_counter!(papyrus_metrics::PAPYRUS_EXECUTION_MISSING_COMPILED_CLASS);
+    ExecutionError::MissingCompiledClass { class_hash }
+}
+
+// Returns the error stored by the state reader during the execution, if any. The state reader can
+// only return Blockifier's errors, so it stores the failures that have a custom error.
+fn stored_state_reader_error(state_reader: &ExecutionStateReader) -> Option<ExecutionError> {
+    if let Some(class_hash) = state_reader.missing_compiled_class.get() {
+        return Some(missing_compiled_class_error(class_hash));
+    }
+    state_reader
+        .failed_native_compilation
+        .get()
+        .map(|class_hash| ExecutionError::NativeCompilationFailed { class_hash })
+}
+
+/// Changes to the state that only affect the execution they're given to and are never written
+/// anywhere. Useful for checking how calls and transactions behave on a state that doesn't exist
+/// (yet), e.g. before an upgrade.
+#[derive(Clone, Default)]
+pub struct StateOverrides {
+    /// Storage values to read instead of the ones in the state, as (contract address, key, value).
+    pub storage: Vec<(ContractAddress, StorageKey, Felt)>,
+    /// Class hashes of contracts to use instead of the ones in the state. The contracts don't
+    /// have to be deployed.
+    pub class_hashes: Vec<(ContractAddress, ClassHash)>,
+    /// Classes to treat as declared, in addition to the ones in the state.
+    pub declared_classes: Vec<(ClassHash, RunnableCompiledClass)>,
+}
+
 /// Whether the only-query bit of the transaction version is on.
 pub type OnlyQuery = bool;
 
 /// Gathers all the possible errors that can be returned from the blockifier.
 type BlockifierError = anyhow::Error;
 
-/// Executes a StarkNet call and returns the execution result.
+/// Executes a StarkNet call and returns the execution result. The call is executed on top of the
+/// given state overrides.
 #[allow(clippy::too_many_arguments)]
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
 // instead.
@@ -229,13 +340,165 @@ pub fn execute_call(
     calldata: Calldata,
     execution_config: &ExecutionConfig,
     override_kzg_da_to_false: bool,
+    state_overrides: StateOverrides,
 ) -> ExecutionResult<CallExecution> {
-    verify_contract_exists(
-        *contract_address,
-        &storage_reader,
+    let mut cached_state = create_call_cached_state(
+        storage_reader,
+        maybe_pending_data,
         state_number,
-        maybe_pending_data.as_ref(),
+        contract_address,
+        execution_config,
+        state_overrides,
+    )?;
+    execute_call_with_cached_state(
+        &mut cached_state,
+        chain_id,
+        block_context_number,
+        contract_address,
+        entry_point_selector,
+        calldata,
+        execution_config,
+        override_kzg_da_to_false,
+    )
+}
+
+/// Same as [execute_call], but also returns the state diff the call induced. Calls are usually
+/// read-only, so the diff is usually empty. The state overrides are not part of the diff.
+#[allow(clippy::too_many_arguments)]
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+pub fn execute_call_with_state_diff(
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    chain_id: &ChainId,
+    state_number: StateNumber,
+    block_context_number: BlockNumber,
+    contract_address: &ContractAddress,
+    entry_point_selector: EntryPointSelector,
+    calldata: Calldata,
+    execution_config: &ExecutionConfig,
+    override_kzg_da_to_false: bool,
+    state_overrides: StateOverrides,
+) -> ExecutionResult<(CallExecution, ThinStateDiff)> {
+    let mut cached_state = create_call_cached_state(
+        storage_reader,
+        maybe_pending_data,
+        state_number,
+        contract_address,
+        execution_config,
+        state_overrides,
     )?;
+    let call_execution = execute_call_with_cached_state(
+        &mut cached_state,
+        chain_id,
+        block_context_number,
+        contract_address,
+        entry_point_selector,
+        calldata,
+        execution_config,
+        override_kzg_da_to_false,
+    )?;
+    let state_diff = induced_state_diff(&mut cached_state, None)?;
+    Ok((call_execution, state_diff))
+}
+
+// Creates the state a call to the given contract is executed on, after verifying the state is
+// synced and the contract exists in it.
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+fn create_call_cached_state(
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    state_number: StateNumber,
+    contract_address: &ContractAddress,
+    execution_config: &ExecutionConfig,
+    state_overrides: StateOverrides,
+) -> ExecutionResult<CachedState<ExecutionStateReader>> {
+    verify_state_number_synced(&storage_reader, state_number)?;
+    let deployed_by_override =
+        state_overrides.class_hashes.iter().any(|(address, _)| address == contract_address);
+    if !deployed_by_override {
+        verify_contract_exists(
+            *contract_address,
+            &storage_reader,
+            state_number,
+            maybe_pending_data.as_ref(),
+        )?;
+    }
+
+    let mut cached_state = create_cached_state(
+        storage_reader,
+        state_number,
+        maybe_pending_data,
+        execution_config.backend,
+    );
+    apply_state_overrides(&mut cached_state, state_overrides);
+    Ok(cached_state)
+}
+
+/// Creates a state at the given state number that can be shared between several calls and
+/// transactions. Values read from the storage by one of them are cached and reused by the
+/// following ones. The accumulated changes can be extracted with
+/// [execution_utils::induced_state_diff].
+pub fn create_cached_state(
+    storage_reader: StorageReader,
+    state_number: StateNumber,
+    maybe_pending_data: Option<PendingData>,
+    backend: ExecutionBackend,
+) -> CachedState<ExecutionStateReader> {
+    CachedState::new(ExecutionStateReader {
+        storage_reader,
+        state_number,
+        maybe_pending_data,
+        missing_compiled_class: Cell::new(None),
+        failed_native_compilation: Cell::new(None),
+        backend,
+        storage_overrides: HashMap::new(),
+        class_hash_overrides: HashMap::new(),
+        declared_class_overrides: HashMap::new(),
+    })
+}
+
+// Layers the overrides on top of the state reader, so that they're read as if they were in the
+// state but never show up in the changes made by the execution.
+fn apply_state_overrides(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    state_overrides: StateOverrides,
+) {
+    let StateOverrides { storage, class_hashes, declared_classes } = state_overrides;
+    let state_reader = &mut cached_state.state;
+    state_reader.storage_overrides.extend(
+        storage.into_iter().map(|(contract_address, key, value)| ((contract_address, key), value)),
+    );
+    state_reader.class_hash_overrides.extend(class_hashes);
+    state_reader.declared_class_overrides.extend(declared_classes);
+}
+
+/// Same as [execute_call], but executes on top of the given cached state. Changes made by the call
+/// are kept in the cached state.
+#[allow(clippy::too_many_arguments)]
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+pub fn execute_call_with_cached_state(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    chain_id: &ChainId,
+    block_context_number: BlockNumber,
+    contract_address: &ContractAddress,
+    entry_point_selector: EntryPointSelector,
+    calldata: Calldata,
+    execution_config: &ExecutionConfig,
+    override_kzg_da_to_false: bool,
+) -> ExecutionResult<CallExecution> {
+    cached_state.state.clear_stored_errors();
+    if cached_state.get_class_hash_at(*contract_address)? == ClassHash::default() {
+        return Err(ExecutionError::ContractNotFound {
+            contract_address: *contract_address,
+            state_number: cached_state.state.state_number,
+        });
+    }
 
     // TODO(yair): check if this is the correct value.
     let mut remaining_gas = execution_config.default_initial_gas_cost;
@@ -251,15 +514,10 @@ pub fn execute_call(
         initial_gas: remaining_gas,
     };
 
-    let mut cached_state = CachedState::new(ExecutionStateReader {
-        storage_reader: storage_reader.clone(),
-        state_number,
-        maybe_pending_data: maybe_pending_data.clone(),
-        missing_compiled_class: Cell::new(None),
-    });
-
+    let storage_reader = cached_state.state.storage_reader.clone();
+    let maybe_pending_data = cached_state.state.maybe_pending_data.clone();
     let block_context = create_block_context(
-        &mut cached_state,
+        cached_state,
         block_context_number,
         chain_id.clone(),
         &storage_reader,
@@ -276,19 +534,32 @@ pub fn execute_call(
         limit_steps_by_resources,
     );
 
-    let res = call_entry_point
-        .execute(&mut cached_state, &mut context, &mut remaining_gas)
-        .map_err(|error| {
-            if let Some(class_hash) = cached_state.state.missing_compiled_class.get() {
-                ExecutionError::MissingCompiledClass { class_hash }
-            } else {
-                ExecutionError::ContractError(error.into())
-            }
-        })?;
+    let res = call_entry_point.execute(cached_state, &mut context, &mut remaining_gas).map_err(
+        |error| {
+            stored_state_reader_error(&cached_state.state)
+                .unwrap_or_else(|| ExecutionError::ContractError(error.into()))
+        },
+    )?;
 
     Ok(res.execution)
 }
 
+// Verifies that the state at the given state number is synced. Otherwise, reads from the storage
+// would fail in a way that doesn't explain the actual problem.
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+fn verify_state_number_synced(
+    storage_reader: &StorageReader,
+    state_number: StateNumber,
+) -> ExecutionResult<()> {
+    let synced = StateNumber(storage_reader.begin_ro_txn()?.get_state_marker()?);
+    if state_number > synced {
+        return Err(ExecutionError::StateNumberOutOfRange { requested: state_number, synced });
+    }
+    Ok(())
+}
+
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
 // instead.
 #[allow(clippy::result_large_err)]
@@ -298,16 +569,19 @@ fn verify_contract_exists(
     state_number: StateNumber,
     maybe_pending_data: Option<&PendingData>,
 ) -> ExecutionResult<()> {
-    execution_utils::get_class_hash_at(
-        &storage_reader.begin_ro_txn()?,
-        state_number,
-        maybe_pending_data.map(|pending_state_diff| {
-            (&pending_state_diff.deployed_contracts, &pending_state_diff.replaced_classes)
-        }),
-        contract_address,
-    )?
-    .ok_or(ExecutionError::ContractNotFound { contract_address, state_number })?;
-    Ok(())
+    // A replaced class in the pending data implies that the contract was already deployed.
+    let deployed_in_pending = maybe_pending_data.is_some_and(|pending_data| {
+        pending_data.deployed_contracts.iter().any(|contract| contract.address == contract_address)
+    });
+    if deployed_in_pending
+        || storage_reader
+            .begin_ro_txn()?
+            .get_state_reader()?
+            .is_contract_deployed(state_number, &contract_address)?
+    {
+        return Ok(());
+    }
+    Err(ExecutionError::ContractNotFound { contract_address, state_number })
 }
 
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
@@ -323,6 +597,7 @@ fn create_block_context(
     // TODO(shahak): Remove this once we stop supporting rpc v0.6.
     override_kzg_da_to_false: bool,
 ) -> ExecutionResult<BlockContext> {
+    verify_state_number_synced(storage_reader, cached_state.state.state_number)?;
     let (
         block_number,
         block_timestamp,
@@ -626,6 +901,7 @@ pub fn estimate_fee(
     validate: bool,
     override_kzg_da_to_false: bool,
 ) -> ExecutionResult<FeeEstimationResult> {
+    verify_txs_count(txs.len(), execution_config)?;
     let (txs_execution_info, block_context) = execute_transactions(
         txs,
         None,
@@ -638,6 +914,9 @@ pub fn estimate_fee(
         false,
         validate,
         override_kzg_da_to_false,
+        None,
+        StateOverrides::default(),
+        false,
     )?;
     let mut result = Vec::new();
     for (index, tx_execution_output) in txs_execution_info.into_iter().enumerate() {
@@ -655,10 +934,90 @@ pub fn estimate_fee(
     Ok(Ok(result))
 }
 
-struct TransactionExecutionOutput {
-    execution_info: TransactionExecutionInfo,
-    induced_state_diff: ThinStateDiff,
-    price_unit: PriceUnit,
+// Rejects requests with more transactions than allowed, before executing any of them.
+#[allow(clippy::result_large_err)]
+fn verify_txs_count(count: usize, execution_config: &ExecutionConfig) -> ExecutionResult<()> {
+    let limit = execution_config.max_txs_per_request;
+    if count > limit {
+        return Err(ExecutionError::TooManyTransactions { count, limit });
+    }
+    Ok(())
+}
+
+/// A message sent from L1 to a contract on L2.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MessageFromL1 {
+    /// The L1 address that sent the message.
+    pub from_address: EthAddress,
+    /// The L2 contract that handles the message.
+    pub to_address: ContractAddress,
+    /// The selector of the L1 handler that handles the message.
+    pub entry_point_selector: EntryPointSelector,
+    /// The payload of the message.
+    pub payload: Calldata,
+}
+
+impl From<MessageFromL1> for L1HandlerTransaction {
+    fn from(message: MessageFromL1) -> Self {
+        // The first argument of an L1 handler is the sender of the message.
+        let mut calldata = vec![Felt::from(message.from_address)];
+        calldata.extend_from_slice(&message.payload.0);
+        Self {
+            version: L1HandlerTransaction::VERSION,
+            contract_address: message.to_address,
+            entry_point_selector: message.entry_point_selector,
+            calldata: Calldata(Arc::new(calldata)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns the fee estimation for handling a message from L1, or the revert reason if the L1
+/// handler reverted.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::result_large_err)]
+pub fn estimate_message_fee(
+    message: MessageFromL1,
+    chain_id: &ChainId,
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    state_number: StateNumber,
+    block_context_block_number: BlockNumber,
+    execution_config: &ExecutionConfig,
+    override_kzg_da_to_false: bool,
+) -> ExecutionResult<Result<FeeEstimation, RevertedTransaction>> {
+    // The paid fee bounds the fee the L1 handler may use. Because we want to estimate the fee, we
+    // pass u128::MAX so the execution won't fail.
+    let txs = vec![ExecutableTransactionInput::L1Handler(message.into(), Fee(u128::MAX), false)];
+    let fee_estimation_result = estimate_fee(
+        txs,
+        chain_id,
+        storage_reader,
+        maybe_pending_data,
+        state_number,
+        block_context_block_number,
+        execution_config,
+        false,
+        override_kzg_da_to_false,
+    )?;
+    Ok(fee_estimation_result.map(|mut fees| {
+        fees.pop().expect("Fee estimation of a single transaction should return a single fee.")
+    }))
+}
+
+/// The output of executing a transaction.
+pub struct TransactionExecutionOutput {
+    /// The execution info returned by the blockifier.
+    pub execution_info: TransactionExecutionInfo,
+    /// The state diff induced by the transaction.
+    pub induced_state_diff: ThinStateDiff,
+    /// The unit of the fee paid by the transaction.
+    pub price_unit: PriceUnit,
+    /// For a reverted invoke transaction, the call info of its re-executed `__execute__` call,
+    /// which the blockifier doesn't return for reverted transactions. None if the transaction
+    /// didn't revert, if the re-execution wasn't requested or if it failed without a call info
+    /// (e.g. in Cairo 0 code).
+    pub reverted_execute_call_info: Option<CallInfo>,
 }
 
 // Executes a series of transactions and returns the execution results.
@@ -679,17 +1038,58 @@ fn execute_transactions(
     charge_fee: bool,
     validate: bool,
     override_kzg_da_to_false: bool,
+    sierra_gas_limits: Option<Vec<Option<GasAmount>>>,
+    state_overrides: StateOverrides,
+    trace_reverted_execute: bool,
 ) -> ExecutionResult<(Vec<TransactionExecutionOutput>, BlockContext)> {
     // The starknet state will be from right before the block in which the transactions should run.
-    let mut cached_state = CachedState::new(ExecutionStateReader {
-        storage_reader: storage_reader.clone(),
+    let mut cached_state = create_cached_state(
+        storage_reader,
         state_number,
-        maybe_pending_data: maybe_pending_data.clone(),
-        missing_compiled_class: Cell::new(None),
-    });
+        maybe_pending_data,
+        execution_config.backend,
+    );
+    apply_state_overrides(&mut cached_state, state_overrides);
+    execute_transactions_with_cached_state(
+        &mut cached_state,
+        txs,
+        tx_hashes,
+        chain_id,
+        block_context_block_number,
+        execution_config,
+        charge_fee,
+        validate,
+        override_kzg_da_to_false,
+        sierra_gas_limits,
+        trace_reverted_execute,
+    )
+}
 
+/// Executes a series of transactions on top of the given cached state and returns the execution
+/// results. The changes made by the transactions are kept in the cached state. If
+/// `trace_reverted_execute` is set, the `__execute__` call of each reverted invoke transaction is
+/// replayed in order to return its call info.
+#[allow(clippy::too_many_arguments)]
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+pub fn execute_transactions_with_cached_state(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    txs: Vec<ExecutableTransactionInput>,
+    tx_hashes: Option<Vec<TransactionHash>>,
+    chain_id: &ChainId,
+    block_context_block_number: BlockNumber,
+    execution_config: &ExecutionConfig,
+    charge_fee: bool,
+    validate: bool,
+    override_kzg_da_to_false: bool,
+    sierra_gas_limits: Option<Vec<Option<GasAmount>>>,
+    trace_reverted_execute: bool,
+) -> ExecutionResult<(Vec<TransactionExecutionOutput>, BlockContext)> {
+    let storage_reader = cached_state.state.storage_reader.clone();
+    let maybe_pending_data = cached_state.state.maybe_pending_data.clone();
     let block_context = create_block_context(
-        &mut cached_state,
+        cached_state,
         block_context_block_number,
         chain_id.clone(),
         &storage_reader,
@@ -720,7 +1120,8 @@ fn execute_transactions(
         } else {
             PriceUnit::Fri
         };
-        let mut transactional_state = CachedState::create_transactional(&mut cached_state);
+        cached_state.state.clear_stored_errors();
+        let mut transactional_state = CachedState::create_transactional(cached_state);
         let deprecated_declared_class_hash = match &tx {
             ExecutableTransactionInput::DeclareV0(
                 DeclareTransactionV0V1 { class_hash, .. },
@@ -736,36 +1137,133 @@ fn execute_transactions(
             ) => Some(*class_hash),
             _ => None,
         };
-        let blockifier_tx = to_blockifier_tx(tx, tx_hash, transaction_index, charge_fee, validate)?;
+        let sierra_gas_limit = sierra_gas_limits
+            .as_ref()
+            .and_then(|limits| limits.get(transaction_index).copied().flatten());
+        let blockifier_tx = to_blockifier_tx(
+            tx,
+            tx_hash,
+            transaction_index,
+            charge_fee,
+            validate,
+            sierra_gas_limit,
+        )?;
         // TODO(Yoni): use the TransactionExecutor instead.
-        let tx_execution_info_result =
+        let mut tx_execution_info_result =
             blockifier_tx.execute(&mut transactional_state, &block_context);
+        let reverted_execute_call_info = match (&blockifier_tx, &tx_execution_info_result) {
+            (BlockifierTransaction::Account(account_tx), Ok(execution_info))
+                if trace_reverted_execute && execution_info.is_reverted() =>
+            {
+                // The call is replayed on the state before the transaction, which can't be read
+                // while the transaction's changes are pending, so the transaction is executed
+                // again after the replay.
+                transactional_state.abort();
+                let call_info = replay_reverted_execute(
+                    cached_state,
+                    account_tx,
+                    execution_info,
+                    &block_context,
+                );
+                transactional_state = CachedState::create_transactional(cached_state);
+                tx_execution_info_result =
+                    blockifier_tx.execute(&mut transactional_state, &block_context);
+                call_info
+            }
+            _ => None,
+        };
         let state_diff =
             induced_state_diff(&mut transactional_state, deprecated_declared_class_hash)?;
         transactional_state.commit();
         let execution_info = tx_execution_info_result.map_err(|error| {
-            if let Some(class_hash) = cached_state.state.missing_compiled_class.get() {
-                ExecutionError::MissingCompiledClass { class_hash }
-            } else {
-                ExecutionError::from((transaction_index, error))
-            }
+            stored_state_reader_error(&cached_state.state)
+                .unwrap_or_else(|| ExecutionError::from((transaction_index, error)))
         })?;
         res.push(TransactionExecutionOutput {
             execution_info,
             induced_state_diff: state_diff,
             price_unit,
+            reverted_execute_call_info,
         });
     }
 
     Ok((res, block_context))
 }
 
+// Re-executes the __execute__ call of a reverted invoke transaction on top of the given state,
+// which should be the state before the transaction, and returns its call info. Failures in Cairo 1
+// code are kept in the call info together with the calls that finished before them, while failures
+// in Cairo 0 code abort the re-execution and return None. The changes made by the re-execution are
+// discarded.
+fn replay_reverted_execute(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    account_tx: &BlockifierAccountTransaction,
+    execution_info: &TransactionExecutionInfo,
+    block_context: &BlockContext,
+) -> Option<CallInfo> {
+    let ApiExecutableAccountTransaction::Invoke(invoke_tx) = &account_tx.tx else {
+        return None;
+    };
+    let entry_point_selector = match &invoke_tx.tx {
+        InvokeTransaction::V0(tx) => tx.entry_point_selector,
+        InvokeTransaction::V1(_) | InvokeTransaction::V3(_) => {
+            selector_from_name(EXECUTE_ENTRY_POINT_NAME)
+        }
+    };
+    let tx_context = Arc::new(block_context.to_tx_context(account_tx));
+    let mut context = EntryPointExecutionContext::new_invoke(
+        tx_context.clone(),
+        account_tx.execution_flags.charge_fee,
+    );
+
+    // Give the call the gas that was left for it after the validation, as in the execution.
+    let mut initial_gas = tx_context.initial_sierra_gas();
+    if let Some(sierra_gas_limit) = account_tx.execution_flags.sierra_gas_limit {
+        initial_gas = initial_gas.min(sierra_gas_limit);
+    }
+    let validate_gas_consumed = execution_info
+        .validate_call_info
+        .as_ref()
+        .map_or(0, |call_info| call_info.execution.gas_consumed);
+    let mut remaining_gas =
+        initial_gas.0.saturating_sub(validate_gas_consumed).min(context.mode_sierra_gas_limit().0);
+
+    let execute_call = CallEntryPoint {
+        class_hash: None,
+        code_address: None,
+        entry_point_type: EntryPointType::External,
+        entry_point_selector,
+        calldata: invoke_tx.calldata(),
+        storage_address: invoke_tx.sender_address(),
+        caller_address: ContractAddress::default(),
+        call_type: BlockifierCallType::Call,
+        initial_gas: remaining_gas,
+    };
+    let mut transactional_state = CachedState::create_transactional(cached_state);
+    let call_info =
+        execute_call.execute(&mut transactional_state, &mut context, &mut remaining_gas).ok();
+    transactional_state.abort();
+    call_info
+}
+
 /// Converts a transaction index and [BlockifierTransactionExecutionError] to an [ExecutionError].
+/// Errors raised before the transaction's execution started (e.g. a bad nonce or a failing
+/// `__validate__`) are converted to [ExecutionError::ValidationFailed] and the rest to
+/// [ExecutionError::ExecutionFailed].
 // TODO(yair): Remove once blockifier arranges the errors hierarchy.
 impl From<(usize, BlockifierTransactionExecutionError)> for ExecutionError {
     fn from(transaction_index_and_error: (usize, BlockifierTransactionExecutionError)) -> Self {
         let (transaction_index, error) = transaction_index_and_error;
-        Self::TransactionExecutionError { transaction_index, execution_error: error.to_string() }
+        let reason = error.to_string();
+        match error {
+            BlockifierTransactionExecutionError::ValidateTransactionError { .. }
+            | BlockifierTransactionExecutionError::PanicInValidate { .. }
+            | BlockifierTransactionExecutionError::InvalidValidateReturnData { .. }
+            | BlockifierTransactionExecutionError::TransactionPreValidationError(_) => {
+                Self::ValidationFailed { transaction_index, reason }
+            }
+            _ => Self::ExecutionFailed { transaction_index, reason },
+        }
     }
 }
 
@@ -800,12 +1298,14 @@ fn to_blockifier_tx(
     transaction_index: usize,
     charge_fee: bool,
     validate: bool,
+    sierra_gas_limit: Option<GasAmount>,
 ) -> ExecutionResult<BlockifierTransaction> {
     // TODO(yair): support only_query version bit (enable in the RPC v0.6 and use the correct
     // value).
     match tx {
         ExecutableTransactionInput::Invoke(invoke_tx, only_query) => {
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::Invoke(invoke_tx),
                 tx_hash,
@@ -818,7 +1318,8 @@ fn to_blockifier_tx(
         }
 
         ExecutableTransactionInput::DeployAccount(deploy_acc_tx, only_query) => {
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::DeployAccount(deploy_acc_tx),
                 tx_hash,
@@ -847,7 +1348,8 @@ fn to_blockifier_tx(
                 err,
             })?;
 
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::Declare(DeclareTransaction::V0(declare_tx)),
                 tx_hash,
@@ -874,7 +1376,8 @@ fn to_blockifier_tx(
                 tx: DeclareTransaction::V1(declare_tx.clone()),
                 err,
             })?;
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::Declare(DeclareTransaction::V1(declare_tx)),
                 tx_hash,
@@ -903,7 +1406,8 @@ fn to_blockifier_tx(
                 tx: DeclareTransaction::V2(declare_tx.clone()),
                 err,
             })?;
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::Declare(DeclareTransaction::V2(declare_tx)),
                 tx_hash,
@@ -932,7 +1436,8 @@ fn to_blockifier_tx(
                 tx: DeclareTransaction::V3(declare_tx.clone()),
                 err,
             })?;
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::Declare(DeclareTransaction::V3(declare_tx)),
                 tx_hash,
@@ -944,7 +1449,8 @@ fn to_blockifier_tx(
             .map_err(|err| ExecutionError::from((transaction_index, err)))
         }
         ExecutableTransactionInput::L1Handler(l1_handler_tx, paid_fee, only_query) => {
-            let execution_flags = ExecutionFlags { only_query, charge_fee, validate };
+            let execution_flags =
+                ExecutionFlags { only_query, charge_fee, validate, sierra_gas_limit };
             BlockifierTransaction::from_api(
                 Transaction::L1Handler(l1_handler_tx),
                 tx_hash,
@@ -959,6 +1465,10 @@ fn to_blockifier_tx(
 }
 
 /// Simulates a series of transactions and returns the transaction traces and the fee estimations.
+/// If `sierra_gas_limits` is given, it must have an entry for each transaction, and the i-th entry
+/// (if present) caps the Sierra gas available to the i-th transaction instead of its resource
+/// bounds.
+/// The transactions are executed on top of the given state overrides.
 // TODO(yair): Return structs instead of tuples.
 // TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
 // instead.
@@ -976,8 +1486,20 @@ pub fn simulate_transactions(
     charge_fee: bool,
     validate: bool,
     override_kzg_da_to_false: bool,
+    sierra_gas_limits: Option<Vec<Option<GasAmount>>>,
+    state_overrides: StateOverrides,
 ) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
-    let trace_constructors = txs.iter().map(get_trace_constructor).collect::<Vec<_>>();
+    verify_txs_count(txs.len(), execution_config)?;
+    if let Some(sierra_gas_limits) = &sierra_gas_limits {
+        if sierra_gas_limits.len() != txs.len() {
+            return Err(ExecutionError::SierraGasLimitsCountMismatch {
+                limits: sierra_gas_limits.len(),
+                transactions: txs.len(),
+            });
+        }
+    }
+    let trace_constructors =
+        txs.iter().map(|tx| get_trace_constructor(tx, TraceDetailLevel::Full)).collect::<Vec<_>>();
     let (execution_results, block_context) = execute_transactions(
         txs,
         tx_hashes,
@@ -990,6 +1512,9 @@ pub fn simulate_transactions(
         charge_fee,
         validate,
         override_kzg_da_to_false,
+        sierra_gas_limits,
+        state_overrides,
+        true,
     )?;
     execution_results
         .into_iter()
@@ -997,14 +1522,187 @@ pub fn simulate_transactions(
         .map(|(tx_execution_output, trace_constructor)| {
             let fee_estimation =
                 tx_execution_output_to_fee_estimation(&tx_execution_output, &block_context)?;
+            let sierra_gas_consumed = sierra_gas_consumed(&tx_execution_output.execution_info);
+            let revert_call_tree = revert_call_tree(
+                &tx_execution_output.execution_info,
+                tx_execution_output.reverted_execute_call_info.as_ref(),
+            );
             match trace_constructor(tx_execution_output.execution_info) {
                 Ok(transaction_trace) => Ok(TransactionSimulationOutput {
                     transaction_trace,
                     induced_state_diff: tx_execution_output.induced_state_diff,
                     fee_estimation,
+                    sierra_gas_consumed,
+                    revert_call_tree,
                 }),
                 Err(e) => Err(e),
             }
         })
         .collect()
 }
+
+/// Re-executes the transactions of a block that is in the storage, on top of the state right
+/// before the block. Useful for verifying the execution is deterministic and for debugging.
+// TODO(Dan, Yair): consider box large elements (because of BadDeclareTransaction) or use ID
+// instead.
+#[allow(clippy::result_large_err)]
+pub fn replay_block(
+    storage_reader: StorageReader,
+    block_number: BlockNumber,
+    chain_id: &ChainId,
+    execution_config: &ExecutionConfig,
+) -> ExecutionResult<Vec<TransactionExecutionOutput>> {
+    let storage_txn = storage_reader.begin_ro_txn()?;
+    let block_transactions = storage_txn
+        .get_block_transactions(block_number)?
+        .ok_or(ExecutionError::BlockNotFound { block_number })?;
+    let transaction_hashes = storage_txn
+        .get_block_transaction_hashes(block_number)?
+        .ok_or(ExecutionError::BlockNotFound { block_number })?;
+    let txs = block_transactions
+        .into_iter()
+        .map(|tx| stored_tx_to_executable_tx(tx, &storage_txn, block_number))
+        .collect::<ExecutionResult<Vec<_>>>()?;
+    drop(storage_txn);
+
+    let (execution_results, _block_context) = execute_transactions(
+        txs,
+        Some(transaction_hashes),
+        chain_id,
+        storage_reader,
+        None,
+        StateNumber::right_before_block(block_number),
+        block_number,
+        execution_config,
+        true,
+        true,
+        false,
+        None,
+        StateOverrides::default(),
+        false,
+    )?;
+    Ok(execution_results)
+}
+
+// Converts a transaction of the given block to an executable transaction. The classes declared in
+// the block are read from the state right after the block.
+#[allow(clippy::result_large_err)]
+fn stored_tx_to_executable_tx(
+    tx: Transaction,
+    storage_txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> ExecutionResult<ExecutableTransactionInput> {
+    let state_number_after_block = StateNumber::unchecked_right_after_block(block_number);
+    let get_deprecated_class = |class_hash: ClassHash| -> ExecutionResult<_> {
+        let deprecated_class = storage_txn
+            .get_state_reader()?
+            .get_deprecated_class_definition_at(state_number_after_block, &class_hash)?
+            .ok_or_else(|| StorageError::DBInconsistency {
+                msg: format!("Missing deprecated class definition of {class_hash}."),
+            })?;
+        let abi_length = calculate_deprecated_class_abi_length(&deprecated_class)?;
+        Ok((deprecated_class, abi_length))
+    };
+    let get_casm_and_class_lengths = |tx: &DeclareTransaction| -> ExecutionResult<_> {
+        let class_hash = tx.class_hash();
+        let casm = storage_txn
+            .get_casm(&class_hash)?
+            .ok_or_else(|| missing_compiled_class_error(class_hash))?;
+        let class = storage_txn
+            .get_state_reader()?
+            .get_class_definition_at(state_number_after_block, &class_hash)?
+            .ok_or_else(|| StorageError::DBInconsistency {
+                msg: format!("Missing class definition of {class_hash}."),
+            })?;
+        let sierra_version = SierraVersion::extract_from_program(&class.sierra_program)
+            .map_err(|err| ExecutionError::BadDeclareTransaction { tx: tx.clone(), err })?;
+        Ok((casm, class.sierra_program.len(), class.abi.len(), sierra_version))
+    };
+
+    match tx {
+        Transaction::Declare(DeclareTransaction::V0(tx)) => {
+            let (deprecated_class, abi_length) = get_deprecated_class(tx.class_hash)?;
+            Ok(ExecutableTransactionInput::DeclareV0(tx, deprecated_class, abi_length, false))
+        }
+        Transaction::Declare(DeclareTransaction::V1(tx)) => {
+            let (deprecated_class, abi_length) = get_deprecated_class(tx.class_hash)?;
+            Ok(ExecutableTransactionInput::DeclareV1(tx, deprecated_class, abi_length, false))
+        }
+        Transaction::Declare(tx @ (DeclareTransaction::V2(_) | DeclareTransaction::V3(_))) => {
+            let (casm, sierra_size, abi_size, sierra_version) = get_casm_and_class_lengths(&tx)?;
+            match tx {
+                DeclareTransaction::V2(tx) => Ok(ExecutableTransactionInput::DeclareV2(
+                    tx,
+                    casm,
+                    sierra_size,
+                    abi_size,
+                    false,
+                    sierra_version,
+                )),
+                DeclareTransaction::V3(tx) => Ok(ExecutableTransactionInput::DeclareV3(
+                    tx,
+                    casm,
+                    sierra_size,
+                    abi_size,
+                    false,
+                    sierra_version,
+                )),
+                DeclareTransaction::V0(_) | DeclareTransaction::V1(_) => {
+                    unreachable!("Declare V0 and V1 are handled above.")
+                }
+            }
+        }
+        Transaction::Deploy(_) => Err(ExecutionError::UnsupportedDeployTransaction),
+        Transaction::DeployAccount(tx) => Ok(ExecutableTransactionInput::DeployAccount(tx, false)),
+        Transaction::Invoke(tx) => Ok(ExecutableTransactionInput::Invoke(tx, false)),
+        // TODO(yair): Use the fee that was paid on L1 once it's stored.
+        Transaction::L1Handler(tx) => Ok(ExecutableTransactionInput::L1Handler(tx, Fee(1), false)),
+    }
+}
+
+/// Executes a series of transactions and returns the transaction traces, without computing the
+/// fee estimations. Intended for consumers that only need the traces, such as debuggers.
+#[allow(clippy::result_large_err)]
+#[allow(clippy::too_many_arguments)]
+pub fn trace_transactions(
+    txs: Vec<ExecutableTransactionInput>,
+    tx_hashes: Option<Vec<TransactionHash>>,
+    chain_id: &ChainId,
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    state_number: StateNumber,
+    block_context_block_number: BlockNumber,
+    execution_config: &ExecutionConfig,
+    charge_fee: bool,
+    validate: bool,
+    override_kzg_da_to_false: bool,
+) -> ExecutionResult<Vec<TransactionTracingOutput>> {
+    let trace_constructors =
+        txs.iter().map(|tx| get_trace_constructor(tx, TraceDetailLevel::Full)).collect::<Vec<_>>();
+    let (execution_results, _block_context) = execute_transactions(
+        txs,
+        tx_hashes,
+        chain_id,
+        storage_reader,
+        maybe_pending_data,
+        state_number,
+        block_context_block_number,
+        execution_config,
+        charge_fee,
+        validate,
+        override_kzg_da_to_false,
+        None,
+        StateOverrides::default(),
+        false,
+    )?;
+    execution_results
+        .into_iter()
+        .zip(trace_constructors)
+        .map(|(tx_execution_output, trace_constructor)| {
+            Ok(TransactionTracingOutput {
+                transaction_trace: trace_constructor(tx_execution_output.execution_info)?,
+                induced_state_diff: tx_execution_output.induced_state_diff,
+            })
+        })
+        .collect()
+}
@@ -9,7 +9,8 @@ use blockifier::execution::call_info::{
     Retdata as BlockifierRetdata,
 };
 use blockifier::execution::entry_point::CallType as BlockifierCallType;
-use blockifier::transaction::objects::TransactionExecutionInfo;
+use blockifier::execution::stack_trace::ErrorStackSegment;
+use blockifier::transaction::objects::{RevertError, TransactionExecutionInfo};
 use blockifier::utils::u64_from_usize;
 use cairo_vm::types::builtin_name::BuiltinName;
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
@@ -23,7 +24,14 @@ use papyrus_common::state::{
     StorageEntry,
 };
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{BlockTimestamp, FeeType, GasPrice, GasPricePerToken};
+use starknet_api::block::{
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockTimestamp,
+    FeeType,
+    GasPrice,
+    GasPricePerToken,
+};
 use starknet_api::contract_class::EntryPointType;
 use starknet_api::core::{
     ClassHash,
@@ -36,6 +44,7 @@ use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::execution_resources::{
     Builtin,
     ExecutionResources,
+    GasAmount,
     GasVector,
     GasVector as StarknetApiGasVector,
 };
@@ -57,6 +66,55 @@ pub struct TransactionSimulationOutput {
     pub induced_state_diff: ThinStateDiff,
     /// The details of the fees charged by the transaction.
     pub fee_estimation: FeeEstimation,
+    /// The Sierra gas consumed by the validation and execution of the transaction.
+    pub sierra_gas_consumed: GasAmount,
+    /// For a reverted transaction, the tree of calls its execution made up to the revert. None if
+    /// the transaction didn't revert or if none of its calls are known.
+    pub revert_call_tree: Option<RevertedCall>,
+}
+
+/// A call made by the execution of a reverted transaction, together with the calls it made.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RevertedCall {
+    /// The address of the contract in whose context the call ran.
+    pub contract_address: ContractAddress,
+    /// The hash of the class whose code the call ran. Differs from the class of the contract in a
+    /// library call.
+    pub class_hash: ClassHash,
+    /// None if the call isn't to an entry point, e.g. a call to a constructor.
+    pub entry_point_selector: Option<EntryPointSelector>,
+    /// Whether the call failed. A failed inner call doesn't fail its caller if the caller handles
+    /// the failure.
+    pub failed: bool,
+    /// The calls made by this call, in the order they were made, including calls that finished
+    /// before the revert.
+    pub inner_calls: Vec<RevertedCall>,
+}
+
+impl From<&CallInfo> for RevertedCall {
+    fn from(call_info: &CallInfo) -> Self {
+        let entry_point_selector = match call_info.call.entry_point_type {
+            EntryPointType::Constructor => None,
+            _ => Some(call_info.call.entry_point_selector),
+        };
+        Self {
+            contract_address: call_info.call.storage_address,
+            class_hash: call_info.call.class_hash.unwrap_or_default(),
+            entry_point_selector,
+            failed: call_info.execution.failed,
+            inner_calls: call_info.inner_calls.iter().map(RevertedCall::from).collect(),
+        }
+    }
+}
+
+/// The output of tracing a transaction. Same as [`TransactionSimulationOutput`] without the fee
+/// estimation.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TransactionTracingOutput {
+    /// The execution trace of the transaction.
+    pub transaction_trace: TransactionTrace,
+    /// The state diff induced by the transaction.
+    pub induced_state_diff: ThinStateDiff,
 }
 
 /// The execution trace of a transaction.
@@ -74,6 +132,41 @@ pub enum TransactionTrace {
     DeployAccount(DeployAccountTransactionTrace),
 }
 
+impl TransactionTrace {
+    // Removes the calls nested deeper than max_depth below the top-level invocations.
+    pub(crate) fn prune_calls(&mut self, max_depth: usize) {
+        let top_level_invocations: Vec<&mut FunctionInvocation> = match self {
+            TransactionTrace::L1Handler(trace) => vec![&mut trace.function_invocation],
+            TransactionTrace::Invoke(trace) => {
+                let execute_invocation = match &mut trace.execute_invocation {
+                    FunctionInvocationResult::Ok(invocation) => Some(invocation),
+                    FunctionInvocationResult::Err(_) => None,
+                };
+                trace
+                    .validate_invocation
+                    .iter_mut()
+                    .chain(execute_invocation)
+                    .chain(trace.fee_transfer_invocation.iter_mut())
+                    .collect()
+            }
+            TransactionTrace::Declare(trace) => trace
+                .validate_invocation
+                .iter_mut()
+                .chain(trace.fee_transfer_invocation.iter_mut())
+                .collect(),
+            TransactionTrace::DeployAccount(trace) => trace
+                .validate_invocation
+                .iter_mut()
+                .chain(Some(&mut trace.constructor_invocation))
+                .chain(trace.fee_transfer_invocation.iter_mut())
+                .collect(),
+        };
+        for invocation in top_level_invocations {
+            invocation.prune_calls(max_depth);
+        }
+    }
+}
+
 /// The execution trace of an Invoke transaction.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct InvokeTransactionTrace {
@@ -182,6 +275,52 @@ pub(crate) fn tx_execution_output_to_fee_estimation(
     })
 }
 
+// Returns the call tree of a reverted transaction's execution, or None if it didn't revert.
+// The tree is taken from the call info of the replayed execution when there is one. Otherwise, it
+// is built from the error stack, which only holds the calls that were active when the execution
+// failed.
+pub(crate) fn revert_call_tree(
+    execution_info: &TransactionExecutionInfo,
+    reverted_execute_call_info: Option<&CallInfo>,
+) -> Option<RevertedCall> {
+    let revert_error = execution_info.revert_error.as_ref()?;
+    if let Some(call_info) = reverted_execute_call_info {
+        return Some(RevertedCall::from(call_info));
+    }
+    // A revert after the execution finished (e.g. due to insufficient fee) has no error stack.
+    let RevertError::Execution(error_stack) = revert_error else {
+        return None;
+    };
+    error_stack
+        .stack
+        .iter()
+        .rev()
+        .filter_map(|segment| match segment {
+            ErrorStackSegment::EntryPoint(frame) => Some(frame),
+            _ => None,
+        })
+        .fold(None, |inner_call, frame| {
+            Some(RevertedCall {
+                contract_address: frame.storage_address,
+                class_hash: frame.class_hash,
+                entry_point_selector: frame.selector,
+                failed: true,
+                inner_calls: inner_call.into_iter().collect(),
+            })
+        })
+}
+
+// Returns the Sierra gas consumed by the validate and execute calls of the transaction.
+pub(crate) fn sierra_gas_consumed(execution_info: &TransactionExecutionInfo) -> GasAmount {
+    GasAmount(
+        [&execution_info.validate_call_info, &execution_info.execute_call_info]
+            .into_iter()
+            .flatten()
+            .map(|call_info| call_info.execution.gas_consumed)
+            .sum(),
+    )
+}
+
 /// The execution trace of a Declare transaction.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct DeclareTransactionTrace {
@@ -312,6 +451,20 @@ pub struct FunctionInvocation {
     pub execution_resources: ExecutionResources,
 }
 
+impl FunctionInvocation {
+    // Removes the calls nested deeper than max_depth below this invocation.
+    fn prune_calls(&mut self, max_depth: usize) {
+        match max_depth.checked_sub(1) {
+            None => self.calls.clear(),
+            Some(inner_max_depth) => {
+                for call in &mut self.calls {
+                    call.prune_calls(inner_max_depth);
+                }
+            }
+        }
+    }
+}
+
 impl TryFrom<(CallInfo, GasVector)> for FunctionInvocation {
     type Error = ExecutionError;
     fn try_from((call_info, gas_vector): (CallInfo, GasVector)) -> ExecutionResult<Self> {
@@ -515,6 +668,65 @@ pub struct PendingData {
     pub classes: PendingClasses,
 }
 
+impl PendingData {
+    /// Creates pending data from a block, as if the block were still pending. This is useful for
+    /// executing on top of a block that isn't the latest one, e.g. simulating the next block.
+    ///
+    /// The definitions of the classes declared in the block aren't part of the state diff, so
+    /// `classes` is left empty.
+    pub fn from_block_header_and_diff(header: BlockHeader, thin_state_diff: ThinStateDiff) -> Self {
+        let BlockHeaderWithoutHash {
+            l1_gas_price,
+            l1_data_gas_price,
+            l2_gas_price,
+            sequencer,
+            timestamp,
+            l1_da_mode,
+            ..
+        } = header.block_header_without_hash;
+        Self {
+            storage_diffs: thin_state_diff
+                .storage_diffs
+                .into_iter()
+                .map(|(address, storage_entries)| {
+                    let storage_entries = storage_entries
+                        .into_iter()
+                        .map(|(key, value)| StorageEntry { key, value })
+                        .collect();
+                    (address, storage_entries)
+                })
+                .collect(),
+            deployed_contracts: thin_state_diff
+                .deployed_contracts
+                .into_iter()
+                .map(|(address, class_hash)| DeployedContract { address, class_hash })
+                .collect(),
+            declared_classes: thin_state_diff
+                .declared_classes
+                .into_iter()
+                .map(|(class_hash, compiled_class_hash)| DeclaredClassHashEntry {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            old_declared_contracts: thin_state_diff.deprecated_declared_classes,
+            nonces: thin_state_diff.nonces,
+            replaced_classes: thin_state_diff
+                .replaced_classes
+                .into_iter()
+                .map(|(address, class_hash)| ReplacedClass { address, class_hash })
+                .collect(),
+            timestamp,
+            l1_gas_price,
+            l1_data_gas_price,
+            l2_gas_price,
+            l1_da_mode,
+            sequencer,
+            classes: PendingClasses::default(),
+        }
+    }
+}
+
 /// The unit of the fee.
 #[derive(
     Debug, Default, Clone, Copy, Eq, Hash, PartialEq, Deserialize, Serialize, PartialOrd, Ord,
@@ -1,15 +1,23 @@
+//! A blockifier state reader on top of the storage.
 #[cfg(test)]
 #[path = "state_reader_test.rs"]
 mod state_reader_test;
 
 use std::cell::Cell;
+use std::collections::HashMap;
+#[cfg(feature = "cairo_native")]
+use std::sync::LazyLock;
 
 use blockifier::execution::contract_class::{
     CompiledClassV0,
     CompiledClassV1,
     RunnableCompiledClass,
 };
+#[cfg(feature = "cairo_native")]
+use blockifier::execution::native::contract_class::NativeCompiledClassV1;
 use blockifier::state::errors::StateError;
+#[cfg(feature = "cairo_native")]
+use blockifier::state::global_cache::{CachedCairoNative, GlobalContractCache};
 use blockifier::state::state_api::{StateReader as BlockifierStateReader, StateResult};
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
 use papyrus_common::state::DeclaredClassHashEntry;
@@ -17,21 +25,57 @@ use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use starknet_api::contract_class::SierraVersion;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+#[cfg(feature = "cairo_native")]
+use starknet_api::state::SierraContractClass;
 use starknet_api::state::{StateNumber, StorageKey};
+#[cfg(feature = "cairo_native")]
+use starknet_sierra_compile::SierraToNativeCompiler;
+#[cfg(feature = "cairo_native")]
+use starknet_sierra_compile::command_line_compiler::CommandLineCompiler;
+#[cfg(feature = "cairo_native")]
+use starknet_sierra_compile::config::SierraToCasmCompilationConfig;
+#[cfg(feature = "cairo_native")]
+use starknet_sierra_compile::utils::into_contract_class_for_compilation;
 use starknet_types_core::felt::Felt;
+#[cfg(feature = "cairo_native")]
+use tracing::debug;
 
-use crate::execution_utils;
 use crate::execution_utils::{get_contract_class, ExecutionUtilsError};
 use crate::objects::PendingData;
+use crate::{execution_utils, ExecutionBackend};
+
+// The number of classes compiled to native that are kept in memory.
+#[cfg(feature = "cairo_native")]
+const NATIVE_CLASS_CACHE_SIZE: usize = 400;
+
+// The results of compiling classes to native, shared by all the readers since the compilation is
+// expensive. A class hash determines the class, so the results are valid at any state.
+#[cfg(feature = "cairo_native")]
+static NATIVE_CLASS_CACHE: LazyLock<GlobalContractCache<CachedCairoNative>> =
+    LazyLock::new(|| GlobalContractCache::new(NATIVE_CLASS_CACHE_SIZE));
 
 /// A view into the state at a specific state number.
 pub struct ExecutionStateReader {
+    /// The reader of the storage the state is read from.
     pub storage_reader: StorageReader,
+    /// The state number the state is read at.
     pub state_number: StateNumber,
+    /// Pending data applied on top of the state, if any.
     pub maybe_pending_data: Option<PendingData>,
-    // We want to return a custom error when missing a compiled class, but we need to return
-    // Blockifier's error, so we store the missing class's hash in case of error.
+    /// We want to return a custom error when missing a compiled class, but we need to return
+    /// Blockifier's error, so we store the missing class's hash in case of error.
     pub missing_compiled_class: Cell<Option<ClassHash>>,
+    /// For the same reason, we store the hash of a class that failed to compile to native when
+    /// the backend doesn't allow falling back to the VM.
+    pub failed_native_compilation: Cell<Option<ClassHash>>,
+    /// The engine used to execute Cairo 1 contracts.
+    pub backend: ExecutionBackend,
+    /// Storage values that are read instead of the ones in the state.
+    pub storage_overrides: HashMap<(ContractAddress, StorageKey), Felt>,
+    /// Class hashes of contracts that are used instead of the ones in the state.
+    pub class_hash_overrides: HashMap<ContractAddress, ClassHash>,
+    /// Classes that are treated as declared, in addition to the ones in the state.
+    pub declared_class_overrides: HashMap<ClassHash, RunnableCompiledClass>,
 }
 
 impl BlockifierStateReader for ExecutionStateReader {
@@ -40,6 +84,9 @@ impl BlockifierStateReader for ExecutionStateReader {
         contract_address: ContractAddress,
         key: StorageKey,
     ) -> StateResult<Felt> {
+        if let Some(value) = self.storage_overrides.get(&(contract_address, key)) {
+            return Ok(*value);
+        }
         execution_utils::get_storage_at(
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             self.state_number,
@@ -64,6 +111,9 @@ impl BlockifierStateReader for ExecutionStateReader {
 
     // Returns the default value if the contract address is not found.
     fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        if let Some(class_hash) = self.class_hash_overrides.get(&contract_address) {
+            return Ok(*class_hash);
+        }
         Ok(execution_utils::get_class_hash_at(
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             self.state_number,
@@ -77,6 +127,76 @@ impl BlockifierStateReader for ExecutionStateReader {
     }
 
     fn get_compiled_class(&self, class_hash: ClassHash) -> StateResult<RunnableCompiledClass> {
+        if let Some(compiled_class) = self.declared_class_overrides.get(&class_hash) {
+            return Ok(compiled_class.clone());
+        }
+        // Loaded classes aren't cached here since the cached state on top of the reader already
+        // caches them across all the calls and transactions that share it.
+        self.load_compiled_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        if let Some(pending_data) = &self.maybe_pending_data {
+            for DeclaredClassHashEntry { class_hash: other_class_hash, compiled_class_hash } in
+                &pending_data.declared_classes
+            {
+                if class_hash == *other_class_hash {
+                    return Ok(*compiled_class_hash);
+                }
+            }
+        }
+        let block_number = self
+            .storage_reader
+            .begin_ro_txn()
+            .map_err(storage_err_to_state_err)?
+            .get_state_reader()
+            .map_err(storage_err_to_state_err)?
+            .get_class_definition_block_number(&class_hash)
+            .map_err(storage_err_to_state_err)?
+            .ok_or(StateError::UndeclaredClassHash(class_hash))?;
+
+        let state_diff = self
+            .storage_reader
+            .begin_ro_txn()
+            .map_err(storage_err_to_state_err)?
+            .get_state_diff(block_number)
+            .map_err(storage_err_to_state_err)?
+            .ok_or(StateError::StateReadError(format!(
+                "Inner storage error. Missing state diff at block {block_number}."
+            )))?;
+
+        let compiled_class_hash = state_diff.declared_classes.get(&class_hash).ok_or(
+            StateError::StateReadError(format!(
+                "Inner storage error. Missing class declaration at block {block_number}, class \
+                 {class_hash}."
+            )),
+        )?;
+
+        Ok(*compiled_class_hash)
+    }
+}
+
+impl ExecutionStateReader {
+    /// Forgets the errors stored by previous executions on this reader.
+    pub fn clear_stored_errors(&self) {
+        self.missing_compiled_class.set(None);
+        self.failed_native_compilation.set(None);
+    }
+
+    // Loads the class for the configured backend.
+    fn load_compiled_class(&self, class_hash: ClassHash) -> StateResult<RunnableCompiledClass> {
+        let compiled_class = self.get_vm_compiled_class(class_hash)?;
+        #[cfg(feature = "cairo_native")]
+        if self.backend != ExecutionBackend::Vm {
+            if let RunnableCompiledClass::V1(casm) = compiled_class {
+                return self.compile_to_native(class_hash, casm);
+            }
+        }
+        Ok(compiled_class)
+    }
+
+    // Returns the class compiled for execution on the VM.
+    fn get_vm_compiled_class(&self, class_hash: ClassHash) -> StateResult<RunnableCompiledClass> {
         if let Some(pending_classes) =
             self.maybe_pending_data.as_ref().map(|pending_data| &pending_data.classes)
         {
@@ -121,44 +241,71 @@ impl BlockifierStateReader for ExecutionStateReader {
         }
     }
 
-    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
-        if let Some(pending_data) = &self.maybe_pending_data {
-            for DeclaredClassHashEntry { class_hash: other_class_hash, compiled_class_hash } in
-                &pending_data.declared_classes
+    // Compiles the given Cairo 1 class to native, or takes it from the native class cache. If the
+    // class can't be compiled and the backend allows it, returns the class for the VM instead.
+    #[cfg(feature = "cairo_native")]
+    fn compile_to_native(
+        &self,
+        class_hash: ClassHash,
+        casm: CompiledClassV1,
+    ) -> StateResult<RunnableCompiledClass> {
+        let cached_native = match NATIVE_CLASS_CACHE.get(&class_hash) {
+            Some(cached_native) => cached_native,
+            None => {
+                let sierra = self.get_sierra(class_hash)?;
+                let compiler = CommandLineCompiler::new(SierraToCasmCompilationConfig::default());
+                let cached_native = match compiler
+                    .compile_to_native(into_contract_class_for_compilation(&sierra))
+                {
+                    Ok(executor) => CachedCairoNative::Compiled(NativeCompiledClassV1::new(
+                        executor,
+                        casm.clone(),
+                    )),
+                    Err(err) => {
+                        debug!("Failed to compile class {class_hash} to native: {err}");
+                        CachedCairoNative::CompilationFailed
+                    }
+                };
+                NATIVE_CLASS_CACHE.set(class_hash, cached_native.clone());
+                cached_native
+            }
+        };
+        match cached_native {
+            CachedCairoNative::Compiled(native) => Ok(RunnableCompiledClass::V1Native(native)),
+            CachedCairoNative::CompilationFailed
+                if self.backend == ExecutionBackend::NativeWithVmFallback =>
             {
-                if class_hash == *other_class_hash {
-                    return Ok(*compiled_class_hash);
-                }
+                debug!(
+                    "Executing class {class_hash} on the VM since it can't be compiled to native."
+                );
+                Ok(RunnableCompiledClass::V1(casm))
+            }
+            CachedCairoNative::CompilationFailed => {
+                self.failed_native_compilation.set(Some(class_hash));
+                Err(StateError::StateReadError(format!(
+                    "Failed to compile class {class_hash} to native"
+                )))
             }
         }
-        let block_number = self
-            .storage_reader
+    }
+
+    #[cfg(feature = "cairo_native")]
+    fn get_sierra(&self, class_hash: ClassHash) -> StateResult<SierraContractClass> {
+        if let Some(ApiContractClass::ContractClass(sierra)) = self
+            .maybe_pending_data
+            .as_ref()
+            .and_then(|pending_data| pending_data.classes.get_class(class_hash))
+        {
+            return Ok(sierra);
+        }
+        self.storage_reader
             .begin_ro_txn()
             .map_err(storage_err_to_state_err)?
             .get_state_reader()
             .map_err(storage_err_to_state_err)?
-            .get_class_definition_block_number(&class_hash)
-            .map_err(storage_err_to_state_err)?
-            .ok_or(StateError::UndeclaredClassHash(class_hash))?;
-
-        let state_diff = self
-            .storage_reader
-            .begin_ro_txn()
-            .map_err(storage_err_to_state_err)?
-            .get_state_diff(block_number)
+            .get_class_definition_at(self.state_number, &class_hash)
             .map_err(storage_err_to_state_err)?
-            .ok_or(StateError::StateReadError(format!(
-                "Inner storage error. Missing state diff at block {block_number}."
-            )))?;
-
-        let compiled_class_hash = state_diff.declared_classes.get(&class_hash).ok_or(
-            StateError::StateReadError(format!(
-                "Inner storage error. Missing class declaration at block {block_number}, class \
-                 {class_hash}."
-            )),
-        )?;
-
-        Ok(*compiled_class_hash)
+            .ok_or(StateError::UndeclaredClassHash(class_hash))
     }
 }
 
@@ -1,4 +1,5 @@
 use std::cell::Cell;
+use std::collections::HashMap;
 
 use assert_matches::assert_matches;
 use blockifier::execution::contract_class::{
@@ -6,6 +7,7 @@ use blockifier::execution::contract_class::{
     CompiledClassV1,
     RunnableCompiledClass,
 };
+use blockifier::state::cached_state::TransactionalState;
 use blockifier::state::errors::StateError;
 use blockifier::state::state_api::StateReader;
 use cairo_lang_utils::bigint::BigUintAsHex;
@@ -34,6 +36,7 @@ use starknet_types_core::felt::Felt;
 use crate::objects::PendingData;
 use crate::state_reader::ExecutionStateReader;
 use crate::test_utils::{get_test_casm, get_test_deprecated_contract_class};
+use crate::{create_cached_state, ExecutionBackend};
 
 const CONTRACT_ADDRESS: &str = "0x2";
 const DEPRECATED_CONTRACT_ADDRESS: &str = "0x1";
@@ -162,6 +165,11 @@ fn read_state() {
         state_number: state_number0,
         maybe_pending_data: None,
         missing_compiled_class: Cell::new(None),
+        failed_native_compilation: Cell::new(None),
+        backend: ExecutionBackend::Vm,
+        storage_overrides: HashMap::new(),
+        class_hash_overrides: HashMap::new(),
+        declared_class_overrides: HashMap::new(),
     };
     let storage_after_block_0 = state_reader0.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_0, Felt::default());
@@ -182,6 +190,11 @@ fn read_state() {
         state_number: state_number1,
         maybe_pending_data: None,
         missing_compiled_class: Cell::new(None),
+        failed_native_compilation: Cell::new(None),
+        backend: ExecutionBackend::Vm,
+        storage_overrides: HashMap::new(),
+        class_hash_overrides: HashMap::new(),
+        declared_class_overrides: HashMap::new(),
     };
     let storage_after_block_1 = state_reader1.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_1, storage_value0);
@@ -204,6 +217,11 @@ fn read_state() {
         state_number: state_number2,
         maybe_pending_data: None,
         missing_compiled_class: Cell::new(None),
+        failed_native_compilation: Cell::new(None),
+        backend: ExecutionBackend::Vm,
+        storage_overrides: HashMap::new(),
+        class_hash_overrides: HashMap::new(),
+        declared_class_overrides: HashMap::new(),
     };
     let nonce_after_block_2 = state_reader2.get_nonce_at(address0).unwrap();
     assert_eq!(nonce_after_block_2, nonce0);
@@ -261,6 +279,45 @@ fn read_state() {
     assert_eq!(state_reader2.get_class_hash_at(address2).unwrap(), class_hash3);
 }
 
+#[test]
+fn compiled_class_is_loaded_once_across_transactions() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = ClassHash(1u128.into());
+    let class = get_test_deprecated_contract_class();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff { deprecated_declared_classes: vec![class_hash], ..Default::default() },
+        )
+        .unwrap()
+        .append_classes(BlockNumber(0), &[], &[(class_hash, &class)])
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut cached_state = create_cached_state(
+        storage_reader,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        None,
+        ExecutionBackend::Vm,
+    );
+    let expected_class = RunnableCompiledClass::V0(CompiledClassV0::try_from(class).unwrap());
+    // Each transaction is executed on its own transactional state. Loading the class in a
+    // transaction that is aborted should still cache it for the following transactions.
+    let transactional_state = TransactionalState::create_transactional(&mut cached_state);
+    assert_eq!(transactional_state.get_compiled_class(class_hash).unwrap(), expected_class);
+    transactional_state.abort();
+
+    // Replace the storage with an empty one. The class should still be returned, which means it's
+    // not read from the storage again.
+    let ((empty_storage_reader, _), _empty_temp_dir) = get_test_storage();
+    cached_state.state.storage_reader = empty_storage_reader;
+    let transactional_state = TransactionalState::create_transactional(&mut cached_state);
+    assert_eq!(transactional_state.get_compiled_class(class_hash).unwrap(), expected_class);
+}
+
 // Make sure we have the arbitrary precision feature of serde_json.
 #[test]
 fn serialization_precision() {
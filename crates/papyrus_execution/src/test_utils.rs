@@ -47,9 +47,16 @@ use starknet_api::{calldata, class_hash, contract_address, felt, nonce};
 use starknet_types_core::felt::Felt;
 
 use crate::execution_utils::selector_from_name;
-use crate::objects::{PendingData, TransactionSimulationOutput};
+use crate::objects::{PendingData, TransactionSimulationOutput, TransactionTracingOutput};
 use crate::testing_instances::get_test_execution_config;
-use crate::{simulate_transactions, ExecutableTransactionInput, OnlyQuery, SierraSize};
+use crate::{
+    simulate_transactions,
+    trace_transactions,
+    ExecutableTransactionInput,
+    OnlyQuery,
+    SierraSize,
+    StateOverrides,
+};
 
 lazy_static! {
     pub static ref CHAIN_ID: ChainId = ChainId::Other(String::from("TEST_CHAIN_ID"));
@@ -88,6 +95,10 @@ pub fn get_test_deprecated_contract_class() -> DeprecatedContractClass {
 pub fn get_test_casm() -> CasmContractClass {
     get_test_instance("casm.json")
 }
+// A Cairo 1 account without validations, taken from the feature contracts of Blockifier.
+pub fn get_test_cairo1_account_casm() -> CasmContractClass {
+    get_test_instance("cairo1_account_casm.json")
+}
 pub fn get_test_erc20_fee_contract_class() -> DeprecatedContractClass {
     get_test_instance("erc20_fee_contract_class.json")
 }
@@ -96,7 +107,16 @@ pub fn get_test_account_class() -> DeprecatedContractClass {
     get_test_instance("account_class.json")
 }
 
-pub fn prepare_storage(mut storage_writer: StorageWriter) {
+pub fn prepare_storage(storage_writer: StorageWriter) {
+    prepare_storage_with_block_1(storage_writer, BlockBody::default(), ThinStateDiff::default());
+}
+
+// Same as prepare_storage, but with the given body and state diff for block 1.
+pub fn prepare_storage_with_block_1(
+    mut storage_writer: StorageWriter,
+    block_1_body: BlockBody,
+    block_1_state_diff: ThinStateDiff,
+) {
     let class_hash0 = class_hash!("0x2");
     let class_hash1 = class_hash!("0x1");
 
@@ -189,9 +209,9 @@ pub fn prepare_storage(mut storage_writer: StorageWriter) {
             },
         )
         .unwrap()
-        .append_body(BlockNumber(1), BlockBody::default())
+        .append_body(BlockNumber(1), block_1_body)
         .unwrap()
-        .append_state_diff(BlockNumber(1), ThinStateDiff::default())
+        .append_state_diff(BlockNumber(1), block_1_state_diff)
         .unwrap()
         .append_classes(BlockNumber(1), &[], &[])
         .unwrap()
@@ -222,6 +242,34 @@ pub fn execute_simulate_transactions(
         validate,
         // TODO: Consider testing without overriding DA (It's already tested in the RPC)
         true,
+        None,
+        StateOverrides::default(),
+    )
+    .unwrap()
+}
+
+pub fn execute_trace_transactions(
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    txs: Vec<ExecutableTransactionInput>,
+    tx_hashes: Option<Vec<TransactionHash>>,
+    charge_fee: bool,
+    validate: bool,
+) -> Vec<TransactionTracingOutput> {
+    let chain_id = ChainId::Other(CHAIN_ID.to_string());
+
+    trace_transactions(
+        txs,
+        tx_hashes,
+        &chain_id,
+        storage_reader,
+        maybe_pending_data,
+        StateNumber::unchecked_right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &get_test_execution_config(),
+        charge_fee,
+        validate,
+        true,
     )
     .unwrap()
 }
@@ -30,7 +30,7 @@ use crate::objects::{
     RevertReason,
     TransactionTrace,
 };
-use crate::ExecutionConfig;
+use crate::{ExecutionBackend, ExecutionConfig};
 
 /// Creates ExecutionConfig for tests.
 pub fn get_test_execution_config() -> ExecutionConfig {
@@ -38,6 +38,8 @@ pub fn get_test_execution_config() -> ExecutionConfig {
         strk_fee_contract_address: contract_address!("0x1001"),
         eth_fee_contract_address: contract_address!("0x1001"),
         default_initial_gas_cost: 10_u64.pow(10),
+        backend: ExecutionBackend::Vm,
+        max_txs_per_request: 100,
     }
 }
 
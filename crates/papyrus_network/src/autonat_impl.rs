@@ -0,0 +1,51 @@
+#[cfg(test)]
+#[path = "autonat_impl_test.rs"]
+mod autonat_impl_test;
+
+use libp2p::{autonat, Multiaddr};
+
+use crate::mixed_behaviour;
+
+/// Whether other peers can dial this node, as determined by AutoNAT probes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum ReachabilityStatus {
+    /// Not enough peers were asked to dial back yet, or AutoNAT is disabled.
+    #[default]
+    Unknown,
+    /// Peers managed to dial this node on the given address.
+    Public(Multiaddr),
+    /// Peers failed to dial this node. This usually means it's behind a NAT or a firewall.
+    Private,
+}
+
+impl From<autonat::NatStatus> for ReachabilityStatus {
+    fn from(nat_status: autonat::NatStatus) -> Self {
+        match nat_status {
+            autonat::NatStatus::Public(address) => Self::Public(address),
+            autonat::NatStatus::Private => Self::Private,
+            autonat::NatStatus::Unknown => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExternalEvent {
+    ReachabilityStatusChanged(ReachabilityStatus),
+}
+
+impl From<autonat::Event> for mixed_behaviour::Event {
+    fn from(event: autonat::Event) -> Self {
+        match event {
+            autonat::Event::StatusChanged { new, .. } => {
+                mixed_behaviour::Event::ExternalEvent(mixed_behaviour::ExternalEvent::Autonat(
+                    ExternalEvent::ReachabilityStatusChanged(new.into()),
+                ))
+            }
+            autonat::Event::InboundProbe(_) | autonat::Event::OutboundProbe(_) => {
+                mixed_behaviour::Event::ToOtherBehaviourEvent(
+                    mixed_behaviour::ToOtherBehaviourEvent::NoOp,
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use libp2p::swarm::SwarmEvent;
+use libp2p::{autonat, Swarm};
+use libp2p_swarm_test::SwarmExt;
+
+use super::{ExternalEvent, ReachabilityStatus};
+use crate::mixed_behaviour;
+
+fn autonat_config() -> autonat::Config {
+    autonat::Config {
+        boot_delay: Duration::ZERO,
+        retry_interval: Duration::from_millis(100),
+        throttle_server_period: Duration::ZERO,
+        // The test transport uses memory and localhost addresses.
+        only_global_ips: false,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn reachable_node_is_reported_as_public() {
+    let mut server = Swarm::new_ephemeral(|keypair| {
+        autonat::Behaviour::new(keypair.public().to_peer_id(), autonat_config())
+    });
+    let mut client = Swarm::new_ephemeral(|keypair| {
+        autonat::Behaviour::new(keypair.public().to_peer_id(), autonat_config())
+    });
+    server.listen().with_memory_addr_external().await;
+    client.listen().with_memory_addr_external().await;
+
+    let server_peer_id = *server.local_peer_id();
+    let server_address = server.external_addresses().next().unwrap().clone();
+    client.behaviour_mut().add_server(server_peer_id, Some(server_address));
+    client.connect(&mut server).await;
+    tokio::spawn(server.loop_on_next());
+
+    let event: mixed_behaviour::Event = client
+        .wait(|event| match event {
+            SwarmEvent::Behaviour(event @ autonat::Event::StatusChanged { .. }) => Some(event),
+            _ => None,
+        })
+        .await
+        .into();
+
+    assert_matches!(
+        event,
+        mixed_behaviour::Event::ExternalEvent(mixed_behaviour::ExternalEvent::Autonat(
+            ExternalEvent::ReachabilityStatusChanged(ReachabilityStatus::Public(_))
+        ))
+    );
+}
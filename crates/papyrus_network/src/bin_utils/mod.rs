@@ -3,13 +3,16 @@ use std::time::Duration;
 
 use libp2p::identity::Keypair;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{noise, yamux, Multiaddr, Swarm, SwarmBuilder};
+use libp2p::{noise, tls, yamux, Multiaddr, Swarm, SwarmBuilder};
 use tracing::debug;
 
+use crate::security::{SecurityConfig, NOISE, TLS};
+
 pub fn build_swarm<Behaviour: NetworkBehaviour>(
     listen_addresses: Vec<String>,
     idle_connection_timeout: Duration,
     secret_key: Option<Vec<u8>>,
+    security_config: &SecurityConfig,
     behaviour: impl FnOnce(Keypair) -> Behaviour,
 ) -> Swarm<Behaviour>
 where
@@ -26,18 +29,33 @@ where
         }
         None => Keypair::generate_ed25519(),
     };
-    let mut swarm = SwarmBuilder::with_existing_identity(key_pair)
-        .with_tokio()
-        .with_tcp(Default::default(), noise::Config::new, yamux::Config::default)
-        .expect("Error building TCP transport")
-        .with_dns()
-        .expect("Error building DNS transport")
-        // TODO: quic transpot does not work (failure appears in the command line when running in debug mode)
-        // .with_quic()
-        .with_behaviour(|key| behaviour(key.clone()))
-        .expect("Error while building the swarm")
-        .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(idle_connection_timeout))
-        .build();
+    // The swarm builder's type depends on the security upgrade, so each accepted combination of
+    // security protocols is built separately.
+    macro_rules! build_swarm_with_security_upgrade {
+        ($security_upgrade:expr) => {
+            SwarmBuilder::with_existing_identity(key_pair)
+                .with_tokio()
+                .with_tcp(Default::default(), $security_upgrade, yamux::Config::default)
+                .expect("Error building TCP transport")
+                .with_dns()
+                .expect("Error building DNS transport")
+                // TODO: quic transpot does not work (failure appears in the command line when running in debug mode)
+                // .with_quic()
+                .with_behaviour(|key| behaviour(key.clone()))
+                .expect("Error while building the swarm")
+                .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(idle_connection_timeout))
+                .build()
+        };
+    }
+    let mut swarm = match security_config.security_protocols().as_slice() {
+        [NOISE] => build_swarm_with_security_upgrade!(noise::Config::new),
+        [TLS] => build_swarm_with_security_upgrade!(tls::Config::new),
+        [NOISE, TLS] => build_swarm_with_security_upgrade!((noise::Config::new, tls::Config::new)),
+        [TLS, NOISE] => build_swarm_with_security_upgrade!((tls::Config::new, noise::Config::new)),
+        security_protocols => {
+            panic!("Unsupported security protocols {security_protocols:?}")
+        }
+    };
     for listen_address in listen_addresses {
         swarm
             .listen_on(listen_address.clone())
@@ -1,12 +1,15 @@
 // TODO(shahak): add flow test
 
+use std::collections::HashSet;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
 use assert_matches::assert_matches;
 use futures::{FutureExt, Stream, StreamExt};
+use libp2p::core::multiaddr::Protocol;
 use libp2p::core::{ConnectedPoint, Endpoint};
+use libp2p::identity::Keypair;
 use libp2p::swarm::behaviour::ConnectionEstablished;
 use libp2p::swarm::{
     ConnectionClosed,
@@ -18,10 +21,17 @@ use libp2p::swarm::{
     ToSwarm,
 };
 use libp2p::{Multiaddr, PeerId};
+use starknet_api::core::ChainId;
 use tokio::time::timeout;
+use validator::Validate;
 use void::Void;
 
+use super::kad_impl::{persist_routing_table, routing_table_peers};
 use super::{Behaviour, DiscoveryConfig, RetryConfig, ToOtherBehaviourEvent};
+use crate::gossipsub_impl::GossipsubValidationMode;
+use crate::mixed_behaviour::MixedBehaviour;
+use crate::peer_manager::PeerManagerConfig;
+use crate::sqmr;
 
 const TIMEOUT: Duration = Duration::from_secs(1);
 const BOOTSTRAP_DIAL_SLEEP_MILLIS: u64 = 1000; // 1 second
@@ -34,6 +44,8 @@ const CONFIG: DiscoveryConfig = DiscoveryConfig {
         factor: 1,
     },
     heartbeat_interval: Duration::ZERO,
+    routing_table_path: None,
+    max_concurrent_dials: 10,
 };
 
 impl Unpin for Behaviour {}
@@ -285,3 +297,134 @@ async fn discovery_performs_queries_even_if_not_connected_to_bootstrap_peer() {
         ToSwarm::GenerateEvent(ToOtherBehaviourEvent::RequestKadQuery(_peer_id))
     );
 }
+
+fn create_mixed_behaviour(discovery_config: DiscoveryConfig) -> MixedBehaviour {
+    MixedBehaviour::new(
+        Keypair::generate_ed25519(),
+        None,
+        sqmr::Config::default(),
+        ChainId::Mainnet,
+        None,
+        discovery_config,
+        PeerManagerConfig::default(),
+        GossipsubValidationMode::default(),
+        false,
+    )
+}
+
+#[test]
+fn seeded_routing_table_peers_are_known_without_discovery() {
+    const NUM_PEERS: u64 = 3;
+    let routing_table_file = tempfile::NamedTempFile::new().unwrap();
+
+    let mut persisting_behaviour = create_mixed_behaviour(DiscoveryConfig::default());
+    let mut persisted_peers = (0..NUM_PEERS)
+        .map(|i| (PeerId::random(), vec![Multiaddr::empty().with(Protocol::Memory(i))]))
+        .collect::<Vec<_>>();
+    for (peer_id, addresses) in &persisted_peers {
+        for address in addresses {
+            persisting_behaviour.kademlia.add_address(peer_id, address.clone());
+        }
+    }
+    persist_routing_table(&mut persisting_behaviour.kademlia, routing_table_file.path());
+
+    // No discovery query is ever polled here, so every known peer comes from the seeded file.
+    let mut seeded_behaviour = create_mixed_behaviour(DiscoveryConfig {
+        routing_table_path: Some(routing_table_file.path().to_path_buf()),
+        ..Default::default()
+    });
+    let mut known_peers = routing_table_peers(&mut seeded_behaviour.kademlia);
+
+    persisted_peers.sort_by_key(|(peer_id, _)| *peer_id);
+    known_peers.sort_by_key(|(peer_id, _)| *peer_id);
+    assert_eq!(known_peers, persisted_peers);
+}
+
+#[test]
+fn missing_routing_table_file_leaves_routing_table_empty() {
+    let routing_table_dir = tempfile::tempdir().unwrap();
+    let mut behaviour = create_mixed_behaviour(DiscoveryConfig {
+        routing_table_path: Some(routing_table_dir.path().join("routing_table.json")),
+        ..Default::default()
+    });
+    assert!(routing_table_peers(&mut behaviour.kademlia).is_empty());
+}
+
+#[tokio::test]
+async fn discovery_limits_concurrent_dials() {
+    const MAX_CONCURRENT_DIALS: usize = 3;
+    const NUM_PEERS: u64 = 10;
+    let mut config = CONFIG;
+    config.max_concurrent_dials = MAX_CONCURRENT_DIALS;
+    // Avoid query events other than the initial one.
+    config.heartbeat_interval = Duration::from_secs(3600);
+
+    let bootstrap_peer_id = PeerId::random();
+    let mut behaviour = Behaviour::new(config, bootstrap_peer_id, Multiaddr::empty());
+    let peers = (0..NUM_PEERS)
+        .map(|i| (PeerId::random(), vec![Multiaddr::empty().with(Protocol::Memory(i))]))
+        .collect::<Vec<_>>();
+    behaviour.queue_dials(peers.clone());
+
+    let mut in_flight_dials = HashSet::new();
+    let mut dialed_peers = Vec::new();
+    loop {
+        while let Some(Some(event)) = behaviour.next().now_or_never() {
+            if let ToSwarm::Dial { opts } = event {
+                let peer_id = opts.get_peer_id().unwrap();
+                assert!(in_flight_dials.insert(peer_id));
+                assert!(in_flight_dials.len() <= MAX_CONCURRENT_DIALS);
+                dialed_peers.push(peer_id);
+            }
+        }
+        // The rest of the dials are queued until a dial ends.
+        if behaviour.num_queued_dials() > 0 {
+            assert_eq!(in_flight_dials.len(), MAX_CONCURRENT_DIALS);
+        }
+
+        let Some(peer_id) = in_flight_dials.iter().next().copied() else {
+            break;
+        };
+        in_flight_dials.remove(&peer_id);
+        // Fail some of the dials and succeed in the rest. The bootstrap dial succeeds, otherwise
+        // it's retried.
+        if peer_id != bootstrap_peer_id && dialed_peers.len() % 2 == 0 {
+            behaviour.on_swarm_event(FromSwarm::DialFailure(DialFailure {
+                peer_id: Some(peer_id),
+                error: &DialError::Aborted,
+                connection_id: ConnectionId::new_unchecked(0),
+            }));
+        } else {
+            behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                connection_id: ConnectionId::new_unchecked(0),
+                endpoint: &ConnectedPoint::Dialer {
+                    address: Multiaddr::empty(),
+                    role_override: Endpoint::Dialer,
+                },
+                failed_addresses: &[],
+                other_established: 0,
+            }));
+        }
+    }
+
+    // The bootstrap peer is dialed first, and then every queued peer exactly once.
+    let expected_dialed_peers = std::iter::once(bootstrap_peer_id)
+        .chain(peers.into_iter().map(|(peer_id, _)| peer_id))
+        .collect::<Vec<_>>();
+    assert_eq!(dialed_peers[0], bootstrap_peer_id);
+    let mut dialed_peers_sorted = dialed_peers.clone();
+    dialed_peers_sorted.sort();
+    let mut expected_dialed_peers_sorted = expected_dialed_peers;
+    expected_dialed_peers_sorted.sort();
+    assert_eq!(dialed_peers_sorted, expected_dialed_peers_sorted);
+}
+
+#[test]
+fn max_concurrent_dials_must_be_positive() {
+    let mut config = DiscoveryConfig::default();
+    assert!(config.validate().is_ok());
+
+    config.max_concurrent_dials = 0;
+    assert!(config.validate().is_err());
+}
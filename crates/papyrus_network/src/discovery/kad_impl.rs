@@ -1,5 +1,8 @@
-use libp2p::kad;
-use tracing::info;
+use std::fs;
+use std::path::Path;
+
+use libp2p::{kad, Multiaddr, PeerId};
+use tracing::{info, warn};
 
 use super::identify_impl::IdentifyToOtherBehaviourEvent;
 use crate::mixed_behaviour::BridgedBehaviour;
@@ -45,3 +48,65 @@ impl<TStore: kad::store::RecordStore + Send + 'static> BridgedBehaviour for kad:
         }
     }
 }
+
+/// The peers in the routing table of the given Kademlia behaviour, along with their addresses.
+pub fn routing_table_peers<TStore: kad::store::RecordStore + Send + 'static>(
+    kademlia: &mut kad::Behaviour<TStore>,
+) -> Vec<(PeerId, Vec<Multiaddr>)> {
+    kademlia
+        .kbuckets()
+        .flat_map(|bucket| {
+            bucket
+                .iter()
+                .map(|entry| {
+                    (*entry.node.key.preimage(), entry.node.value.iter().cloned().collect())
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Adds the peers written by [`persist_routing_table`] to the given Kademlia behaviour and returns
+/// them. A missing or malformed file is logged and ignored.
+pub fn seed_routing_table<TStore: kad::store::RecordStore + Send + 'static>(
+    kademlia: &mut kad::Behaviour<TStore>,
+    path: &Path,
+) -> Vec<(PeerId, Vec<Multiaddr>)> {
+    if !path.exists() {
+        info!("Routing table file {path:?} doesn't exist. Not seeding the routing table.");
+        return Vec::new();
+    }
+    let peers: Vec<(PeerId, Vec<Multiaddr>)> = match fs::read(path)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|error| error.to_string()))
+    {
+        Ok(peers) => peers,
+        Err(error) => {
+            warn!("Failed reading routing table from {path:?}: {error}");
+            return Vec::new();
+        }
+    };
+    info!("Seeding the routing table with {} peers from {path:?}", peers.len());
+    for (peer_id, addresses) in &peers {
+        for address in addresses {
+            kademlia.add_address(peer_id, address.clone());
+        }
+    }
+    peers
+}
+
+/// Writes the peers in the routing table of the given Kademlia behaviour to the given path. A
+/// failure is logged and ignored.
+pub fn persist_routing_table<TStore: kad::store::RecordStore + Send + 'static>(
+    kademlia: &mut kad::Behaviour<TStore>,
+    path: &Path,
+) {
+    let peers = routing_table_peers(kademlia);
+    let result = serde_json::to_vec(&peers)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| fs::write(path, bytes).map_err(|error| error.to_string()));
+    match result {
+        Ok(()) => info!("Persisted {} routing table peers to {path:?}", peers.len()),
+        Err(error) => warn!("Failed persisting routing table to {path:?}: {error}"),
+    }
+}
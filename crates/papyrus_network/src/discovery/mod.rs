@@ -5,7 +5,8 @@ mod flow_test;
 pub mod identify_impl;
 pub mod kad_impl;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::task::{ready, Context, Poll};
 use std::time::Duration;
 
@@ -31,10 +32,16 @@ use papyrus_config::converters::{
     deserialize_milliseconds_to_duration,
     deserialize_seconds_to_duration,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use tokio_retry::strategy::ExponentialBackoff;
+use validator::Validate;
 
 use crate::mixed_behaviour;
 use crate::mixed_behaviour::BridgedBehaviour;
@@ -50,6 +57,11 @@ pub struct Behaviour {
     is_bootstrap_in_kad_routing_table: bool,
     bootstrap_dial_retry_strategy: ExponentialBackoff,
     query_sleep_future: Option<BoxFuture<'static, ()>>,
+    // Peers (other than the bootstrap peer) waiting for a free dial slot.
+    peers_to_dial: VecDeque<(PeerId, Vec<Multiaddr>)>,
+    // Peers (other than the bootstrap peer) we've requested to dial and haven't yet connected to
+    // or failed dialing.
+    dialing_peers: HashSet<PeerId>,
 }
 
 #[derive(Debug)]
@@ -83,6 +95,11 @@ impl NetworkBehaviour for Behaviour {
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm<'_>) {
+        if let FromSwarm::DialFailure(DialFailure { peer_id: Some(peer_id), .. })
+        | FromSwarm::ConnectionEstablished(ConnectionEstablished { peer_id, .. }) = &event
+        {
+            self.dialing_peers.remove(peer_id);
+        }
         match event {
             FromSwarm::DialFailure(DialFailure { peer_id: Some(peer_id), .. })
                 if peer_id == self.bootstrap_peer_id =>
@@ -146,6 +163,23 @@ impl NetworkBehaviour for Behaviour {
             ));
         }
 
+        // The bootstrap peer is dialed before the queued peers, unless it's waiting to be redialed.
+        let is_bootstrap_peer_waiting_for_dial = !self.is_dialing_to_bootstrap_peer
+            && !self.is_connected_to_bootstrap_peer
+            && self.sleep_future_for_dialing_bootstrap_peer.is_none();
+        let has_free_dial_slot = self.num_in_flight_dials() < self.config.max_concurrent_dials;
+        if has_free_dial_slot && !is_bootstrap_peer_waiting_for_dial {
+            if let Some((peer_id, addresses)) = self.peers_to_dial.pop_front() {
+                self.dialing_peers.insert(peer_id);
+                return Poll::Ready(ToSwarm::Dial {
+                    opts: DialOpts::peer_id(peer_id)
+                        .addresses(addresses)
+                        .condition(PeerCondition::DisconnectedAndNotDialing)
+                        .build(),
+                });
+            }
+        }
+
         // Unpacking self so that we can create 2 futures that use different members of self
         let Self {
             is_dialing_to_bootstrap_peer,
@@ -160,6 +194,11 @@ impl NetworkBehaviour for Behaviour {
 
         let bootstrap_dial_future = async move {
             if !(*is_dialing_to_bootstrap_peer) && !(*is_connected_to_bootstrap_peer) {
+                // If all dial slots are taken, this future is recreated on the next poll, which
+                // happens when one of the dials ends.
+                if !has_free_dial_slot {
+                    return pending().await;
+                }
                 if let Some(sleep_future) = sleep_future_for_dialing_bootstrap_peer {
                     sleep_future.await;
                 }
@@ -198,11 +237,16 @@ impl NetworkBehaviour for Behaviour {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
 pub struct DiscoveryConfig {
     pub bootstrap_dial_retry_config: RetryConfig,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     pub heartbeat_interval: Duration,
+    pub routing_table_path: Option<PathBuf>,
+    // Limits only the dials this behaviour makes. Kademlia dials the peers its queries discover on
+    // its own, and those dials aren't counted.
+    #[validate(range(min = 1))]
+    pub max_concurrent_dials: usize,
 }
 
 impl Default for DiscoveryConfig {
@@ -210,22 +254,43 @@ impl Default for DiscoveryConfig {
         Self {
             bootstrap_dial_retry_config: RetryConfig::default(),
             heartbeat_interval: Duration::from_millis(100),
+            routing_table_path: None,
+            max_concurrent_dials: 10,
         }
     }
 }
 
 impl SerializeConfig for DiscoveryConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dump = BTreeMap::from([ser_param(
-            "heartbeat_interval",
-            &self.heartbeat_interval.as_millis(),
-            "The interval between each discovery (Kademlia) query in milliseconds.",
-            ParamPrivacyInput::Public,
-        )]);
+        let mut dump = BTreeMap::from([
+            ser_param(
+                "heartbeat_interval",
+                &self.heartbeat_interval.as_millis(),
+                "The interval between each discovery (Kademlia) query in milliseconds.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_concurrent_dials",
+                &self.max_concurrent_dials,
+                "The maximum number of dials to the bootstrap peer and to the routing table peers \
+                 that are in progress at the same time. The rest are queued. Dials made by \
+                 Kademlia queries aren't limited. Should be positive.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
         dump.append(&mut append_sub_config_name(
             self.bootstrap_dial_retry_config.dump(),
             "bootstrap_dial_retry_config",
         ));
+        dump.extend(ser_optional_param(
+            &self.routing_table_path,
+            PathBuf::new(),
+            "routing_table_path",
+            "A file to which the known peers of the Kademlia routing table are written on \
+             shutdown. If the file exists on startup, the routing table is seeded with its peers \
+             in addition to the bootstrap peer.",
+            ParamPrivacyInput::Public,
+        ));
         dump
     }
 }
@@ -296,9 +361,31 @@ impl Behaviour {
             is_bootstrap_in_kad_routing_table: false,
             bootstrap_dial_retry_strategy,
             query_sleep_future: None,
+            peers_to_dial: VecDeque::new(),
+            dialing_peers: HashSet::new(),
         }
     }
 
+    /// Queues dials to the given peers, such as the peers the routing table was seeded with. At
+    /// most `max_concurrent_dials` dials are in progress at any time.
+    pub fn queue_dials(&mut self, peers: impl IntoIterator<Item = (PeerId, Vec<Multiaddr>)>) {
+        let bootstrap_peer_id = self.bootstrap_peer_id;
+        self.peers_to_dial.extend(
+            peers.into_iter().filter(|(peer_id, addresses)| {
+                *peer_id != bootstrap_peer_id && !addresses.is_empty()
+            }),
+        );
+    }
+
+    fn num_in_flight_dials(&self) -> usize {
+        self.dialing_peers.len() + usize::from(self.is_dialing_to_bootstrap_peer)
+    }
+
+    #[cfg(test)]
+    pub fn num_queued_dials(&self) -> usize {
+        self.peers_to_dial.len()
+    }
+
     #[cfg(test)]
     pub fn bootstrap_peer_id(&self) -> PeerId {
         self.bootstrap_peer_id
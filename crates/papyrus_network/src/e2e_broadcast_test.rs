@@ -8,7 +8,7 @@ use libp2p_swarm_test::SwarmExt;
 use starknet_api::core::ChainId;
 
 use crate::discovery::DiscoveryConfig;
-use crate::gossipsub_impl::Topic;
+use crate::gossipsub_impl::{GossipsubValidationMode, Topic};
 use crate::mixed_behaviour::MixedBehaviour;
 use crate::network_manager::{BroadcastTopicClientTrait, GenericNetworkManager};
 use crate::peer_manager::PeerManagerConfig;
@@ -18,6 +18,14 @@ use crate::sqmr::Bytes;
 const TIMEOUT: Duration = Duration::from_secs(5);
 
 async fn create_swarm(bootstrap_peer_multiaddr: Option<Multiaddr>) -> Swarm<MixedBehaviour> {
+    create_swarm_with_validation_mode(bootstrap_peer_multiaddr, GossipsubValidationMode::default())
+        .await
+}
+
+async fn create_swarm_with_validation_mode(
+    bootstrap_peer_multiaddr: Option<Multiaddr>,
+    gossipsub_validation_mode: GossipsubValidationMode,
+) -> Swarm<MixedBehaviour> {
     let mut swarm = Swarm::new_ephemeral(|keypair| {
         MixedBehaviour::new(
             keypair.clone(),
@@ -27,6 +35,8 @@ async fn create_swarm(bootstrap_peer_multiaddr: Option<Multiaddr>) -> Swarm<Mixe
             None,
             DiscoveryConfig::default(),
             PeerManagerConfig::default(),
+            gossipsub_validation_mode,
+            false,
         )
     });
     // Not using SwarmExt::listen because it panics if the swarm emits other events
@@ -129,3 +139,177 @@ async fn broadcast_subscriber_end_to_end_test() {
         }
     }
 }
+
+#[tokio::test]
+async fn subscribed_peers_report_each_other_in_topic_mesh() {
+    let topic = Topic::new("TOPIC");
+    let bootstrap_swarm = create_swarm(None).await;
+    let bootstrap_peer_multiaddr = bootstrap_swarm.external_addresses().next().unwrap().clone();
+    let bootstrap_peer_multiaddr =
+        bootstrap_peer_multiaddr.with_p2p(*bootstrap_swarm.local_peer_id()).unwrap();
+    let bootstrap_network_manager = create_network_manager(bootstrap_swarm);
+    let mut network_manager1 =
+        create_network_manager(create_swarm(Some(bootstrap_peer_multiaddr.clone())).await);
+    let mut network_manager2 =
+        create_network_manager(create_swarm(Some(bootstrap_peer_multiaddr)).await);
+
+    let _subscriber_channels1 =
+        network_manager1.register_broadcast_topic::<Number>(topic.clone(), BUFFER_SIZE).unwrap();
+    let _subscriber_channels2 =
+        network_manager2.register_broadcast_topic::<Number>(topic.clone(), BUFFER_SIZE).unwrap();
+    let mut network_client1 = network_manager1.get_network_client();
+    let mut network_client2 = network_manager2.get_network_client();
+
+    tokio::select! {
+        _ = network_manager1.run() => panic!("network manager ended"),
+        _ = network_manager2.run() => panic!("network manager ended"),
+        _ = bootstrap_network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(
+            TIMEOUT, async move {
+                // The mesh is built on the gossipsub heartbeat, so poll until both peers see it.
+                loop {
+                    let peer_count1 =
+                        network_client1.topic_peer_count(&topic).await.unwrap();
+                    let peer_count2 =
+                        network_client2.topic_peer_count(&topic).await.unwrap();
+                    if peer_count1 >= 1 && peer_count2 >= 1 {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        ) => {
+            result.unwrap()
+        }
+    }
+}
+
+#[tokio::test]
+async fn strict_validation_mode_forwards_only_continued_messages() {
+    const STRICT_MODE_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+    let topic = Topic::new("TOPIC");
+    // The peers are connected in a line: 0 <-> 1 <-> 2. There's no bootstrap peer, so peers 0 and
+    // 2 won't discover each other and messages from peer 0 reach peer 2 only if peer 1 forwards
+    // them.
+    let mut swarm0 = create_swarm_with_validation_mode(None, GossipsubValidationMode::Strict).await;
+    let swarm1 = create_swarm_with_validation_mode(None, GossipsubValidationMode::Strict).await;
+    let mut swarm2 = create_swarm_with_validation_mode(None, GossipsubValidationMode::Strict).await;
+    let swarm1_multiaddr = swarm1.external_addresses().next().unwrap().clone();
+    let swarm1_multiaddr = swarm1_multiaddr.with_p2p(*swarm1.local_peer_id()).unwrap();
+    swarm0.dial(swarm1_multiaddr.clone()).unwrap();
+    swarm2.dial(swarm1_multiaddr).unwrap();
+
+    let mut network_managers = [swarm0, swarm1, swarm2].map(|swarm| {
+        let mut network_manager = create_network_manager(swarm);
+        network_manager.gossipsub_validation_mode = GossipsubValidationMode::Strict;
+        network_manager
+    });
+    let mut subscriber_channels = network_managers
+        .each_mut()
+        .map(|network_manager| {
+            network_manager.register_broadcast_topic::<Number>(topic.clone(), BUFFER_SIZE).unwrap()
+        })
+        .into_iter();
+    let mut subscriber_channels0 = subscriber_channels.next().unwrap();
+    let mut subscriber_channels1 = subscriber_channels.next().unwrap();
+    let mut subscriber_channels2 = subscriber_channels.next().unwrap();
+    let mut network_client1 = network_managers[1].get_network_client();
+    let [network_manager0, network_manager1, network_manager2] = network_managers;
+
+    tokio::select! {
+        _ = network_manager0.run() => panic!("network manager ended"),
+        _ = network_manager1.run() => panic!("network manager ended"),
+        _ = network_manager2.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(
+            STRICT_MODE_TEST_TIMEOUT, async move {
+                // Wait until peer 1 has both other peers in its mesh, so it's able to forward.
+                while network_client1.topic_peer_count(&topic).await.unwrap() < 2 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                let number = Number(1);
+                subscriber_channels0.broadcast_topic_client.broadcast_message(number).await.unwrap();
+
+                let (received_number, metadata) =
+                    subscriber_channels1.broadcasted_messages_receiver.next().await.unwrap();
+                assert_eq!(received_number.unwrap(), number);
+
+                // Peer 1 didn't validate the message yet, so it shouldn't reach peer 2.
+                let result = tokio::time::timeout(
+                    Duration::from_secs(1),
+                    subscriber_channels2.broadcasted_messages_receiver.next(),
+                )
+                .await;
+                assert!(result.is_err(), "An unvalidated message was forwarded.");
+
+                subscriber_channels1
+                    .broadcast_topic_client
+                    .continue_propagation(&metadata)
+                    .await
+                    .unwrap();
+                let (received_number, _metadata) =
+                    subscriber_channels2.broadcasted_messages_receiver.next().await.unwrap();
+                assert_eq!(received_number.unwrap(), number);
+            }
+        ) => {
+            result.unwrap()
+        }
+    }
+}
+
+#[tokio::test]
+async fn send_to_peers_reaches_only_the_given_peers() {
+    let topic = Topic::new("TOPIC");
+    let sender_swarm = create_swarm(None).await;
+    let sender_multiaddr = sender_swarm.external_addresses().next().unwrap().clone();
+    let sender_peer_id = *sender_swarm.local_peer_id();
+    let sender_multiaddr = sender_multiaddr.with_p2p(sender_peer_id).unwrap();
+    let mut receiver_swarms = Vec::new();
+    for _ in 0..3 {
+        receiver_swarms.push(create_swarm(Some(sender_multiaddr.clone())).await);
+    }
+    let peer_ids: Vec<_> = receiver_swarms.iter().map(|swarm| *swarm.local_peer_id()).collect();
+
+    let mut network_managers: Vec<_> =
+        std::iter::once(sender_swarm).chain(receiver_swarms).map(create_network_manager).collect();
+    let mut subscriber_channels: Vec<_> = network_managers
+        .iter_mut()
+        .map(|network_manager| {
+            network_manager
+                .register_broadcast_topic_with_direct_messages::<Number>(topic.clone(), BUFFER_SIZE)
+                .unwrap()
+        })
+        .collect();
+    let [sender_network_manager, network_manager1, network_manager2, network_manager3]: [_; 4] =
+        network_managers.try_into().ok().unwrap();
+
+    tokio::select! {
+        _ = sender_network_manager.run() => panic!("network manager ended"),
+        _ = network_manager1.run() => panic!("network manager ended"),
+        _ = network_manager2.run() => panic!("network manager ended"),
+        _ = network_manager3.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(
+            TIMEOUT, async move {
+                // Give the receivers time to connect to the sender.
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let number = Number(1);
+                subscriber_channels[0]
+                    .broadcast_topic_client
+                    .send_to_peers(number, &[peer_ids[0], peer_ids[1]])
+                    .await
+                    .unwrap();
+
+                for receiver_channels in &mut subscriber_channels[1..3] {
+                    let (received_number, metadata) =
+                        receiver_channels.broadcasted_messages_receiver.next().await.unwrap();
+                    assert_eq!(received_number.unwrap(), number);
+                    assert_eq!(metadata.originator_id.private_get_peer_id(), sender_peer_id);
+                }
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                let mut receiver_channels3 = subscriber_channels.pop().unwrap();
+                assert!(receiver_channels3.broadcasted_messages_receiver.next().now_or_never().is_none());
+            }
+        ) => {
+            result.unwrap()
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use libp2p::gossipsub::TopicHash;
+use libp2p::gossipsub::{MessageId, TopicHash};
 use libp2p::{gossipsub, PeerId};
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
 use crate::mixed_behaviour;
@@ -11,10 +12,30 @@ pub type Topic = gossipsub::IdentTopic;
 #[cfg(not(test))]
 pub type Topic = gossipsub::Sha256Topic;
 
+/// Whether a received message is forwarded to other peers before or after the application
+/// validates it.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum GossipsubValidationMode {
+    /// Messages are forwarded as soon as they're received. This minimizes the propagation latency,
+    /// but invalid messages may be forwarded before the application reports them.
+    #[default]
+    Permissive,
+    /// Messages are forwarded only after the application calls `continue_propagation` on them.
+    /// This prevents forwarding invalid data at the cost of adding the validation time to the
+    /// propagation latency. Messages that the application doesn't continue are never forwarded.
+    Strict,
+}
+
 #[derive(Debug)]
 pub enum ExternalEvent {
     #[allow(dead_code)]
-    Received { originated_peer_id: PeerId, message: Bytes, topic_hash: TopicHash },
+    Received {
+        originated_peer_id: PeerId,
+        message: Bytes,
+        topic_hash: TopicHash,
+        message_id: MessageId,
+        propagation_source: PeerId,
+    },
 }
 
 impl From<gossipsub::Event> for mixed_behaviour::Event {
@@ -22,7 +43,8 @@ impl From<gossipsub::Event> for mixed_behaviour::Event {
         match event {
             gossipsub::Event::Message {
                 message: gossipsub::Message { data, topic, source, .. },
-                ..
+                message_id,
+                propagation_source,
             } => {
                 let Some(originated_peer_id) = source else {
                     error!(
@@ -38,6 +60,8 @@ impl From<gossipsub::Event> for mixed_behaviour::Event {
                         originated_peer_id,
                         message: data,
                         topic_hash: topic,
+                        message_id,
+                        propagation_source,
                     },
                 ))
             }
@@ -2,6 +2,7 @@
 /// to the [`Starknet p2p specs`]
 ///
 /// [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
+pub mod autonat_impl;
 mod bin_utils;
 mod discovery;
 #[cfg(test)]
@@ -10,6 +11,7 @@ pub mod gossipsub_impl;
 mod mixed_behaviour;
 pub mod network_manager;
 mod peer_manager;
+pub mod security;
 mod sqmr;
 #[cfg(test)]
 mod test_utils;
@@ -19,6 +21,7 @@ use std::collections::BTreeMap;
 use std::time::Duration;
 
 use discovery::DiscoveryConfig;
+use gossipsub_impl::GossipsubValidationMode;
 use libp2p::Multiaddr;
 use papyrus_config::converters::{
     deserialize_optional_vec_u8,
@@ -34,6 +37,7 @@ use papyrus_config::dumping::{
 use papyrus_config::validators::validate_vec_u256;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use peer_manager::PeerManagerConfig;
+use security::SecurityConfig;
 use serde::{Deserialize, Serialize};
 use starknet_api::core::ChainId;
 use validator::Validate;
@@ -52,8 +56,14 @@ pub struct NetworkConfig {
     pub secret_key: Option<Vec<u8>>,
     pub advertised_multiaddr: Option<Multiaddr>,
     pub chain_id: ChainId,
+    #[validate]
     pub discovery_config: DiscoveryConfig,
     pub peer_manager_config: PeerManagerConfig,
+    #[validate]
+    pub security_config: SecurityConfig,
+    pub gossipsub_validation_mode: GossipsubValidationMode,
+    pub default_protocol_buffer_size: usize,
+    pub enable_autonat: bool,
 }
 
 impl SerializeConfig for NetworkConfig {
@@ -111,6 +121,30 @@ impl SerializeConfig for NetworkConfig {
         config.extend(append_sub_config_name(self.discovery_config.dump(), "discovery_config"));
         config
             .extend(append_sub_config_name(self.peer_manager_config.dump(), "peer_manager_config"));
+        config.extend(append_sub_config_name(self.security_config.dump(), "security_config"));
+        config.extend([ser_param(
+            "gossipsub_validation_mode",
+            &self.gossipsub_validation_mode,
+            "Whether broadcasted messages are forwarded to other peers as soon as they're \
+             received (Permissive) or only after the application validated them and continued \
+             their propagation (Strict). Strict prevents forwarding invalid messages but adds \
+             latency.",
+            ParamPrivacyInput::Public,
+        )]);
+        config.extend([ser_param(
+            "default_protocol_buffer_size",
+            &self.default_protocol_buffer_size,
+            "The size of the channel buffers of protocols and topics that were registered without \
+             an explicit buffer size.",
+            ParamPrivacyInput::Public,
+        )]);
+        config.extend([ser_param(
+            "enable_autonat",
+            &self.enable_autonat,
+            "Whether to ask connected peers to dial back to this node in order to find out if \
+             it's publicly reachable. The result is logged and reported in the metrics.",
+            ParamPrivacyInput::Public,
+        )]);
         config
     }
 }
@@ -127,6 +161,10 @@ impl Default for NetworkConfig {
             chain_id: ChainId::Mainnet,
             discovery_config: DiscoveryConfig::default(),
             peer_manager_config: PeerManagerConfig::default(),
+            security_config: SecurityConfig::default(),
+            gossipsub_validation_mode: GossipsubValidationMode::default(),
+            default_protocol_buffer_size: network_manager::DEFAULT_PROTOCOL_BUFFER_SIZE,
+            enable_autonat: false,
         }
     }
 }
@@ -5,14 +5,15 @@ use libp2p::kad::store::MemoryStore;
 use libp2p::swarm::behaviour::toggle::Toggle;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{gossipsub, identify, kad, Multiaddr, PeerId, StreamProtocol};
+use libp2p::{autonat, gossipsub, identify, kad, Multiaddr, PeerId, StreamProtocol};
 use starknet_api::core::ChainId;
 
 use crate::discovery::identify_impl::{IdentifyToOtherBehaviourEvent, IDENTIFY_PROTOCOL_VERSION};
-use crate::discovery::kad_impl::KadToOtherBehaviourEvent;
+use crate::discovery::kad_impl::{seed_routing_table, KadToOtherBehaviourEvent};
 use crate::discovery::DiscoveryConfig;
+use crate::gossipsub_impl::GossipsubValidationMode;
 use crate::peer_manager::PeerManagerConfig;
-use crate::{discovery, gossipsub_impl, peer_manager, sqmr};
+use crate::{autonat_impl, discovery, gossipsub_impl, peer_manager, sqmr};
 
 const ONE_MEGA: usize = 1 << 20;
 
@@ -27,6 +28,7 @@ pub struct MixedBehaviour {
     pub kademlia: kad::Behaviour<MemoryStore>,
     pub sqmr: sqmr::Behaviour,
     pub gossipsub: gossipsub::Behaviour,
+    pub autonat: Toggle<autonat::Behaviour>,
 }
 
 #[derive(Debug)]
@@ -39,6 +41,7 @@ pub enum Event {
 pub enum ExternalEvent {
     Sqmr(sqmr::behaviour::ExternalEvent),
     GossipSub(gossipsub_impl::ExternalEvent),
+    Autonat(autonat_impl::ExternalEvent),
 }
 
 #[derive(Debug)]
@@ -66,6 +69,8 @@ impl MixedBehaviour {
         node_version: Option<String>,
         discovery_config: DiscoveryConfig,
         peer_manager_config: PeerManagerConfig,
+        gossipsub_validation_mode: GossipsubValidationMode,
+        enable_autonat: bool,
     ) -> Self {
         let public_key = keypair.public();
         let local_peer_id = PeerId::from_public_key(&public_key);
@@ -74,17 +79,35 @@ impl MixedBehaviour {
             StreamProtocol::try_from_owned(format!("/starknet/kad/{}/1.0.0", chain_id))
                 .expect("Failed to create StreamProtocol from a string that starts with /"),
         ]);
+        // TODO: change kademlia protocol name
+        let mut kademlia = kad::Behaviour::with_config(
+            local_peer_id,
+            MemoryStore::new(local_peer_id),
+            kademlia_config,
+        );
+        let seeded_peers = discovery_config
+            .routing_table_path
+            .as_ref()
+            .map(|routing_table_path| seed_routing_table(&mut kademlia, routing_table_path))
+            .unwrap_or_default();
+        let mut gossipsub_config_builder = gossipsub::ConfigBuilder::default();
+        gossipsub_config_builder.max_transmit_size(ONE_MEGA);
+        if gossipsub_validation_mode == GossipsubValidationMode::Strict {
+            gossipsub_config_builder.validate_messages();
+        }
         Self {
             peer_manager: peer_manager::PeerManager::new(peer_manager_config),
             discovery: bootstrap_peer_multiaddr
                 .map(|bootstrap_peer_multiaddr| {
-                    discovery::Behaviour::new(
+                    let mut discovery = discovery::Behaviour::new(
                         discovery_config,
                         DialOpts::from(bootstrap_peer_multiaddr.clone())
                             .get_peer_id()
                             .expect("bootstrap_peer_multiaddr doesn't have a peer id"),
                         bootstrap_peer_multiaddr.clone(),
-                    )
+                    );
+                    discovery.queue_dials(seeded_peers);
+                    discovery
                 })
                 .into(),
             identify: match node_version {
@@ -97,25 +120,20 @@ impl MixedBehaviour {
                     public_key,
                 )),
             },
-            // TODO: change kademlia protocol name
-            kademlia: kad::Behaviour::with_config(
-                local_peer_id,
-                MemoryStore::new(local_peer_id),
-                kademlia_config,
-            ),
+            kademlia,
             sqmr: sqmr::Behaviour::new(streamed_bytes_config),
             gossipsub: gossipsub::Behaviour::new(
                 gossipsub::MessageAuthenticity::Signed(keypair),
-                gossipsub::ConfigBuilder::default()
-                    .max_transmit_size(ONE_MEGA)
-                    .build()
-                    .expect("Failed to build gossipsub config"),
+                gossipsub_config_builder.build().expect("Failed to build gossipsub config"),
             )
             .unwrap_or_else(|err_string| {
                 panic!(
                     "Failed creating gossipsub behaviour due to the following error: {err_string}"
                 )
             }),
+            autonat: enable_autonat
+                .then(|| autonat::Behaviour::new(local_peer_id, autonat::Config::default()))
+                .into(),
         }
     }
 }
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, Stream};
+use futures::{FutureExt, StreamExt};
+use libp2p::StreamProtocol;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+
+use crate::sqmr::OutboundSessionId;
+
+/// The SQMR protocol used for heartbeats. The query and the response are empty.
+pub(crate) const HEARTBEAT_PROTOCOL: StreamProtocol = StreamProtocol::new("/starknet/heartbeat/1");
+
+pub(crate) enum HeartbeatEvent {
+    /// It's time to send a heartbeat to the connected peers.
+    SendHeartbeats,
+    /// The heartbeat sent on the given session wasn't answered in time.
+    Timeout(OutboundSessionId),
+}
+
+/// Tracks the heartbeats we sent to peers in order to detect peers that keep their connection open
+/// but don't answer queries.
+#[derive(Default)]
+pub(crate) struct Heartbeat {
+    // None if the heartbeat is disabled.
+    interval: Option<Interval>,
+    timeout: Duration,
+    pending_sessions: HashSet<OutboundSessionId>,
+    timeouts: FuturesUnordered<BoxFuture<'static, OutboundSessionId>>,
+}
+
+impl Heartbeat {
+    /// A zero interval disables the heartbeat.
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        if interval.is_zero() {
+            return Self::default();
+        }
+        let mut interval = interval_at(Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self { interval: Some(interval), timeout, ..Default::default() }
+    }
+
+    pub fn on_heartbeat_sent(&mut self, outbound_session_id: OutboundSessionId) {
+        self.pending_sessions.insert(outbound_session_id);
+        self.timeouts
+            .push(tokio::time::sleep(self.timeout).map(move |_| outbound_session_id).boxed());
+    }
+
+    /// Returns true if the given session is a heartbeat that wasn't answered yet.
+    pub fn on_response(&mut self, outbound_session_id: OutboundSessionId) -> bool {
+        self.pending_sessions.remove(&outbound_session_id)
+    }
+}
+
+impl Stream for Heartbeat {
+    type Item = HeartbeatEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(interval) = &mut self.interval {
+            if interval.poll_tick(cx).is_ready() {
+                return Poll::Ready(Some(HeartbeatEvent::SendHeartbeats));
+            }
+        }
+        while let Poll::Ready(Some(outbound_session_id)) = self.timeouts.poll_next_unpin(cx) {
+            if self.pending_sessions.remove(&outbound_session_id) {
+                return Poll::Ready(Some(HeartbeatEvent::Timeout(outbound_session_id)));
+            }
+        }
+        Poll::Pending
+    }
+}
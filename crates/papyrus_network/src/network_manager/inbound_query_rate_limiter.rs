@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use libp2p::PeerId;
+use tokio::time::Instant;
+
+const WINDOW_DURATION: Duration = Duration::from_secs(1);
+
+/// Limits the number of inbound queries each peer can send us per second, so that a single peer
+/// can't flood us with queries.
+#[derive(Default)]
+pub(crate) struct InboundQueryRateLimiter {
+    // None if there's no limit.
+    max_queries_per_sec: Option<u32>,
+    // The start of the current window of each peer and the number of queries it sent in it.
+    peer_windows: HashMap<PeerId, (Instant, u32)>,
+}
+
+impl InboundQueryRateLimiter {
+    /// A zero limit disables the rate limiting.
+    pub fn new(max_queries_per_sec: u32) -> Self {
+        Self {
+            max_queries_per_sec: (max_queries_per_sec > 0).then_some(max_queries_per_sec),
+            ..Default::default()
+        }
+    }
+
+    /// Counts a query from the given peer. Returns false if the peer exceeded the limit and the
+    /// query should be rejected.
+    pub fn try_accept_query(&mut self, peer_id: PeerId) -> bool {
+        let Some(max_queries_per_sec) = self.max_queries_per_sec else {
+            return true;
+        };
+        let now = Instant::now();
+        let (window_start, num_queries) = self.peer_windows.entry(peer_id).or_insert((now, 0));
+        if now.duration_since(*window_start) >= WINDOW_DURATION {
+            *window_start = now;
+            *num_queries = 0;
+        }
+        if *num_queries >= max_queries_per_sec {
+            return false;
+        }
+        *num_queries += 1;
+        true
+    }
+
+    pub fn on_peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.peer_windows.remove(peer_id);
+    }
+}
@@ -1,3 +1,5 @@
+mod heartbeat;
+mod inbound_query_rate_limiter;
 mod swarm_trait;
 
 #[cfg(test)]
@@ -5,9 +7,11 @@ mod test;
 #[cfg(any(test, feature = "testing"))]
 pub mod test_utils;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::channel::mpsc::{Receiver, SendError, Sender};
@@ -19,15 +23,25 @@ use futures::{pin_mut, FutureExt, Sink, SinkExt, StreamExt};
 use libp2p::gossipsub::{SubscriptionError, TopicHash};
 use libp2p::swarm::SwarmEvent;
 use libp2p::{Multiaddr, PeerId, StreamProtocol, Swarm};
-use metrics::gauge;
+use metrics::{gauge, increment_counter};
 use papyrus_common::metrics as papyrus_metrics;
-use papyrus_network_types::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
+use papyrus_network_types::network_types::{
+    BroadcastedMessageMetadata,
+    OpaqueMessageId,
+    OpaquePeerId,
+};
 use sqmr::Bytes;
+use tokio::time::Instant;
 use tracing::{debug, error, info, trace, warn};
+use validator::Validate;
 
+use self::heartbeat::{Heartbeat, HeartbeatEvent, HEARTBEAT_PROTOCOL};
+use self::inbound_query_rate_limiter::InboundQueryRateLimiter;
 use self::swarm_trait::SwarmTrait;
+use crate::autonat_impl::{self, ReachabilityStatus};
 use crate::bin_utils::build_swarm;
-use crate::gossipsub_impl::Topic;
+use crate::discovery::kad_impl::persist_routing_table;
+use crate::gossipsub_impl::{GossipsubValidationMode, Topic};
 use crate::mixed_behaviour::{self, BridgedBehaviour};
 use crate::sqmr::behaviour::SessionError;
 use crate::sqmr::{self, InboundSessionId, OutboundSessionId, SessionId};
@@ -45,25 +59,81 @@ pub enum NetworkError {
 // TODO: Understand whats the correct thing to do here.
 const MESSAGE_METADATA_BUFFER_SIZE: usize = 100000;
 
+pub(crate) const DEFAULT_PROTOCOL_BUFFER_SIZE: usize = 10000;
+
+/// The protocol over which broadcast topic messages are sent directly to chosen peers instead of
+/// through gossipsub. Each message is sent on its own session, which the receiver closes
+/// immediately. It's only supported once a topic that accepts direct messages is registered.
+pub(crate) const DIRECT_MESSAGE_PROTOCOL: StreamProtocol =
+    StreamProtocol::new("/starknet/direct_message/1");
+
+// Gossipsub updates its mesh on its heartbeat, which is every second by default, so there's no
+// point in refreshing the mesh size metrics more often.
+const TOPIC_MESH_METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A request to a running network manager. Requests that expect an answer carry the sender to
+/// answer on.
+pub enum NetworkCommand {
+    /// Answered with the number of peers in our gossipsub mesh for the topic.
+    TopicPeerCount { topic_hash: TopicHash, peer_count_sender: oneshot::Sender<usize> },
+    /// Answered with a snapshot of the SQMR sessions that are in flight.
+    SessionsInfo { sessions_info_sender: oneshot::Sender<Vec<SessionInfo>> },
+    /// Sends the message on the topic directly to the given peers. Not answered.
+    SendToPeers { topic_hash: TopicHash, message: Bytes, peers: Vec<PeerId> },
+    /// Answered with the reachability status AutoNAT determined last.
+    ReachabilityStatus { reachability_status_sender: oneshot::Sender<ReachabilityStatus> },
+    /// Answered with the addresses we listen on once there's at least one.
+    ListeningAddresses { listening_addresses_sender: oneshot::Sender<Vec<Multiaddr>> },
+}
+
+/// Each registered protocol and topic communicates with the network manager through channels of
+/// the size given at registration, or of `default_protocol_buffer_size` from the config if none
+/// was given. When a buffer is full:
+/// * Sending a query, a broadcast or a response from the application waits until there's room.
+/// * Queries, responses and broadcasts received from peers are dropped with a warning, so that a
+///   slow consumer doesn't stall the rest of the network.
 pub struct GenericNetworkManager<SwarmT: SwarmTrait> {
     swarm: SwarmT,
     inbound_protocol_to_buffer_size: HashMap<StreamProtocol, usize>,
     sqmr_inbound_response_receivers: StreamHashMap<InboundSessionId, ResponsesReceiver>,
     sqmr_inbound_payload_senders: HashMap<StreamProtocol, SqmrServerSender>,
-    sqmr_outbound_payload_receivers: StreamHashMap<StreamProtocol, SqmrClientReceiver>,
+    // Keyed by the protocols each query offers, ordered by preference.
+    sqmr_outbound_payload_receivers: StreamHashMap<Vec<StreamProtocol>, SqmrClientReceiver>,
     sqmr_outbound_response_senders: HashMap<OutboundSessionId, ResponsesSender>,
-    sqmr_outbound_report_receivers_awaiting_assignment: HashMap<OutboundSessionId, ReportReceiver>,
+    sqmr_outbound_report_receivers_awaiting_assignment:
+        HashMap<OutboundSessionId, (ReportReceiver, NegotiatedProtocolSender)>,
     // Splitting the broadcast receivers from the broadcasted senders in order to poll all
     // receivers simultaneously.
     // Each receiver has a matching sender and vice versa (i.e the maps have the same keys).
     messages_to_broadcast_receivers: StreamHashMap<TopicHash, Receiver<Bytes>>,
     broadcasted_messages_senders: HashMap<TopicHash, Sender<(Bytes, BroadcastedMessageMetadata)>>,
+    // Direct messages bypass gossipsub's validation and scoring, so they're only sent and accepted
+    // on topics that were registered to allow them.
+    direct_message_topics: HashSet<TopicHash>,
+    // The last message we broadcasted on each sticky topic. Topics registered as sticky have an
+    // entry here even before the first message was broadcasted.
+    sticky_topic_last_messages: HashMap<TopicHash, Option<Bytes>>,
     reported_peer_receivers: FuturesUnordered<BoxFuture<'static, Option<PeerId>>>,
     advertised_multiaddr: Option<Multiaddr>,
     reported_peers_receiver: Receiver<PeerId>,
     reported_peers_sender: Sender<PeerId>,
     continue_propagation_sender: Sender<BroadcastedMessageMetadata>,
     continue_propagation_receiver: Receiver<BroadcastedMessageMetadata>,
+    network_commands_sender: Sender<NetworkCommand>,
+    network_commands_receiver: Receiver<NetworkCommand>,
+    reachability_status: ReachabilityStatus,
+    listening_addresses: Vec<Multiaddr>,
+    // Requests for the listening addresses that arrived before we listened on any address.
+    pending_listening_addresses_senders: Vec<oneshot::Sender<Vec<Multiaddr>>>,
+    // The protocol and start time of each session that didn't finish yet. For outbound sessions,
+    // the protocol is the most preferred one the query offered.
+    active_sessions: HashMap<SessionId, (StreamProtocol, Instant)>,
+    // Where to write the Kademlia routing table when the network manager is dropped.
+    routing_table_path: Option<PathBuf>,
+    heartbeat: Heartbeat,
+    inbound_query_rate_limiter: InboundQueryRateLimiter,
+    pub(crate) gossipsub_validation_mode: GossipsubValidationMode,
+    default_protocol_buffer_size: usize,
     // Fields for metrics
     num_active_inbound_sessions: usize,
     num_active_outbound_sessions: usize,
@@ -71,12 +141,13 @@ pub struct GenericNetworkManager<SwarmT: SwarmTrait> {
 
 impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     pub async fn run(mut self) -> Result<(), NetworkError> {
+        let mut topic_mesh_metrics_interval = tokio::time::interval(TOPIC_MESH_METRICS_INTERVAL);
         loop {
             tokio::select! {
                 Some(event) = self.swarm.next() => self.handle_swarm_event(event)?,
                 Some(res) = self.sqmr_inbound_response_receivers.next() => self.handle_response_for_inbound_query(res),
-                Some((protocol, client_payload)) = self.sqmr_outbound_payload_receivers.next() => {
-                    self.handle_local_sqmr_payload(protocol, client_payload.expect("An SQMR client channel should not be terminated."))
+                Some((protocols, client_payload)) = self.sqmr_outbound_payload_receivers.next() => {
+                    self.handle_local_sqmr_payload(protocols, client_payload.expect("An SQMR client channel should not be terminated."))
                 }
                 Some((topic_hash, message)) = self.messages_to_broadcast_receivers.next() => {
                     self.broadcast_message(
@@ -89,8 +160,16 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                 Some(Some(peer_id)) = self.reported_peer_receivers.next() => self.swarm.report_peer_as_malicious(peer_id),
                 Some(peer_id) = self.reported_peers_receiver.next() => self.swarm.report_peer_as_malicious(peer_id),
                 Some(broadcasted_message_metadata) = self.continue_propagation_receiver.next() => {
-                    self.swarm.continue_propagation(broadcasted_message_metadata);
+                    // In permissive mode the message was already forwarded when it was received.
+                    if self.gossipsub_validation_mode == GossipsubValidationMode::Strict {
+                        self.swarm.continue_propagation(broadcasted_message_metadata);
+                    }
                 }
+                Some(network_command) = self.network_commands_receiver.next() => {
+                    self.handle_network_command(network_command);
+                }
+                Some(heartbeat_event) = self.heartbeat.next() => self.handle_heartbeat_event(heartbeat_event),
+                _ = topic_mesh_metrics_interval.tick() => self.update_topic_mesh_metrics(),
             }
         }
     }
@@ -108,6 +187,8 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             futures::channel::mpsc::channel(MESSAGE_METADATA_BUFFER_SIZE);
         let (continue_propagation_sender, continue_propagation_receiver) =
             futures::channel::mpsc::channel(MESSAGE_METADATA_BUFFER_SIZE);
+        let (network_commands_sender, network_commands_receiver) =
+            futures::channel::mpsc::channel(MESSAGE_METADATA_BUFFER_SIZE);
         Self {
             swarm,
             inbound_protocol_to_buffer_size: HashMap::new(),
@@ -118,22 +199,34 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             sqmr_outbound_report_receivers_awaiting_assignment: HashMap::new(),
             messages_to_broadcast_receivers: StreamHashMap::new(HashMap::new()),
             broadcasted_messages_senders: HashMap::new(),
+            direct_message_topics: HashSet::new(),
+            sticky_topic_last_messages: HashMap::new(),
             reported_peer_receivers,
             advertised_multiaddr,
             reported_peers_receiver,
             reported_peers_sender,
             continue_propagation_sender,
             continue_propagation_receiver,
+            network_commands_sender,
+            network_commands_receiver,
+            reachability_status: ReachabilityStatus::default(),
+            listening_addresses: Vec::new(),
+            pending_listening_addresses_senders: Vec::new(),
+            active_sessions: HashMap::new(),
+            routing_table_path: None,
+            heartbeat: Heartbeat::default(),
+            inbound_query_rate_limiter: InboundQueryRateLimiter::default(),
+            gossipsub_validation_mode: GossipsubValidationMode::default(),
+            default_protocol_buffer_size: DEFAULT_PROTOCOL_BUFFER_SIZE,
             num_active_inbound_sessions: 0,
             num_active_outbound_sessions: 0,
         }
     }
 
-    // TODO: Support multiple protocols where they're all different versions of the same protocol
     pub fn register_sqmr_protocol_server<Query, Response>(
         &mut self,
         protocol: String,
-        buffer_size: usize,
+        buffer_size: impl Into<Option<usize>>,
     ) -> SqmrServerReceiver<Query, Response>
     where
         Bytes: From<Response>,
@@ -143,19 +236,59 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     {
         let protocol = StreamProtocol::try_from_owned(protocol)
             .expect("Could not parse protocol into StreamProtocol.");
-        self.swarm.add_new_supported_inbound_protocol(protocol.clone());
-        if let Some(_old_buffer_size) =
-            self.inbound_protocol_to_buffer_size.insert(protocol.clone(), buffer_size)
-        {
-            panic!("Protocol '{}' has already been registered as a server.", protocol);
-        }
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_sqmr_protocol_server_inner(vec![protocol], buffer_size)
+    }
+
+    /// Same as [`register_sqmr_protocol_server`](Self::register_sqmr_protocol_server), but serves
+    /// several versions of the protocol through the same channel. Each version is served on
+    /// `{protocol}/{version}` (e.g. `/starknet/headers/1.0.0`).
+    /// Panics if any of the versions is already registered as a server.
+    pub fn register_sqmr_protocol_server_with_versions<Query, Response>(
+        &mut self,
+        protocol: String,
+        versions: Vec<String>,
+        buffer_size: impl Into<Option<usize>>,
+    ) -> SqmrServerReceiver<Query, Response>
+    where
+        Bytes: From<Response>,
+        Query: TryFrom<Bytes> + Clone,
+        <Query as TryFrom<Bytes>>::Error: Clone,
+        Response: 'static,
+    {
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_sqmr_protocol_server_inner(
+            versioned_protocols(&protocol, versions),
+            buffer_size,
+        )
+    }
+
+    fn register_sqmr_protocol_server_inner<Query, Response>(
+        &mut self,
+        protocols: Vec<StreamProtocol>,
+        buffer_size: usize,
+    ) -> SqmrServerReceiver<Query, Response>
+    where
+        Bytes: From<Response>,
+        Query: TryFrom<Bytes> + Clone,
+        <Query as TryFrom<Bytes>>::Error: Clone,
+        Response: 'static,
+    {
         let (inbound_payload_sender, inbound_payload_receiver) =
             futures::channel::mpsc::channel(buffer_size);
-        let insert_result = self
-            .sqmr_inbound_payload_senders
-            .insert(protocol.clone(), Box::new(inbound_payload_sender));
-        if insert_result.is_some() {
-            panic!("Protocol '{}' has already been registered as a server.", protocol);
+        for protocol in protocols {
+            self.swarm.add_new_supported_inbound_protocol(protocol.clone());
+            if let Some(_old_buffer_size) =
+                self.inbound_protocol_to_buffer_size.insert(protocol.clone(), buffer_size)
+            {
+                panic!("Protocol '{}' has already been registered as a server.", protocol);
+            }
+            let insert_result = self
+                .sqmr_inbound_payload_senders
+                .insert(protocol.clone(), Box::new(inbound_payload_sender.clone()));
+            if insert_result.is_some() {
+                panic!("Protocol '{}' has already been registered as a server.", protocol);
+            }
         }
 
         let inbound_payload_receiver = inbound_payload_receiver
@@ -164,13 +297,13 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     }
 
     /// Register a new subscriber for sending a single query and receiving multiple responses.
+    /// If `buffer_size` is `None`, the configured `default_protocol_buffer_size` is used.
     /// Panics if the given protocol is already subscribed.
-    // TODO: Support multiple protocols where they're all different versions of the same protocol
     // TODO: Seperate query and response buffer sizes.
     pub fn register_sqmr_protocol_client<Query, Response>(
         &mut self,
         protocol: String,
-        buffer_size: usize,
+        buffer_size: impl Into<Option<usize>>,
     ) -> SqmrClientSender<Query, Response>
     where
         Bytes: From<Query>,
@@ -180,27 +313,129 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     {
         let protocol = StreamProtocol::try_from_owned(protocol)
             .expect("Could not parse protocol into StreamProtocol.");
-        self.swarm.add_new_supported_inbound_protocol(protocol.clone());
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_sqmr_protocol_client_inner(vec![protocol], buffer_size)
+    }
+
+    /// Same as [`register_sqmr_protocol_client`](Self::register_sqmr_protocol_client), but each
+    /// query offers the peer several versions of the protocol, on `{protocol}/{version}`. The
+    /// highest version that the peer supports is used for the session.
+    /// Panics if the highest version is already subscribed.
+    pub fn register_sqmr_protocol_client_with_versions<Query, Response>(
+        &mut self,
+        protocol: String,
+        versions: Vec<String>,
+        buffer_size: impl Into<Option<usize>>,
+    ) -> SqmrClientSender<Query, Response>
+    where
+        Bytes: From<Query>,
+        Response: TryFrom<Bytes> + 'static + Send,
+        <Response as TryFrom<Bytes>>::Error: 'static + Send,
+        Query: 'static,
+    {
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_sqmr_protocol_client_inner(
+            versioned_protocols(&protocol, versions),
+            buffer_size,
+        )
+    }
+
+    fn register_sqmr_protocol_client_inner<Query, Response>(
+        &mut self,
+        protocols: Vec<StreamProtocol>,
+        buffer_size: usize,
+    ) -> SqmrClientSender<Query, Response>
+    where
+        Bytes: From<Query>,
+        Response: TryFrom<Bytes> + 'static + Send,
+        <Response as TryFrom<Bytes>>::Error: 'static + Send,
+        Query: 'static,
+    {
+        for protocol in &protocols {
+            self.swarm.add_new_supported_inbound_protocol(protocol.clone());
+        }
         let (payload_sender, payload_receiver) = futures::channel::mpsc::channel(buffer_size);
 
         let insert_result = self
             .sqmr_outbound_payload_receivers
-            .insert(protocol.clone(), Box::new(payload_receiver));
+            .insert(protocols.clone(), Box::new(payload_receiver));
         if insert_result.is_some() {
-            panic!("Protocol '{}' has already been registered as a client.", protocol);
+            panic!("Protocols {:?} have already been registered as a client.", protocols);
         };
 
         SqmrClientSender::new(Box::new(payload_sender), buffer_size)
     }
 
+    fn buffer_size_or_default(&self, buffer_size: impl Into<Option<usize>>) -> usize {
+        buffer_size.into().unwrap_or(self.default_protocol_buffer_size)
+    }
+
+    /// Returns a client for inspecting the state of the network manager while it runs.
+    pub fn get_network_client(&self) -> NetworkClient {
+        NetworkClient { network_commands_sender: self.network_commands_sender.clone() }
+    }
+
     /// Register a new subscriber for broadcasting and receiving broadcasts for a given topic.
+    /// If `buffer_size` is `None`, the configured `default_protocol_buffer_size` is used.
     /// Panics if this topic is already subscribed.
     // TODO: consider splitting into register_broadcast_topic_client and
     // register_broadcast_topic_server
     pub fn register_broadcast_topic<T>(
+        &mut self,
+        topic: Topic,
+        buffer_size: impl Into<Option<usize>>,
+    ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
+    where
+        T: TryFrom<Bytes> + 'static,
+        Bytes: From<T>,
+    {
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_broadcast_topic_inner(topic, buffer_size, false, false)
+    }
+
+    /// Same as [`register_broadcast_topic`](Self::register_broadcast_topic), but messages on this
+    /// topic can also be sent directly to chosen peers with
+    /// [`BroadcastTopicClient::send_to_peers`], and such messages from peers are accepted. Direct
+    /// messages skip gossipsub's validation and peer scoring, and they aren't propagated further.
+    /// Panics if this topic is already subscribed.
+    pub fn register_broadcast_topic_with_direct_messages<T>(
+        &mut self,
+        topic: Topic,
+        buffer_size: impl Into<Option<usize>>,
+    ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
+    where
+        T: TryFrom<Bytes> + 'static,
+        Bytes: From<T>,
+    {
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_broadcast_topic_inner(topic, buffer_size, false, true)
+    }
+
+    /// Same as [`register_broadcast_topic`](Self::register_broadcast_topic), but the last message
+    /// we broadcasted on this topic is sent directly to each newly connected peer, so that peers
+    /// that missed it will converge faster. Only the single most recent message is kept. Sticky
+    /// topics accept direct messages, as in
+    /// [`register_broadcast_topic_with_direct_messages`](Self::register_broadcast_topic_with_direct_messages).
+    /// Panics if this topic is already subscribed.
+    pub fn register_sticky_broadcast_topic<T>(
+        &mut self,
+        topic: Topic,
+        buffer_size: impl Into<Option<usize>>,
+    ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
+    where
+        T: TryFrom<Bytes> + 'static,
+        Bytes: From<T>,
+    {
+        let buffer_size = self.buffer_size_or_default(buffer_size);
+        self.register_broadcast_topic_inner(topic, buffer_size, true, true)
+    }
+
+    fn register_broadcast_topic_inner<T>(
         &mut self,
         topic: Topic,
         buffer_size: usize,
+        is_sticky: bool,
+        allows_direct_messages: bool,
     ) -> Result<BroadcastTopicChannels<T>, SubscriptionError>
     where
         T: TryFrom<Bytes> + 'static,
@@ -229,6 +464,16 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             panic!("Topic '{}' has already been registered.", topic);
         }
 
+        if allows_direct_messages {
+            if self.direct_message_topics.is_empty() {
+                self.swarm.add_new_supported_inbound_protocol(DIRECT_MESSAGE_PROTOCOL);
+            }
+            self.direct_message_topics.insert(topic_hash.clone());
+        }
+        if is_sticky {
+            self.sticky_topic_last_messages.insert(topic_hash.clone(), None);
+        }
+
         let broadcasted_messages_fn: BroadcastReceivedMessagesConverterFn<T> =
             |(x, broadcasted_message_metadata)| (T::try_from(x), broadcasted_message_metadata);
         let broadcasted_messages_receiver =
@@ -252,7 +497,9 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         Ok(BroadcastTopicChannels {
             broadcasted_messages_receiver,
             broadcast_topic_client: BroadcastTopicClient::new(
+                topic_hash,
                 messages_to_broadcast_sender,
+                self.network_commands_sender.clone(),
                 reported_messages_sender,
                 continue_propagation_sender,
             ),
@@ -265,20 +512,26 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     ) -> Result<(), NetworkError> {
         #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
         match event {
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => {
                 debug!("Connected to peer id: {peer_id:?}");
                 gauge!(
                     papyrus_metrics::PAPYRUS_NUM_CONNECTED_PEERS,
                     self.swarm.num_connected_peers() as f64
                 );
+                if num_established.get() == 1 {
+                    self.send_sticky_messages_to_peer(peer_id);
+                }
             }
-            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+            SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
                 match cause {
                     Some(connection_error) => {
                         debug!("Connection to {peer_id:?} closed due to {connection_error:?}.")
                     }
                     None => debug!("Connection to {peer_id:?} closed."),
                 }
+                if num_established == 0 {
+                    self.inbound_query_rate_limiter.on_peer_disconnected(&peer_id);
+                }
                 gauge!(
                     papyrus_metrics::PAPYRUS_NUM_CONNECTED_PEERS,
                     self.swarm.num_connected_peers() as f64
@@ -307,11 +560,22 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                 );
             }
             SwarmEvent::NewListenAddr { address, .. } => {
+                info!("Listening on {address}");
+                self.listening_addresses.push(address.clone());
+                for listening_addresses_sender in self.pending_listening_addresses_senders.drain(..)
+                {
+                    // The requester may have stopped waiting for the answer.
+                    let _ = listening_addresses_sender.send(self.listening_addresses.clone());
+                }
                 // TODO(shahak): Find a better way to filter private addresses.
                 if !is_localhost(&address) && self.advertised_multiaddr.is_none() {
                     self.swarm.add_external_address(address);
                 }
             }
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                info!("No longer listening on {address}");
+                self.listening_addresses.retain(|other| *other != address);
+            }
             SwarmEvent::IncomingConnection { .. }
             | SwarmEvent::Dialing { .. }
             | SwarmEvent::NewExternalAddrCandidate { .. }
@@ -349,10 +613,59 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             mixed_behaviour::ExternalEvent::GossipSub(event) => {
                 self.handle_gossipsub_behaviour_event(event)?;
             }
+            mixed_behaviour::ExternalEvent::Autonat(event) => {
+                self.handle_autonat_event(event);
+            }
         }
         Ok(())
     }
 
+    fn handle_autonat_event(&mut self, event: autonat_impl::ExternalEvent) {
+        let autonat_impl::ExternalEvent::ReachabilityStatusChanged(reachability_status) = event;
+        match &reachability_status {
+            ReachabilityStatus::Public(address) => {
+                info!("Peers can reach this node on {address:?}.");
+                gauge!(papyrus_metrics::PAPYRUS_IS_PUBLICLY_REACHABLE, 1f64);
+            }
+            ReachabilityStatus::Private => {
+                warn!(
+                    "Peers fail to dial this node. It's probably behind a NAT or a firewall, so \
+                     it won't receive inbound connections."
+                );
+                gauge!(papyrus_metrics::PAPYRUS_IS_PUBLICLY_REACHABLE, 0f64);
+            }
+            ReachabilityStatus::Unknown => {
+                debug!("The reachability status of this node is unknown.");
+            }
+        }
+        self.reachability_status = reachability_status;
+    }
+
+    fn handle_network_command(&mut self, network_command: NetworkCommand) {
+        // In all the answers below, the requester may have stopped waiting for the answer.
+        match network_command {
+            NetworkCommand::TopicPeerCount { topic_hash, peer_count_sender } => {
+                let _ = peer_count_sender.send(self.topic_peer_count(&topic_hash));
+            }
+            NetworkCommand::SessionsInfo { sessions_info_sender } => {
+                let _ = sessions_info_sender.send(self.sessions_info());
+            }
+            NetworkCommand::SendToPeers { topic_hash, message, peers } => {
+                self.send_message_to_peers(topic_hash, message, peers);
+            }
+            NetworkCommand::ReachabilityStatus { reachability_status_sender } => {
+                let _ = reachability_status_sender.send(self.reachability_status.clone());
+            }
+            NetworkCommand::ListeningAddresses { listening_addresses_sender } => {
+                if self.listening_addresses.is_empty() {
+                    self.pending_listening_addresses_senders.push(listening_addresses_sender);
+                } else {
+                    let _ = listening_addresses_sender.send(self.listening_addresses.clone());
+                }
+            }
+        }
+    }
+
     // TODO(shahak): Move this logic to mixed_behaviour.
     fn handle_to_other_behaviour_event(&mut self, event: mixed_behaviour::ToOtherBehaviourEvent) {
         if let mixed_behaviour::ToOtherBehaviourEvent::NoOp = event {
@@ -403,11 +716,35 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         inbound_session_id: InboundSessionId,
         query: Vec<u8>,
     ) {
+        self.active_sessions
+            .insert(inbound_session_id.into(), (protocol_name.clone(), Instant::now()));
         self.num_active_inbound_sessions += 1;
         gauge!(
             papyrus_metrics::PAPYRUS_NUM_ACTIVE_INBOUND_SESSIONS,
             self.num_active_inbound_sessions as f64
         );
+        if protocol_name == HEARTBEAT_PROTOCOL {
+            self.respond_to_heartbeat(inbound_session_id);
+            return;
+        }
+        if !self.inbound_query_rate_limiter.try_accept_query(peer_id) {
+            debug!(
+                "Peer {peer_id:?} exceeded the inbound query rate limit. Rejecting the query of \
+                 session {inbound_session_id:?}."
+            );
+            increment_counter!(papyrus_metrics::PAPYRUS_NUM_REJECTED_INBOUND_QUERIES);
+            if let Err(e) = self.swarm.close_inbound_session(inbound_session_id) {
+                error!(
+                    "Failed to reject inbound query. Session id: {inbound_session_id:?} not found \
+                     error: {e:?}"
+                );
+            }
+            return;
+        }
+        if protocol_name == DIRECT_MESSAGE_PROTOCOL {
+            self.handle_direct_message(peer_id, inbound_session_id, query);
+            return;
+        }
         let (report_sender, report_receiver) = oneshot::channel::<()>();
         self.handle_new_report_receiver(peer_id, report_receiver);
         // TODO: consider returning error instead of panic.
@@ -431,7 +768,7 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         // TODO(shahak): Close the inbound session if the buffer is full.
         send_now(
             query_sender,
-            SqmrServerPayload { query, report_sender, responses_sender },
+            SqmrServerPayload { query, report_sender, responses_sender, protocol: protocol_name },
             format!(
                 "Received an inbound query while the buffer is full. Dropping query for session \
                  {inbound_session_id:?}"
@@ -449,10 +786,24 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
             "Received response from peer for session id: {outbound_session_id:?}. sending to sync \
              subscriber."
         );
-        if let Some(report_receiver) =
+        if self.heartbeat.on_response(outbound_session_id) {
+            trace!("Peer {peer_id:?} answered heartbeat on session {outbound_session_id:?}.");
+            return;
+        }
+        if let Some((report_receiver, negotiated_protocol_sender)) =
             self.sqmr_outbound_report_receivers_awaiting_assignment.remove(&outbound_session_id)
         {
-            self.handle_new_report_receiver(peer_id, report_receiver)
+            self.handle_new_report_receiver(peer_id, report_receiver);
+            // The protocol is negotiated before any response is received.
+            if let Some(protocol) = self.swarm.get_negotiated_protocol(outbound_session_id) {
+                if let Some((active_session_protocol, _)) =
+                    self.active_sessions.get_mut(&outbound_session_id.into())
+                {
+                    *active_session_protocol = protocol.clone();
+                }
+                // The client may have dropped the session already.
+                let _ = negotiated_protocol_sender.send(protocol);
+            }
         }
         if let Some(response_sender) =
             self.sqmr_outbound_response_senders.get_mut(&outbound_session_id)
@@ -471,9 +822,18 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
 
     fn handle_sqmr_event_session_failed(&mut self, session_id: SessionId, error: SessionError) {
         error!("Session {session_id:?} failed on {error:?}");
+        self.active_sessions.remove(&session_id);
         self.report_session_removed_to_metrics(session_id);
         // TODO: Handle reputation and retry.
         if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+            // A peer that doesn't support heartbeats isn't unresponsive, so its heartbeat
+            // shouldn't time out.
+            if matches!(error, SessionError::RemoteDoesntSupportProtocol)
+                && self.heartbeat.on_response(outbound_session_id)
+            {
+                debug!("Peer doesn't support heartbeats. Session id: {outbound_session_id:?}");
+                return;
+            }
             self.sqmr_outbound_response_senders.remove(&outbound_session_id);
             if let Some(_report_receiver) =
                 self.sqmr_outbound_report_receivers_awaiting_assignment.remove(&outbound_session_id)
@@ -488,6 +848,7 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
 
     fn handle_sqmr_event_session_finished_successfully(&mut self, session_id: SessionId) {
         debug!("Session completed successfully. session_id: {session_id:?}");
+        self.active_sessions.remove(&session_id);
         self.report_session_removed_to_metrics(session_id);
         if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
             self.sqmr_outbound_response_senders.remove(&outbound_session_id);
@@ -506,10 +867,18 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         &mut self,
         event: gossipsub_impl::ExternalEvent,
     ) -> Result<(), NetworkError> {
-        let gossipsub_impl::ExternalEvent::Received { originated_peer_id, message, topic_hash } =
-            event;
+        let gossipsub_impl::ExternalEvent::Received {
+            originated_peer_id,
+            message,
+            topic_hash,
+            message_id,
+            propagation_source,
+        } = event;
+        self.topic_peer_count(&topic_hash);
         let broadcasted_message_metadata = BroadcastedMessageMetadata {
             originator_id: OpaquePeerId::private_new(originated_peer_id),
+            message_id: OpaqueMessageId::private_new(message_id.0),
+            propagation_source: OpaquePeerId::private_new(propagation_source),
         };
         let Some(sender) = self.broadcasted_messages_senders.get_mut(&topic_hash) else {
             panic!(
@@ -556,11 +925,17 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
 
     fn handle_local_sqmr_payload(
         &mut self,
-        protocol: StreamProtocol,
+        protocols: Vec<StreamProtocol>,
         client_payload: SqmrClientPayload,
     ) {
-        let SqmrClientPayload { query, report_receiver, responses_sender } = client_payload;
-        match self.swarm.send_query(query, PeerId::random(), protocol.clone()) {
+        let SqmrClientPayload {
+            query,
+            report_receiver,
+            responses_sender,
+            negotiated_protocol_sender,
+        } = client_payload;
+        let preferred_protocol = protocols.first().cloned();
+        match self.swarm.send_query(query, PeerId::random(), protocols) {
             #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
             Ok(outbound_session_id) => {
                 debug!(
@@ -572,9 +947,13 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
                     papyrus_metrics::PAPYRUS_NUM_ACTIVE_OUTBOUND_SESSIONS,
                     self.num_active_outbound_sessions as f64
                 );
+                if let Some(protocol) = preferred_protocol {
+                    self.active_sessions
+                        .insert(outbound_session_id.into(), (protocol, Instant::now()));
+                }
                 self.sqmr_outbound_response_senders.insert(outbound_session_id, responses_sender);
                 self.sqmr_outbound_report_receivers_awaiting_assignment
-                    .insert(outbound_session_id, report_receiver);
+                    .insert(outbound_session_id, (report_receiver, negotiated_protocol_sender));
             }
             Err(e) => {
                 info!(
@@ -585,10 +964,202 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
         }
     }
 
+    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+    fn handle_heartbeat_event(&mut self, event: HeartbeatEvent) {
+        match event {
+            HeartbeatEvent::SendHeartbeats => {
+                for peer_id in self.swarm.connected_peer_ids() {
+                    match self.swarm.send_query_to_peer(Bytes::new(), peer_id, HEARTBEAT_PROTOCOL) {
+                        Ok(outbound_session_id) => {
+                            self.num_active_outbound_sessions += 1;
+                            gauge!(
+                                papyrus_metrics::PAPYRUS_NUM_ACTIVE_OUTBOUND_SESSIONS,
+                                self.num_active_outbound_sessions as f64
+                            );
+                            self.active_sessions.insert(
+                                outbound_session_id.into(),
+                                (HEARTBEAT_PROTOCOL, Instant::now()),
+                            );
+                            self.heartbeat.on_heartbeat_sent(outbound_session_id);
+                        }
+                        Err(e) => {
+                            debug!("Failed to send heartbeat to peer {peer_id:?}. Error: {e:?}")
+                        }
+                    }
+                }
+            }
+            HeartbeatEvent::Timeout(outbound_session_id) => {
+                match self
+                    .swarm
+                    .get_peer_id_from_session_id(SessionId::OutboundSessionId(outbound_session_id))
+                {
+                    Ok(peer_id) => {
+                        warn!(
+                            "Peer {peer_id:?} didn't answer heartbeat on session \
+                             {outbound_session_id:?}. Reporting it as unresponsive."
+                        );
+                        self.swarm.report_peer_as_unresponsive(peer_id);
+                    }
+                    // The session was already closed, e.g. because the peer disconnected.
+                    Err(_) => debug!(
+                        "Heartbeat session {outbound_session_id:?} timed out after it was closed."
+                    ),
+                }
+            }
+        }
+    }
+
+    fn respond_to_heartbeat(&mut self, inbound_session_id: InboundSessionId) {
+        let result = self
+            .swarm
+            .send_response(Bytes::new(), inbound_session_id)
+            .and_then(|()| self.swarm.close_inbound_session(inbound_session_id));
+        if let Err(e) = result {
+            error!(
+                "Failed to respond to heartbeat. Session id: {inbound_session_id:?} not found \
+                 error: {e:?}"
+            );
+        }
+    }
+
+    fn handle_direct_message(
+        &mut self,
+        peer_id: PeerId,
+        inbound_session_id: InboundSessionId,
+        query: Vec<u8>,
+    ) {
+        if let Err(e) = self.swarm.close_inbound_session(inbound_session_id) {
+            error!(
+                "Failed to close direct message session. Session id: {inbound_session_id:?} not \
+                 found error: {e:?}"
+            );
+        }
+        let Some((topic_hash, message)) = decode_direct_message(query) else {
+            warn!("Peer {peer_id:?} sent a malformed direct message. Dropping it.");
+            return;
+        };
+        if !self.direct_message_topics.contains(&topic_hash) {
+            warn!(
+                "Peer {peer_id:?} sent a direct message on a topic that doesn't allow direct \
+                 messages with hash {topic_hash:?}. Dropping it."
+            );
+            return;
+        }
+        let Some(sender) = self.broadcasted_messages_senders.get_mut(&topic_hash) else {
+            warn!(
+                "Peer {peer_id:?} sent a direct message on a topic we're not subscribed to with \
+                 hash {topic_hash:?}. Dropping it."
+            );
+            return;
+        };
+        let broadcasted_message_metadata = BroadcastedMessageMetadata {
+            originator_id: OpaquePeerId::private_new(peer_id),
+            message_id: OpaqueMessageId::private_new(
+                inbound_session_id.value.to_be_bytes().to_vec(),
+            ),
+            propagation_source: OpaquePeerId::private_new(peer_id),
+        };
+        if let Err(e) = sender.try_send((message, broadcasted_message_metadata)) {
+            warn!(
+                "Failed to deliver direct message for topic with hash {topic_hash:?}. Dropping \
+                 it. Error: {e:?}"
+            );
+        }
+    }
+
+    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+    fn send_message_to_peers(&mut self, topic_hash: TopicHash, message: Bytes, peers: Vec<PeerId>) {
+        if !self.direct_message_topics.contains(&topic_hash) {
+            warn!(
+                "Tried to send a message directly to peers on a topic that doesn't allow direct \
+                 messages with hash {topic_hash:?}. Dropping it."
+            );
+            return;
+        }
+        let query = encode_direct_message(&topic_hash, &message);
+        for peer_id in peers {
+            match self.swarm.send_query_to_peer(query.clone(), peer_id, DIRECT_MESSAGE_PROTOCOL) {
+                Ok(outbound_session_id) => {
+                    self.num_active_outbound_sessions += 1;
+                    gauge!(
+                        papyrus_metrics::PAPYRUS_NUM_ACTIVE_OUTBOUND_SESSIONS,
+                        self.num_active_outbound_sessions as f64
+                    );
+                    self.active_sessions.insert(
+                        outbound_session_id.into(),
+                        (DIRECT_MESSAGE_PROTOCOL, Instant::now()),
+                    );
+                }
+                Err(e) => warn!(
+                    "Failed to send message on topic with hash {topic_hash:?} to peer \
+                     {peer_id:?}. Error: {e:?}"
+                ),
+            }
+        }
+    }
+
     fn broadcast_message(&mut self, message: Bytes, topic_hash: TopicHash) {
+        if let Some(last_message) = self.sticky_topic_last_messages.get_mut(&topic_hash) {
+            *last_message = Some(message.clone());
+        }
+        self.topic_peer_count(&topic_hash);
         self.swarm.broadcast_message(message, topic_hash);
     }
 
+    fn sessions_info(&self) -> Vec<SessionInfo> {
+        let now = Instant::now();
+        self.active_sessions
+            .iter()
+            .map(|(session_id, (protocol, start_time))| SessionInfo {
+                protocol: protocol.clone(),
+                // Outbound sessions aren't assigned to a peer until the peer manager picks one.
+                peer_id: self.swarm.get_peer_id_from_session_id(*session_id).ok(),
+                direction: match session_id {
+                    SessionId::InboundSessionId(_) => SessionDirection::Inbound,
+                    SessionId::OutboundSessionId(_) => SessionDirection::Outbound,
+                },
+                age: now.duration_since(*start_time),
+            })
+            .collect()
+    }
+
+    // Returns the number of peers in our mesh for the given topic and reports it to the metrics.
+    fn topic_peer_count(&self, topic_hash: &TopicHash) -> usize {
+        let peer_count = self.swarm.topic_peer_count(topic_hash);
+        #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+        gauge!(
+            papyrus_metrics::PAPYRUS_NUM_TOPIC_MESH_PEERS,
+            peer_count as f64,
+            "topic" => topic_hash.to_string()
+        );
+        peer_count
+    }
+
+    fn update_topic_mesh_metrics(&self) {
+        for topic_hash in self.broadcasted_messages_senders.keys() {
+            self.topic_peer_count(topic_hash);
+        }
+    }
+
+    // Only the new peer gets the messages, so that the rest of the topic isn't flooded whenever
+    // someone connects.
+    fn send_sticky_messages_to_peer(&mut self, peer_id: PeerId) {
+        let sticky_messages = self
+            .sticky_topic_last_messages
+            .iter()
+            .filter_map(|(topic_hash, last_message)| {
+                last_message.clone().map(|message| (topic_hash.clone(), message))
+            })
+            .collect::<Vec<_>>();
+        for (topic_hash, message) in sticky_messages {
+            debug!(
+                "Sending last message of sticky topic with hash {topic_hash:?} to new peer \
+                 {peer_id:?}."
+            );
+            self.send_message_to_peers(topic_hash, message, vec![peer_id]);
+        }
+    }
+
     fn report_session_removed_to_metrics(&mut self, session_id: SessionId) {
         #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
         match session_id {
@@ -620,6 +1191,36 @@ impl<SwarmT: SwarmTrait> GenericNetworkManager<SwarmT> {
     }
 }
 
+// A dot separated part of a protocol version. Parts that aren't numbers (e.g. pre-release tags)
+// are ordered below the numeric ones.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum VersionPart {
+    Text(String),
+    Number(u64),
+}
+
+// Returns the protocol name of each version, from the highest version to the lowest.
+fn versioned_protocols(protocol: &str, mut versions: Vec<String>) -> Vec<StreamProtocol> {
+    versions.sort_by_cached_key(|version| {
+        std::cmp::Reverse(
+            version
+                .split('.')
+                .map(|part| {
+                    part.parse::<u64>()
+                        .map_or_else(|_| VersionPart::Text(part.to_string()), VersionPart::Number)
+                })
+                .collect::<Vec<_>>(),
+        )
+    });
+    versions
+        .into_iter()
+        .map(|version| {
+            StreamProtocol::try_from_owned(format!("{protocol}/{version}"))
+                .expect("Could not parse protocol into StreamProtocol.")
+        })
+        .collect()
+}
+
 fn send_now<Item>(sender: &mut GenericSender<Item>, item: Item, buffer_full_message: String) {
     pin_mut!(sender);
     match sender.as_mut().send(item).now_or_never() {
@@ -633,6 +1234,35 @@ fn send_now<Item>(sender: &mut GenericSender<Item>, item: Item, buffer_full_mess
     }
 }
 
+impl<SwarmT: SwarmTrait> Drop for GenericNetworkManager<SwarmT> {
+    fn drop(&mut self) {
+        if let Some(routing_table_path) = &self.routing_table_path {
+            persist_routing_table(&mut self.swarm.behaviour_mut().kademlia, routing_table_path);
+        }
+    }
+}
+
+// A direct message is the length of the topic hash as a big endian u32, the topic hash and then
+// the message itself.
+fn encode_direct_message(topic_hash: &TopicHash, message: &[u8]) -> Bytes {
+    let topic_hash = topic_hash.as_str().as_bytes();
+    let topic_hash_len =
+        u32::try_from(topic_hash.len()).expect("Topic hash length should fit in u32");
+    let mut query = Vec::with_capacity(4 + topic_hash.len() + message.len());
+    query.extend_from_slice(&topic_hash_len.to_be_bytes());
+    query.extend_from_slice(topic_hash);
+    query.extend_from_slice(message);
+    query
+}
+
+fn decode_direct_message(mut query: Bytes) -> Option<(TopicHash, Bytes)> {
+    let topic_hash_len_bytes: [u8; 4] = query.get(..4)?.try_into().ok()?;
+    let topic_hash_end = 4 + usize::try_from(u32::from_be_bytes(topic_hash_len_bytes)).ok()?;
+    let topic_hash = String::from_utf8(query.get(4..topic_hash_end)?.to_vec()).ok()?;
+    let message = query.split_off(topic_hash_end);
+    Some((TopicHash::from_raw(topic_hash), message))
+}
+
 pub type NetworkManager = GenericNetworkManager<Swarm<mixed_behaviour::MixedBehaviour>>;
 
 impl NetworkManager {
@@ -647,38 +1277,155 @@ impl NetworkManager {
             chain_id,
             discovery_config,
             peer_manager_config,
+            security_config,
+            gossipsub_validation_mode,
+            default_protocol_buffer_size,
+            enable_autonat,
         } = config;
+        let heartbeat = Heartbeat::new(
+            peer_manager_config.heartbeat_interval,
+            peer_manager_config.heartbeat_timeout,
+        );
+        let inbound_query_rate_limiter =
+            InboundQueryRateLimiter::new(peer_manager_config.max_inbound_queries_per_sec);
+        security_config.validate().expect("Invalid network security config");
+
+        let routing_table_path = discovery_config.routing_table_path.clone();
 
         // TODO(shahak): Add quic transport.
         let listen_addresses = vec![format!("/ip4/0.0.0.0/tcp/{tcp_port}")];
 
-        let swarm = build_swarm(listen_addresses, idle_connection_timeout, secret_key, |key| {
-            mixed_behaviour::MixedBehaviour::new(
-                key,
-                bootstrap_peer_multiaddr.clone(),
-                sqmr::Config { session_timeout },
-                chain_id,
-                node_version,
-                discovery_config,
-                peer_manager_config,
-            )
-        });
+        let swarm = build_swarm(
+            listen_addresses,
+            idle_connection_timeout,
+            secret_key,
+            &security_config,
+            |key| {
+                mixed_behaviour::MixedBehaviour::new(
+                    key,
+                    bootstrap_peer_multiaddr.clone(),
+                    sqmr::Config { session_timeout },
+                    chain_id,
+                    node_version,
+                    discovery_config,
+                    peer_manager_config,
+                    gossipsub_validation_mode,
+                    enable_autonat,
+                )
+            },
+        );
         let advertised_multiaddr = advertised_multiaddr.map(|address| {
             address
                 .with_p2p(*swarm.local_peer_id())
                 .expect("advertised_multiaddr has a peer id different than the local peer id")
         });
-        Self::generic_new(swarm, advertised_multiaddr)
+        let mut network_manager = Self::generic_new(swarm, advertised_multiaddr);
+        network_manager.routing_table_path = routing_table_path;
+        // Answering heartbeats is always on, so that peers which enabled them won't flag us.
+        network_manager.swarm.add_new_supported_inbound_protocol(HEARTBEAT_PROTOCOL);
+        network_manager.heartbeat = heartbeat;
+        network_manager.inbound_query_rate_limiter = inbound_query_rate_limiter;
+        network_manager.gossipsub_validation_mode = gossipsub_validation_mode;
+        network_manager.default_protocol_buffer_size = default_protocol_buffer_size;
+        network_manager
     }
 
     pub fn get_local_peer_id(&self) -> String {
         self.swarm.local_peer_id().to_string()
     }
+
+    /// Sets a callback that is called with a peer found through discovery and its address when
+    /// dialing it fails before we ever connected to it. Useful for diagnosing NAT and
+    /// connectivity issues.
+    pub fn set_unreachable_discovered_peer_callback(
+        &mut self,
+        callback: impl Fn(PeerId, &Multiaddr) + Send + 'static,
+    ) {
+        self.swarm
+            .behaviour_mut()
+            .peer_manager
+            .set_unreachable_discovered_peer_callback(Box::new(callback));
+    }
+}
+
+/// Inspects the state of a running network manager. All the methods fail if the network manager
+/// stopped running.
+#[derive(Clone)]
+pub struct NetworkClient {
+    network_commands_sender: Sender<NetworkCommand>,
+}
+
+impl NetworkClient {
+    /// Returns the number of peers in the network manager's gossipsub mesh for the given topic.
+    pub async fn topic_peer_count(&mut self, topic: &Topic) -> Result<usize, oneshot::Canceled> {
+        self.request(|peer_count_sender| NetworkCommand::TopicPeerCount {
+            topic_hash: topic.hash(),
+            peer_count_sender,
+        })
+        .await
+    }
+
+    /// Returns a snapshot of the SQMR sessions that are in flight.
+    pub async fn sessions_info(&mut self) -> Result<Vec<SessionInfo>, oneshot::Canceled> {
+        self.request(|sessions_info_sender| NetworkCommand::SessionsInfo { sessions_info_sender })
+            .await
+    }
+
+    /// Returns the last reachability status AutoNAT determined. This is always
+    /// [`ReachabilityStatus::Unknown`] if `enable_autonat` is off.
+    pub async fn reachability_status(&mut self) -> Result<ReachabilityStatus, oneshot::Canceled> {
+        self.request(|reachability_status_sender| NetworkCommand::ReachabilityStatus {
+            reachability_status_sender,
+        })
+        .await
+    }
+
+    /// Waits until the network manager listens on at least one address and returns the addresses
+    /// it listens on. These contain the actual port, which differs from the configured one if
+    /// it's 0.
+    pub async fn listening_addresses(&mut self) -> Result<Vec<Multiaddr>, oneshot::Canceled> {
+        self.request(|listening_addresses_sender| NetworkCommand::ListeningAddresses {
+            listening_addresses_sender,
+        })
+        .await
+    }
+
+    async fn request<T>(
+        &mut self,
+        network_command_fn: impl FnOnce(oneshot::Sender<T>) -> NetworkCommand,
+    ) -> Result<T, oneshot::Canceled> {
+        let (answer_sender, answer_receiver) = oneshot::channel();
+        self.network_commands_sender
+            .send(network_command_fn(answer_sender))
+            .await
+            .map_err(|_| oneshot::Canceled)?;
+        answer_receiver.await
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionDirection {
+    Inbound,
+    Outbound,
+}
+
+/// A snapshot of an SQMR session that didn't finish yet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionInfo {
+    /// For outbound sessions, this is the most preferred protocol the query offered.
+    pub protocol: StreamProtocol,
+    /// None if the session wasn't assigned to a peer yet.
+    pub peer_id: Option<PeerId>,
+    pub direction: SessionDirection,
+    pub age: Duration,
 }
 
 pub type ReportSender = oneshot::Sender<()>;
 type ReportReceiver = oneshot::Receiver<()>;
 
+type NegotiatedProtocolSender = oneshot::Sender<StreamProtocol>;
+type NegotiatedProtocolReceiver = oneshot::Receiver<StreamProtocol>;
+
 type GenericSender<T> = Box<dyn Sink<T, Error = SendError> + Unpin + Send>;
 // Box<S> implements Stream only if S: Stream + Unpin
 pub type GenericReceiver<T> = Box<dyn Stream<Item = T> + Unpin + Send>;
@@ -739,21 +1486,34 @@ where
         query: Query,
     ) -> Result<ClientResponsesManager<Response>, SendError> {
         let (report_sender, report_receiver) = oneshot::channel::<()>();
+        let (negotiated_protocol_sender, negotiated_protocol_receiver) = oneshot::channel();
         let (responses_sender, responses_receiver) =
             futures::channel::mpsc::channel(self.buffer_size);
         let responses_receiver = Box::new(responses_receiver);
         let query = Bytes::from(query);
         let responses_sender =
             Box::new(responses_sender.with(|response| ready(Ok(Response::try_from(response)))));
-        let payload = SqmrClientPayload { query, report_receiver, responses_sender };
+        let payload = SqmrClientPayload {
+            query,
+            report_receiver,
+            responses_sender,
+            negotiated_protocol_sender,
+        };
         self.sender.send(payload).await?;
-        Ok(ClientResponsesManager { report_sender, responses_receiver })
+        Ok(ClientResponsesManager {
+            report_sender,
+            responses_receiver,
+            negotiated_protocol_receiver,
+            negotiated_protocol: None,
+        })
     }
 }
 
 pub struct ClientResponsesManager<Response: TryFrom<Bytes>> {
     report_sender: ReportSender,
     pub(crate) responses_receiver: ClientResponsesReceiver<Response>,
+    negotiated_protocol_receiver: NegotiatedProtocolReceiver,
+    negotiated_protocol: Option<StreamProtocol>,
 }
 
 impl<Response: TryFrom<Bytes>> ClientResponsesManager<Response> {
@@ -764,6 +1524,17 @@ impl<Response: TryFrom<Bytes>> ClientResponsesManager<Response> {
             error!("Failed to report peer. Error: {e:?}");
         }
     }
+
+    /// Returns the protocol negotiated with the peer for this query. The protocol is known once
+    /// the first response was received, and None is returned before that.
+    pub fn negotiated_protocol(&mut self) -> Option<StreamProtocol> {
+        if self.negotiated_protocol.is_none() {
+            if let Ok(Some(protocol)) = self.negotiated_protocol_receiver.try_recv() {
+                self.negotiated_protocol = Some(protocol);
+            }
+        }
+        self.negotiated_protocol.clone()
+    }
 }
 
 impl<Response: TryFrom<Bytes>> Stream for ClientResponsesManager<Response> {
@@ -780,6 +1551,7 @@ pub struct SqmrClientPayload {
     query: Bytes,
     report_receiver: ReportReceiver,
     responses_sender: ResponsesSender,
+    negotiated_protocol_sender: NegotiatedProtocolSender,
 }
 
 pub struct SqmrServerReceiver<Query, Response>
@@ -810,6 +1582,7 @@ where
     query: Result<Query, <Query as TryFrom<Bytes>>::Error>,
     report_sender: ReportSender,
     responses_sender: ServerResponsesSender<Response>,
+    protocol: StreamProtocol,
 }
 
 impl<Query, Response> ServerQueryManager<Query, Response>
@@ -821,6 +1594,12 @@ where
         &self.query
     }
 
+    /// Returns the protocol the query was received on, i.e. the version of the protocol that was
+    /// negotiated with the peer.
+    pub fn protocol(&self) -> &StreamProtocol {
+        &self.protocol
+    }
+
     pub fn report_peer(self) {
         debug!("Reporting peer from server to network");
         if let Err(e) = self.report_sender.send(()) {
@@ -847,13 +1626,13 @@ where
     Query: TryFrom<Bytes>,
 {
     fn from(payload: SqmrServerPayload) -> Self {
-        let SqmrServerPayload { query, report_sender, responses_sender } = payload;
+        let SqmrServerPayload { query, report_sender, responses_sender, protocol } = payload;
         let query = Query::try_from(query);
         let responses_sender =
             Box::new(responses_sender.with(|response| ready(Ok(Bytes::from(response)))));
         let responses_sender = ServerResponsesSender { sender: responses_sender };
 
-        Self { query, report_sender, responses_sender }
+        Self { query, report_sender, responses_sender, protocol }
     }
 }
 
@@ -863,6 +1642,7 @@ struct SqmrServerPayload {
     query: Bytes,
     report_sender: ReportSender,
     responses_sender: ResponsesSender,
+    protocol: StreamProtocol,
 }
 
 #[async_trait]
@@ -880,7 +1660,9 @@ pub trait BroadcastTopicClientTrait<T> {
 
 #[derive(Clone)]
 pub struct BroadcastTopicClient<T: TryFrom<Bytes>> {
+    topic_hash: TopicHash,
     messages_to_broadcast_sender: BroadcastTopicSender<T, Bytes>,
+    network_commands_sender: Sender<NetworkCommand>,
     reported_messages_sender: BroadcastTopicSender<BroadcastedMessageMetadata, PeerId>,
     continue_propagation_sender: Sender<BroadcastedMessageMetadata>,
 }
@@ -888,18 +1670,43 @@ pub struct BroadcastTopicClient<T: TryFrom<Bytes>> {
 impl<T: TryFrom<Bytes>> BroadcastTopicClient<T> {
     // TODO(matan): Remove once consensus_manager no longer needs to build fake channels.
     pub fn new(
+        topic_hash: TopicHash,
         messages_to_broadcast_sender: BroadcastTopicSender<T, Bytes>,
+        network_commands_sender: Sender<NetworkCommand>,
         reported_messages_sender: BroadcastTopicSender<BroadcastedMessageMetadata, PeerId>,
         continue_propagation_sender: Sender<BroadcastedMessageMetadata>,
     ) -> Self {
         BroadcastTopicClient {
+            topic_hash,
             messages_to_broadcast_sender,
+            network_commands_sender,
             reported_messages_sender,
             continue_propagation_sender,
         }
     }
 }
 
+impl<T: TryFrom<Bytes>> BroadcastTopicClient<T>
+where
+    Bytes: From<T>,
+{
+    /// Sends the message only to the given peers, directly instead of through gossipsub. The
+    /// peers receive it on this topic as if it was broadcasted, but they don't propagate it
+    /// further. Peers we're not connected to are skipped. The message is dropped unless the topic
+    /// was registered with
+    /// [`register_broadcast_topic_with_direct_messages`](GenericNetworkManager::register_broadcast_topic_with_direct_messages)
+    /// or as a sticky topic, and peers only accept it on such topics.
+    pub async fn send_to_peers(&mut self, message: T, peers: &[PeerId]) -> Result<(), SendError> {
+        self.network_commands_sender
+            .send(NetworkCommand::SendToPeers {
+                topic_hash: self.topic_hash.clone(),
+                message: Bytes::from(message),
+                peers: peers.to_vec(),
+            })
+            .await
+    }
+}
+
 #[async_trait]
 impl<T: TryFrom<Bytes> + Send> BroadcastTopicClientTrait<T> for BroadcastTopicClient<T> {
     async fn broadcast_message(&mut self, message: T) -> Result<(), SendError> {
@@ -1,9 +1,9 @@
 use futures::stream::Stream;
-use libp2p::gossipsub::{SubscriptionError, TopicHash};
+use libp2p::gossipsub::{MessageAcceptance, MessageId, SubscriptionError, TopicHash};
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::{DialError, NetworkBehaviour, SwarmEvent};
 use libp2p::{Multiaddr, PeerId, StreamProtocol, Swarm};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use super::BroadcastedMessageMetadata;
 use crate::gossipsub_impl::Topic;
@@ -21,7 +21,23 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
         inbound_session_id: InboundSessionId,
     ) -> Result<(), SessionIdNotFoundError>;
 
+    /// Sends a query offering the given protocols, ordered by preference.
     fn send_query(
+        &mut self,
+        query: Vec<u8>,
+        peer_id: PeerId,
+        protocols: Vec<StreamProtocol>,
+    ) -> Result<OutboundSessionId, PeerNotConnected>;
+
+    /// Returns the protocol that was negotiated for the given outbound session, if the session
+    /// was already negotiated.
+    fn get_negotiated_protocol(
+        &self,
+        outbound_session_id: OutboundSessionId,
+    ) -> Option<StreamProtocol>;
+
+    /// Sends a query directly to the given peer, without going through the peer manager.
+    fn send_query_to_peer(
         &mut self,
         query: Vec<u8>,
         peer_id: PeerId,
@@ -32,6 +48,8 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
 
     fn num_connected_peers(&self) -> usize;
 
+    fn connected_peer_ids(&self) -> Vec<PeerId>;
+
     fn close_inbound_session(
         &mut self,
         session_id: InboundSessionId,
@@ -53,9 +71,15 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
     // TODO: change this to report_peer and add an argument for the score.
     fn report_peer_as_malicious(&mut self, peer_id: PeerId);
 
+    /// Reports a peer that stopped answering queries and disconnects from it.
+    fn report_peer_as_unresponsive(&mut self, peer_id: PeerId);
+
     fn add_new_supported_inbound_protocol(&mut self, protocol_name: StreamProtocol);
 
     fn continue_propagation(&mut self, message_metadata: BroadcastedMessageMetadata);
+
+    /// Returns the number of peers in our gossipsub mesh for the given topic.
+    fn topic_peer_count(&self, topic_hash: &TopicHash) -> usize;
 }
 
 impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
@@ -72,9 +96,25 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
         &mut self,
         query: Vec<u8>,
         _peer_id: PeerId,
+        protocols: Vec<StreamProtocol>,
+    ) -> Result<OutboundSessionId, PeerNotConnected> {
+        Ok(self.behaviour_mut().sqmr.start_query_with_protocol_versions(query, protocols))
+    }
+
+    fn get_negotiated_protocol(
+        &self,
+        outbound_session_id: OutboundSessionId,
+    ) -> Option<StreamProtocol> {
+        self.behaviour().sqmr.get_negotiated_protocol(outbound_session_id.into()).ok()
+    }
+
+    fn send_query_to_peer(
+        &mut self,
+        query: Vec<u8>,
+        peer_id: PeerId,
         protocol: StreamProtocol,
     ) -> Result<OutboundSessionId, PeerNotConnected> {
-        Ok(self.behaviour_mut().sqmr.start_query(query, protocol))
+        self.behaviour_mut().sqmr.start_query_to_peer(query, protocol, peer_id)
     }
 
     fn dial(&mut self, peer_multiaddr: Multiaddr) -> Result<(), DialError> {
@@ -84,6 +124,11 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
     fn num_connected_peers(&self) -> usize {
         self.network_info().num_peers()
     }
+
+    fn connected_peer_ids(&self) -> Vec<PeerId> {
+        self.connected_peers().copied().collect()
+    }
+
     fn close_inbound_session(
         &mut self,
         session_id: InboundSessionId,
@@ -133,10 +178,37 @@ impl SwarmTrait for Swarm<mixed_behaviour::MixedBehaviour> {
             .report_peer(peer_id, ReputationModifier::Misconduct { misconduct_score: MALICIOUS });
     }
 
+    fn report_peer_as_unresponsive(&mut self, peer_id: PeerId) {
+        let _ =
+            self.behaviour_mut().peer_manager.report_peer(peer_id, ReputationModifier::Unstable);
+        if self.disconnect_peer_id(peer_id).is_err() {
+            warn!("Tried to disconnect from peer {peer_id:?} which is already disconnected.");
+        }
+    }
+
     fn add_new_supported_inbound_protocol(&mut self, protocol: StreamProtocol) {
         self.behaviour_mut().sqmr.add_new_supported_inbound_protocol(protocol);
     }
 
-    // TODO(shahak): Implement this function.
-    fn continue_propagation(&mut self, _message_metadata: BroadcastedMessageMetadata) {}
+    fn continue_propagation(&mut self, message_metadata: BroadcastedMessageMetadata) {
+        let message_id = MessageId(message_metadata.message_id.private_get_message_id());
+        let propagation_source = message_metadata.propagation_source.private_get_peer_id();
+        match self.behaviour_mut().gossipsub.report_message_validation_result(
+            &message_id,
+            &propagation_source,
+            MessageAcceptance::Accept,
+        ) {
+            Ok(true) => {}
+            // The message was removed from the cache because it's too old or it was already
+            // validated.
+            Ok(false) => {
+                debug!("Couldn't continue propagation of message {message_id:?}. It's not cached.")
+            }
+            Err(err) => warn!("Failed continuing propagation of message {message_id:?}: {err:?}"),
+        }
+    }
+
+    fn topic_peer_count(&self, topic_hash: &TopicHash) -> usize {
+        self.behaviour().gossipsub.mesh_peers(topic_hash).count()
+    }
 }
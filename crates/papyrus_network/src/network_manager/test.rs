@@ -12,21 +12,34 @@ use futures::future::FutureExt;
 use futures::stream::Stream;
 use futures::{pin_mut, Future, SinkExt, StreamExt};
 use lazy_static::lazy_static;
+use libp2p::core::multiaddr::Protocol;
 use libp2p::core::ConnectedPoint;
-use libp2p::gossipsub::{SubscriptionError, TopicHash};
+use libp2p::gossipsub::{MessageId, SubscriptionError, TopicHash};
 use libp2p::swarm::ConnectionId;
 use libp2p::{Multiaddr, PeerId, StreamProtocol};
 use tokio::select;
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 
+use super::heartbeat::{Heartbeat, HEARTBEAT_PROTOCOL};
+use super::inbound_query_rate_limiter::InboundQueryRateLimiter;
 use super::swarm_trait::{Event, SwarmTrait};
-use super::{BroadcastTopicChannels, GenericNetworkManager};
+use super::{
+    decode_direct_message,
+    encode_direct_message,
+    versioned_protocols,
+    BroadcastTopicChannels,
+    GenericNetworkManager,
+    NetworkManager,
+    SessionDirection,
+    DIRECT_MESSAGE_PROTOCOL,
+};
+use crate::autonat_impl::{self, ReachabilityStatus};
 use crate::gossipsub_impl::{self, Topic};
-use crate::mixed_behaviour;
 use crate::network_manager::{BroadcastTopicClientTrait, ServerQueryManager};
 use crate::sqmr::behaviour::{PeerNotConnected, SessionIdNotFoundError};
-use crate::sqmr::{Bytes, GenericEvent, InboundSessionId, OutboundSessionId};
+use crate::sqmr::{Bytes, GenericEvent, InboundSessionId, OutboundSessionId, SessionId};
+use crate::{mixed_behaviour, NetworkConfig};
 
 const TIMEOUT: Duration = Duration::from_secs(1);
 
@@ -38,14 +51,22 @@ lazy_static! {
 
 #[derive(Default)]
 struct MockSwarm {
-    pub pending_events: Queue<Event>,
+    pub pending_events: Arc<Queue<Event>>,
     pub subscribed_topics: HashSet<TopicHash>,
     broadcasted_messages_senders: Vec<UnboundedSender<(Bytes, TopicHash)>>,
+    queries_sent_to_peers_senders: Vec<UnboundedSender<(Bytes, PeerId, StreamProtocol)>>,
     reported_peer_senders: Vec<UnboundedSender<PeerId>>,
+    unresponsive_peer_senders: Vec<UnboundedSender<PeerId>>,
     supported_inbound_protocols_senders: Vec<UnboundedSender<StreamProtocol>>,
     inbound_session_id_to_response_sender: HashMap<InboundSessionId, UnboundedSender<Bytes>>,
     next_outbound_session_id: usize,
     first_polled_event_notifier: Option<oneshot::Sender<()>>,
+    // If set, this is the only connected peer and all outbound sessions are assigned to it.
+    connected_peer: Option<PeerId>,
+    // The peer accepts the most preferred protocol of each query.
+    outbound_session_id_to_negotiated_protocol: HashMap<OutboundSessionId, StreamProtocol>,
+    // If set, the connected peer answers every heartbeat we send it.
+    answers_heartbeats: bool,
 }
 
 impl Stream for MockSwarm {
@@ -89,12 +110,26 @@ impl MockSwarm {
         receiver
     }
 
+    pub fn stream_queries_sent_to_peers(
+        &mut self,
+    ) -> impl Stream<Item = (Bytes, PeerId, StreamProtocol)> {
+        let (sender, receiver) = unbounded();
+        self.queries_sent_to_peers_senders.push(sender);
+        receiver
+    }
+
     pub fn get_reported_peers_stream(&mut self) -> impl Stream<Item = PeerId> {
         let (sender, receiver) = unbounded();
         self.reported_peer_senders.push(sender);
         receiver
     }
 
+    pub fn get_unresponsive_peers_stream(&mut self) -> impl Stream<Item = PeerId> {
+        let (sender, receiver) = unbounded();
+        self.unresponsive_peer_senders.push(sender);
+        receiver
+    }
+
     pub fn get_supported_inbound_protocol(&mut self) -> impl Stream<Item = StreamProtocol> {
         let (sender, receiver) = unbounded();
         self.supported_inbound_protocols_senders.push(sender);
@@ -137,9 +172,12 @@ impl SwarmTrait for MockSwarm {
         &mut self,
         query: Vec<u8>,
         peer_id: PeerId,
-        _protocol: StreamProtocol,
+        protocols: Vec<StreamProtocol>,
     ) -> Result<OutboundSessionId, PeerNotConnected> {
         let outbound_session_id = OutboundSessionId { value: self.next_outbound_session_id };
+        if let Some(protocol) = protocols.into_iter().next() {
+            self.outbound_session_id_to_negotiated_protocol.insert(outbound_session_id, protocol);
+        }
         self.create_response_events_for_query_each_num_becomes_response(
             query,
             outbound_session_id,
@@ -149,11 +187,44 @@ impl SwarmTrait for MockSwarm {
         Ok(outbound_session_id)
     }
 
+    fn get_negotiated_protocol(
+        &self,
+        outbound_session_id: OutboundSessionId,
+    ) -> Option<StreamProtocol> {
+        self.outbound_session_id_to_negotiated_protocol.get(&outbound_session_id).cloned()
+    }
+
+    fn send_query_to_peer(
+        &mut self,
+        query: Vec<u8>,
+        peer_id: PeerId,
+        protocol: StreamProtocol,
+    ) -> Result<OutboundSessionId, PeerNotConnected> {
+        for sender in &self.queries_sent_to_peers_senders {
+            sender.unbounded_send((query.clone(), peer_id, protocol.clone())).unwrap();
+        }
+        let outbound_session_id = OutboundSessionId { value: self.next_outbound_session_id };
+        self.next_outbound_session_id += 1;
+        if self.answers_heartbeats && protocol == HEARTBEAT_PROTOCOL {
+            self.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+                mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::ReceivedResponse {
+                    response: Bytes::new(),
+                    outbound_session_id,
+                    peer_id,
+                }),
+            )));
+        }
+        Ok(outbound_session_id)
+    }
+
     fn dial(&mut self, _peer: Multiaddr) -> Result<(), libp2p::swarm::DialError> {
         Ok(())
     }
     fn num_connected_peers(&self) -> usize {
-        0
+        usize::from(self.connected_peer.is_some())
+    }
+    fn connected_peer_ids(&self) -> Vec<PeerId> {
+        self.connected_peer.into_iter().collect()
     }
     fn close_inbound_session(
         &mut self,
@@ -190,6 +261,13 @@ impl SwarmTrait for MockSwarm {
             sender.unbounded_send(peer_id).unwrap();
         }
     }
+
+    fn report_peer_as_unresponsive(&mut self, peer_id: PeerId) {
+        for sender in &self.unresponsive_peer_senders {
+            sender.unbounded_send(peer_id).unwrap();
+        }
+    }
+
     fn add_new_supported_inbound_protocol(&mut self, protocol_name: StreamProtocol) {
         for sender in &self.supported_inbound_protocols_senders {
             sender.unbounded_send(protocol_name.clone()).unwrap();
@@ -200,13 +278,17 @@ impl SwarmTrait for MockSwarm {
         &self,
         _session_id: crate::sqmr::SessionId,
     ) -> Result<PeerId, SessionIdNotFoundError> {
-        Ok(PeerId::random())
+        Ok(self.connected_peer.unwrap_or_else(PeerId::random))
     }
 
     // TODO (shahak): Add test for continue propagation.
     fn continue_propagation(&mut self, _message_metadata: super::BroadcastedMessageMetadata) {
         unimplemented!()
     }
+
+    fn topic_peer_count(&self, _topic_hash: &TopicHash) -> usize {
+        0
+    }
 }
 
 const BUFFER_SIZE: usize = 100;
@@ -295,8 +377,9 @@ async fn process_incoming_query() {
     let responses_clone = responses.clone();
     select! {
         _ = async move {
-            let ServerQueryManager{query: query_got, report_sender: _report_sender, mut responses_sender} = inbound_payload_receiver.next().await.unwrap();
+            let ServerQueryManager{query: query_got, report_sender: _report_sender, mut responses_sender, protocol: protocol_got} = inbound_payload_receiver.next().await.unwrap();
             assert_eq!(query_got.unwrap(), query);
+            assert_eq!(protocol_got, protocol);
             for response in responses_clone {
                 responses_sender.feed(response).await.unwrap();
             }
@@ -312,6 +395,59 @@ async fn process_incoming_query() {
     }
 }
 
+#[test]
+fn versioned_protocols_are_ordered_from_highest_version() {
+    assert_eq!(
+        versioned_protocols(
+            "/starknet/headers",
+            vec![
+                "1.0.0".to_string(),
+                "1.10.0".to_string(),
+                "1.10.0-rc".to_string(),
+                "1.2.0".to_string()
+            ]
+        ),
+        vec![
+            StreamProtocol::new("/starknet/headers/1.10.0"),
+            StreamProtocol::new("/starknet/headers/1.10.0-rc"),
+            StreamProtocol::new("/starknet/headers/1.2.0"),
+            StreamProtocol::new("/starknet/headers/1.0.0"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn client_gets_the_negotiated_protocol_version() {
+    let mut mock_swarm = MockSwarm::default();
+    mock_swarm.pending_events.push(get_test_connection_established_event(PeerId::random()));
+    let (event_notifier, first_event_listner) = oneshot::channel();
+    mock_swarm.first_polled_event_notifier = Some(event_notifier);
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+
+    let mut payload_sender = network_manager
+        .register_sqmr_protocol_client_with_versions::<Vec<u8>, Vec<u8>>(
+            "/starknet/headers".to_string(),
+            vec!["1.0.0".to_string(), "2.0.0".to_string()],
+            BUFFER_SIZE,
+        );
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        _ = first_event_listner.then(|_| async move {
+            let mut client_responses_manager =
+                payload_sender.send_new_query(VEC1.clone()).await.unwrap();
+            client_responses_manager.next().await.unwrap().unwrap();
+            assert_eq!(
+                client_responses_manager.negotiated_protocol(),
+                Some(StreamProtocol::new("/starknet/headers/2.0.0"))
+            );
+        }) => {},
+        _ = sleep(Duration::from_secs(5)) => {
+            panic!("Test timed out");
+        }
+    }
+}
+
 #[tokio::test]
 async fn broadcast_message() {
     let topic = Topic::new("TOPIC");
@@ -352,6 +488,8 @@ async fn receive_broadcasted_message_and_report_it() {
             originated_peer_id,
             message: message.clone(),
             topic_hash: topic.hash(),
+            message_id: MessageId(vec![1]),
+            propagation_source: originated_peer_id,
         }),
     )));
     let mut reported_peer_receiver = mock_swarm.get_reported_peers_stream();
@@ -380,6 +518,335 @@ async fn receive_broadcasted_message_and_report_it() {
     }
 }
 
+#[tokio::test]
+async fn received_broadcasts_are_dropped_when_the_default_buffer_is_full() {
+    const DEFAULT_BUFFER_SIZE: usize = 1;
+    const NUM_MESSAGES: u8 = 5;
+    let topic = Topic::new("TOPIC");
+    let messages: Vec<Bytes> = (0..NUM_MESSAGES).map(|i| vec![i]).collect();
+
+    let mock_swarm = MockSwarm::default();
+    for (i, message) in messages.iter().enumerate() {
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::GossipSub(gossipsub_impl::ExternalEvent::Received {
+                originated_peer_id: PeerId::random(),
+                message: message.clone(),
+                topic_hash: topic.hash(),
+                message_id: MessageId(vec![u8::try_from(i).unwrap()]),
+                propagation_source: PeerId::random(),
+            }),
+        )));
+    }
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    network_manager.default_protocol_buffer_size = DEFAULT_BUFFER_SIZE;
+    let mut broadcasted_messages_receiver = network_manager
+        .register_broadcast_topic::<Bytes>(topic, None)
+        .unwrap()
+        .broadcasted_messages_receiver;
+
+    // Don't consume the messages while the network manager receives them.
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        _ = sleep(TIMEOUT) => {}
+    }
+
+    let mut received_messages = Vec::new();
+    while let Some((message, _metadata)) = broadcasted_messages_receiver.next().await {
+        received_messages.push(message.unwrap());
+    }
+    // The oldest messages are kept and the rest are dropped. Futures channels have an extra slot
+    // for the sender on top of the buffer size.
+    assert_eq!(received_messages, messages[..=DEFAULT_BUFFER_SIZE]);
+}
+
+#[tokio::test]
+async fn sticky_topic_sends_last_message_to_new_peer() {
+    let topic = Topic::new("TOPIC");
+    let first_message = vec![1u8, 2u8, 3u8];
+    let second_message = vec![4u8, 5u8];
+
+    let mut mock_swarm = MockSwarm::default();
+    let pending_events = mock_swarm.pending_events.clone();
+    let mut messages_we_broadcasted_stream = mock_swarm.stream_messages_we_broadcasted();
+    let mut queries_sent_to_peers_stream = mock_swarm.stream_queries_sent_to_peers();
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+
+    let mut broadcast_topic_client = network_manager
+        .register_sticky_broadcast_topic(topic.clone(), BUFFER_SIZE)
+        .unwrap()
+        .broadcast_topic_client;
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            for message in [&first_message, &second_message] {
+                broadcast_topic_client.broadcast_message(message.clone()).await.unwrap();
+                let (actual_message, _) = messages_we_broadcasted_stream.next().await.unwrap();
+                assert_eq!(*message, actual_message);
+            }
+
+            let new_peer_id = PeerId::random();
+            pending_events.push(get_test_connection_established_event(new_peer_id));
+            let (query, peer_id, protocol) = queries_sent_to_peers_stream.next().await.unwrap();
+            assert_eq!(new_peer_id, peer_id);
+            assert_eq!(DIRECT_MESSAGE_PROTOCOL, protocol);
+            // Only the most recent message is sent.
+            assert_eq!(decode_direct_message(query), Some((topic.hash(), second_message)));
+
+            // The message isn't published again on the topic.
+            broadcast_topic_client.broadcast_message(first_message.clone()).await.unwrap();
+            let (actual_message, _) = messages_we_broadcasted_stream.next().await.unwrap();
+            assert_eq!(first_message, actual_message);
+        }) => result.unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn direct_messages_are_accepted_only_on_topics_that_allow_them() {
+    let gossip_only_topic = Topic::new("GOSSIP_ONLY_TOPIC");
+    let direct_topic = Topic::new("DIRECT_TOPIC");
+    let message = vec![1u8, 2u8, 3u8];
+
+    let mut mock_swarm = MockSwarm::default();
+    let mut responses_futures = Vec::new();
+    for (session_index, topic) in [&gossip_only_topic, &direct_topic].into_iter().enumerate() {
+        let inbound_session_id = InboundSessionId { value: session_index };
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+                query: encode_direct_message(&topic.hash(), &message),
+                inbound_session_id,
+                peer_id: PeerId::random(),
+                protocol_name: DIRECT_MESSAGE_PROTOCOL,
+            }),
+        )));
+        responses_futures
+            .push(mock_swarm.get_responses_sent_to_inbound_session(inbound_session_id));
+    }
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    let mut gossip_only_messages_receiver = network_manager
+        .register_broadcast_topic::<Vec<u8>>(gossip_only_topic, BUFFER_SIZE)
+        .unwrap()
+        .broadcasted_messages_receiver;
+    let mut direct_messages_receiver = network_manager
+        .register_broadcast_topic_with_direct_messages::<Vec<u8>>(direct_topic, BUFFER_SIZE)
+        .unwrap()
+        .broadcasted_messages_receiver;
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            let (actual_message, _) = direct_messages_receiver.next().await.unwrap();
+            assert_eq!(message, actual_message.unwrap());
+            // Both sessions are closed without responses once handled.
+            for responses_future in responses_futures {
+                assert!(responses_future.await.is_empty());
+            }
+        }) => result.unwrap(),
+    }
+    assert!(gossip_only_messages_receiver.next().now_or_never().flatten().is_none());
+}
+
+#[tokio::test]
+async fn peer_not_answering_heartbeats_is_reported() {
+    const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(10);
+    const HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(50);
+    let peer_id = PeerId::random();
+
+    // The mock swarm answers a query with a response per byte, so the peer never answers the
+    // empty heartbeat query.
+    let mut mock_swarm = MockSwarm { connected_peer: Some(peer_id), ..Default::default() };
+    let mut unresponsive_peers_stream = mock_swarm.get_unresponsive_peers_stream();
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    network_manager.heartbeat = Heartbeat::new(HEARTBEAT_INTERVAL, HEARTBEAT_TIMEOUT);
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, unresponsive_peers_stream.next()) => {
+            assert_eq!(peer_id, result.unwrap().unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn peer_answering_heartbeats_is_not_reported() {
+    const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(10);
+    const HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(50);
+    // Enough heartbeats for the first ones to pass their timeout.
+    const NUM_HEARTBEATS: usize = 10;
+    let peer_id = PeerId::random();
+
+    let mut mock_swarm =
+        MockSwarm { connected_peer: Some(peer_id), answers_heartbeats: true, ..Default::default() };
+    let mut unresponsive_peers_stream = mock_swarm.get_unresponsive_peers_stream();
+    let queries_sent_to_peers_stream = mock_swarm.stream_queries_sent_to_peers();
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    network_manager.heartbeat = Heartbeat::new(HEARTBEAT_INTERVAL, HEARTBEAT_TIMEOUT);
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(
+            TIMEOUT,
+            queries_sent_to_peers_stream.take(NUM_HEARTBEATS).collect::<Vec<_>>(),
+        ) => {
+            for (query, heartbeat_peer_id, protocol) in result.unwrap() {
+                assert!(query.is_empty());
+                assert_eq!(peer_id, heartbeat_peer_id);
+                assert_eq!(HEARTBEAT_PROTOCOL, protocol);
+            }
+        }
+    }
+    // The network manager was dropped, so the stream ends.
+    assert!(unresponsive_peers_stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn inbound_queries_above_rate_limit_are_rejected() {
+    const MAX_INBOUND_QUERIES_PER_SEC: u32 = 2;
+    let flooding_peer_id = PeerId::random();
+    let other_peer_id = PeerId::random();
+
+    // The flooding peer sends one query more than the limit, and then another peer sends a query.
+    let mut mock_swarm = MockSwarm::default();
+    let mut responses_futures = Vec::new();
+    for (session_index, peer_id) in
+        [flooding_peer_id, flooding_peer_id, flooding_peer_id, other_peer_id]
+            .into_iter()
+            .enumerate()
+    {
+        let inbound_session_id = InboundSessionId { value: session_index };
+        mock_swarm.pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+            mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+                query: vec![u8::try_from(session_index).unwrap()],
+                inbound_session_id,
+                peer_id,
+                protocol_name: SIGNED_BLOCK_HEADER_PROTOCOL,
+            }),
+        )));
+        responses_futures
+            .push(mock_swarm.get_responses_sent_to_inbound_session(inbound_session_id));
+    }
+    let rejected_session_responses_fut = responses_futures.remove(2);
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    network_manager.inbound_query_rate_limiter =
+        InboundQueryRateLimiter::new(MAX_INBOUND_QUERIES_PER_SEC);
+    let mut inbound_payload_receiver = network_manager
+        .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(
+            SIGNED_BLOCK_HEADER_PROTOCOL.to_string(),
+            BUFFER_SIZE,
+        );
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            // Keeping the accepted sessions open until the end of the test.
+            let mut server_query_managers = Vec::new();
+            for expected_query in [vec![0], vec![1], vec![3]] {
+                let server_query_manager = inbound_payload_receiver.next().await.unwrap();
+                assert_eq!(server_query_manager.query.clone().unwrap(), expected_query);
+                server_query_managers.push(server_query_manager);
+            }
+            // The rejected session was closed without responses.
+            assert!(rejected_session_responses_fut.await.is_empty());
+        }) => result.unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn sessions_info_contains_open_sessions() {
+    let peer_id = PeerId::random();
+    let inbound_session_id = InboundSessionId { value: 0 };
+
+    let mock_swarm = MockSwarm { connected_peer: Some(peer_id), ..Default::default() };
+    let pending_events = mock_swarm.pending_events.clone();
+    pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+        mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::NewInboundSession {
+            query: VEC1.clone(),
+            inbound_session_id,
+            peer_id,
+            protocol_name: SIGNED_BLOCK_HEADER_PROTOCOL,
+        }),
+    )));
+
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    let mut inbound_payload_receiver = network_manager
+        .register_sqmr_protocol_server::<Vec<u8>, Vec<u8>>(
+            SIGNED_BLOCK_HEADER_PROTOCOL.to_string(),
+            BUFFER_SIZE,
+        );
+    let mut network_client = network_manager.get_network_client();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            let server_query_manager = inbound_payload_receiver.next().await.unwrap();
+            let sessions_info = network_client.sessions_info().await.unwrap();
+            assert_eq!(sessions_info.len(), 1);
+            assert_eq!(sessions_info[0].protocol, SIGNED_BLOCK_HEADER_PROTOCOL);
+            assert_eq!(sessions_info[0].peer_id, Some(peer_id));
+            assert_eq!(sessions_info[0].direction, SessionDirection::Inbound);
+
+            drop(server_query_manager);
+            pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+                mixed_behaviour::ExternalEvent::Sqmr(GenericEvent::SessionFinishedSuccessfully {
+                    session_id: SessionId::InboundSessionId(inbound_session_id),
+                }),
+            )));
+            while !network_client.sessions_info().await.unwrap().is_empty() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        }) => result.unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn listening_addresses_contain_the_bound_port() {
+    let config = NetworkConfig { tcp_port: 0, ..Default::default() };
+    let network_manager = NetworkManager::new(config, None);
+    let mut network_client = network_manager.get_network_client();
+
+    let listening_addresses = tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(
+            TIMEOUT, network_client.listening_addresses()
+        ) => result.unwrap().unwrap(),
+    };
+    for address in listening_addresses {
+        assert!(
+            address.iter().any(|protocol| matches!(protocol, Protocol::Tcp(port) if port != 0)),
+            "Listening address {address} doesn't have a concrete TCP port"
+        );
+    }
+}
+
+#[tokio::test]
+async fn reachability_status_is_reported_to_network_client() {
+    let mock_swarm = MockSwarm::default();
+    let pending_events = mock_swarm.pending_events.clone();
+    let mut network_manager = GenericNetworkManager::generic_new(mock_swarm, None);
+    let mut network_client = network_manager.get_network_client();
+
+    tokio::select! {
+        _ = network_manager.run() => panic!("network manager ended"),
+        result = tokio::time::timeout(TIMEOUT, async {
+            assert_eq!(network_client.reachability_status().await.unwrap(), ReachabilityStatus::Unknown);
+            pending_events.push(Event::Behaviour(mixed_behaviour::Event::ExternalEvent(
+                mixed_behaviour::ExternalEvent::Autonat(
+                    autonat_impl::ExternalEvent::ReachabilityStatusChanged(ReachabilityStatus::Private),
+                ),
+            )));
+            while network_client.reachability_status().await.unwrap() != ReachabilityStatus::Private {
+                sleep(Duration::from_millis(10)).await;
+            }
+        }) => result.unwrap(),
+    }
+}
+
 fn get_test_connection_established_event(mock_peer_id: PeerId) -> Event {
     Event::ConnectionEstablished {
         peer_id: mock_peer_id,
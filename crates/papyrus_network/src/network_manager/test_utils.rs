@@ -8,9 +8,9 @@ use futures::sink::With;
 use futures::stream::Map;
 use futures::{SinkExt, StreamExt};
 use libp2p::core::multiaddr::Protocol;
-use libp2p::gossipsub::SubscriptionError;
+use libp2p::gossipsub::{SubscriptionError, TopicHash};
 use libp2p::identity::Keypair;
-use libp2p::{Multiaddr, PeerId};
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
 
 use super::{
     BroadcastReceivedMessagesConverterFn,
@@ -18,6 +18,7 @@ use super::{
     BroadcastTopicClient,
     BroadcastedMessageMetadata,
     GenericReceiver,
+    NetworkCommand,
     NetworkError,
     NetworkManager,
     ReportReceiver,
@@ -79,13 +80,22 @@ where
     let responses_sender = ServerResponsesSender { sender: Box::new(responses_sender) };
     let responses_receiver = Box::new(responses_receiver);
     (
-        ServerQueryManager { query: Ok(query), report_sender, responses_sender },
+        ServerQueryManager {
+            query: Ok(query),
+            report_sender,
+            responses_sender,
+            protocol: MOCK_PROTOCOL,
+        },
         report_receiver,
         responses_receiver,
     )
 }
 
 const CHANNEL_BUFFER_SIZE: usize = 10000;
+// The mocked SQMR channels aren't registered on a real protocol.
+const MOCK_PROTOCOL: StreamProtocol = StreamProtocol::new("/mock_protocol");
+// The mocked channels aren't registered on a real topic.
+const MOCK_TOPIC_HASH: &str = "mock_topic";
 
 pub fn mock_register_broadcast_topic<T>() -> Result<TestSubscriberChannels<T>, SubscriptionError>
 where
@@ -116,10 +126,15 @@ where
     let (continue_propagation_sender, mock_continue_propagation_receiver) =
         futures::channel::mpsc::channel(CHANNEL_BUFFER_SIZE);
 
+    let (network_commands_sender, mock_network_commands_receiver) =
+        futures::channel::mpsc::channel(CHANNEL_BUFFER_SIZE);
+
     let subscriber_channels = BroadcastTopicChannels {
         broadcasted_messages_receiver,
         broadcast_topic_client: BroadcastTopicClient::new(
+            TopicHash::from_raw(MOCK_TOPIC_HASH),
             messages_to_broadcast_sender,
+            network_commands_sender,
             reported_messages_sender,
             continue_propagation_sender,
         ),
@@ -144,6 +159,7 @@ where
         messages_to_broadcast_receiver: mock_messages_to_broadcast_receiver,
         reported_messages_receiver: mock_reported_messages_receiver,
         continue_propagation_receiver: mock_continue_propagation_receiver,
+        network_commands_receiver: mock_network_commands_receiver,
     };
 
     Ok(TestSubscriberChannels { subscriber_channels, mock_network })
@@ -238,7 +254,14 @@ where
     Bytes: From<Response>,
 {
     fn from(payload: SqmrClientPayload) -> Self {
-        let SqmrClientPayload { query, report_receiver, responses_sender } = payload;
+        let SqmrClientPayload {
+            query,
+            report_receiver,
+            responses_sender,
+            negotiated_protocol_sender,
+        } = payload;
+        // The client may have dropped the session already.
+        let _ = negotiated_protocol_sender.send(MOCK_PROTOCOL);
         let query = Query::try_from(query);
         let responses_sender =
             Box::new(responses_sender.with(|response: Response| ready(Ok(Bytes::from(response)))));
@@ -270,6 +293,7 @@ pub struct BroadcastNetworkMock<T: TryFrom<Bytes>> {
     pub messages_to_broadcast_receiver: MockMessagesToBroadcastReceiver<T>,
     pub reported_messages_receiver: Receiver<PeerId>,
     pub continue_propagation_receiver: Receiver<BroadcastedMessageMetadata>,
+    pub network_commands_receiver: Receiver<NetworkCommand>,
 }
 
 pub struct TestSubscriberChannels<T: TryFrom<Bytes>> {
@@ -102,6 +102,7 @@ impl NetworkBehaviour for PeerManager {
                     );
                     return;
                 }
+                self.on_dial_failure(peer_id);
                 let res = self.report_peer(peer_id, super::ReputationModifier::Unstable);
                 if res.is_err() {
                     warn!("Dial failure of an unknown peer. peer id: {}", peer_id)
@@ -124,6 +125,7 @@ impl NetworkBehaviour for PeerManager {
                 connection_id,
                 ..
             }) => {
+                self.unconnected_discovered_peers.remove(&peer_id);
                 if let Some(sessions) = self.peers_pending_dial_with_sessions.remove(&peer_id) {
                     self.pending_events.extend(sessions.iter().map(|outbound_session_id| {
                         ToSwarm::GenerateEvent(ToOtherBehaviourEvent::SessionAssigned {
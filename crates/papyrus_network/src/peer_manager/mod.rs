@@ -1,11 +1,13 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::Duration;
 
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::ToSwarm;
-use libp2p::PeerId;
+use libp2p::{Multiaddr, PeerId};
+use metrics::increment_counter;
+use papyrus_common::metrics as papyrus_metrics;
 use papyrus_config::converters::{
     deserialize_milliseconds_to_duration,
     deserialize_seconds_to_duration,
@@ -52,14 +54,26 @@ pub struct PeerManager {
     peers_pending_dial_with_sessions: HashMap<PeerId, Vec<OutboundSessionId>>,
     sessions_received_when_no_peers: Vec<OutboundSessionId>,
     sleep_waiting_for_unblocked_peer: Option<BoxFuture<'static, ()>>,
+    // Peers found through discovery that we didn't connect to yet.
+    unconnected_discovered_peers: HashSet<PeerId>,
+    unreachable_discovered_peer_callback: Option<UnreachablePeerCallback>,
 }
 
+/// Called with a peer found through discovery and its address when dialing it fails before we
+/// ever connected to it.
+pub type UnreachablePeerCallback = Box<dyn Fn(PeerId, &Multiaddr) + Send>;
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct PeerManagerConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     malicious_timeout_seconds: Duration,
     #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
     unstable_timeout_millis: Duration,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub(crate) heartbeat_interval: Duration,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub(crate) heartbeat_timeout: Duration,
+    pub(crate) max_inbound_queries_per_sec: u32,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -78,6 +92,11 @@ impl Default for PeerManagerConfig {
             // 1 year.
             malicious_timeout_seconds: Duration::from_secs(3600 * 24 * 365),
             unstable_timeout_millis: Duration::from_millis(1000),
+            // Disabled.
+            heartbeat_interval: Duration::ZERO,
+            heartbeat_timeout: Duration::from_millis(5000),
+            // Unlimited.
+            max_inbound_queries_per_sec: 0,
         }
     }
 }
@@ -97,6 +116,27 @@ impl SerializeConfig for PeerManagerConfig {
                 "The duration in milliseconds a peer blacklisted after being reported as unstable.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "heartbeat_interval",
+                &self.heartbeat_interval.as_millis(),
+                "The interval in milliseconds between heartbeats sent to the connected peers. \
+                 Peers that don't answer a heartbeat are reported as unstable and disconnected. 0 \
+                 disables the heartbeat.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "heartbeat_timeout",
+                &self.heartbeat_timeout.as_millis(),
+                "The time in milliseconds a peer has to answer a heartbeat.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_inbound_queries_per_sec",
+                &self.max_inbound_queries_per_sec,
+                "The maximal number of queries a peer can send us per second. Queries above this \
+                 rate are rejected. 0 means unlimited.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -114,6 +154,28 @@ impl PeerManager {
             peers_pending_dial_with_sessions: HashMap::new(),
             sessions_received_when_no_peers: Vec::new(),
             sleep_waiting_for_unblocked_peer: None,
+            unconnected_discovered_peers: HashSet::new(),
+            unreachable_discovered_peer_callback: None,
+        }
+    }
+
+    pub(crate) fn set_unreachable_discovered_peer_callback(
+        &mut self,
+        callback: UnreachablePeerCallback,
+    ) {
+        self.unreachable_discovered_peer_callback = Some(callback);
+    }
+
+    // Should be called when dialing the given peer failed.
+    fn on_dial_failure(&mut self, peer_id: PeerId) {
+        if !self.unconnected_discovered_peers.remove(&peer_id) {
+            return;
+        }
+        increment_counter!(papyrus_metrics::PAPYRUS_NUM_UNREACHABLE_DISCOVERED_PEERS);
+        if let (Some(callback), Some(peer)) =
+            (&self.unreachable_discovered_peer_callback, self.peers.get(&peer_id))
+        {
+            callback(peer_id, &peer.multiaddr());
         }
     }
 
@@ -281,6 +343,11 @@ impl BridgedBehaviour for PeerManager {
                     return;
                 };
 
+                // Peers found by identify are already connected to us.
+                if let mixed_behaviour::ToOtherBehaviourEvent::Discovery(_) = event {
+                    increment_counter!(papyrus_metrics::PAPYRUS_NUM_DISCOVERED_PEERS);
+                    self.unconnected_discovered_peers.insert(*peer_id);
+                }
                 let peer = Peer::new(*peer_id, address.clone());
                 self.add_peer(peer);
             }
@@ -2,6 +2,7 @@
 
 use core::{panic, time};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -9,18 +10,22 @@ use assert_matches::assert_matches;
 use futures::future::poll_fn;
 use futures::{FutureExt, Stream, StreamExt};
 use libp2p::swarm::behaviour::ConnectionEstablished;
-use libp2p::swarm::{ConnectionId, NetworkBehaviour, ToSwarm};
+use libp2p::swarm::{ConnectionId, DialError, DialFailure, FromSwarm, NetworkBehaviour, ToSwarm};
 use libp2p::{Multiaddr, PeerId};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_common::metrics as papyrus_metrics;
+use papyrus_test_utils::prometheus_is_contained;
+use prometheus_parse::Value::Counter;
 use tokio::time::sleep;
 use void::Void;
 
 use super::behaviour_impl::ToOtherBehaviourEvent;
 use crate::discovery::identify_impl::IdentifyToOtherBehaviourEvent;
-use crate::mixed_behaviour;
 use crate::mixed_behaviour::BridgedBehaviour;
 use crate::peer_manager::peer::{Peer, PeerTrait};
 use crate::peer_manager::{PeerManager, PeerManagerConfig, ReputationModifier, MALICIOUS};
 use crate::sqmr::OutboundSessionId;
+use crate::{discovery, mixed_behaviour};
 
 impl Unpin for PeerManager {}
 
@@ -156,8 +161,11 @@ async fn peer_assignment_no_unblocked_peers() {
     const BLOCKED_UNTIL: Duration = Duration::from_secs(5);
     const TIMEOUT: Duration = Duration::from_secs(1);
     // Create a new peer manager
-    let config =
-        PeerManagerConfig { malicious_timeout_seconds: TIMEOUT, unstable_timeout_millis: TIMEOUT };
+    let config = PeerManagerConfig {
+        malicious_timeout_seconds: TIMEOUT,
+        unstable_timeout_millis: TIMEOUT,
+        ..Default::default()
+    };
     let mut peer_manager: PeerManager = PeerManager::new(config.clone());
 
     // Create a session
@@ -487,3 +495,70 @@ fn identify_on_unknown_peer_is_added_to_peer_manager() {
     assert!(res_peer_id.peer_id() == peer_id);
     assert!(res_peer_id.multiaddr() == address);
 }
+
+#[test]
+fn failed_dial_to_discovered_peer_is_reported() {
+    let handle = PrometheusBuilder::new().install_recorder().unwrap();
+    // Other tests may change the counters, so only their changes during this test are checked.
+    let counter_value =
+        |metric_name: &str| match prometheus_is_contained(handle.render(), metric_name, &[]) {
+            Some(Counter(count)) => count,
+            None => 0f64,
+            Some(value) => panic!("Expected a counter, got {value:?}"),
+        };
+    let num_discovered_peers_before = counter_value(papyrus_metrics::PAPYRUS_NUM_DISCOVERED_PEERS);
+    let num_unreachable_peers_before =
+        counter_value(papyrus_metrics::PAPYRUS_NUM_UNREACHABLE_DISCOVERED_PEERS);
+
+    let mut peer_manager = PeerManager::new(PeerManagerConfig::default());
+    let unreachable_peers = Arc::new(Mutex::new(Vec::new()));
+    let unreachable_peers_clone = unreachable_peers.clone();
+    peer_manager.set_unreachable_discovered_peer_callback(Box::new(move |peer_id, address| {
+        unreachable_peers_clone.lock().unwrap().push((peer_id, address.clone()));
+    }));
+
+    // Discover a peer with an address nothing listens on and a peer we manage to connect to.
+    let peer_id = PeerId::random();
+    let address = Multiaddr::empty().with_p2p(peer_id).unwrap();
+    let connected_peer_id = PeerId::random();
+    for (peer_id, address) in [
+        (peer_id, address.clone()),
+        (connected_peer_id, Multiaddr::empty().with_p2p(connected_peer_id).unwrap()),
+    ] {
+        peer_manager.on_other_behaviour_event(&mixed_behaviour::ToOtherBehaviourEvent::Discovery(
+            discovery::ToOtherBehaviourEvent::FoundListenAddresses {
+                peer_id,
+                listen_addresses: vec![address],
+            },
+        ));
+    }
+    assert_eq!(
+        counter_value(papyrus_metrics::PAPYRUS_NUM_DISCOVERED_PEERS) - num_discovered_peers_before,
+        2.0
+    );
+    peer_manager.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id: connected_peer_id,
+        connection_id: ConnectionId::new_unchecked(0),
+        endpoint: &libp2p::core::ConnectedPoint::Dialer {
+            address: Multiaddr::empty(),
+            role_override: libp2p::core::Endpoint::Dialer,
+        },
+        failed_addresses: &[],
+        other_established: 0,
+    }));
+
+    // A later dial failure to the peer we connected to doesn't make it unreachable.
+    for peer_id in [peer_id, connected_peer_id] {
+        peer_manager.on_swarm_event(FromSwarm::DialFailure(DialFailure {
+            peer_id: Some(peer_id),
+            error: &DialError::NoAddresses,
+            connection_id: ConnectionId::new_unchecked(1),
+        }));
+    }
+    assert_eq!(
+        counter_value(papyrus_metrics::PAPYRUS_NUM_UNREACHABLE_DISCOVERED_PEERS)
+            - num_unreachable_peers_before,
+        1.0
+    );
+    assert_eq!(*unreachable_peers.lock().unwrap(), vec![(peer_id, address)]);
+}
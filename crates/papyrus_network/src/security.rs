@@ -0,0 +1,70 @@
+#[cfg(test)]
+#[path = "security_test.rs"]
+mod security_test;
+
+use std::collections::BTreeMap;
+
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+pub(crate) const NOISE: &str = "noise";
+pub(crate) const TLS: &str = "tls";
+
+const SUPPORTED_SECURITY_PROTOCOLS: [&str; 2] = [NOISE, TLS];
+
+/// Restricts the security upgrades that are negotiated on connections. Streams are always
+/// multiplexed with yamux.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Validate)]
+pub struct SecurityConfig {
+    /// A comma separated list of the accepted security protocols, ordered by preference.
+    #[validate(custom = "validate_security_protocols")]
+    pub security_protocols: String,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self { security_protocols: NOISE.to_string() }
+    }
+}
+
+impl SerializeConfig for SecurityConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([ser_param(
+            "security_protocols",
+            &self.security_protocols,
+            "A comma separated list of the security protocols accepted on connections, ordered by \
+             preference. Supported protocols: noise, tls.",
+            ParamPrivacyInput::Public,
+        )])
+    }
+}
+
+impl SecurityConfig {
+    pub(crate) fn security_protocols(&self) -> Vec<&str> {
+        parse_security_protocols(&self.security_protocols)
+    }
+}
+
+fn parse_security_protocols(security_protocols: &str) -> Vec<&str> {
+    security_protocols.split(',').map(str::trim).collect()
+}
+
+fn validate_security_protocols(security_protocols: &str) -> Result<(), ValidationError> {
+    let security_protocols = parse_security_protocols(security_protocols);
+    if security_protocols
+        .iter()
+        .any(|security_protocol| !SUPPORTED_SECURITY_PROTOCOLS.contains(security_protocol))
+    {
+        return Err(ValidationError::new("Unsupported security protocol"));
+    }
+    if security_protocols
+        .iter()
+        .enumerate()
+        .any(|(i, security_protocol)| security_protocols[..i].contains(security_protocol))
+    {
+        return Err(ValidationError::new("Duplicate security protocol"));
+    }
+    Ok(())
+}
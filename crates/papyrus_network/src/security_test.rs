@@ -0,0 +1,38 @@
+use validator::Validate;
+
+use super::SecurityConfig;
+use crate::network_manager::NetworkManager;
+use crate::NetworkConfig;
+
+fn security_config(security_protocols: &str) -> SecurityConfig {
+    SecurityConfig { security_protocols: security_protocols.to_string() }
+}
+
+fn network_config(security_config: SecurityConfig) -> NetworkConfig {
+    NetworkConfig { tcp_port: 0, security_config, ..Default::default() }
+}
+
+#[test]
+fn supported_security_configs_are_valid() {
+    for security_protocols in ["noise", "tls", "noise,tls", "tls, noise"] {
+        security_config(security_protocols).validate().unwrap();
+    }
+}
+
+#[test]
+fn unsupported_security_configs_are_invalid() {
+    for security_protocols in ["ssl", "noise,ssl", "noise,noise", ""] {
+        assert!(security_config(security_protocols).validate().is_err());
+    }
+}
+
+#[tokio::test]
+async fn network_manager_builds_with_restricted_security_config() {
+    NetworkManager::new(network_config(security_config("tls")), None);
+}
+
+#[tokio::test]
+#[should_panic(expected = "Invalid network security config")]
+async fn network_manager_rejects_unsupported_security_config() {
+    NetworkManager::new(network_config(security_config("ssl")), None);
+}
@@ -13,6 +13,7 @@ use libp2p::core::Endpoint;
 use libp2p::swarm::{
     ConnectionClosed,
     ConnectionDenied,
+    ConnectionEstablished,
     ConnectionHandler,
     ConnectionId,
     FromSwarm,
@@ -113,8 +114,11 @@ pub struct Behaviour {
     next_inbound_session_id: Arc<AtomicUsize>,
     dropped_sessions: HashSet<SessionId>,
     wakers_waiting_for_event: Vec<Waker>,
-    outbound_sessions_pending_peer_assignment: HashMap<OutboundSessionId, (Bytes, StreamProtocol)>,
+    outbound_sessions_pending_peer_assignment:
+        HashMap<OutboundSessionId, (Bytes, Vec<StreamProtocol>)>,
     supported_inbound_protocols: HashSet<StreamProtocol>,
+    session_id_to_negotiated_protocol: HashMap<SessionId, StreamProtocol>,
+    peer_to_connection_ids: HashMap<PeerId, Vec<ConnectionId>>,
 }
 
 impl Behaviour {
@@ -129,6 +133,8 @@ impl Behaviour {
             wakers_waiting_for_event: Default::default(),
             outbound_sessions_pending_peer_assignment: Default::default(),
             supported_inbound_protocols: Default::default(),
+            session_id_to_negotiated_protocol: Default::default(),
+            peer_to_connection_ids: Default::default(),
         }
     }
 
@@ -137,12 +143,23 @@ impl Behaviour {
         &mut self,
         query: Bytes,
         protocol_name: StreamProtocol,
+    ) -> OutboundSessionId {
+        self.start_query_with_protocol_versions(query, vec![protocol_name])
+    }
+
+    /// Same as [`start_query`](Self::start_query), but offers the assigned peer several versions
+    /// of the protocol, ordered by preference. The session uses the first one the peer supports.
+    /// Use [`get_negotiated_protocol`](Self::get_negotiated_protocol) to know which one it was.
+    pub fn start_query_with_protocol_versions(
+        &mut self,
+        query: Bytes,
+        protocol_names: Vec<StreamProtocol>,
     ) -> OutboundSessionId {
         let outbound_session_id = self.next_outbound_session_id;
         self.next_outbound_session_id.value += 1;
 
         self.outbound_sessions_pending_peer_assignment
-            .insert(outbound_session_id, (query, protocol_name));
+            .insert(outbound_session_id, (query, protocol_names));
         info!("Requesting peer assignment for outbound session: {:?}.", outbound_session_id);
         self.add_event_to_queue(ToSwarm::GenerateEvent(Event::ToOtherBehaviourEvent(
             ToOtherBehaviourEvent::RequestPeerAssignment { outbound_session_id },
@@ -151,6 +168,37 @@ impl Behaviour {
         outbound_session_id
     }
 
+    /// Start a query with the given peer, bypassing the peer assignment. Fails if we're not
+    /// connected to the peer.
+    pub fn start_query_to_peer(
+        &mut self,
+        query: Bytes,
+        protocol_name: StreamProtocol,
+        peer_id: PeerId,
+    ) -> Result<OutboundSessionId, PeerNotConnected> {
+        let connection_id = *self
+            .peer_to_connection_ids
+            .get(&peer_id)
+            .and_then(|connection_ids| connection_ids.first())
+            .ok_or(PeerNotConnected)?;
+        let outbound_session_id = self.next_outbound_session_id;
+        self.next_outbound_session_id.value += 1;
+
+        self.session_id_to_peer_id_and_connection_id
+            .insert(outbound_session_id.into(), (peer_id, connection_id));
+        self.add_event_to_queue(ToSwarm::NotifyHandler {
+            peer_id,
+            handler: NotifyHandler::One(connection_id),
+            event: RequestFromBehaviourEvent::CreateOutboundSession {
+                query,
+                outbound_session_id,
+                protocol_names: vec![protocol_name],
+            },
+        });
+
+        Ok(outbound_session_id)
+    }
+
     /// Send a response message to an open inbound session.
     pub fn send_response(
         &mut self,
@@ -198,6 +246,18 @@ impl Behaviour {
         Ok(())
     }
 
+    /// Returns the protocol that was negotiated for the given session. Outbound sessions have a
+    /// negotiated protocol only once the assigned peer accepted one of the offered protocols.
+    pub fn get_negotiated_protocol(
+        &self,
+        session_id: SessionId,
+    ) -> Result<StreamProtocol, SessionIdNotFoundError> {
+        self.session_id_to_negotiated_protocol
+            .get(&session_id)
+            .cloned()
+            .ok_or(SessionIdNotFoundError)
+    }
+
     pub(crate) fn get_peer_id_and_connection_id_from_session_id(
         &self,
         session_id: SessionId,
@@ -256,10 +316,26 @@ impl NetworkBehaviour for Behaviour {
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm<'_>) {
-        let FromSwarm::ConnectionClosed(ConnectionClosed { peer_id, connection_id, .. }) = event
-        else {
-            return;
+        let (peer_id, connection_id) = match event {
+            FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                connection_id,
+                ..
+            }) => {
+                self.peer_to_connection_ids.entry(peer_id).or_default().push(connection_id);
+                return;
+            }
+            FromSwarm::ConnectionClosed(ConnectionClosed { peer_id, connection_id, .. }) => {
+                (peer_id, connection_id)
+            }
+            _ => return,
         };
+        if let Some(connection_ids) = self.peer_to_connection_ids.get_mut(&peer_id) {
+            connection_ids.retain(|id| *id != connection_id);
+            if connection_ids.is_empty() {
+                self.peer_to_connection_ids.remove(&peer_id);
+            }
+        }
         let mut session_ids = Vec::new();
         self.session_id_to_peer_id_and_connection_id.retain(
             |session_id, (session_peer_id, session_connection_id)| {
@@ -272,6 +348,7 @@ impl NetworkBehaviour for Behaviour {
             },
         );
         for session_id in session_ids {
+            self.session_id_to_negotiated_protocol.remove(&session_id);
             self.add_event_to_queue(ToSwarm::GenerateEvent(Event::External(
                 ExternalEvent::SessionFailed { session_id, error: SessionError::ConnectionClosed },
             )));
@@ -289,13 +366,20 @@ impl NetworkBehaviour for Behaviour {
                 let converted_event = event.into();
                 let mut is_event_muted = false;
                 match converted_event {
-                    ExternalEvent::NewInboundSession { inbound_session_id, .. } => {
+                    ExternalEvent::NewInboundSession {
+                        inbound_session_id,
+                        ref protocol_name,
+                        ..
+                    } => {
                         self.session_id_to_peer_id_and_connection_id
                             .insert(inbound_session_id.into(), (peer_id, connection_id));
+                        self.session_id_to_negotiated_protocol
+                            .insert(inbound_session_id.into(), protocol_name.clone());
                     }
                     ExternalEvent::SessionFailed { session_id, .. }
                     | ExternalEvent::SessionFinishedSuccessfully { session_id, .. } => {
                         self.session_id_to_peer_id_and_connection_id.remove(&session_id);
+                        self.session_id_to_negotiated_protocol.remove(&session_id);
                         let is_dropped = self.dropped_sessions.remove(&session_id);
                         if is_dropped {
                             is_event_muted = true;
@@ -315,6 +399,14 @@ impl NetworkBehaviour for Behaviour {
             }
             RequestToBehaviourEvent::NotifySessionDropped { session_id } => {
                 self.dropped_sessions.remove(&session_id);
+                self.session_id_to_negotiated_protocol.remove(&session_id);
+            }
+            RequestToBehaviourEvent::NotifyOutboundSessionNegotiated {
+                outbound_session_id,
+                protocol_name,
+            } => {
+                self.session_id_to_negotiated_protocol
+                    .insert(outbound_session_id.into(), protocol_name);
             }
         }
     }
@@ -348,7 +440,7 @@ impl BridgedBehaviour for Behaviour {
         self.session_id_to_peer_id_and_connection_id
             .insert((*outbound_session_id).into(), (*peer_id, *connection_id));
 
-        let Some((query, protocol_name)) =
+        let Some((query, protocol_names)) =
             self.outbound_sessions_pending_peer_assignment.remove(outbound_session_id)
         else {
             error!(
@@ -364,7 +456,7 @@ impl BridgedBehaviour for Behaviour {
             event: RequestFromBehaviourEvent::CreateOutboundSession {
                 query,
                 outbound_session_id: *outbound_session_id,
-                protocol_name,
+                protocol_names,
             },
         });
     }
@@ -149,12 +149,12 @@ async fn validate_create_outbound_session_event(
         event,
         ToSwarm::NotifyHandler {
             peer_id: event_peer_id,
-            event: RequestFromBehaviourEvent::CreateOutboundSession { query: event_query, outbound_session_id: event_outbound_session_id, protocol_name },
+            event: RequestFromBehaviourEvent::CreateOutboundSession { query: event_query, outbound_session_id: event_outbound_session_id, protocol_names },
             ..
         } if *peer_id == event_peer_id
             && *outbound_session_id == event_outbound_session_id
             && *query == event_query
-            && protocol_name == PROTOCOL_NAME.clone()
+            && protocol_names == vec![PROTOCOL_NAME.clone()]
     );
 }
 
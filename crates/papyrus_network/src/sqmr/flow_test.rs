@@ -20,6 +20,9 @@ const NUM_MESSAGES_PER_SESSION: usize = 5;
 
 pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/example");
 pub const OTHER_PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/other");
+pub const PROTOCOL_VERSION_1: StreamProtocol = StreamProtocol::new("/example/1.0.0");
+pub const PROTOCOL_VERSION_2: StreamProtocol = StreamProtocol::new("/example/2.0.0");
+pub const PROTOCOL_VERSION_3: StreamProtocol = StreamProtocol::new("/example/3.0.0");
 
 type SwarmEventAlias<BehaviourTrait> = SwarmEvent<<BehaviourTrait as NetworkBehaviour>::ToSwarm>;
 
@@ -324,3 +327,74 @@ async fn everyone_sends_to_everyone() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn highest_common_protocol_version_is_negotiated() {
+    let (mut swarms_stream, connection_ids) = create_fully_connected_swarms_stream(2, || {
+        let mut behaviour = Behaviour::new(Config { session_timeout: Duration::from_secs(5) });
+        for protocol in [PROTOCOL_VERSION_1, PROTOCOL_VERSION_2] {
+            behaviour.add_new_supported_inbound_protocol(protocol);
+        }
+        behaviour
+    })
+    .await;
+
+    let peer_ids = swarms_stream.keys().copied().collect::<Vec<_>>();
+    let (outbound_peer_id, inbound_peer_id) = (peer_ids[0], peer_ids[1]);
+
+    // The outbound peer offers a version the inbound peer doesn't support before the versions they
+    // have in common.
+    let outbound_session_id = swarms_stream
+        .get_mut(&outbound_peer_id)
+        .unwrap()
+        .behaviour_mut()
+        .start_query_with_protocol_versions(
+            get_bytes_from_query_indices(outbound_peer_id, inbound_peer_id),
+            vec![PROTOCOL_VERSION_3, PROTOCOL_VERSION_2, PROTOCOL_VERSION_1],
+        );
+
+    loop {
+        let (peer_id, event) = swarms_stream.next().await.unwrap();
+        let SwarmEvent::Behaviour(event) = event.unwrap() else {
+            continue;
+        };
+        let swarm = swarms_stream.get_mut(&peer_id).unwrap();
+        match event {
+            Event::ToOtherBehaviourEvent(ToOtherBehaviourEvent::RequestPeerAssignment {
+                outbound_session_id: event_outbound_session_id,
+            }) => {
+                assert_eq!(event_outbound_session_id, outbound_session_id);
+                assign_peer_to_outbound_session(
+                    swarm,
+                    inbound_peer_id,
+                    outbound_session_id,
+                    connection_ids[&(outbound_peer_id, inbound_peer_id)],
+                );
+            }
+            Event::External(ExternalEvent::NewInboundSession {
+                inbound_session_id,
+                protocol_name,
+                ..
+            }) => {
+                assert_eq!(protocol_name, PROTOCOL_VERSION_2);
+                assert_eq!(
+                    swarm.behaviour().get_negotiated_protocol(inbound_session_id.into()).unwrap(),
+                    PROTOCOL_VERSION_2
+                );
+                swarm.behaviour_mut().send_response(vec![0u8], inbound_session_id).unwrap();
+            }
+            Event::External(ExternalEvent::ReceivedResponse {
+                outbound_session_id: event_outbound_session_id,
+                ..
+            }) => {
+                assert_eq!(event_outbound_session_id, outbound_session_id);
+                assert_eq!(
+                    swarm.behaviour().get_negotiated_protocol(outbound_session_id.into()).unwrap(),
+                    PROTOCOL_VERSION_2
+                );
+                break;
+            }
+            _ => {}
+        }
+    }
+}
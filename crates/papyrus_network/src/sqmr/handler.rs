@@ -38,7 +38,7 @@ pub enum RequestFromBehaviourEvent {
     CreateOutboundSession {
         query: Bytes,
         outbound_session_id: OutboundSessionId,
-        protocol_name: StreamProtocol,
+        protocol_names: Vec<StreamProtocol>,
     },
     SendResponse {
         response: Bytes,
@@ -55,7 +55,13 @@ pub enum RequestFromBehaviourEvent {
 #[derive(Debug)]
 pub enum RequestToBehaviourEvent {
     GenerateEvent(GenericEvent<SessionError>),
-    NotifySessionDropped { session_id: SessionId },
+    NotifySessionDropped {
+        session_id: SessionId,
+    },
+    NotifyOutboundSessionNegotiated {
+        outbound_session_id: OutboundSessionId,
+        protocol_name: StreamProtocol,
+    },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -249,7 +255,7 @@ impl ConnectionHandler for Handler {
             RequestFromBehaviourEvent::CreateOutboundSession {
                 query,
                 outbound_session_id,
-                protocol_name,
+                protocol_names,
             } => {
                 // TODO(shahak) Consider extracting to a utility function to prevent forgetfulness
                 // of the timeout.
@@ -258,7 +264,7 @@ impl ConnectionHandler for Handler {
                 // on_behaviour_event. See https://github.com/libp2p/rust-libp2p/issues/5147
                 self.pending_events.push_back(ConnectionHandlerEvent::OutboundSubstreamRequest {
                     protocol: SubstreamProtocol::new(
-                        OutboundProtocol { query, protocol_name },
+                        OutboundProtocol { query, protocol_names },
                         outbound_session_id,
                     )
                     .with_timeout(self.config.session_timeout),
@@ -331,12 +337,20 @@ impl ConnectionHandler for Handler {
     ) {
         match event {
             ConnectionEvent::FullyNegotiatedOutbound(FullyNegotiatedOutbound {
-                protocol: mut read_stream,
+                protocol: (mut read_stream, protocol_name),
                 info: outbound_session_id,
             }) => {
                 if self.dropped_outbound_sessions_non_negotiated.remove(&outbound_session_id) {
                     return;
                 }
+                // No need to wake because the swarm guarantees that `poll` will be called after
+                // on_connection_event. See https://github.com/libp2p/rust-libp2p/issues/5147
+                self.pending_events.push_back(ConnectionHandlerEvent::NotifyBehaviour(
+                    RequestToBehaviourEvent::NotifyOutboundSessionNegotiated {
+                        outbound_session_id,
+                        protocol_name,
+                    },
+                ));
                 self.id_to_outbound_session.insert(
                     outbound_session_id,
                     stream! {
@@ -67,7 +67,7 @@ fn simulate_request_to_send_query_from_swarm(
     handler.on_behaviour_event(RequestFromBehaviourEvent::CreateOutboundSession {
         query,
         outbound_session_id,
-        protocol_name: PROTOCOL_NAME.clone(),
+        protocol_names: vec![PROTOCOL_NAME.clone()],
     });
 }
 
@@ -101,7 +101,10 @@ fn simulate_negotiated_outbound_session_from_swarm(
     outbound_session_id: OutboundSessionId,
 ) {
     handler.on_connection_event(ConnectionEvent::FullyNegotiatedOutbound(
-        FullyNegotiatedOutbound { protocol: outbound_stream.split().0, info: outbound_session_id },
+        FullyNegotiatedOutbound {
+            protocol: (outbound_stream.split().0, PROTOCOL_NAME.clone()),
+            info: outbound_session_id,
+        },
     ));
 }
 
@@ -201,6 +204,23 @@ async fn validate_session_dropped_event(handler: &mut Handler, session_id: Sessi
     );
 }
 
+async fn validate_outbound_session_negotiated_event(
+    handler: &mut Handler,
+    outbound_session_id: OutboundSessionId,
+) {
+    let event = handler.next().await.unwrap();
+    assert_matches!(
+        event,
+        ConnectionHandlerEvent::NotifyBehaviour(
+            RequestToBehaviourEvent::NotifyOutboundSessionNegotiated {
+                outbound_session_id: event_outbound_session_id,
+                protocol_name,
+            }
+        ) if event_outbound_session_id == outbound_session_id
+            && protocol_name == PROTOCOL_NAME.clone()
+    );
+}
+
 fn validate_no_events(handler: &mut Handler) {
     assert!(handler.next().now_or_never().is_none());
 }
@@ -359,6 +379,7 @@ async fn process_outbound_session() {
         outbound_stream,
         outbound_session_id,
     );
+    validate_outbound_session_negotiated_event(&mut handler, outbound_session_id).await;
 
     let dummy_data_vec = dummy_data();
     for response in &dummy_data_vec {
@@ -464,6 +485,7 @@ async fn outbound_session_dropped_after_negotiation() {
         outbound_stream,
         outbound_session_id,
     );
+    validate_outbound_session_negotiated_event(&mut handler, outbound_session_id).await;
 
     simulate_request_to_drop_session(&mut handler, outbound_session_id.into());
     validate_session_dropped_event(&mut handler, outbound_session_id.into()).await;
@@ -2,7 +2,7 @@
 #[path = "protocol_test.rs"]
 mod protocol_test;
 
-use std::{io, iter};
+use std::io;
 
 use futures::future::BoxFuture;
 use futures::io::{ReadHalf, WriteHalf};
@@ -53,15 +53,17 @@ where
 #[derive(Debug)]
 pub struct OutboundProtocol {
     pub query: Bytes,
-    pub protocol_name: StreamProtocol,
+    /// The protocols to offer the other peer, ordered by preference. The first protocol the other
+    /// peer supports is the one used for the session.
+    pub protocol_names: Vec<StreamProtocol>,
 }
 
 impl UpgradeInfo for OutboundProtocol {
     type Info = StreamProtocol;
-    type InfoIter = iter::Once<Self::Info>;
+    type InfoIter = Vec<Self::Info>;
 
     fn protocol_info(&self) -> Self::InfoIter {
-        iter::once(self.protocol_name.clone())
+        self.protocol_names.clone()
     }
 }
 
@@ -69,15 +71,15 @@ impl<Stream> OutboundUpgrade<Stream> for OutboundProtocol
 where
     Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
-    type Output = ReadHalf<Stream>;
+    type Output = (ReadHalf<Stream>, StreamProtocol);
     type Error = io::Error;
     type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
 
-    fn upgrade_outbound(self, stream: Stream, _: Self::Info) -> Self::Future {
+    fn upgrade_outbound(self, stream: Stream, protocol_name: Self::Info) -> Self::Future {
         async move {
             let (read_half, write_half) = stream.split();
             write_message_without_length_prefix(&self.query, write_half).await?;
-            Ok(read_half)
+            Ok((read_half, protocol_name))
         }
         .boxed()
     }
@@ -11,9 +11,10 @@ pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/example/1.0.0");
 
 #[test]
 fn outbound_protocol_info() {
+    let protocol_names = vec![StreamProtocol::new("/example/2.0.0"), PROTOCOL_NAME];
     let outbound_protocol =
-        OutboundProtocol { query: Default::default(), protocol_name: PROTOCOL_NAME };
-    assert_eq!(outbound_protocol.protocol_info().collect::<Vec<_>>(), vec![PROTOCOL_NAME]);
+        OutboundProtocol { query: Default::default(), protocol_names: protocol_names.clone() };
+    assert_eq!(outbound_protocol.protocol_info(), protocol_names);
 }
 
 #[test]
@@ -28,7 +29,8 @@ async fn positive_flow() {
     let (inbound_stream, outbound_stream, _) = get_connected_streams().await;
 
     let query = vec![1u8, 2u8, 3u8];
-    let outbound_protocol = OutboundProtocol { query: query.clone(), protocol_name: PROTOCOL_NAME };
+    let outbound_protocol =
+        OutboundProtocol { query: query.clone(), protocol_names: vec![PROTOCOL_NAME] };
     let inbound_protocol = InboundProtocol::new(vec![PROTOCOL_NAME]);
 
     tokio::join!(
@@ -42,8 +44,9 @@ async fn positive_flow() {
             }
         },
         async move {
-            let mut stream =
+            let (mut stream, protocol_name) =
                 outbound_protocol.upgrade_outbound(outbound_stream, PROTOCOL_NAME).await.unwrap();
+            assert_eq!(protocol_name, PROTOCOL_NAME);
             for expected_response in dummy_data() {
                 let response = read_message(&mut stream).await.unwrap().unwrap();
                 assert_eq!(response, expected_response);
@@ -55,7 +58,8 @@ async fn positive_flow() {
 #[tokio::test]
 async fn inbound_dropped() {
     let (inbound_stream, outbound_stream, _) = get_connected_streams().await;
-    let outbound_protocol = OutboundProtocol { query: vec![0u8], protocol_name: PROTOCOL_NAME };
+    let outbound_protocol =
+        OutboundProtocol { query: vec![0u8], protocol_names: vec![PROTOCOL_NAME] };
 
     drop(inbound_stream);
 
@@ -5,6 +5,10 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct BroadcastedMessageMetadata {
     pub originator_id: OpaquePeerId,
+    pub message_id: OpaqueMessageId,
+    /// The peer that sent us the message. It may be different from the originator if the message
+    /// was forwarded.
+    pub propagation_source: OpaquePeerId,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -21,3 +25,18 @@ impl OpaquePeerId {
         self.0
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct OpaqueMessageId(Vec<u8>);
+
+impl OpaqueMessageId {
+    /// This function shouldn't be used by anyone except for the papyrus_network crate
+    pub fn private_new(message_id: Vec<u8>) -> Self {
+        Self(message_id)
+    }
+
+    /// This function shouldn't be used by anyone except for the papyrus_network crate
+    pub fn private_get_message_id(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+}
@@ -2,7 +2,7 @@ use libp2p::PeerId;
 use papyrus_test_utils::{auto_impl_get_test_instance, GetTestInstance};
 use rand_chacha::ChaCha8Rng;
 
-use crate::network_types::{BroadcastedMessageMetadata, OpaquePeerId};
+use crate::network_types::{BroadcastedMessageMetadata, OpaqueMessageId, OpaquePeerId};
 
 impl GetTestInstance for OpaquePeerId {
     // TODO: use the given rng by copying the libp2p implementation.
@@ -11,8 +11,16 @@ impl GetTestInstance for OpaquePeerId {
     }
 }
 
+impl GetTestInstance for OpaqueMessageId {
+    fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
+        Self::private_new(Vec::<u8>::get_test_instance(rng))
+    }
+}
+
 auto_impl_get_test_instance! {
     pub struct BroadcastedMessageMetadata {
         pub originator_id: OpaquePeerId,
+        pub message_id: OpaqueMessageId,
+        pub propagation_source: OpaquePeerId,
     }
 }
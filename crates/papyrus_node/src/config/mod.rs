@@ -58,6 +58,7 @@ pub struct NodeConfig {
     #[validate]
     pub storage: StorageConfig,
     /// None if the syncing should be disabled.
+    #[validate]
     pub sync: Option<SyncConfig>,
     /// One of p2p_sync or sync must be None.
     /// If P2P sync is active, then network must be active too.
@@ -9,7 +9,7 @@ use std::time::Duration;
 
 use futures::StreamExt;
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerConfig;
-use papyrus_common::metrics::COLLECT_PROFILING_METRICS;
+use papyrus_common::metrics::{COLLECT_PROFILING_METRICS, PAPYRUS_SYNC_CHECKPOINT};
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_config::presentation::get_config_presentation;
 use papyrus_config::validators::config_validate;
@@ -31,7 +31,7 @@ use papyrus_storage::{open_storage, StorageReader, StorageWriter};
 use papyrus_sync::sources::base_layer::EthereumBaseLayerSource;
 use papyrus_sync::sources::central::{CentralError, CentralSource, CentralSourceConfig};
 use papyrus_sync::sources::pending::PendingSource;
-use papyrus_sync::{StateSync, SyncConfig};
+use papyrus_sync::{StateSync, SyncConfig, SyncPauseControl};
 use starknet_api::block::{BlockHash, BlockHashAndNumber};
 use starknet_api::felt;
 use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
@@ -243,7 +243,7 @@ async fn run_sync(
     let pending_source =
         PendingSource::new(central_config, VERSION_FULL).map_err(CentralError::ClientCreation)?;
     let base_layer_source = EthereumBaseLayerSource::new(base_layer_config);
-    let sync = StateSync::new(
+    let mut sync = StateSync::new(
         sync_config,
         shared_highest_block,
         pending_data,
@@ -253,7 +253,13 @@ async fn run_sync(
         base_layer_source,
         storage_reader.clone(),
         storage_writer,
+        SyncPauseControl::default(),
     );
+    // Lets external backup tools know up to which block the storage is safe to snapshot.
+    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+    sync.set_checkpoint_callback(|block_number| {
+        metrics::gauge!(PAPYRUS_SYNC_CHECKPOINT, block_number.0 as f64);
+    });
     Ok(sync.run().await?)
 }
 
@@ -305,6 +311,7 @@ async fn spawn_sync_client(
                 storage_writer,
                 p2p_sync_client_channels,
                 futures::stream::pending().boxed(),
+                None,
             );
             tokio::spawn(async move { Ok(p2p_sync.run().await.map(|_never| ())?) })
         }
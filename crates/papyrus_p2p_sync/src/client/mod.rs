@@ -45,6 +45,7 @@ use starknet_api::transaction::FullTransaction;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use state_diff::StateDiffStreamBuilder;
 use stream_builder::{DataStreamBuilder, DataStreamResult};
+use tokio::sync::broadcast;
 use tokio_stream::StreamExt;
 use tracing::{info, instrument};
 use transaction::TransactionStreamFactory;
@@ -216,6 +217,8 @@ pub struct P2PSyncClient {
     storage_writer: StorageWriter,
     p2p_sync_channels: P2PSyncClientChannels,
     internal_blocks_receiver: BoxStream<'static, (BlockNumber, SyncBlock)>,
+    // Gets the number of every block whose state diff is committed to the storage.
+    new_block_notification_sender: Option<broadcast::Sender<BlockNumber>>,
 }
 
 impl P2PSyncClient {
@@ -225,8 +228,16 @@ impl P2PSyncClient {
         storage_writer: StorageWriter,
         p2p_sync_channels: P2PSyncClientChannels,
         internal_blocks_receiver: BoxStream<'static, (BlockNumber, SyncBlock)>,
+        new_block_notification_sender: Option<broadcast::Sender<BlockNumber>>,
     ) -> Self {
-        Self { config, storage_reader, storage_writer, p2p_sync_channels, internal_blocks_receiver }
+        Self {
+            config,
+            storage_reader,
+            storage_writer,
+            p2p_sync_channels,
+            internal_blocks_receiver,
+            new_block_notification_sender,
+        }
     }
 
     #[instrument(skip(self), level = "debug", err)]
@@ -243,6 +254,7 @@ impl P2PSyncClient {
             mut storage_writer,
             p2p_sync_channels,
             mut internal_blocks_receiver,
+            new_block_notification_sender,
         } = self;
         let mut data_stream =
             p2p_sync_channels.create_stream(storage_reader, config, internal_blocks_receivers);
@@ -255,7 +267,15 @@ impl P2PSyncClient {
                 }
                 data = data_stream.next() => {
                     let data = data.expect("Sync data stream should never end")?;
+                    let new_block_number = data.new_block_number();
                     data.write_to_storage(&mut storage_writer)?;
+                    if let (Some(sender), Some(block_number)) =
+                        (&new_block_notification_sender, new_block_number)
+                    {
+                        // Sending fails only when there are no subscribers, in which case there's
+                        // no one to notify.
+                        let _ = sender.send(block_number);
+                    }
                 }
             }
         }
@@ -34,6 +34,10 @@ impl BlockData for (ThinStateDiff, BlockNumber) {
         gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, self.1.unchecked_next().0 as f64);
         Ok(())
     }
+
+    fn new_block_number(&self) -> Option<BlockNumber> {
+        Some(self.1)
+    }
 }
 
 pub(crate) struct StateDiffStreamBuilder;
@@ -167,6 +167,7 @@ async fn state_diff_basic_flow() {
             }
             .boxed()
         })));
+        actions.push(Action::CheckNewBlockNotification(BlockNumber(i.try_into().unwrap())));
     }
     actions.push(Action::SendStateDiff(DataOrFin(None)));
 
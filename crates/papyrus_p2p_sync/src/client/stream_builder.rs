@@ -28,6 +28,12 @@ pub(crate) trait BlockData: Send {
         self: Box<Self>,
         storage_writer: &mut StorageWriter,
     ) -> Result<(), StorageError>;
+
+    /// The block that becomes synced once this data is written, if any. A block is considered
+    /// synced once its state diff is written.
+    fn new_block_number(&self) -> Option<BlockNumber> {
+        None
+    }
 }
 
 pub(crate) enum BlockNumberLimit {
@@ -45,7 +45,7 @@ use starknet_api::hash::StarkHash;
 use starknet_api::transaction::FullTransaction;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
 use starknet_types_core::felt::Felt;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 
 use super::{P2PSyncClient, P2PSyncClientChannels, P2PSyncClientConfig};
 
@@ -116,6 +116,7 @@ pub fn setup() -> TestArgs {
         storage_writer,
         p2p_sync_channels,
         futures::stream::pending().boxed(),
+        None,
     );
     TestArgs {
         p2p_sync,
@@ -163,6 +164,9 @@ pub enum Action {
     /// Sends an internal block to the sync.
     #[allow(dead_code)]
     SendInternalBlock(BlockNumber, SyncBlock),
+    /// Check that the sync notified that the given block was synced, and that it didn't notify on
+    /// any block before it that wasn't checked yet.
+    CheckNewBlockNotification(BlockNumber),
 }
 
 // TODO(shahak): add support for state diffs, transactions and classes.
@@ -196,12 +200,15 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
         class_sender,
     };
     let (mut internal_block_sender, internal_block_receiver) = mpsc::channel(buffer_size);
+    let (new_block_notification_sender, mut new_block_notification_receiver) =
+        broadcast::channel(buffer_size);
     let p2p_sync = P2PSyncClient::new(
         p2p_sync_config,
         storage_reader.clone(),
         storage_writer,
         p2p_sync_channels,
         internal_block_receiver.boxed(),
+        Some(new_block_notification_sender),
     );
 
     let mut headers_current_query_responses_manager = None;
@@ -300,6 +307,10 @@ pub async fn run_test(max_query_lengths: HashMap<DataType, u64>, actions: Vec<Ac
                     Action::SendInternalBlock(block_number, sync_block) => {
                         internal_block_sender.send((block_number, sync_block)).await.unwrap();
                     }
+                    Action::CheckNewBlockNotification(expected_block_number) => {
+                        let block_number = new_block_notification_receiver.recv().await.unwrap();
+                        assert_eq!(block_number, expected_block_number);
+                    }
                     Action::RunP2pSync => {
                         sync_future_sender.take().expect("Called RunP2pSync twice").send(()).expect("Failed to send message to run P2P sync");
                     }
@@ -1,11 +1,14 @@
-use std::str::FromStr;
-
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use syn::parse::{Parse, ParseStream, Result};
+use syn::visit_mut::VisitMut;
 use syn::{
     parse_macro_input,
-    ExprLit,
+    Data,
+    DeriveInput,
+    Expr,
+    ExprCall,
+    Fields,
     Ident,
     ItemFn,
     ItemTrait,
@@ -14,6 +17,7 @@ use syn::{
     Meta,
     Token,
     TraitItem,
+    Type,
 };
 
 /// This macro is a wrapper around the "rpc" macro supplied by the jsonrpsee library that generates
@@ -42,9 +46,16 @@ use syn::{
 ///     fn block_number(&self) -> Result<BlockNumber, Error>;
 /// }
 /// ```
+///
+/// The namespace defaults to "starknet" and can be overridden with an optional argument:
+/// ```rust,ignore
+/// #[versioned_rpc("V0_6_0", namespace = "custom")]
+/// ```
 #[proc_macro_attribute]
 pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let version = parse_macro_input!(attr as syn::LitStr);
+    let VersionedRpcMacroInput { version, namespace } =
+        parse_macro_input!(attr as VersionedRpcMacroInput);
+    let namespace = namespace.map_or_else(|| DEFAULT_RPC_NAMESPACE.to_string(), |ns| ns.value());
     let item_trait = parse_macro_input!(input as ItemTrait);
 
     let trait_name = &item_trait.ident;
@@ -94,7 +105,7 @@ pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
 
     // generate the versioned trait with the new method signatures
     let versioned_trait = syn::ItemTrait {
-        attrs: vec![syn::parse_quote!(#[rpc(server, client, namespace = "starknet")])],
+        attrs: vec![syn::parse_quote!(#[rpc(server, client, namespace = #namespace)])],
         vis: visibility.clone(),
         unsafety: None,
         auto_token: None,
@@ -111,6 +122,30 @@ pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
     versioned_trait.to_token_stream().into()
 }
 
+const DEFAULT_RPC_NAMESPACE: &str = "starknet";
+
+struct VersionedRpcMacroInput {
+    version: LitStr,
+    namespace: Option<LitStr>,
+}
+
+impl Parse for VersionedRpcMacroInput {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let version = input.parse()?;
+        if input.is_empty() {
+            return Ok(VersionedRpcMacroInput { version, namespace: None });
+        }
+        input.parse::<Token![,]>()?;
+        let key: Ident = input.parse()?;
+        if key != "namespace" {
+            return Err(syn::Error::new(key.span(), "expected `namespace = \"...\"`"));
+        }
+        input.parse::<Token![=]>()?;
+        let namespace = input.parse()?;
+        Ok(VersionedRpcMacroInput { version, namespace: Some(namespace) })
+    }
+}
+
 /// This macro will emit a histogram metric with the given name and the latency of the function.
 /// In addition, also a debug log with the metric name and the execution time will be emitted.
 /// The macro also receives a boolean for whether it will be emitted only when
@@ -130,42 +165,128 @@ pub fn versioned_rpc(attr: TokenStream, input: TokenStream) -> TokenStream {
 /// “<metric_name>: <execution_time>”
 /// The metric will be emitted regardless of the value of the profiling configuration,
 /// since the config value is false.
+///
+/// A malformed attribute (e.g. `#[latency_histogram("metric_name")]`) results in a compile error
+/// pointing at the offending token.
 #[proc_macro_attribute]
 pub fn latency_histogram(attr: TokenStream, input: TokenStream) -> TokenStream {
-    let mut input_fn = parse_macro_input!(input as ItemFn);
-    let parts = attr
-        .to_string()
-        .split(',')
-        .map(|s| {
-            TokenStream::from_str(s)
-                .expect("Expecting metric name and bool (is for profiling only)")
-        })
-        .collect::<Vec<_>>();
-    let metric_name_as_tokenstream = parts
-        .first()
-        .expect("attribute should include metric name and controll with config boolean")
-        .clone();
-    // TODO: consider naming the input value instead of providing a bool
-    // TODO: consider adding support for metrics levels (e.g. debug, info, warn, error) instead of
-    // boolean
-    let controll_with_config_as_tokenstream = parts
-        .get(1)
-        .expect("attribute should include metric name and controll with config boolean")
-        .clone();
-    let metric_name = parse_macro_input!(metric_name_as_tokenstream as ExprLit);
-    let controll_with_config = parse_macro_input!(controll_with_config_as_tokenstream as LitBool);
-    let origin_block = &mut input_fn.block;
+    let input_fn = parse_macro_input!(input as ItemFn);
+    let macro_input = parse_macro_input!(attr as LatencyHistogramMacroInput);
+    let measured_expr = input_fn.block.to_token_stream();
+    wrap_fn_with_latency_histogram(
+        input_fn,
+        macro_input,
+        quote! {},
+        measured_expr,
+        quote! { std::time::Duration::ZERO },
+    )
+}
+
+/// The async counterpart of [`macro@latency_histogram`], for `async fn`s.
+///
+/// The metric records the time spent awaiting the function body, measured from the first poll of
+/// the returned future until it completes. Time the future spends before it's first polled is
+/// excluded.
+///
+/// Work the body hands off with `spawn_blocking` is measured from the moment it starts running:
+/// every `spawn_blocking(closure)` call written in the body is rewritten so that the time the
+/// closure waits for a free blocking thread is subtracted from the recorded duration. Calls made
+/// by other functions the body awaits aren't rewritten.
+///
+/// # Example
+/// ```rust,ignore
+/// #[async_latency_histogram("metric_name", false)]
+/// async fn foo() {
+///     tokio::task::spawn_blocking(|| { /* Some code ... */ }).await.unwrap();
+/// }
+/// ```
+///
+/// Applying the macro to a non-async function results in a compile error.
+#[proc_macro_attribute]
+pub fn async_latency_histogram(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(input as ItemFn);
+    let macro_input = parse_macro_input!(attr as LatencyHistogramMacroInput);
+    if input_fn.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(
+            input_fn.sig.fn_token,
+            "async_latency_histogram can only be applied to an async function",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let mut origin_block = input_fn.block.clone();
+    SpawnBlockingQueueTimeRecorder.visit_block_mut(&mut origin_block);
+    let setup = quote! {
+        let __latency_histogram_queue_time =
+            std::sync::Arc::new(std::sync::Mutex::new(std::time::Duration::ZERO));
+        let __latency_histogram_body_queue_time =
+            std::sync::Arc::clone(&__latency_histogram_queue_time);
+    };
+    // Wrapping the body in an async block keeps early returns and `?` inside the measured
+    // section.
+    let measured_expr = quote! { async move #origin_block.await };
+    let excluded_duration = quote! {
+        __latency_histogram_queue_time.lock().map(|queue_time| *queue_time).unwrap_or_default()
+    };
+    wrap_fn_with_latency_histogram(input_fn, macro_input, setup, measured_expr, excluded_duration)
+}
+
+// Rewrites the argument of every `spawn_blocking` call into a closure that adds the time it waited
+// to start running to `__latency_histogram_body_queue_time`, and then runs the original closure.
+struct SpawnBlockingQueueTimeRecorder;
 
+impl VisitMut for SpawnBlockingQueueTimeRecorder {
+    fn visit_expr_call_mut(&mut self, call: &mut ExprCall) {
+        syn::visit_mut::visit_expr_call_mut(self, call);
+        let Expr::Path(function) = call.func.as_ref() else {
+            return;
+        };
+        let is_spawn_blocking =
+            function.path.segments.last().is_some_and(|segment| segment.ident == "spawn_blocking");
+        if !is_spawn_blocking || call.args.len() != 1 {
+            return;
+        }
+        let work = &call.args[0];
+        call.args[0] = syn::parse_quote! {
+            {
+                let __latency_histogram_work = #work;
+                let __latency_histogram_work_queue_time =
+                    std::sync::Arc::clone(&__latency_histogram_body_queue_time);
+                let __latency_histogram_spawn_time = std::time::Instant::now();
+                move || {
+                    if let Ok(mut queue_time) = __latency_histogram_work_queue_time.lock() {
+                        *queue_time += __latency_histogram_spawn_time.elapsed();
+                    }
+                    __latency_histogram_work()
+                }
+            }
+        };
+    }
+
+    // Nested functions have their own measurement, if any.
+    fn visit_item_fn_mut(&mut self, _item_fn: &mut ItemFn) {}
+}
+
+// Replaces the function body with one that evaluates `measured_expr` and records its duration,
+// minus `excluded_duration`, which is evaluated after `measured_expr`. `setup` runs before both.
+fn wrap_fn_with_latency_histogram(
+    input_fn: ItemFn,
+    LatencyHistogramMacroInput { metric_name, controll_with_config }: LatencyHistogramMacroInput,
+    setup: impl ToTokens,
+    measured_expr: impl ToTokens,
+    excluded_duration: impl ToTokens,
+) -> TokenStream {
     // Create a new block with the metric update.
     let expanded_block = quote! {
         {
+            #setup
             let mut start_function_time = None;
             if !#controll_with_config || (#controll_with_config && *(papyrus_common::metrics::COLLECT_PROFILING_METRICS.get().unwrap_or(&false))) {
                 start_function_time=Some(std::time::Instant::now());
             }
-            let return_value=#origin_block;
+            let return_value=#measured_expr;
             if let Some(start_time) = start_function_time {
-                let exec_time = start_time.elapsed().as_secs_f64();
+                let exec_time = start_time.elapsed().saturating_sub(#excluded_duration).as_secs_f64();
                 metrics::histogram!(#metric_name, exec_time);
                 tracing::debug!("{}: {}", #metric_name, exec_time);
             }
@@ -182,6 +303,39 @@ pub fn latency_histogram(attr: TokenStream, input: TokenStream) -> TokenStream {
     modified_function.to_token_stream().into()
 }
 
+struct LatencyHistogramMacroInput {
+    metric_name: LitStr,
+    // TODO: consider naming the input value instead of providing a bool
+    // TODO: consider adding support for metrics levels (e.g. debug, info, warn, error) instead of
+    // boolean
+    controll_with_config: LitBool,
+}
+
+impl Parse for LatencyHistogramMacroInput {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let metric_name: LitStr = input.parse().map_err(|error| {
+            syn::Error::new(error.span(), "expected the metric name as a string literal")
+        })?;
+        if input.is_empty() {
+            return Err(syn::Error::new(
+                metric_name.span(),
+                "expected the metric name to be followed by a boolean (is for profiling only)",
+            ));
+        }
+        input.parse::<Token![,]>()?;
+        let controll_with_config: LitBool = input.parse().map_err(|error| {
+            syn::Error::new(
+                error.span(),
+                "expected a boolean literal for whether the metric is for profiling only",
+            )
+        })?;
+        if !input.is_empty() {
+            return Err(input.error("unexpected input after the profiling boolean"));
+        }
+        Ok(LatencyHistogramMacroInput { metric_name, controll_with_config })
+    }
+}
+
 struct HandleResponseVariantsMacroInput {
     response_enum: Ident,
     request_response_enum_var: Ident,
@@ -232,6 +386,9 @@ impl Parse for HandleResponseVariantsMacroInput {
 ///     )),
 /// }
 /// ```
+///
+/// In addition, the variant is referenced outside of the match, so that a nonexistent variant is
+/// reported at the macro invocation.
 #[proc_macro]
 pub fn handle_response_variants(input: TokenStream) -> TokenStream {
     let HandleResponseVariantsMacroInput {
@@ -242,14 +399,226 @@ pub fn handle_response_variants(input: TokenStream) -> TokenStream {
     } = parse_macro_input!(input as HandleResponseVariantsMacroInput);
 
     let expanded = quote! {
-        match response? {
-            #response_enum::#request_response_enum_var(Ok(response)) => Ok(response),
-            #response_enum::#request_response_enum_var(Err(response)) => {
-                Err(#component_client_error::#component_error(response))
+        {
+            // Reference the variant outside of the match so that a nonexistent variant is reported
+            // at the macro invocation.
+            const _: fn() = || {
+                let _ = #response_enum::#request_response_enum_var;
+            };
+            match response? {
+                #response_enum::#request_response_enum_var(Ok(response)) => Ok(response),
+                #response_enum::#request_response_enum_var(Err(response)) => {
+                    Err(#component_client_error::#component_error(response))
+                }
+                unexpected_response => Err(#component_client_error::ClientError(ClientError::UnexpectedResponse(format!("{unexpected_response:?}")))),
             }
-            unexpected_response => Err(#component_client_error::ClientError(ClientError::UnexpectedResponse(format!("{unexpected_response:?}")))),
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Derives `papyrus_config::dumping::SerializeConfig` for a struct with named fields, generating
+/// `dump` from the `config` attribute of each field:
+/// * `#[config(desc = "...", privacy = "public")]` -- a param serialized with `ser_param`. The
+///   privacy is either "public" or "private". `Option` params are serialized with
+///   `ser_optional_param`, dumping `default` (or `Default::default()` if it isn't given) when the
+///   param is `None`, e.g. `#[config(desc = "...", privacy = "public", default = 1000)]`.
+/// * `#[config(sub_config)]` -- a sub config dumped under the field's name with
+///   `append_sub_config_name`, or with `ser_optional_sub_config` if it's an `Option`.
+/// * `#[config(skip)]` -- a field that isn't dumped.
+///
+/// Every field must have exactly one of the above. Fields that need a conversion before being
+/// serialized (e.g. a `Duration` dumped as seconds) should be dumped by a hand-written
+/// `SerializeConfig` implementation.
+///
+/// # Example
+/// Given this code:
+/// ```rust,ignore
+/// #[derive(DumpConfig)]
+/// pub struct ExampleConfig {
+///     #[config(desc = "The port to listen on.", privacy = "public")]
+///     pub port: u16,
+///     #[config(sub_config)]
+///     pub db_config: DbConfig,
+/// }
+/// ```
+///
+/// The macro will generate this code:
+/// ```rust,ignore
+/// impl SerializeConfig for ExampleConfig {
+///     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+///         let mut config = BTreeMap::new();
+///         config.extend([ser_param(
+///             "port",
+///             &self.port,
+///             "The port to listen on.",
+///             ParamPrivacyInput::Public,
+///         )]);
+///         config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
+///         config
+///     }
+/// }
+/// ```
+#[proc_macro_derive(DumpConfig, attributes(config))]
+pub fn derive_dump_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_dump_config_inner(input).unwrap_or_else(|error| error.to_compile_error().into())
+}
+
+enum DumpConfigField {
+    Param { desc: LitStr, privacy: Ident, default: Option<Expr> },
+    SubConfig,
+    Skip,
+}
+
+fn derive_dump_config_inner(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data_struct) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "DumpConfig supports only structs"));
+    };
+    let Fields::Named(fields) = &data_struct.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "DumpConfig supports only structs with named fields",
+        ));
+    };
+
+    let mut dump_statements = Vec::new();
+    for field in &fields.named {
+        let field_ident = field.ident.as_ref().expect("Named fields should have an identifier.");
+        let field_name = field_ident.to_string();
+        let is_optional = is_option_type(&field.ty);
+        match parse_dump_config_field(field)? {
+            DumpConfigField::Param { desc, privacy, default } if is_optional => {
+                let default = match default {
+                    Some(default) => default.to_token_stream(),
+                    None => quote! { Default::default() },
+                };
+                dump_statements.push(quote! {
+                    config.extend(papyrus_config::dumping::ser_optional_param(
+                        &self.#field_ident,
+                        #default,
+                        #field_name,
+                        #desc,
+                        papyrus_config::ParamPrivacyInput::#privacy,
+                    ));
+                })
+            }
+            DumpConfigField::Param { default: Some(default), .. } => {
+                return Err(syn::Error::new_spanned(
+                    default,
+                    "`default` is supported only for Option fields",
+                ));
+            }
+            DumpConfigField::Param { desc, privacy, default: None } => {
+                dump_statements.push(quote! {
+                    config.extend([papyrus_config::dumping::ser_param(
+                        #field_name,
+                        &self.#field_ident,
+                        #desc,
+                        papyrus_config::ParamPrivacyInput::#privacy,
+                    )]);
+                })
+            }
+            DumpConfigField::SubConfig if is_optional => dump_statements.push(quote! {
+                config.extend(papyrus_config::dumping::ser_optional_sub_config(
+                    &self.#field_ident,
+                    #field_name,
+                ));
+            }),
+            DumpConfigField::SubConfig => dump_statements.push(quote! {
+                config.extend(papyrus_config::dumping::append_sub_config_name(
+                    papyrus_config::dumping::SerializeConfig::dump(&self.#field_ident),
+                    #field_name,
+                ));
+            }),
+            DumpConfigField::Skip => {}
+        }
+    }
+
+    let struct_ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics papyrus_config::dumping::SerializeConfig
+            for #struct_ident #type_generics #where_clause
+        {
+            fn dump(
+                &self,
+            ) -> std::collections::BTreeMap<papyrus_config::ParamPath, papyrus_config::SerializedParam>
+            {
+                #[allow(unused_mut)]
+                let mut config = std::collections::BTreeMap::new();
+                #(#dump_statements)*
+                config
+            }
+        }
+    }
+    .into())
+}
+
+fn parse_dump_config_field(field: &syn::Field) -> Result<DumpConfigField> {
+    let mut config_attrs = field.attrs.iter().filter(|attr| attr.path().is_ident("config"));
+    let (Some(attr), None) = (config_attrs.next(), config_attrs.next()) else {
+        return Err(syn::Error::new_spanned(
+            field,
+            "expected exactly one #[config(...)] attribute on every field",
+        ));
+    };
+
+    let mut desc = None;
+    let mut privacy = None;
+    let mut default = None;
+    let mut is_sub_config = false;
+    let mut is_skipped = false;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("desc") {
+            desc = Some(meta.value()?.parse::<LitStr>()?);
+        } else if meta.path.is_ident("privacy") {
+            let privacy_str: LitStr = meta.value()?.parse()?;
+            privacy = Some(match privacy_str.value().as_str() {
+                "public" => Ident::new("Public", privacy_str.span()),
+                "private" => Ident::new("Private", privacy_str.span()),
+                _ => {
+                    return Err(syn::Error::new(
+                        privacy_str.span(),
+                        "expected privacy to be \"public\" or \"private\"",
+                    ));
+                }
+            });
+        } else if meta.path.is_ident("default") {
+            default = Some(meta.value()?.parse::<Expr>()?);
+        } else if meta.path.is_ident("sub_config") {
+            is_sub_config = true;
+        } else if meta.path.is_ident("skip") {
+            is_skipped = true;
+        } else {
+            return Err(meta.error("unsupported config attribute"));
+        }
+        Ok(())
+    })?;
+
+    match (desc, privacy, default, is_sub_config, is_skipped) {
+        (Some(desc), Some(privacy), default, false, false) => {
+            Ok(DumpConfigField::Param { desc, privacy, default })
+        }
+        (None, None, None, true, false) => Ok(DumpConfigField::SubConfig),
+        (None, None, None, false, true) => Ok(DumpConfigField::Skip),
+        _ => Err(syn::Error::new_spanned(
+            attr,
+            "expected either `desc` and `privacy` (and optionally `default`), `sub_config` or \
+             `skip`",
+        )),
+    }
+}
+
+// Whether the type is written as `Option<...>` (possibly with a path, e.g. `std::option::Option`).
+fn is_option_type(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path.qself.is_none()
+        && type_path.path.segments.last().is_some_and(|segment| {
+            segment.ident == "Option"
+                && matches!(segment.arguments, syn::PathArguments::AngleBracketed(_))
+        })
+}
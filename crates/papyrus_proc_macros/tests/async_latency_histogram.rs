@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_common::metrics::COLLECT_PROFILING_METRICS;
+use papyrus_proc_macros::async_latency_histogram;
+use papyrus_test_utils::prometheus_is_contained;
+use prometheus_parse::Value::Untyped;
+
+const SLEEP_DURATION: Duration = Duration::from_millis(100);
+// How long the only blocking thread is kept busy, so that the work `foo` hands off waits for it.
+const BLOCKING_THREAD_BUSY_DURATION: Duration = Duration::from_millis(300);
+
+#[test]
+fn async_latency_histogram_measures_awaited_duration() {
+    COLLECT_PROFILING_METRICS.set(false).unwrap();
+
+    #[async_latency_histogram("foo_async_histogram", false)]
+    async fn foo() -> usize {
+        tokio::time::sleep(SLEEP_DURATION).await;
+        tokio::task::spawn_blocking(|| 1000).await.unwrap()
+    }
+
+    #[async_latency_histogram("bar_async_histogram", true)]
+    async fn bar() -> usize {
+        1000
+    }
+
+    let handle = PrometheusBuilder::new().install_recorder().unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .max_blocking_threads(1)
+        .enable_time()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        assert_eq!(bar().await, 1000);
+        assert!(handle.render().is_empty());
+
+        // The closure `foo` spawns waits for the blocking thread until this task ends.
+        let busy_blocking_thread =
+            tokio::task::spawn_blocking(|| std::thread::sleep(BLOCKING_THREAD_BUSY_DURATION));
+        let future = foo();
+        // Creating the future without awaiting it must not count towards the measured duration.
+        tokio::time::sleep(SLEEP_DURATION).await;
+        assert_eq!(future.await, 1000);
+        busy_blocking_thread.await.unwrap();
+    });
+
+    assert_eq!(
+        prometheus_is_contained(handle.render(), "foo_async_histogram_count", &[]),
+        Some(Untyped(1f64))
+    );
+    let Some(Untyped(measured)) =
+        prometheus_is_contained(handle.render(), "foo_async_histogram_sum", &[])
+    else {
+        panic!("foo_async_histogram_sum is missing.");
+    };
+    // `foo` is awaited for about BLOCKING_THREAD_BUSY_DURATION - SLEEP_DURATION, most of which is
+    // spent waiting for the blocking thread.
+    assert!(measured >= SLEEP_DURATION.as_secs_f64(), "Measured {measured}s.");
+    assert!(measured < (SLEEP_DURATION * 2).as_secs_f64(), "Measured {measured}s.");
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_optional_sub_config,
+    ser_param,
+    SerializeConfig,
+};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_proc_macros::DumpConfig;
+
+#[derive(Clone, Default, DumpConfig)]
+struct InnerConfig {
+    #[config(desc = "An inner param.", privacy = "private")]
+    secret: String,
+}
+
+#[derive(Clone, Default, DumpConfig)]
+struct DerivedConfig {
+    #[config(desc = "A public param.", privacy = "public")]
+    port: u16,
+    #[config(desc = "An optional param.", privacy = "public")]
+    name: Option<String>,
+    #[config(desc = "An optional param with a default.", privacy = "public", default = 10)]
+    limit: Option<u64>,
+    #[config(sub_config)]
+    inner: InnerConfig,
+    #[config(sub_config)]
+    optional_inner: Option<InnerConfig>,
+    #[config(skip)]
+    #[allow(dead_code)]
+    runtime_only: bool,
+}
+
+struct HandWrittenConfig(DerivedConfig);
+
+impl SerializeConfig for HandWrittenConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = BTreeMap::from_iter([ser_param(
+            "port",
+            &self.0.port,
+            "A public param.",
+            ParamPrivacyInput::Public,
+        )]);
+        config.extend(ser_optional_param(
+            &self.0.name,
+            String::default(),
+            "name",
+            "An optional param.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_param(
+            &self.0.limit,
+            10,
+            "limit",
+            "An optional param with a default.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(append_sub_config_name(
+            BTreeMap::from_iter([ser_param(
+                "secret",
+                &self.0.inner.secret,
+                "An inner param.",
+                ParamPrivacyInput::Private,
+            )]),
+            "inner",
+        ));
+        config.extend(ser_optional_sub_config(&self.0.optional_inner, "optional_inner"));
+        config
+    }
+}
+
+#[test]
+fn derived_dump_matches_hand_written_dump() {
+    let config = DerivedConfig {
+        port: 8080,
+        name: Some("name".to_owned()),
+        limit: Some(5),
+        inner: InnerConfig { secret: "secret".to_owned() },
+        optional_inner: Some(InnerConfig { secret: "other secret".to_owned() }),
+        runtime_only: true,
+    };
+    assert_eq!(config.dump(), HandWrittenConfig(config.clone()).dump());
+}
+
+#[test]
+fn derived_dump_of_default_config() {
+    let config = DerivedConfig::default();
+    assert_eq!(config.dump(), HandWrittenConfig(config.clone()).dump());
+}
@@ -0,0 +1,5 @@
+#[test]
+fn handle_response_variants_nonexistent_variant() {
+    let test_cases = trybuild::TestCases::new();
+    test_cases.compile_fail("tests/ui/handle_response_variants_*.rs");
+}
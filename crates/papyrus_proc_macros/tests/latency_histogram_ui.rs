@@ -0,0 +1,5 @@
+#[test]
+fn latency_histogram_malformed_attribute() {
+    let test_cases = trybuild::TestCases::new();
+    test_cases.compile_fail("tests/ui/latency_histogram_*.rs");
+}
@@ -0,0 +1,32 @@
+use papyrus_proc_macros::handle_response_variants;
+
+#[derive(Debug)]
+enum ClientError {
+    UnexpectedResponse(String),
+}
+
+#[derive(Debug)]
+struct ComponentError;
+
+#[derive(Debug)]
+enum ComponentClientError {
+    ClientError(ClientError),
+    ComponentError(ComponentError),
+}
+
+impl From<ClientError> for ComponentClientError {
+    fn from(error: ClientError) -> Self {
+        ComponentClientError::ClientError(error)
+    }
+}
+
+#[derive(Debug)]
+enum Response {
+    GetValue(Result<u8, ComponentError>),
+}
+
+fn get_value(response: Result<Response, ClientError>) -> Result<u8, ComponentClientError> {
+    handle_response_variants!(Response, NonexistentVariant, ComponentClientError, ComponentError)
+}
+
+fn main() {}
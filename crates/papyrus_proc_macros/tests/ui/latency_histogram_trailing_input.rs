@@ -0,0 +1,6 @@
+use papyrus_proc_macros::latency_histogram;
+
+#[latency_histogram("foo_histogram", false, true)]
+fn foo() {}
+
+fn main() {}
@@ -0,0 +1,39 @@
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use papyrus_proc_macros::versioned_rpc;
+
+#[versioned_rpc("V0_8")]
+pub trait DefaultNamespaceRpc {
+    #[method(name = "foo")]
+    fn foo(&self) -> RpcResult<u8>;
+}
+
+#[versioned_rpc("V0_8", namespace = "custom")]
+pub trait CustomNamespaceRpc {
+    #[method(name = "foo")]
+    fn foo(&self) -> RpcResult<u8>;
+}
+
+struct RpcImpl;
+
+impl DefaultNamespaceRpcV0_8Server for RpcImpl {
+    fn foo(&self) -> RpcResult<u8> {
+        Ok(0)
+    }
+}
+
+impl CustomNamespaceRpcV0_8Server for RpcImpl {
+    fn foo(&self) -> RpcResult<u8> {
+        Ok(0)
+    }
+}
+
+#[test]
+fn versioned_rpc_namespace() {
+    let default_namespace_methods = DefaultNamespaceRpcV0_8Server::into_rpc(RpcImpl);
+    assert!(default_namespace_methods.method("starknet_V0_8_foo").is_some());
+
+    let custom_namespace_methods = CustomNamespaceRpcV0_8Server::into_rpc(RpcImpl);
+    assert!(custom_namespace_methods.method("custom_V0_8_foo").is_some());
+    assert!(custom_namespace_methods.method("starknet_V0_8_foo").is_none());
+}
@@ -6,7 +6,7 @@ use jsonrpsee::server::RpcModule;
 use jsonrpsee::types::ErrorObjectOwned;
 use jsonschema::JSONSchema;
 use papyrus_common::pending_classes::PendingClasses;
-use papyrus_execution::ExecutionConfig;
+use papyrus_execution::{ExecutionBackend, ExecutionConfig};
 use papyrus_storage::test_utils::get_test_storage_by_scope;
 use papyrus_storage::{StorageScope, StorageWriter};
 use pretty_assertions::assert_eq;
@@ -33,6 +33,8 @@ pub fn get_test_rpc_config() -> RpcConfig {
             eth_fee_contract_address: contract_address!("0x1001"),
             strk_fee_contract_address: contract_address!("0x1001"),
             default_initial_gas_cost: 10000000000,
+            backend: ExecutionBackend::Vm,
+            max_txs_per_request: 100,
         },
         server_address: String::from("127.0.0.1:0"),
         max_events_chunk_size: 10,
@@ -13,6 +13,7 @@ use papyrus_execution::{
     simulate_transactions as exec_simulate_transactions,
     ExecutableTransactionInput,
     ExecutionConfig,
+    StateOverrides,
 };
 use papyrus_storage::body::events::{EventIndex, EventsReader};
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
@@ -899,6 +900,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 request.calldata,
                 &execution_config,
                 DONT_IGNORE_L1_DA_MODE,
+                StateOverrides::default(),
             )
         })
         .await
@@ -1083,6 +1085,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 charge_fee,
                 validate,
                 DONT_IGNORE_L1_DA_MODE,
+                None,
+                StateOverrides::default(),
             )
         })
         .await
@@ -1232,6 +1236,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 true,
                 true,
                 DONT_IGNORE_L1_DA_MODE,
+                None,
+                StateOverrides::default(),
             )
         })
         .await
@@ -1348,6 +1354,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 true,
                 true,
                 DONT_IGNORE_L1_DA_MODE,
+                None,
+                StateOverrides::default(),
             )
         })
         .await
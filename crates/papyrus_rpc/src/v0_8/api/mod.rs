@@ -5,6 +5,7 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use flate2::bufread::GzDecoder;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_common::deprecated_class_abi::calculate_deprecated_class_abi_length;
 use papyrus_common::pending_classes::ApiContractClass;
@@ -38,8 +39,10 @@ use super::broadcasted_transaction::{
 };
 use super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::error::{
+    validation_failure,
     ContractError,
     JsonRpcError,
+    TransactionExecutionError,
     BLOCK_NOT_FOUND,
     CONTRACT_NOT_FOUND,
     INVALID_CONTINUATION_TOKEN,
@@ -667,6 +670,17 @@ pub(crate) fn execution_error_to_error_object_owned(err: ExecutionError) -> Erro
             rpc_err.into()
         }
         ExecutionError::ContractNotFound { .. } => CONTRACT_NOT_FOUND.into(),
+        // The requested block isn't synced yet.
+        ExecutionError::StateNumberOutOfRange { .. } => BLOCK_NOT_FOUND.into(),
+        ExecutionError::TooManyTransactions { .. } => {
+            ErrorObjectOwned::owned(InvalidParams.code(), err.to_string(), None::<()>)
+        }
+        ExecutionError::ValidationFailed { reason, .. } => validation_failure(reason).into(),
+        ExecutionError::ExecutionFailed { transaction_index, reason } => {
+            let rpc_err: JsonRpcError<TransactionExecutionError> =
+                TransactionExecutionError { transaction_index, execution_error: reason }.into();
+            rpc_err.into()
+        }
         _ => internal_server_error(err),
     }
 }
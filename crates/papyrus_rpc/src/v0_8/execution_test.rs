@@ -5,6 +5,7 @@ use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::indexmap;
 use jsonrpsee::core::Error;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
@@ -25,7 +26,7 @@ use papyrus_execution::objects::{
     RevertReason,
 };
 use papyrus_execution::testing_instances::get_storage_var_address;
-use papyrus_execution::ExecutableTransactionInput;
+use papyrus_execution::{ExecutableTransactionInput, ExecutionError};
 use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::CasmStorageWriter;
@@ -62,7 +63,7 @@ use starknet_api::core::{
 use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::deprecated_contract_class::ContractClass as SN_API_DeprecatedContractClass;
 use starknet_api::hash::StarkHash;
-use starknet_api::state::{StorageKey, ThinStateDiff as StarknetApiStateDiff};
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff as StarknetApiStateDiff};
 use starknet_api::test_utils::{path_in_resources, read_json_file};
 use starknet_api::transaction::fields::{Calldata, Fee};
 use starknet_api::transaction::{
@@ -90,6 +91,7 @@ use tokio::sync::RwLock;
 use super::api::api_impl::JsonRpcServerImpl;
 use super::api::{
     decompress_program,
+    execution_error_to_error_object_owned,
     SimulatedTransaction,
     SimulationFlag,
     TransactionTraceWithHash,
@@ -1334,6 +1336,21 @@ fn broadcasted_to_executable_invoke() {
     );
 }
 
+#[test]
+fn request_execution_errors_are_client_errors() {
+    let err = execution_error_to_error_object_owned(ExecutionError::StateNumberOutOfRange {
+        requested: StateNumber(BlockNumber(2)),
+        synced: StateNumber(BlockNumber(1)),
+    });
+    assert_eq!(err, BLOCK_NOT_FOUND.into());
+
+    let err = execution_error_to_error_object_owned(ExecutionError::TooManyTransactions {
+        count: 2,
+        limit: 1,
+    });
+    assert_eq!(err.code(), InvalidParams.code());
+}
+
 #[test]
 fn get_decompressed_program() {
     let compressed = get_test_compressed_program();
@@ -25,7 +25,11 @@ use starknet_api::core::{
     Nonce,
 };
 use starknet_api::data_availability::DataAvailabilityMode;
-use starknet_api::execution_resources::GasAmount;
+use starknet_api::execution_resources::{
+    DataAvailabilityResourcesRpc,
+    ExecutionResourcesRpc,
+    GasAmount,
+};
 use starknet_api::serde_utils::bytes_from_hex_str;
 use starknet_api::transaction::fields::{
     AccountDeploymentData,
@@ -966,8 +970,13 @@ impl Add for ExecutionResources {
 
 impl From<starknet_api::execution_resources::ExecutionResources> for ExecutionResources {
     fn from(value: starknet_api::execution_resources::ExecutionResources) -> Self {
-        let l1_gas = value.da_gas_consumed.l1_gas;
-        let l1_data_gas = value.da_gas_consumed.l1_data_gas;
+        ExecutionResourcesRpc::from(value).into()
+    }
+}
+
+impl From<ExecutionResourcesRpc> for ExecutionResources {
+    fn from(value: ExecutionResourcesRpc) -> Self {
+        let DataAvailabilityResourcesRpc { l1_gas, l1_data_gas } = value.data_availability;
         Self {
             computation_resources: value.into(),
             data_availability: DataAvailabilityResources { l1_gas, l1_data_gas },
@@ -977,6 +986,12 @@ impl From<starknet_api::execution_resources::ExecutionResources> for ExecutionRe
 
 impl From<starknet_api::execution_resources::ExecutionResources> for ComputationResources {
     fn from(value: starknet_api::execution_resources::ExecutionResources) -> Self {
+        ExecutionResourcesRpc::from(value).into()
+    }
+}
+
+impl From<ExecutionResourcesRpc> for ComputationResources {
+    fn from(value: ExecutionResourcesRpc) -> Self {
         Self {
             steps: value.steps,
             builtin_instance_counter: value
@@ -986,7 +1001,7 @@ impl From<starknet_api::execution_resources::ExecutionResources> for Computation
                     v.try_into().ok().and_then(|v| (k.try_into().ok().map(|k| (k, v))))
                 })
                 .collect(),
-            memory_holes: value.memory_holes.try_into().ok(),
+            memory_holes: value.memory_holes.and_then(|memory_holes| memory_holes.try_into().ok()),
         }
     }
 }
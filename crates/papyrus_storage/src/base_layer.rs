@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let (reader, mut writer) = open_storage(storage_config)?;
@@ -1,8 +1,8 @@
-use std::fs::{read_to_string, File};
+use std::fs::File;
 use std::time::Duration;
 
 use clap::{Arg, Command};
-use papyrus_common::storage_query::StorageQuery;
+use papyrus_common::storage_query::{load_storage_queries, StorageQueriesFormat, StorageQuery};
 use papyrus_storage::db::DbConfig;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::StorageConfig;
@@ -19,14 +19,10 @@ pub fn main() {
 
     // Creates List of queries to be executed.
     println!("Creating queries");
-    let mut queries: Vec<StorageQuery> = Vec::new();
-    for line in read_to_string(cli_params.queries_file_path)
-        .expect("Should be able to read the queries file")
-        .lines()
-    {
-        queries
-            .push(serde_json::from_str(line).expect("Query should be a valid query json object"));
-    }
+    let queries_file =
+        File::open(cli_params.queries_file_path).expect("Should be able to open the queries file");
+    let queries = load_storage_queries(queries_file, cli_params.queries_format)
+        .expect("Queries file should contain valid queries");
 
     // Open storage to execute the queries.
     println!("Opening storage");
@@ -163,6 +159,7 @@ struct CliParams {
     db_path: String,
     output_file_path: String,
     chain_id: ChainId,
+    queries_format: StorageQueriesFormat,
 }
 
 fn get_cli_params() -> CliParams {
@@ -195,6 +192,14 @@ fn get_cli_params() -> CliParams {
                 .required(true)
                 .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
         )
+        .arg(
+            Arg::new("queries_format")
+                .short('f')
+                .long("queries_format")
+                .value_parser(["json", "binary"])
+                .default_value("json")
+                .help("The format of the queries file: a json object per line or bincode"),
+        )
         .get_matches();
 
     let queries_file_path = matches
@@ -209,5 +214,18 @@ fn get_cli_params() -> CliParams {
     let chain_id =
         matches.get_one::<String>("chain_id").expect("Missing parse chain_id").to_string();
 
-    CliParams { queries_file_path, db_path, output_file_path, chain_id: chain_id.into() }
+    let queries_format =
+        match matches.get_one::<String>("queries_format").expect("Missing queries_format").as_str()
+        {
+            "binary" => StorageQueriesFormat::Binary,
+            _ => StorageQueriesFormat::Json,
+        };
+
+    CliParams {
+        queries_file_path,
+        db_path,
+        output_file_path,
+        chain_id: chain_id.into(),
+        queries_format,
+    }
 }
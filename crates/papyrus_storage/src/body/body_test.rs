@@ -9,7 +9,7 @@ use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
 use crate::db::table_types::Table;
 use crate::db::{DbError, KeyAlreadyExistsError};
 use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
-use crate::{OffsetKind, StorageError, StorageScope, StorageWriter};
+use crate::{MarkerKind, OffsetKind, StorageError, StorageScope, StorageWriter};
 
 #[tokio::test]
 async fn append_body() {
@@ -139,6 +139,15 @@ async fn append_body() {
         Some(tx_hashes[2])
     );
 
+    // Check transactions by position in the block.
+    assert_eq!(
+        txn.get_transaction_at(BlockNumber(2), 1).unwrap(),
+        Some((txs[2].clone(), tx_hashes[2]))
+    );
+    assert_eq!(txn.get_transaction_at(BlockNumber(2), 2).unwrap(), None);
+    assert_eq!(txn.get_transaction_at(BlockNumber(1), 0).unwrap(), None);
+    assert_eq!(txn.get_transaction_at(BlockNumber(3), 0).unwrap(), None);
+
     // Check block transactions.
     assert_eq!(txn.get_block_transactions(BlockNumber(0)).unwrap(), Some(vec![txs[0].clone()]));
     assert_eq!(txn.get_block_transactions(BlockNumber(1)).unwrap(), Some(vec![]));
@@ -174,6 +183,12 @@ async fn append_body() {
     assert_eq!(txn.get_block_transactions_count(BlockNumber(1)).unwrap(), Some(0));
     assert_eq!(txn.get_block_transactions_count(BlockNumber(2)).unwrap(), Some(2));
     assert_eq!(txn.get_block_transactions_count(BlockNumber(3)).unwrap(), None);
+    for block_number in [BlockNumber(0), BlockNumber(1), BlockNumber(2), BlockNumber(3)] {
+        assert_eq!(
+            txn.get_block_transactions_count(block_number).unwrap(),
+            txn.get_block_transactions(block_number).unwrap().map(|txs| txs.len())
+        );
+    }
 }
 
 #[tokio::test]
@@ -411,3 +426,38 @@ fn update_offset_table() {
         file_offset_table.get(&txn.txn, &OffsetKind::TransactionOutput).unwrap().unwrap()
     );
 }
+
+#[test]
+fn append_body_if_absent() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let body = get_test_body(2, None, None, None);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body_if_absent(BlockNumber(0), body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Re-appending an identical body is skipped.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body_if_absent(BlockNumber(0), body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_block_transactions(BlockNumber(0)).unwrap(), Some(body.transactions));
+
+    let result =
+        writer.begin_rw_txn().unwrap().append_body_if_absent(BlockNumber(0), BlockBody::default());
+    assert_matches!(
+        result,
+        Err(StorageError::ConflictingBlockData {
+            block_number: BlockNumber(0),
+            marker_kind: MarkerKind::Body
+        })
+    );
+}
@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! // The API allows read-only interactions with the events. To write events, use the body writer.
@@ -49,6 +50,7 @@
 mod events_test;
 
 use std::collections::VecDeque;
+use std::ops::Range;
 
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
@@ -57,15 +59,17 @@ use starknet_api::transaction::{
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    TransactionOffsetInBlock,
     TransactionOutput,
 };
+use starknet_types_core::felt::Felt;
 
 use super::TransactionMetadataTable;
 use crate::body::{EventsTableKey, TransactionIndex};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursor, DbCursorTrait, NoValue, SimpleTable, Table};
 use crate::db::{DbTransaction, RO};
-use crate::{FileHandlers, StorageResult, StorageTxn, TransactionMetadata};
+use crate::{FileHandlers, StorageError, StorageResult, StorageTxn, TransactionMetadata};
 
 /// An identifier of an event.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -91,6 +95,20 @@ pub trait EventsReader<'txn, 'env> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over the indices of the events emitted in the given block range that
+    /// match the filters. The address filter is applied through the events table, so only the
+    /// events of that contract are read. The key filter is checked on each event: its i-th entry
+    /// lists the allowed values of the i-th key of the event, where an empty entry allows any
+    /// value but still requires the key to exist.
+    ///
+    /// Errors are returned as items of the iterator, after which it ends.
+    fn iter_events_matching(
+        &'env self,
+        address_filter: Option<ContractAddress>,
+        key_filter: Option<Vec<Vec<Felt>>>,
+        range: Range<BlockNumber>,
+    ) -> EventIndexIterMatching<'txn, 'env>;
 }
 
 // TODO: support all read transactions (including RW).
@@ -109,6 +127,35 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
 
         Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(event_index, to_block_number)?))
     }
+
+    fn iter_events_matching(
+        &'env self,
+        address_filter: Option<ContractAddress>,
+        key_filter: Option<Vec<Vec<Felt>>>,
+        range: Range<BlockNumber>,
+    ) -> EventIndexIterMatching<'txn, 'env> {
+        let first_event_index = EventIndex(
+            TransactionIndex(range.start, TransactionOffsetInBlock(0)),
+            EventIndexInTransactionOutput(0),
+        );
+        let events = match (range.start < range.end).then(|| range.end.prev()).flatten() {
+            Some(last_block_number) => {
+                self.iter_events(address_filter, first_event_index, last_block_number).map(Some)
+            }
+            None => Ok(None),
+        };
+        let (events, error) = match events {
+            Ok(events) => (events, None),
+            Err(err) => (None, Some(err)),
+        };
+        EventIndexIterMatching {
+            events,
+            error,
+            address_filter,
+            key_filter: key_filter.unwrap_or_default(),
+            end_block_number: range.end,
+        }
+    }
 }
 
 // TODO(dvir): add transaction hash to the return value. In the RPC when returning events this is
@@ -137,6 +184,72 @@ impl Iterator for EventIter<'_, '_> {
     }
 }
 
+impl EventIter<'_, '_> {
+    fn next_event(
+        &mut self,
+    ) -> StorageResult<Option<((ContractAddress, EventIndex), EventContent)>> {
+        match self {
+            EventIter::ByContractAddress(it) => it.next(),
+            EventIter::ByEventIndex(it) => it.next(),
+        }
+    }
+}
+
+/// An iterator over the indices of the events that match a filter, returned by
+/// [`EventsReader::iter_events_matching`].
+pub struct EventIndexIterMatching<'txn, 'env> {
+    // None once the iteration is over.
+    events: Option<EventIter<'txn, 'env>>,
+    // An error from creating the inner iterator, returned as the first item.
+    error: Option<StorageError>,
+    address_filter: Option<ContractAddress>,
+    key_filter: Vec<Vec<Felt>>,
+    end_block_number: BlockNumber,
+}
+
+impl Iterator for EventIndexIterMatching<'_, '_> {
+    type Item = StorageResult<EventIndex>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        let events = self.events.as_mut()?;
+        loop {
+            let next_event = match events.next_event() {
+                Ok(next_event) => next_event,
+                Err(err) => {
+                    self.events = None;
+                    return Some(Err(err));
+                }
+            };
+            // Iterating by contract address continues to the events of the following addresses,
+            // and neither iteration stops exactly at the end of the range.
+            let Some(((_, event_index), content)) =
+                next_event.filter(|((from_address, event_index), _)| {
+                    event_index.0.0 < self.end_block_number
+                        && self.address_filter.is_none_or(|address| address == *from_address)
+                })
+            else {
+                self.events = None;
+                return None;
+            };
+            if do_event_keys_match_filter(&content, &self.key_filter) {
+                return Some(Ok(event_index));
+            }
+        }
+    }
+}
+
+fn do_event_keys_match_filter(content: &EventContent, key_filter: &[Vec<Felt>]) -> bool {
+    key_filter.iter().enumerate().all(|(i, allowed_keys)| {
+        content
+            .keys
+            .get(i)
+            .is_some_and(|key| allowed_keys.is_empty() || allowed_keys.contains(&key.0))
+    })
+}
+
 /// This iterator goes over the events in the order of the events table key.
 /// That is, the events iterated first by the contract address and then by the event index.
 pub struct EventIterByContractAddress<'env, 'txn> {
@@ -9,8 +9,10 @@ use starknet_api::transaction::{
     EventContent,
     EventData,
     EventIndexInTransactionOutput,
+    EventKey,
     TransactionOffsetInBlock,
 };
+use starknet_types_core::felt::Felt;
 
 use crate::body::events::{get_events_from_tx, EventIndex, EventsReader};
 use crate::body::{BodyStorageWriter, TransactionIndex};
@@ -253,3 +255,84 @@ fn get_events_from_tx_test() {
     assert_eq!(get_events_from_tx(events.clone(), tx_index, ca1, 3), vec![]);
     assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 3), vec![]);
 }
+
+#[test]
+fn iter_events_matching() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let ca1 = 1u32.into();
+    let ca2 = 2u32.into();
+    let (key0, key1, key2, key3) = (Felt::ZERO, Felt::ONE, Felt::TWO, Felt::THREE);
+    let keys = vec![vec![EventKey(key0), EventKey(key1)], vec![EventKey(key2), EventKey(key3)]];
+    let block = get_test_block(10, Some(3), Some(vec![ca1, ca2]), Some(keys));
+    let block_number = block.header.block_header_without_hash.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The events with their indices, in the order they were emitted.
+    let all_events = block
+        .body
+        .transaction_outputs
+        .iter()
+        .enumerate()
+        .flat_map(|(tx_i, tx_output)| {
+            tx_output.events().iter().enumerate().map(move |(event_i, event)| {
+                let event_index = EventIndex(
+                    TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                    EventIndexInTransactionOutput(event_i),
+                );
+                (event_index, event.clone())
+            })
+        })
+        .collect::<Vec<_>>();
+    let expected_event_indices = |predicate: &dyn Fn(&Event) -> bool| {
+        all_events
+            .iter()
+            .filter(|(_, event)| predicate(event))
+            .map(|(event_index, _)| *event_index)
+            .collect::<Vec<_>>()
+    };
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let range = block_number..block_number.unchecked_next();
+    let iter_events_matching = |address_filter, key_filter| {
+        txn.iter_events_matching(address_filter, key_filter, range.clone())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+
+    // No filters.
+    assert_eq!(iter_events_matching(None, None), expected_event_indices(&|_| true));
+
+    // Address only.
+    let ca2_event_indices = expected_event_indices(&|event| event.from_address == ca2);
+    assert!(!ca2_event_indices.is_empty());
+    assert_eq!(iter_events_matching(Some(ca2), None), ca2_event_indices);
+
+    // Keys only. An empty entry matches any key.
+    let key_filter = vec![vec![], vec![key3]];
+    let key3_event_indices =
+        expected_event_indices(&|event| event.content.keys[1] == EventKey(key3));
+    assert!(!key3_event_indices.is_empty());
+    assert_eq!(iter_events_matching(None, Some(key_filter)), key3_event_indices);
+
+    // Address and keys.
+    let key_filter = vec![vec![key0], vec![key2, key3]];
+    let combined_event_indices = expected_event_indices(&|event| {
+        event.from_address == ca1 && event.content.keys[0] == EventKey(key0)
+    });
+    assert_eq!(iter_events_matching(Some(ca1), Some(key_filter)), combined_event_indices);
+
+    // A filter on a key the events don't have matches nothing.
+    assert_eq!(iter_events_matching(None, Some(vec![vec![], vec![], vec![]])), vec![]);
+
+    // Blocks outside the range aren't iterated.
+    let later_range = block_number.unchecked_next()..BlockNumber(block_number.0 + 2);
+    assert_eq!(txn.iter_events_matching(Some(ca1), None, later_range).count(), 0);
+}
@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! let block = Block::default();
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
@@ -98,6 +99,14 @@ pub trait BodyStorageReader {
         transaction_index: TransactionIndex,
     ) -> StorageResult<Option<Transaction>>;
 
+    /// Returns the transaction at the given position in the block and its hash, or None if the
+    /// block doesn't exist or has no transaction at that position.
+    fn get_transaction_at(
+        &self,
+        block_number: BlockNumber,
+        index: usize,
+    ) -> StorageResult<Option<(Transaction, TransactionHash)>>;
+
     /// Returns the transaction output at the given index.
     fn get_transaction_output(
         &self,
@@ -155,6 +164,15 @@ where
     // TODO(yair): make this work without consuming the body.
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self>;
 
+    /// Same as [`append_body`](Self::append_body), but if the block already has a body, succeeds
+    /// without writing when it's identical to the given one, and fails with
+    /// [`StorageError::ConflictingBlockData`] otherwise. Makes re-syncing stored blocks safe.
+    fn append_body_if_absent(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self>;
+
     /// Removes a block body from the storage and returns the removed data.
     fn revert_body(
         self,
@@ -182,6 +200,21 @@ impl<Mode: TransactionKind> BodyStorageReader for StorageTxn<'_, Mode> {
         Ok(Some(transaction))
     }
 
+    fn get_transaction_at(
+        &self,
+        block_number: BlockNumber,
+        index: usize,
+    ) -> StorageResult<Option<(Transaction, TransactionHash)>> {
+        let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(index));
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let Some(tx_metadata) = transaction_metadata_table.get(&self.txn, &transaction_index)?
+        else {
+            return Ok(None);
+        };
+        let transaction = self.file_handlers.get_transaction_unchecked(tx_metadata.tx_location)?;
+        Ok(Some((transaction, tx_metadata.tx_hash)))
+    }
+
     fn get_transaction_output(
         &self,
         transaction_index: TransactionIndex,
@@ -369,6 +402,36 @@ impl BodyStorageWriter for StorageTxn<'_, RW> {
         Ok(self)
     }
 
+    fn append_body_if_absent(
+        self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self> {
+        if block_number >= self.get_body_marker()? {
+            return self.append_body(block_number, block_body);
+        }
+        // Bodies aren't stored in this scope, so there's nothing to compare to.
+        if self.scope == StorageScope::StateOnly {
+            return Ok(self);
+        }
+        let stored_body = BlockBody {
+            transactions: self.get_block_transactions(block_number)?.unwrap_or_default(),
+            transaction_outputs: self
+                .get_block_transaction_outputs(block_number)?
+                .unwrap_or_default(),
+            transaction_hashes: self
+                .get_block_transaction_hashes(block_number)?
+                .unwrap_or_default(),
+        };
+        if stored_body != block_body {
+            return Err(StorageError::ConflictingBlockData {
+                block_number,
+                marker_kind: MarkerKind::Body,
+            });
+        }
+        Ok(self)
+    }
+
     fn revert_body(
         self,
         block_number: BlockNumber,
@@ -30,6 +30,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let class_hash = ClassHash::default();
@@ -92,6 +93,20 @@ pub trait ClassStorageReader {
     /// Returns the Cairo 1 class with the given hash.
     fn get_class(&self, class_hash: &ClassHash) -> StorageResult<Option<SierraContractClass>>;
 
+    /// Returns the Cairo 1 class with the given hash in its stored (compressed) format, without
+    /// decompressing or deserializing it.
+    ///
+    /// The bytes are a version byte (currently 0) followed by the storage serialization of
+    /// [`SierraContractClass`]: the Sierra program, then the contract class version, the entry
+    /// points by type and the ABI. The Sierra program and the ABI are each zstd-compressed and
+    /// prefixed by the varint length of the compressed bytes; the contract class version is a
+    /// varint length-prefixed UTF-8 string.
+    fn get_class_compressed_bytes(&self, class_hash: &ClassHash) -> StorageResult<Option<Vec<u8>>>;
+
+    /// Returns whether a Cairo 1 class with the given hash is stored, without reading the class
+    /// itself.
+    fn contains_class(&self, class_hash: &ClassHash) -> StorageResult<bool>;
+
     /// Returns the Cairo 0 class with the given hash.
     fn get_deprecated_class(
         &self,
@@ -133,6 +148,19 @@ impl<Mode: TransactionKind> ClassStorageReader for StorageTxn<'_, Mode> {
             .transpose()
     }
 
+    fn get_class_compressed_bytes(&self, class_hash: &ClassHash) -> StorageResult<Option<Vec<u8>>> {
+        let declared_classes_table = self.open_table(&self.tables.declared_classes)?;
+        let contract_class_location = declared_classes_table.get(&self.txn, class_hash)?;
+        contract_class_location
+            .map(|location| self.file_handlers.get_contract_class_bytes_unchecked(location))
+            .transpose()
+    }
+
+    fn contains_class(&self, class_hash: &ClassHash) -> StorageResult<bool> {
+        let declared_classes_table = self.open_table(&self.tables.declared_classes)?;
+        Ok(declared_classes_table.get(&self.txn, class_hash)?.is_some())
+    }
+
     fn get_deprecated_class(
         &self,
         class_hash: &ClassHash,
@@ -1,4 +1,5 @@
 use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::indexmap;
 use pretty_assertions::assert_eq;
 use starknet_api::block::BlockNumber;
@@ -9,9 +10,12 @@ use starknet_api::state::{SierraContractClass, StateNumber, ThinStateDiff};
 use starknet_api::test_utils::read_json_file;
 
 use super::{ClassStorageReader, ClassStorageWriter};
+use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::db::serialization::{ValueSerde, VersionZeroWrapper};
+use crate::db::table_types::Table;
 use crate::state::{StateStorageReader, StateStorageWriter};
-use crate::test_utils::get_test_storage;
-use crate::StorageError;
+use crate::test_utils::{get_test_storage, get_test_storage_with_config_by_scope};
+use crate::{open_storage, ClassFilesOffsets, OffsetKind, StorageError, StorageScope};
 
 #[test]
 fn append_classes_writes_correct_data() {
@@ -121,3 +125,185 @@ fn append_deprecated_class_not_in_state_diff() {
         expected_deprecated_class
     );
 }
+
+#[test]
+fn get_class_compressed_bytes_decodes_to_stored_class() {
+    let class_json = read_json_file("class.json");
+    let expected_class: SierraContractClass = serde_json::from_value(class_json).unwrap();
+    let class_hash = ClassHash::default();
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                declared_classes: indexmap! { class_hash => CompiledClassHash::default() },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .append_classes(BlockNumber(0), &[(class_hash, &expected_class)], &[])
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let bytes = txn.get_class_compressed_bytes(&class_hash).unwrap().unwrap();
+    let decoded =
+        <VersionZeroWrapper<SierraContractClass> as ValueSerde>::deserialize(&mut bytes.as_slice())
+            .unwrap();
+    assert_eq!(decoded, txn.get_class(&class_hash).unwrap().unwrap());
+
+    assert!(txn.get_class_compressed_bytes(&ClassHash(StarkHash::ONE)).unwrap().is_none());
+}
+
+#[test]
+fn contains_class() {
+    let class_json = read_json_file("class.json");
+    let class: SierraContractClass = serde_json::from_value(class_json).unwrap();
+    let class_hash = ClassHash::default();
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert!(!reader.begin_ro_txn().unwrap().contains_class(&class_hash).unwrap());
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                declared_classes: indexmap! { class_hash => CompiledClassHash::default() },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .append_classes(BlockNumber(0), &[(class_hash, &class)], &[])
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert!(txn.contains_class(&class_hash).unwrap());
+    assert!(!txn.contains_class(&ClassHash(StarkHash::ONE)).unwrap());
+}
+
+#[test]
+fn attach_prebuilt_class_files() {
+    let class: SierraContractClass = serde_json::from_value(read_json_file("class.json")).unwrap();
+    let casm: CasmContractClass =
+        serde_json::from_value(read_json_file("compiled_class.json")).unwrap();
+    let class_hash = ClassHash::default();
+
+    // Build the class files in another storage.
+    let ((builder_reader, mut builder_writer), builder_config, _builder_temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    builder_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                declared_classes: indexmap! { class_hash => CompiledClassHash::default() },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .append_classes(BlockNumber(0), &[(class_hash, &class)], &[])
+        .unwrap()
+        .append_casm(&class_hash, &casm)
+        .unwrap()
+        .commit()
+        .unwrap();
+    builder_writer.sync_all().unwrap();
+    let builder_txn = builder_reader.begin_ro_txn().unwrap();
+    let get_location = |table_id| {
+        builder_txn
+            .open_table(table_id)
+            .unwrap()
+            .get(&builder_txn.txn, &class_hash)
+            .unwrap()
+            .unwrap()
+    };
+    let class_location = get_location(&builder_txn.tables.declared_classes);
+    let casm_location = get_location(&builder_txn.tables.casms);
+    let file_offsets_table = builder_txn.open_table(&builder_txn.tables.file_offsets).unwrap();
+    let get_offset =
+        |offset_kind| file_offsets_table.get(&builder_txn.txn, &offset_kind).unwrap().unwrap();
+    let offsets = ClassFilesOffsets {
+        contract_class: get_offset(OffsetKind::ContractClass),
+        casm: get_offset(OffsetKind::Casm),
+    };
+    let contract_class_path = builder_config.db_config.path().join("contract_class.dat");
+    let casm_path = builder_config.db_config.path().join("casm.dat");
+
+    // A storage whose tables hold the locations of the classes but whose files are empty.
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.declared_classes)
+        .unwrap()
+        .insert(&txn.txn, &class_hash, &class_location)
+        .unwrap();
+    txn.open_table(&txn.tables.casms)
+        .unwrap()
+        .insert(&txn.txn, &class_hash, &casm_location)
+        .unwrap();
+    txn.commit().unwrap();
+
+    // Every location should be within the offset of its file.
+    let result = writer.attach_prebuilt_class_files(
+        &contract_class_path,
+        &casm_path,
+        ClassFilesOffsets { casm: offsets.casm - 1, ..offsets },
+    );
+    let location_end = casm_location.next_offset();
+    assert_matches!(
+        result,
+        Err(StorageError::PrebuiltFileOffsetMismatch {
+            offset_kind: OffsetKind::Casm,
+            class_hash: mismatched_class_hash,
+            location_end: mismatched_location_end,
+            ..
+        }) if mismatched_class_hash == class_hash && mismatched_location_end == location_end
+    );
+    assert!(contract_class_path.exists());
+    assert!(casm_path.exists());
+
+    // A reader that begins while the files are attached sees either the old offsets or the new
+    // offsets together with the new files.
+    std::thread::scope(|scope| {
+        let concurrent_reader = scope.spawn(|| {
+            loop {
+                let txn = reader.begin_ro_txn().unwrap();
+                let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+                let contract_class_offset =
+                    file_offsets_table.get(&txn.txn, &OffsetKind::ContractClass).unwrap();
+                if contract_class_offset == Some(offsets.contract_class) {
+                    assert_eq!(txn.get_class(&class_hash).unwrap().unwrap(), class);
+                    assert_eq!(txn.get_casm(&class_hash).unwrap().unwrap(), casm);
+                    break;
+                }
+            }
+        });
+        writer.attach_prebuilt_class_files(&contract_class_path, &casm_path, offsets).unwrap();
+        concurrent_reader.join().unwrap();
+    });
+
+    // Readers that were created before the files were attached read from the new files.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_class(&class_hash).unwrap().unwrap(), class);
+    assert_eq!(txn.get_casm(&class_hash).unwrap().unwrap(), casm);
+    drop(txn);
+
+    // The files were moved into the storage, so the classes are there after reopening it.
+    assert!(!contract_class_path.exists());
+    assert!(!casm_path.exists());
+    drop((reader, writer));
+    let (reader, _writer) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_class(&class_hash).unwrap().unwrap(), class);
+    assert_eq!(txn.get_casm(&class_hash).unwrap().unwrap(), casm);
+}
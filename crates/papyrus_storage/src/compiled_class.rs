@@ -21,6 +21,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let (reader, mut writer) = open_storage(storage_config)?;
@@ -64,6 +65,10 @@ pub trait CasmStorageReader {
     /// Returns the Cairo assembly of a class given its Sierra class hash.
     fn get_casm(&self, class_hash: &ClassHash) -> StorageResult<Option<CasmContractClass>>;
 
+    /// Returns whether the Cairo assembly of a class is stored, without reading the assembly
+    /// itself.
+    fn contains_casm(&self, class_hash: &ClassHash) -> StorageResult<bool>;
+
     /// Returns the CASM and Sierra contract classes for the given hash.
     /// If both exist, returns `(Some(casm), Some(sierra))`.
     /// If neither, returns `(None, None)`.
@@ -87,6 +92,13 @@ where
     /// Stores the Cairo assembly of a class, mapped to its class hash.
     // To enforce that no commit happen after a failure, we consume and return Self on success.
     fn append_casm(self, class_hash: &ClassHash, casm: &CasmContractClass) -> StorageResult<Self>;
+
+    /// Stores the Cairo assembly of a class, overwriting the existing one if there is one.
+    ///
+    /// Meant for recovering from a corrupted CASM. The new CASM is appended to the file and the
+    /// old one is left as unreferenced space in it.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn replace_casm(self, class_hash: &ClassHash, casm: &CasmContractClass) -> StorageResult<Self>;
 }
 
 impl<Mode: TransactionKind> CasmStorageReader for StorageTxn<'_, Mode> {
@@ -96,6 +108,11 @@ impl<Mode: TransactionKind> CasmStorageReader for StorageTxn<'_, Mode> {
         casm_location.map(|location| self.file_handlers.get_casm_unchecked(location)).transpose()
     }
 
+    fn contains_casm(&self, class_hash: &ClassHash) -> StorageResult<bool> {
+        let casm_table = self.open_table(&self.tables.casms)?;
+        Ok(casm_table.get(&self.txn, class_hash)?.is_some())
+    }
+
     fn get_casm_and_sierra(
         &self,
         class_hash: &ClassHash,
@@ -129,6 +146,26 @@ impl CasmStorageWriter for StorageTxn<'_, RW> {
         )?;
         Ok(self)
     }
+
+    #[latency_histogram("storage_replace_casm_latency_seconds", false)]
+    fn replace_casm(self, class_hash: &ClassHash, casm: &CasmContractClass) -> StorageResult<Self> {
+        let casm_table = self.open_table(&self.tables.casms)?;
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let state_diff_table = self.open_table(&self.tables.state_diffs)?;
+        let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+
+        let location = self.file_handlers.append_casm(casm);
+        casm_table.upsert(&self.txn, class_hash, &location)?;
+        file_offset_table.upsert(&self.txn, &OffsetKind::Casm, &location.next_offset())?;
+        update_marker(
+            &self.txn,
+            &markers_table,
+            &state_diff_table,
+            self.file_handlers.clone(),
+            class_hash,
+        )?;
+        Ok(self)
+    }
 }
 
 fn update_marker<'env>(
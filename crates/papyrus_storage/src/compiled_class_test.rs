@@ -5,6 +5,7 @@ use pretty_assertions::assert_eq;
 use rstest::rstest;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkHash;
 use starknet_api::state::SierraContractClass;
 use starknet_api::test_utils::read_json_file;
 
@@ -32,6 +33,21 @@ fn append_casm() {
     assert_eq!(casm, expected_casm);
 }
 
+#[test]
+fn contains_casm() {
+    let casm_json = read_json_file("compiled_class.json");
+    let casm: CasmContractClass = serde_json::from_value(casm_json).unwrap();
+    let class_hash = ClassHash::default();
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert!(!reader.begin_ro_txn().unwrap().contains_casm(&class_hash).unwrap());
+
+    writer.begin_rw_txn().unwrap().append_casm(&class_hash, &casm).unwrap().commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert!(txn.contains_casm(&class_hash).unwrap());
+    assert!(!txn.contains_casm(&ClassHash(StarkHash::ONE)).unwrap());
+}
+
 #[rstest]
 fn test_casm_and_sierra(
     #[values(true, false)] has_casm: bool,
@@ -116,3 +132,30 @@ fn casm_rewrite() {
         value: _
     })) if key == format!("{:?}", ClassHash::default()));
 }
+
+#[test]
+fn replace_casm() {
+    let casm_json = read_json_file("compiled_class.json");
+    let original_casm: CasmContractClass = serde_json::from_value(casm_json).unwrap();
+    let replacement_casm = CasmContractClass::get_test_instance(&mut get_rng());
+    let class_hash = ClassHash::default();
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_casm(&class_hash, &original_casm)
+        .unwrap()
+        .commit()
+        .unwrap();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .replace_casm(&class_hash, &replacement_casm)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let casm = reader.begin_ro_txn().unwrap().get_casm(&class_hash).unwrap().unwrap();
+    assert_eq!(casm, replacement_casm);
+}
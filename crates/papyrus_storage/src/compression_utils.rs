@@ -12,31 +12,36 @@ use crate::db::serialization::{StorageSerde, StorageSerdeError};
 // TODO(Dvir): consider defining this for each type separately and pass it as an argument to the
 // decompress function.
 pub(crate) const MAX_DECOMPRESSED_SIZE: usize = 1 << 28; // 256 MB
-// The compression level to use. Higher levels are slower but compress better.
-const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+// The default compression level. Higher levels are slower but compress better.
+pub(crate) const DEFAULT_COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
 
 /// Returns the compressed data in a vector.
 ///
 /// # Arguments
 /// * data - bytes to compress.
+/// * compression_level - the zstd compression level to use.
 ///
 /// # Errors
 /// Returns [`std::io::Error`] if any read error is encountered.
-pub fn compress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-    zstd::bulk::compress(data, COMPRESSION_LEVEL)
+pub fn compress(data: &[u8], compression_level: i32) -> Result<Vec<u8>, std::io::Error> {
+    zstd::bulk::compress(data, compression_level)
 }
 
 /// Serialized and then compress object.
 ///
 /// # Arguments
 /// * object - the object to serialize and compress.
+/// * compression_level - the zstd compression level to use.
 ///
 /// # Errors
 /// Returns [`StorageSerdeError`] if any error is encountered in the serialization or compression.
-pub fn serialize_and_compress(object: &impl StorageSerde) -> Result<Vec<u8>, StorageSerdeError> {
+pub fn serialize_and_compress(
+    object: &impl StorageSerde,
+    compression_level: i32,
+) -> Result<Vec<u8>, StorageSerdeError> {
     let mut buf = Vec::new();
-    object.serialize_into(&mut buf)?;
-    Ok(compress(buf.as_slice())?)
+    object.serialize_into_with_compression_level(&mut buf, compression_level)?;
+    Ok(compress(buf.as_slice(), compression_level)?)
 }
 
 /// Decompress data and returns it as bytes in a vector.
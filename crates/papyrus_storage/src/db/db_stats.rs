@@ -78,6 +78,13 @@ impl DbReader {
         })
     }
 
+    // Returns the size in bytes of the part of the database map that is in use.
+    pub(crate) fn get_used_size(&self) -> DbResult<u64> {
+        let last_page_number =
+            u64::try_from(self.env.info()?.last_pgno()).expect("usize should fit in u64");
+        Ok((last_page_number + 1) * u64::from(self.env.stat()?.page_size()))
+    }
+
     // Returns information about the database.
     pub(crate) fn get_db_info(&self) -> DbResult<Info> {
         Ok(self.env.info()?)
@@ -1,12 +1,25 @@
+use std::collections::BTreeMap;
+
 use assert_matches::assert_matches;
+use indexmap::IndexMap;
 use libmdbx::PageSize;
 use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHeader, BlockNumber};
+use starknet_api::core::{ContractAddress, Nonce};
+use starknet_api::state::ThinStateDiff;
 use tempfile::TempDir;
 
+use crate::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::class::{ClassStorageReader, ClassStorageWriter};
+use crate::compiled_class::CasmStorageReader;
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::Table;
-use crate::db::{get_page_size, open_env, DbError, DbIter, DbReader, DbResult, DbWriter};
-use crate::test_utils::get_test_config;
+use crate::db::{get_page_size, open_env, DbConfig, DbError, DbIter, DbReader, DbResult, DbWriter};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage, get_test_storage_with_config_by_scope};
+use crate::{open_storage, MarkerKind, StorageError, StorageReader, StorageScope};
 
 pub(crate) fn get_test_env() -> ((DbReader, DbWriter), TempDir) {
     let (config, temp_dir) = get_test_config(None);
@@ -62,6 +75,44 @@ fn open_env_with_enforce_file_exists() {
     assert_matches!(result, Err(DbError::FileDoesNotExist(_)));
 }
 
+#[test]
+fn open_env_with_custom_max_readers() {
+    const MAX_READERS: u32 = 100;
+    let (config, _temp_dir) = get_test_config(None);
+    let db_config = DbConfig { max_readers: MAX_READERS, ..config.db_config };
+    let (reader, _writer) = open_env(&db_config).expect("Failed to open environment.");
+
+    let txns = (0..MAX_READERS)
+        .map(|_| reader.begin_ro_txn())
+        .collect::<DbResult<Vec<_>>>()
+        .expect("Failed to hold max_readers concurrent read transactions.");
+    assert_eq!(txns.len(), usize::try_from(MAX_READERS).unwrap());
+}
+
+#[test]
+fn begin_ro_txn_with_too_many_readers() {
+    const MAX_READERS: u32 = 8;
+    // MDBX may round the number of reader slots up, so the exact limit isn't known in advance.
+    const MAX_ATTEMPTS: usize = 1 << 13;
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.db_config.max_readers = MAX_READERS;
+    let (reader, _writer) = open_storage(config).unwrap();
+
+    let mut txns = Vec::new();
+    let err = loop {
+        assert!(txns.len() < MAX_ATTEMPTS, "Expected the readers to be exhausted.");
+        match reader.begin_ro_txn() {
+            Ok(txn) => txns.push(txn),
+            Err(err) => break err,
+        }
+    };
+    assert_matches!(err, StorageError::TooManyReaders);
+
+    // Releasing a transaction frees a reader slot.
+    txns.pop();
+    reader.begin_ro_txn().unwrap();
+}
+
 #[test]
 fn txns_scenarios() {
     // Create an environment and a table.
@@ -104,6 +155,195 @@ fn txns_scenarios() {
     assert_eq!(table.get(&txn3, b"key").unwrap(), None);
 }
 
+#[test]
+fn total_disk_usage() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let state_diff = ThinStateDiff {
+        nonces: IndexMap::from([(ContractAddress::default(), Nonce::default())]),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let disk_usage = reader.total_disk_usage().unwrap();
+    assert!(disk_usage.db_size > 0);
+    assert_eq!(disk_usage.mmap_files_sizes.len(), reader.mmap_files_stats().len());
+    assert!(disk_usage.mmap_files_sizes.values().all(|size| *size > 0));
+    assert_eq!(
+        disk_usage.total,
+        disk_usage.db_size + disk_usage.mmap_files_sizes.values().sum::<u64>()
+    );
+}
+
+#[test]
+fn all_markers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert!(reader.all_markers().unwrap().is_empty());
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .append_classes(BlockNumber(0), &[], &[])
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let markers = reader.all_markers().unwrap();
+    for marker_kind in [
+        MarkerKind::Header,
+        MarkerKind::Body,
+        MarkerKind::State,
+        MarkerKind::Class,
+        MarkerKind::CompiledClass,
+        MarkerKind::BaseLayerBlock,
+    ] {
+        assert_eq!(
+            markers.get(&marker_kind),
+            Some(&BlockNumber(1)),
+            "Unexpected {marker_kind:?} marker."
+        );
+    }
+    // The event marker is currently unsupported and never set.
+    assert_eq!(markers.len(), 6);
+}
+
+#[test]
+fn blocks_missing_state_diff() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for block_number in 0..3 {
+        txn = txn
+            .append_header(BlockNumber(block_number), &BlockHeader::default())
+            .unwrap()
+            .append_state_diff(BlockNumber(block_number), ThinStateDiff::default())
+            .unwrap();
+    }
+    // A header whose state diff wasn't downloaded yet.
+    txn = txn.append_header(BlockNumber(3), &BlockHeader::default()).unwrap();
+    txn.commit().unwrap();
+    assert!(reader.blocks_missing_state_diff(BlockNumber(0)..BlockNumber(3)).unwrap().is_empty());
+
+    // Deliberately remove a state diff from the middle.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.state_diffs).unwrap().delete(&txn.txn, &BlockNumber(1)).unwrap();
+    txn.commit().unwrap();
+
+    assert_eq!(
+        reader.blocks_missing_state_diff(BlockNumber(0)..BlockNumber(10)).unwrap(),
+        vec![BlockNumber(1), BlockNumber(3)]
+    );
+    assert_eq!(
+        reader.blocks_missing_state_diff(BlockNumber(2)..BlockNumber(4)).unwrap(),
+        vec![BlockNumber(3)]
+    );
+}
+
+// Killing the process can't be done from a test. To reproduce it manually, kill the node with
+// SIGKILL right after `sync_all` returns, and check that the data is there after a restart.
+#[test]
+fn sync_all_persists_data() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let state_diff = ThinStateDiff {
+        nonces: IndexMap::from([(ContractAddress::default(), Nonce::default())]),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    writer.sync_all().unwrap();
+    // Syncing again without new data should work as well.
+    writer.sync_all().unwrap();
+    drop(reader);
+    drop(writer);
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff));
+}
+
+fn get_markers(reader: &StorageReader) -> BTreeMap<MarkerKind, BlockNumber> {
+    let txn = reader.begin_ro_txn().unwrap();
+    BTreeMap::from([
+        (MarkerKind::Header, txn.get_header_marker().unwrap()),
+        (MarkerKind::Body, txn.get_body_marker().unwrap()),
+        (MarkerKind::State, txn.get_state_marker().unwrap()),
+        (MarkerKind::Class, txn.get_class_marker().unwrap()),
+        (MarkerKind::CompiledClass, txn.get_compiled_class_marker().unwrap()),
+        (MarkerKind::BaseLayerBlock, txn.get_base_layer_block_marker().unwrap()),
+    ])
+}
+
+#[test]
+fn set_markers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let markers = BTreeMap::from([
+        (MarkerKind::Header, BlockNumber(5)),
+        (MarkerKind::Body, BlockNumber(5)),
+        (MarkerKind::State, BlockNumber(3)),
+        (MarkerKind::Class, BlockNumber(3)),
+        (MarkerKind::CompiledClass, BlockNumber(2)),
+        (MarkerKind::BaseLayerBlock, BlockNumber(1)),
+    ]);
+    writer.set_markers(markers.clone()).unwrap();
+    assert_eq!(get_markers(&reader), markers);
+
+    // Markers that aren't given keep their value.
+    writer.set_markers(BTreeMap::from([(MarkerKind::Body, BlockNumber(4))])).unwrap();
+    let mut expected_markers = markers;
+    expected_markers.insert(MarkerKind::Body, BlockNumber(4));
+    assert_eq!(get_markers(&reader), expected_markers);
+}
+
+#[test]
+fn set_inconsistent_markers_fails() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .set_markers(BTreeMap::from([
+            (MarkerKind::Header, BlockNumber(5)),
+            (MarkerKind::State, BlockNumber(3)),
+        ]))
+        .unwrap();
+    let markers_before = get_markers(&reader);
+
+    // The state marker is above the header marker.
+    let result = writer.set_markers(BTreeMap::from([
+        (MarkerKind::Header, BlockNumber(5)),
+        (MarkerKind::State, BlockNumber(6)),
+    ]));
+    assert_matches!(result, Err(StorageError::InconsistentMarkers { .. }));
+    // The header marker is below the existing state marker.
+    let result = writer.set_markers(BTreeMap::from([(MarkerKind::Header, BlockNumber(2))]));
+    assert_matches!(result, Err(StorageError::InconsistentMarkers { .. }));
+    // The compiled class marker is above the class marker.
+    let result = writer.set_markers(BTreeMap::from([(MarkerKind::CompiledClass, BlockNumber(1))]));
+    assert_matches!(result, Err(StorageError::InconsistentMarkers { .. }));
+
+    assert_eq!(get_markers(&reader), markers_before);
+}
+
 #[test]
 
 fn table_stats() {
@@ -29,7 +29,7 @@ use std::path::PathBuf;
 use std::result;
 use std::sync::Arc;
 
-use libmdbx::{DatabaseFlags, Geometry, PageSize, WriteMap};
+use libmdbx::{DatabaseFlags, Geometry, Mode, PageSize, WriteMap};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::validators::validate_ascii;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -43,7 +43,7 @@ use self::table_types::{DbCursor, DbCursorTrait};
 use crate::db::table_types::TableType;
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 18;
+const MAX_DBS: usize = 19;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -70,6 +70,9 @@ pub struct DbConfig {
     pub max_size: usize,
     /// The growth step of the database.
     pub growth_step: isize,
+    /// The maximum number of concurrent read transactions. MDBX allocates the reader slots when
+    /// the environment is opened, so this can't be changed while the node is running.
+    pub max_readers: u32,
 }
 
 impl Default for DbConfig {
@@ -81,6 +84,7 @@ impl Default for DbConfig {
             min_size: 1 << 20,    // 1MB
             max_size: 1 << 40,    // 1TB
             growth_step: 1 << 32, // 4GB
+            max_readers: 1 << 13, // 8K readers
         }
     }
 }
@@ -127,6 +131,12 @@ impl SerializeConfig for DbConfig {
                  grow.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_readers",
+                &self.max_readers,
+                "The maximum number of concurrent read transactions.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -195,7 +205,6 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
     if config.enforce_file_exists && !db_file_path.exists() {
         return Err(DbError::FileDoesNotExist(db_file_path));
     }
-    const MAX_READERS: u32 = 1 << 13; // 8K readers
     let env = Arc::new(
         Environment::new()
             .set_geometry(Geometry {
@@ -205,7 +214,7 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
                 ..Default::default()
             })
             .set_max_tables(MAX_DBS)
-            .set_max_readers(MAX_READERS)
+            .set_max_readers(config.max_readers)
             .set_flags(DatabaseFlags {
                 // There is no locality of pages in the database almost at all, so readahead will
                 // fill the RAM with garbage.
@@ -219,6 +228,27 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
     Ok((DbReader { env: env.clone() }, DbWriter { env }))
 }
 
+/// Opens an existing MDBX environment in read-only mode and returns a reader to it.
+/// Unlike [`open_env`], the environment is never created or modified.
+pub(crate) fn open_env_read_only(config: &DbConfig) -> DbResult<DbReader> {
+    let db_file_path = config.path().join("mdbx.dat");
+    if !db_file_path.exists() {
+        return Err(DbError::FileDoesNotExist(db_file_path));
+    }
+    let env = Arc::new(
+        Environment::new()
+            .set_max_tables(MAX_DBS)
+            .set_max_readers(config.max_readers)
+            .set_flags(DatabaseFlags {
+                mode: Mode::ReadOnly,
+                no_rdahead: true,
+                ..Default::default()
+            })
+            .open(&config.path())?,
+    );
+    Ok(DbReader { env })
+}
+
 // Size in bytes.
 const MDBX_MIN_PAGESIZE: usize = 256;
 const MDBX_MAX_PAGESIZE: usize = 65536; // 64KB
@@ -256,6 +286,12 @@ impl DbWriter {
     pub(crate) fn begin_rw_txn(&mut self) -> DbResult<DbWriteTransaction<'_>> {
         Ok(DbWriteTransaction { txn: self.env.begin_rw_txn()? })
     }
+
+    // Forces the committed transactions to be written to the disk.
+    pub(crate) fn sync(&self) -> DbResult<()> {
+        self.env.sync(true)?;
+        Ok(())
+    }
 }
 
 type DbWriteTransaction<'env> = DbTransaction<'env, RW>;
@@ -4,6 +4,7 @@ use std::marker::PhantomData;
 
 use tracing::{debug, error};
 
+use crate::compression_utils::DEFAULT_COMPRESSION_LEVEL;
 use crate::db::DbError;
 
 /// Trait for serializing and deserializing values.
@@ -33,6 +34,16 @@ impl<T: StorageSerde> StorageSerdeEx for T {
 pub trait StorageSerde: Sized {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError>;
 
+    /// Serializes the object, compressing its compressed parts with the given zstd compression
+    /// level. Objects without compressed parts are serialized as in `serialize_into`.
+    fn serialize_into_with_compression_level(
+        &self,
+        res: &mut impl std::io::Write,
+        _compression_level: i32,
+    ) -> Result<(), StorageSerdeError> {
+        self.serialize_into(res)
+    }
+
     // TODO(dan): consider returning a result here. We probably transform this into
     // InnerDeserialization error, so having more context might be useful.
     fn deserialize_from(bytes: &mut impl std::io::Read) -> Option<Self>;
@@ -46,7 +57,14 @@ impl<T> Key for T where T: StorageSerdeEx + Ord + Clone {}
 pub(crate) trait ValueSerde {
     type Value: StorageSerde + Debug;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError>;
+    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+        Self::serialize_with_compression_level(obj, DEFAULT_COMPRESSION_LEVEL)
+    }
+    /// Serializes the value, compressing its compressed parts with the given compression level.
+    fn serialize_with_compression_level(
+        obj: &Self::Value,
+        compression_level: i32,
+    ) -> Result<Vec<u8>, DbError>;
     // TODO(yair): Return a result here.
     fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value>;
 }
@@ -60,8 +78,14 @@ pub(crate) struct NoVersionValueWrapper<T: StorageSerde> {
 impl<T: StorageSerde + Debug> ValueSerde for NoVersionValueWrapper<T> {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
-        StorageSerdeEx::serialize(obj)
+    fn serialize_with_compression_level(
+        obj: &Self::Value,
+        compression_level: i32,
+    ) -> Result<Vec<u8>, DbError> {
+        let mut res = Vec::new();
+        obj.serialize_into_with_compression_level(&mut res, compression_level)
+            .map_err(|_| DbError::Serialization)?;
+        Ok(res)
     }
 
     fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
@@ -82,10 +106,14 @@ const VERSION_ZERO: u8 = 0;
 impl<T: StorageSerde + Debug> ValueSerde for VersionZeroWrapper<T> {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+    fn serialize_with_compression_level(
+        obj: &Self::Value,
+        compression_level: i32,
+    ) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
         res.write_all(&[VERSION_ZERO]).expect("Failed to write version");
-        obj.serialize_into(&mut res).map_err(|_| DbError::Serialization)?;
+        obj.serialize_into_with_compression_level(&mut res, compression_level)
+            .map_err(|_| DbError::Serialization)?;
         Ok(res)
     }
 
@@ -128,10 +156,14 @@ impl<T: StorageSerde + Debug + Migratable, const VERSION: u8> ValueSerde
 {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+    fn serialize_with_compression_level(
+        obj: &Self::Value,
+        compression_level: i32,
+    ) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
         res.write_all(&[VERSION]).expect("Failed to write version");
-        obj.serialize_into(&mut res).map_err(|_| DbError::Serialization)?;
+        obj.serialize_into_with_compression_level(&mut res, compression_level)
+            .map_err(|_| DbError::Serialization)?;
         Ok(res)
     }
 
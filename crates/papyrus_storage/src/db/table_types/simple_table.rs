@@ -15,6 +15,7 @@ use crate::db::{
     DbCursor,
     DbError,
     DbKeyType,
+    DbReader,
     DbTransaction,
     DbValueType,
     DbWriter,
@@ -47,6 +48,38 @@ impl DbWriter {
     }
 }
 
+impl DbReader {
+    // Returns the identifier of an existing simple table, or None if the table was never created.
+    pub(crate) fn get_simple_table<K: KeyTrait + Debug, V: ValueSerde + Debug>(
+        &self,
+        name: &'static str,
+    ) -> DbResult<Option<TableIdentifier<K, V, SimpleTable>>> {
+        let txn = self.env.begin_ro_txn()?;
+        match txn.open_table(Some(name)) {
+            Ok(_) => Ok(Some(TableIdentifier {
+                name,
+                _key_type: PhantomData {},
+                _value_type: PhantomData {},
+                _table_type: PhantomData {},
+            })),
+            Err(libmdbx::Error::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> TableHandle<'env, K, V, SimpleTable> {
+    // Returns the value of the given key exactly as it's stored, without deserializing it.
+    pub(crate) fn get_raw<Mode: TransactionKind>(
+        &'env self,
+        txn: &'env DbTransaction<'env, Mode>,
+        key: &K,
+    ) -> DbResult<Option<Vec<u8>>> {
+        let bin_key = key.serialize()?;
+        Ok(txn.txn.get::<Cow<'env, [u8]>>(&self.database, &bin_key)?.map(Cow::into_owned))
+    }
+}
+
 impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
     for TableHandle<'env, K, V, SimpleTable>
 {
@@ -20,6 +20,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! let block = Block::default();
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
@@ -38,6 +39,8 @@
 #[path = "header_test.rs"]
 mod header_test;
 
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     BlockHash,
@@ -60,8 +63,8 @@ use starknet_api::core::{
 use starknet_api::data_availability::L1DataAvailabilityMode;
 use tracing::debug;
 
-use crate::db::serialization::NoVersionValueWrapper;
-use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
+use crate::db::table_types::{DbCursor, DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
 
@@ -113,6 +116,43 @@ pub trait HeaderStorageReader {
         &self,
         block_number: BlockNumber,
     ) -> StorageResult<Option<BlockSignature>>;
+
+    /// Returns the header of the block with the given number along with its signature, if one is
+    /// stored.
+    fn get_block_header_with_signature(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<(BlockHeader, Option<BlockSignature>)>>;
+
+    /// Returns an iterator over the headers of the blocks in the given range, in ascending order.
+    /// The headers are read with a single cursor, which is much faster than calling
+    /// [`get_block_header`](Self::get_block_header) for each block in large ranges. The iterator
+    /// stops at the first block that isn't stored.
+    fn iter_headers(
+        &self,
+        range: Range<BlockNumber>,
+    ) -> impl Iterator<Item = StorageResult<(BlockNumber, BlockHeader)>> + '_;
+
+    /// Returns the first block whose timestamp is at or after the given timestamp, or None if all
+    /// the stored blocks are older. Relies on block timestamps being non-decreasing, and reads
+    /// a logarithmic number of headers.
+    fn block_at_timestamp(&self, timestamp: BlockTimestamp) -> StorageResult<Option<BlockNumber>>;
+
+    /// Returns the number and header of the block with each of the given hashes, in the order of
+    /// the hashes. The entry of a hash of a block that isn't stored is None.
+    fn get_headers_by_hashes(
+        &self,
+        hashes: &[BlockHash],
+    ) -> StorageResult<Vec<Option<(BlockNumber, BlockHeader)>>>;
+
+    /// Returns the header of the block with the given number exactly as it's stored, for callers
+    /// that need to reproduce its bytes. The bytes are a version byte, currently always 0,
+    /// followed by the storage serialization of the header. The Starknet version of the block
+    /// isn't part of them, it's stored separately.
+    fn get_block_header_raw_bytes(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<Vec<u8>>>;
 }
 
 /// Interface for writing data related to the block headers.
@@ -128,6 +168,15 @@ where
         block_header: &BlockHeader,
     ) -> StorageResult<Self>;
 
+    /// Same as [`append_header`](Self::append_header), but if the block already has a header,
+    /// succeeds without writing when it's identical to the given one, and fails with
+    /// [`StorageError::ConflictingBlockData`] otherwise. Makes re-syncing stored blocks safe.
+    fn append_header_if_absent(
+        self,
+        block_number: BlockNumber,
+        block_header: &BlockHeader,
+    ) -> StorageResult<Self>;
+
     /// Update the starknet version if needed.
     fn update_starknet_version(
         self,
@@ -165,28 +214,7 @@ impl<Mode: TransactionKind> HeaderStorageReader for StorageTxn<'_, Mode> {
         let Some(starknet_version) = self.get_starknet_version(block_number)? else {
             return Ok(None);
         };
-        Ok(Some(BlockHeader {
-            block_hash: block_header.block_hash,
-            block_header_without_hash: BlockHeaderWithoutHash {
-                parent_hash: block_header.parent_hash,
-                block_number: block_header.block_number,
-                l1_gas_price: block_header.l1_gas_price,
-                l1_data_gas_price: block_header.l1_data_gas_price,
-                l2_gas_price: block_header.l2_gas_price,
-                state_root: block_header.state_root,
-                sequencer: block_header.sequencer,
-                timestamp: block_header.timestamp,
-                l1_da_mode: block_header.l1_da_mode,
-                starknet_version,
-            },
-            state_diff_commitment: block_header.state_diff_commitment,
-            transaction_commitment: block_header.transaction_commitment,
-            event_commitment: block_header.event_commitment,
-            receipt_commitment: block_header.receipt_commitment,
-            state_diff_length: block_header.state_diff_length,
-            n_transactions: block_header.n_transactions,
-            n_events: block_header.n_events,
-        }))
+        Ok(Some(to_block_header(block_header, starknet_version)))
     }
 
     fn get_block_number_by_hash(
@@ -198,6 +226,33 @@ impl<Mode: TransactionKind> HeaderStorageReader for StorageTxn<'_, Mode> {
         Ok(block_number)
     }
 
+    fn get_block_header_raw_bytes(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<Vec<u8>>> {
+        let headers_table = self.open_table(&self.tables.headers)?;
+        Ok(headers_table.get_raw(&self.txn, &block_number)?)
+    }
+
+    fn get_headers_by_hashes(
+        &self,
+        hashes: &[BlockHash],
+    ) -> StorageResult<Vec<Option<(BlockNumber, BlockHeader)>>> {
+        let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
+        hashes
+            .iter()
+            .map(|block_hash| {
+                let Some(block_number) = block_hash_to_number_table.get(&self.txn, block_hash)?
+                else {
+                    return Ok(None);
+                };
+                Ok(self
+                    .get_block_header(block_number)?
+                    .map(|block_header| (block_number, block_header)))
+            })
+            .collect()
+    }
+
     // TODO(shahak): Internalize this function.
     fn get_starknet_version(
         &self,
@@ -232,6 +287,176 @@ impl<Mode: TransactionKind> HeaderStorageReader for StorageTxn<'_, Mode> {
         let block_signature = block_signatures_table.get(&self.txn, &block_number)?;
         Ok(block_signature)
     }
+
+    fn get_block_header_with_signature(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<(BlockHeader, Option<BlockSignature>)>> {
+        let Some(block_header) = self.get_block_header(block_number)? else {
+            return Ok(None);
+        };
+        Ok(Some((block_header, self.get_block_signature(block_number)?)))
+    }
+
+    fn iter_headers(
+        &self,
+        range: Range<BlockNumber>,
+    ) -> impl Iterator<Item = StorageResult<(BlockNumber, BlockHeader)>> + '_ {
+        // An error while opening the cursors is returned as the only item of the iterator.
+        let (header_iter, error) = match HeaderIter::new(self, range) {
+            Ok(header_iter) => (Some(header_iter), None),
+            Err(err) => (None, Some(err)),
+        };
+        error.map(Err).into_iter().chain(header_iter.into_iter().flatten())
+    }
+
+    fn block_at_timestamp(&self, timestamp: BlockTimestamp) -> StorageResult<Option<BlockNumber>> {
+        let headers_table = self.open_table(&self.tables.headers)?;
+        let header_marker = self.get_header_marker()?;
+        // The answer is in the range [low, high], where the header marker means there's no such
+        // block.
+        let mut low = 0;
+        let mut high = header_marker.0;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let block_number = BlockNumber(middle);
+            let block_header = headers_table.get(&self.txn, &block_number)?.ok_or_else(|| {
+                StorageError::DBInconsistency {
+                    msg: format!(
+                        "Block {block_number} is below the header marker {header_marker} but has \
+                         no header."
+                    ),
+                }
+            })?;
+            if block_header.timestamp >= timestamp {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        let block_number = BlockNumber(low);
+        Ok((block_number < header_marker).then_some(block_number))
+    }
+}
+
+type HeadersTableCursor<'txn, Mode> =
+    DbCursor<'txn, Mode, BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>;
+type StarknetVersionTableCursor<'txn, Mode> =
+    DbCursor<'txn, Mode, BlockNumber, VersionZeroWrapper<StarknetVersion>, SimpleTable>;
+
+// Iterates the headers table and the Starknet version table side by side, since the Starknet
+// version table only holds the blocks in which the version changed.
+struct HeaderIter<'txn, Mode: TransactionKind> {
+    headers_cursor: HeadersTableCursor<'txn, Mode>,
+    starknet_version_cursor: StarknetVersionTableCursor<'txn, Mode>,
+    next_header: Option<(BlockNumber, StorageBlockHeader)>,
+    starknet_version: StarknetVersion,
+    next_starknet_version: Option<(BlockNumber, StarknetVersion)>,
+    end: BlockNumber,
+}
+
+impl<'txn, Mode: TransactionKind> HeaderIter<'txn, Mode> {
+    fn new(txn: &'txn StorageTxn<'_, Mode>, range: Range<BlockNumber>) -> StorageResult<Self> {
+        let headers_table = txn.open_table(&txn.tables.headers)?;
+        let mut headers_cursor = headers_table.cursor(&txn.txn)?;
+        let starknet_version_table = txn.open_table(&txn.tables.starknet_version)?;
+        let mut starknet_version_cursor = starknet_version_table.cursor(&txn.txn)?;
+
+        let mut next_header = headers_cursor.lower_bound(&range.start)?;
+        // The Starknet version of the first block is the last one that was set at or before it.
+        let starknet_version = match range.start.next() {
+            Some(next_block_number) => {
+                starknet_version_cursor.lower_bound(&next_block_number)?;
+                starknet_version_cursor.prev()?
+            }
+            None => None,
+        };
+        let (starknet_version, next_starknet_version) = match starknet_version {
+            Some((_block_number, starknet_version)) => {
+                (starknet_version, starknet_version_cursor.next()?)
+            }
+            // There are no headers up to the first block of the range.
+            None => {
+                next_header = None;
+                (StarknetVersion::default(), None)
+            }
+        };
+        // The iterator stops at the first missing block, so it shouldn't start after one.
+        if next_header.as_ref().is_some_and(|(block_number, _)| *block_number != range.start) {
+            next_header = None;
+        }
+
+        Ok(Self {
+            headers_cursor,
+            starknet_version_cursor,
+            next_header,
+            starknet_version,
+            next_starknet_version,
+            end: range.end,
+        })
+    }
+
+    fn next(&mut self) -> StorageResult<Option<(BlockNumber, BlockHeader)>> {
+        let Some((block_number, block_header)) = self.next_header.take() else {
+            return Ok(None);
+        };
+        if block_number >= self.end {
+            return Ok(None);
+        }
+        while let Some((version_block_number, starknet_version)) = self.next_starknet_version {
+            if version_block_number > block_number {
+                break;
+            }
+            self.starknet_version = starknet_version;
+            self.next_starknet_version = self.starknet_version_cursor.next()?;
+        }
+        self.next_header = self
+            .headers_cursor
+            .next()?
+            .filter(|(next_block_number, _)| block_number.next() == Some(*next_block_number));
+        Ok(Some((block_number, to_block_header(block_header, self.starknet_version))))
+    }
+}
+
+impl<Mode: TransactionKind> Iterator for HeaderIter<'_, Mode> {
+    type Item = StorageResult<(BlockNumber, BlockHeader)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Stop after an error, since the cursors' positions are unknown.
+        let result = HeaderIter::next(self).transpose();
+        if matches!(result, Some(Err(_))) {
+            self.next_header = None;
+        }
+        result
+    }
+}
+
+fn to_block_header(
+    block_header: StorageBlockHeader,
+    starknet_version: StarknetVersion,
+) -> BlockHeader {
+    BlockHeader {
+        block_hash: block_header.block_hash,
+        block_header_without_hash: BlockHeaderWithoutHash {
+            parent_hash: block_header.parent_hash,
+            block_number: block_header.block_number,
+            l1_gas_price: block_header.l1_gas_price,
+            l1_data_gas_price: block_header.l1_data_gas_price,
+            l2_gas_price: block_header.l2_gas_price,
+            state_root: block_header.state_root,
+            sequencer: block_header.sequencer,
+            timestamp: block_header.timestamp,
+            l1_da_mode: block_header.l1_da_mode,
+            starknet_version,
+        },
+        state_diff_commitment: block_header.state_diff_commitment,
+        transaction_commitment: block_header.transaction_commitment,
+        event_commitment: block_header.event_commitment,
+        receipt_commitment: block_header.receipt_commitment,
+        state_diff_length: block_header.state_diff_length,
+        n_transactions: block_header.n_transactions,
+        n_events: block_header.n_events,
+    }
 }
 
 impl HeaderStorageWriter for StorageTxn<'_, RW> {
@@ -300,6 +525,23 @@ impl HeaderStorageWriter for StorageTxn<'_, RW> {
         Ok(self)
     }
 
+    fn append_header_if_absent(
+        self,
+        block_number: BlockNumber,
+        block_header: &BlockHeader,
+    ) -> StorageResult<Self> {
+        if block_number >= self.get_header_marker()? {
+            return self.append_header(block_number, block_header);
+        }
+        if self.get_block_header(block_number)?.as_ref() != Some(block_header) {
+            return Err(StorageError::ConflictingBlockData {
+                block_number,
+                marker_kind: MarkerKind::Header,
+            });
+        }
+        Ok(self)
+    }
+
     fn revert_header(
         self,
         block_number: BlockNumber,
@@ -356,28 +598,7 @@ impl HeaderStorageWriter for StorageTxn<'_, RW> {
 
         Ok((
             self,
-            Some(BlockHeader {
-                block_hash: reverted_header.block_hash,
-                block_header_without_hash: BlockHeaderWithoutHash {
-                    parent_hash: reverted_header.parent_hash,
-                    block_number: reverted_header.block_number,
-                    l1_gas_price: reverted_header.l1_gas_price,
-                    l1_data_gas_price: reverted_header.l1_data_gas_price,
-                    l2_gas_price: reverted_header.l2_gas_price,
-                    state_root: reverted_header.state_root,
-                    sequencer: reverted_header.sequencer,
-                    timestamp: reverted_header.timestamp,
-                    l1_da_mode: reverted_header.l1_da_mode,
-                    starknet_version,
-                },
-                state_diff_commitment: reverted_header.state_diff_commitment,
-                transaction_commitment: reverted_header.transaction_commitment,
-                event_commitment: reverted_header.event_commitment,
-                receipt_commitment: reverted_header.receipt_commitment,
-                state_diff_length: reverted_header.state_diff_length,
-                n_transactions: reverted_header.n_transactions,
-                n_events: reverted_header.n_events,
-            }),
+            Some(to_block_header(reverted_header, starknet_version)),
             reverted_block_signature,
         ))
     }
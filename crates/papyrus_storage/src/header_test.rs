@@ -6,12 +6,20 @@ use starknet_api::block::{
     BlockHeaderWithoutHash,
     BlockNumber,
     BlockSignature,
+    BlockTimestamp,
 };
 use starknet_api::felt;
 
-use crate::header::{HeaderStorageReader, HeaderStorageWriter, StarknetVersion};
+use crate::db::serialization::{ValueSerde, VersionZeroWrapper};
+use crate::db::table_types::Table;
+use crate::header::{
+    HeaderStorageReader,
+    HeaderStorageWriter,
+    StarknetVersion,
+    StorageBlockHeader,
+};
 use crate::test_utils::get_test_storage;
-use crate::{StorageError, StorageWriter};
+use crate::{MarkerKind, StorageError, StorageWriter};
 
 #[tokio::test]
 async fn append_header() {
@@ -269,6 +277,44 @@ fn block_signature() {
     );
 }
 
+#[test]
+fn block_header_with_signature() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let signed_header =
+        BlockHeader { block_hash: BlockHash(felt!("0x0")), ..BlockHeader::default() };
+    let unsigned_header = BlockHeader {
+        block_hash: BlockHash(felt!("0x1")),
+        block_header_without_hash: BlockHeaderWithoutHash {
+            block_number: BlockNumber(1),
+            ..BlockHeaderWithoutHash::default()
+        },
+        ..BlockHeader::default()
+    };
+    let signature = BlockSignature::default();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &signed_header)
+        .unwrap()
+        .append_block_signature(BlockNumber(0), &signature)
+        .unwrap()
+        .append_header(BlockNumber(1), &unsigned_header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_block_header_with_signature(BlockNumber(0)).unwrap(),
+        Some((signed_header, Some(signature)))
+    );
+    assert_eq!(
+        txn.get_block_header_with_signature(BlockNumber(1)).unwrap(),
+        Some((unsigned_header, None))
+    );
+    assert!(txn.get_block_header_with_signature(BlockNumber(2)).unwrap().is_none());
+}
+
 #[test]
 fn get_reverted_block_signature_returns_none() {
     let ((reader, mut writer), _temp_dir) = get_test_storage();
@@ -302,3 +348,188 @@ fn revert_overflowing_block_number() {
     assert!(header.is_none());
     assert!(signature.is_none());
 }
+
+#[test]
+fn iter_headers_matches_point_lookups() {
+    const N_BLOCKS: u64 = 6;
+    // The Starknet version changes mid range so that the iterator has to track it.
+    let starknet_versions = [
+        StarknetVersion::default(),
+        StarknetVersion::default(),
+        StarknetVersion::V0_9_1,
+        StarknetVersion::V0_9_1,
+        StarknetVersion::V0_12_0,
+        StarknetVersion::V0_12_0,
+    ];
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, starknet_version) in starknet_versions.into_iter().enumerate() {
+        let block_number = BlockNumber(i.try_into().unwrap());
+        let block_header = BlockHeader {
+            block_hash: BlockHash(felt!(block_number.0)),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                starknet_version,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn.append_header(block_number, &block_header).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (start, end) in [(0, N_BLOCKS), (1, 5), (3, 3), (4, N_BLOCKS + 3)] {
+        let iterated_headers = txn
+            .iter_headers(BlockNumber(start)..BlockNumber(end))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let looked_up_headers = (start..end.min(N_BLOCKS))
+            .map(|i| (BlockNumber(i), txn.get_block_header(BlockNumber(i)).unwrap().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(iterated_headers, looked_up_headers);
+    }
+    assert_eq!(txn.iter_headers(BlockNumber(N_BLOCKS)..BlockNumber(N_BLOCKS + 1)).count(), 0);
+}
+
+#[test]
+fn block_at_timestamp() {
+    // Equal timestamps are allowed, as long as they don't decrease.
+    let timestamps = [10, 20, 20, 30, 45];
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert_eq!(reader.begin_ro_txn().unwrap().block_at_timestamp(BlockTimestamp(0)).unwrap(), None);
+
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, timestamp) in timestamps.into_iter().enumerate() {
+        let block_number = BlockNumber(i.try_into().unwrap());
+        let block_header = BlockHeader {
+            block_hash: BlockHash(felt!(block_number.0)),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                timestamp: BlockTimestamp(timestamp),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        txn = txn.append_header(block_number, &block_header).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (timestamp, expected_block_number) in [
+        // Before the genesis timestamp.
+        (0, Some(0)),
+        (10, Some(0)),
+        (11, Some(1)),
+        (20, Some(1)),
+        (21, Some(3)),
+        (31, Some(4)),
+        (45, Some(4)),
+        // After the tip.
+        (46, None),
+    ] {
+        assert_eq!(
+            txn.block_at_timestamp(BlockTimestamp(timestamp)).unwrap(),
+            expected_block_number.map(BlockNumber),
+            "timestamp {timestamp}"
+        );
+    }
+}
+
+#[test]
+fn append_header_if_absent() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let block_header =
+        |block_hash| BlockHeader { block_hash: BlockHash(felt!(block_hash)), ..Default::default() };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header_if_absent(BlockNumber(0), &block_header(1_u8))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Re-appending an identical header is skipped.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header_if_absent(BlockNumber(0), &block_header(1_u8))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_block_header(BlockNumber(0)).unwrap(), Some(block_header(1_u8)));
+
+    let result =
+        writer.begin_rw_txn().unwrap().append_header_if_absent(BlockNumber(0), &block_header(2_u8));
+    assert_matches!(
+        result,
+        Err(StorageError::ConflictingBlockData {
+            block_number: BlockNumber(0),
+            marker_kind: MarkerKind::Header
+        })
+    );
+}
+
+#[test]
+fn get_headers_by_hashes() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_2_headers(&mut writer);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let header_0 = txn.get_block_header(BlockNumber(0)).unwrap().unwrap();
+    let header_1 = txn.get_block_header(BlockNumber(1)).unwrap().unwrap();
+    let unknown_hash = BlockHash(felt!("0x2"));
+
+    // The result keeps the order of the hashes, including repeated ones.
+    let hashes = [header_1.block_hash, unknown_hash, header_0.block_hash, header_1.block_hash];
+    assert_eq!(
+        txn.get_headers_by_hashes(&hashes).unwrap(),
+        vec![
+            Some((BlockNumber(1), header_1.clone())),
+            None,
+            Some((BlockNumber(0), header_0)),
+            Some((BlockNumber(1), header_1)),
+        ]
+    );
+    assert_eq!(txn.get_headers_by_hashes(&[]).unwrap(), vec![]);
+}
+
+#[test]
+fn block_header_raw_bytes() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let header = BlockHeader {
+        block_hash: BlockHash(felt!("0x1234")),
+        block_header_without_hash: BlockHeaderWithoutHash {
+            timestamp: BlockTimestamp(17),
+            ..Default::default()
+        },
+        n_transactions: 3,
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let raw_bytes = txn.get_block_header_raw_bytes(BlockNumber(0)).unwrap().unwrap();
+    assert_eq!(raw_bytes[0], 0);
+    let stored_header = txn
+        .txn
+        .open_table(&txn.tables.headers)
+        .unwrap()
+        .get(&txn.txn, &BlockNumber(0))
+        .unwrap()
+        .unwrap();
+    let deserialized_header =
+        VersionZeroWrapper::<StorageBlockHeader>::deserialize(&mut raw_bytes.as_slice()).unwrap();
+    assert_eq!(deserialized_header, stored_header);
+    assert_eq!(deserialized_header.block_hash, header.block_hash);
+
+    assert!(txn.get_block_header_raw_bytes(BlockNumber(1)).unwrap().is_none());
+}
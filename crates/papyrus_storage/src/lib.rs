@@ -34,6 +34,7 @@
 //!     min_size: 1 << 20,    // 1MB
 //!     max_size: 1 << 35,    // 32GB
 //!     growth_step: 1 << 26, // 64MB
+//!     max_readers: 1 << 13, // 8K readers
 //! };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let (reader, mut writer) = open_storage(storage_config)?;
@@ -87,6 +88,7 @@ pub mod storage_metrics;
 pub mod compression_utils;
 pub mod db;
 pub mod header;
+pub mod metadata;
 pub mod mmap_file;
 mod serialization;
 pub mod state;
@@ -103,13 +105,15 @@ pub mod test_utils;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fs;
-use std::sync::Arc;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 use body::events::EventIndex;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use db::db_stats::{DbTableStats, DbWholeStats};
 use db::serialization::{Key, NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
-use db::table_types::{CommonPrefix, NoValue, Table, TableType};
+use db::table_types::{CommonPrefix, DbCursorTrait, NoValue, Table, TableType};
 use mmap_file::{
     open_file,
     FileHandler,
@@ -137,6 +141,7 @@ use crate::body::TransactionIndex;
 use crate::db::table_types::SimpleTable;
 use crate::db::{
     open_env,
+    open_env_read_only,
     DbConfig,
     DbError,
     DbReader,
@@ -151,11 +156,16 @@ use crate::db::{
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::MMapFileStats;
 use crate::state::data::IndexedDeprecatedContractClass;
-use crate::version::{VersionStorageReader, VersionStorageWriter};
+use crate::version::{
+    VersionStorageReader,
+    VersionStorageWriter,
+    VERSION_BLOCKS_KEY,
+    VERSION_STATE_KEY,
+};
 
 // For more details on the storage version, see the module documentation.
 /// The current version of the storage state code.
-pub const STORAGE_VERSION_STATE: Version = Version { major: 4, minor: 0 };
+pub const STORAGE_VERSION_STATE: Version = Version { major: 4, minor: 1 };
 /// The current version of the storage blocks code.
 pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 4, minor: 0 };
 
@@ -184,6 +194,7 @@ pub fn open_storage(
         events: db_writer.create_common_prefix_table("events")?,
         headers: db_writer.create_simple_table("headers")?,
         markers: db_writer.create_simple_table("markers")?,
+        node_metadata: db_writer.create_simple_table("node_metadata")?,
         nonces: db_writer.create_common_prefix_table("nonces")?,
         file_offsets: db_writer.create_simple_table("file_offsets")?,
         state_diffs: db_writer.create_simple_table("state_diffs")?,
@@ -196,18 +207,28 @@ pub fn open_storage(
     });
     let (file_writers, file_readers) = open_storage_files(
         &storage_config.db_config,
-        storage_config.mmap_file_config,
+        storage_config.mmap_file_config.clone(),
         db_reader.clone(),
         &tables.file_offsets,
     )?;
 
+    let file_readers = Arc::new(RwLock::new(file_readers));
+
     let reader = StorageReader {
         db_reader,
         tables: tables.clone(),
         scope: storage_config.scope,
+        file_readers: file_readers.clone(),
+    };
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        file_writers,
         file_readers,
+        files_path: storage_config.db_config.path(),
+        mmap_file_config: storage_config.mmap_file_config,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
 
     let writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
@@ -395,6 +416,105 @@ fn verify_storage_version(reader: StorageReader) -> StorageResult<()> {
     }
 }
 
+/// The compatibility of an existing storage version with the storage version of the crate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum VersionCompatibility {
+    /// The versions are equal.
+    Compatible,
+    /// The storage has a lower minor version. Opening the storage will update its version, after
+    /// which older nodes won't be able to open it.
+    Migratable,
+    /// The storage has a different major version or a higher minor version, and can't be opened
+    /// by the crate. Re-sync is needed.
+    RequiresResync,
+}
+
+/// The result of [`check_storage_compatibility`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompatibilityReport {
+    /// The state version of the existing storage, or None if the storage was never initialized.
+    pub state_version: Option<Version>,
+    /// The blocks version of the existing storage, or None if the storage was never initialized
+    /// or is in state-only mode.
+    pub blocks_version: Option<Version>,
+    /// Whether the storage can be opened with the given config.
+    pub compatibility: VersionCompatibility,
+}
+
+/// Checks whether the storage can be opened by [`open_storage`] with the given config, without
+/// modifying the storage. Intended as a pre-flight check before upgrading a node, since opening
+/// the storage may update its version irreversibly.
+pub fn check_storage_compatibility(
+    storage_config: &StorageConfig,
+) -> StorageResult<CompatibilityReport> {
+    check_storage_compatibility_with_crate_versions(
+        storage_config,
+        &STORAGE_VERSION_STATE,
+        &STORAGE_VERSION_BLOCKS,
+    )
+}
+
+fn check_storage_compatibility_with_crate_versions(
+    storage_config: &StorageConfig,
+    crate_state_version: &Version,
+    crate_blocks_version: &Version,
+) -> StorageResult<CompatibilityReport> {
+    // A missing storage will be initialized with the crate version.
+    let uninitialized_storage_report = CompatibilityReport {
+        state_version: None,
+        blocks_version: None,
+        compatibility: VersionCompatibility::Compatible,
+    };
+    if !storage_config.db_config.path().join("mdbx.dat").exists()
+        && !storage_config.db_config.enforce_file_exists
+    {
+        return Ok(uninitialized_storage_report);
+    }
+
+    let db_reader = open_env_read_only(&storage_config.db_config)?;
+    let Some(storage_version_table) =
+        db_reader.get_simple_table::<String, NoVersionValueWrapper<Version>>("storage_version")?
+    else {
+        return Ok(uninitialized_storage_report);
+    };
+    let txn = db_reader.begin_ro_txn()?;
+    let version_table = txn.open_table(&storage_version_table)?;
+    let state_version = version_table.get(&txn, &VERSION_STATE_KEY.to_string())?;
+    let blocks_version = version_table.get(&txn, &VERSION_BLOCKS_KEY.to_string())?;
+    let Some(existing_state_version) = &state_version else {
+        return Ok(uninitialized_storage_report);
+    };
+
+    let mut compatibility = get_version_compatibility(crate_state_version, existing_state_version);
+    match (&blocks_version, storage_config.scope) {
+        (Some(existing_blocks_version), StorageScope::FullArchive) => {
+            compatibility = compatibility
+                .max(get_version_compatibility(crate_blocks_version, existing_blocks_version));
+        }
+        // The storage cannot change from state-only to full-archive mode.
+        (None, StorageScope::FullArchive) => {
+            compatibility = VersionCompatibility::RequiresResync;
+        }
+        // The blocks version is deleted when a full-archive storage is opened in state-only mode.
+        (_, StorageScope::StateOnly) => {}
+    }
+    Ok(CompatibilityReport { state_version, blocks_version, compatibility })
+}
+
+fn get_version_compatibility(
+    crate_version: &Version,
+    storage_version: &Version,
+) -> VersionCompatibility {
+    if crate_version.major != storage_version.major {
+        return VersionCompatibility::RequiresResync;
+    }
+    match crate_version.minor.cmp(&storage_version.minor) {
+        std::cmp::Ordering::Equal => VersionCompatibility::Compatible,
+        std::cmp::Ordering::Greater => VersionCompatibility::Migratable,
+        std::cmp::Ordering::Less => VersionCompatibility::RequiresResync,
+    }
+}
+
 /// The categories of data to save in the storage.
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub enum StorageScope {
@@ -410,7 +530,8 @@ pub enum StorageScope {
 #[derive(Clone)]
 pub struct StorageReader {
     db_reader: DbReader,
-    file_readers: FileHandlers<RO>,
+    // Shared with the writer, which may replace some of the files.
+    file_readers: Arc<RwLock<FileHandlers<RO>>>,
     tables: Arc<Tables>,
     scope: StorageScope,
 }
@@ -419,9 +540,16 @@ impl StorageReader {
     /// Takes a snapshot of the current state of the storage and returns a [`StorageTxn`] for
     /// reading data from the storage.
     pub fn begin_ro_txn(&self) -> StorageResult<StorageTxn<'_, RO>> {
+        // The files are locked while beginning the transaction, so that the transaction and the
+        // files are from the same side of StorageWriter::attach_prebuilt_class_files.
+        let file_readers = self.file_readers.read().expect("Lock should not be poisoned");
+        let txn = self.db_reader.begin_ro_txn().map_err(|err| match err {
+            DbError::Inner(libmdbx::Error::ReadersFull) => StorageError::TooManyReaders,
+            err => err.into(),
+        })?;
         Ok(StorageTxn {
-            txn: self.db_reader.begin_ro_txn()?,
-            file_handlers: self.file_readers.clone(),
+            txn,
+            file_handlers: file_readers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
         })
@@ -438,13 +566,67 @@ impl StorageReader {
 
     /// Returns metadata about the memory mapped files in the storage.
     pub fn mmap_files_stats(&self) -> HashMap<String, MMapFileStats> {
-        self.file_readers.stats()
+        self.file_readers.read().expect("Lock should not be poisoned").stats()
+    }
+
+    /// Returns the disk space used by the storage: the used part of the database map and the
+    /// memory mapped files.
+    pub fn total_disk_usage(&self) -> StorageResult<DiskUsage> {
+        let db_size = self.db_reader.get_used_size()?;
+        let mmap_files_sizes: BTreeMap<String, u64> = self
+            .mmap_files_stats()
+            .into_iter()
+            .map(|(name, stats)| {
+                (name, u64::try_from(stats.size()).expect("usize should fit in u64"))
+            })
+            .collect();
+        let total = db_size + mmap_files_sizes.values().sum::<u64>();
+        Ok(DiskUsage { db_size, mmap_files_sizes, total })
     }
 
     /// Returns the scope of the storage.
     pub fn get_scope(&self) -> StorageScope {
         self.scope
     }
+
+    /// Returns all the markers stored in the storage. A marker is the first block number for which
+    /// the corresponding data doesn't exist yet. Markers that were never set are missing from the
+    /// result.
+    pub fn all_markers(&self) -> StorageResult<BTreeMap<MarkerKind, BlockNumber>> {
+        let txn = self.begin_ro_txn()?;
+        let markers_table = txn.open_table(&txn.tables.markers)?;
+        let mut cursor = markers_table.cursor(&txn.txn)?;
+        let mut markers = BTreeMap::new();
+        while let Some((marker_kind, block_number)) = cursor.next()? {
+            markers.insert(marker_kind, block_number);
+        }
+        Ok(markers)
+    }
+
+    /// Returns the block numbers in the given range that have a header but no state diff, in
+    /// ascending order. Such gaps may be left by a crash or a partial sync, and a repair tool can
+    /// use this to find them. Blocks without a header are ignored.
+    pub fn blocks_missing_state_diff(
+        &self,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<Vec<BlockNumber>> {
+        let txn = self.begin_ro_txn()?;
+        let headers_table = txn.open_table(&txn.tables.headers)?;
+        let state_diffs_table = txn.open_table(&txn.tables.state_diffs)?;
+        let mut headers_cursor = headers_table.cursor(&txn.txn)?;
+        let mut missing = Vec::new();
+        let mut current_header = headers_cursor.lower_bound(&block_range.start)?;
+        while let Some((block_number, _header)) = current_header {
+            if block_number >= block_range.end {
+                break;
+            }
+            if state_diffs_table.get(&txn.txn, &block_number)?.is_none() {
+                missing.push(block_number);
+            }
+            current_header = headers_cursor.next()?;
+        }
+        Ok(missing)
+    }
 }
 
 /// A struct for starting RW transactions ([`StorageTxn`]) to the storage.
@@ -453,8 +635,12 @@ impl StorageReader {
 pub struct StorageWriter {
     db_writer: DbWriter,
     file_writers: FileHandlers<RW>,
+    file_readers: Arc<RwLock<FileHandlers<RO>>>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    // The directory of the memory mapped files.
+    files_path: PathBuf,
+    mmap_file_config: MmapFileConfig,
 }
 
 impl StorageWriter {
@@ -468,6 +654,156 @@ impl StorageWriter {
             scope: self.scope,
         })
     }
+
+    /// Forces all the committed data to be written to the disk, including the memory mapped
+    /// files. Once this returns, the data survives a crash of the process or of the machine.
+    pub fn sync_all(&self) -> StorageResult<()> {
+        self.file_writers.sync_all()?;
+        Ok(self.db_writer.sync()?)
+    }
+
+    /// Sets the given markers atomically, leaving the other markers unchanged. Meant for repair
+    /// and migration tooling, the sync updates the markers while writing the data.
+    ///
+    /// Returns [`StorageError::InconsistentMarkers`] without changing any marker if the resulting
+    /// markers violate the invariants between them (e.g. the state marker is above the header
+    /// marker).
+    pub fn set_markers(&mut self, markers: BTreeMap<MarkerKind, BlockNumber>) -> StorageResult<()> {
+        let txn = self.begin_rw_txn()?;
+        {
+            let markers_table = txn.open_table(&txn.tables.markers)?;
+            let mut resulting_markers = BTreeMap::new();
+            for marker_kind in [
+                MarkerKind::Header,
+                MarkerKind::Body,
+                MarkerKind::State,
+                MarkerKind::Class,
+                MarkerKind::CompiledClass,
+                MarkerKind::BaseLayerBlock,
+            ] {
+                let marker = match markers.get(&marker_kind) {
+                    Some(marker) => *marker,
+                    None => markers_table.get(&txn.txn, &marker_kind)?.unwrap_or_default(),
+                };
+                resulting_markers.insert(marker_kind, marker);
+            }
+            let marker = |marker_kind| resulting_markers[&marker_kind];
+            let header_marker = marker(MarkerKind::Header);
+            let is_consistent = marker(MarkerKind::CompiledClass) <= marker(MarkerKind::Class)
+                && marker(MarkerKind::Class) <= marker(MarkerKind::State)
+                && marker(MarkerKind::State) <= header_marker
+                && marker(MarkerKind::Body) <= header_marker
+                && marker(MarkerKind::BaseLayerBlock) <= header_marker;
+            if !is_consistent {
+                return Err(StorageError::InconsistentMarkers { markers: resulting_markers });
+            }
+            for (marker_kind, block_number) in &markers {
+                markers_table.upsert(&txn.txn, marker_kind, block_number)?;
+            }
+        }
+        txn.commit()
+    }
+
+    /// Replaces the contract class and CASM files with files that were built offline, e.g. for a
+    /// fast bootstrap. The `declared_classes` and `casms` tables should already hold the locations
+    /// of the classes in the new files, and `offsets` are the offsets in the new files where the
+    /// data ends.
+    ///
+    /// Returns [`StorageError::PrebuiltFileOffsetMismatch`] without changing anything if a
+    /// location in the tables is beyond the given offset of its file. Otherwise, the new files are
+    /// moved into the storage directory, so they should be on the same file system, and then the
+    /// offsets are committed and the storage switches to the new files at once, so transactions
+    /// that begin afterwards read the classes from them.
+    pub fn attach_prebuilt_class_files(
+        &mut self,
+        contract_class_path: &Path,
+        casm_path: &Path,
+        offsets: ClassFilesOffsets,
+    ) -> StorageResult<()> {
+        let mmap_file_config = self.mmap_file_config.clone();
+        let files_path = self.files_path.clone();
+        let file_readers = self.file_readers.clone();
+        let txn = self.begin_rw_txn()?;
+        let ((contract_class_writer, contract_class_reader), (casm_writer, casm_reader)) = {
+            let declared_classes_table = txn.open_table(&txn.tables.declared_classes)?;
+            let casms_table = txn.open_table(&txn.tables.casms)?;
+            for (offset_kind, table, offset) in [
+                (OffsetKind::ContractClass, &declared_classes_table, offsets.contract_class),
+                (OffsetKind::Casm, &casms_table, offsets.casm),
+            ] {
+                let mut cursor = table.cursor(&txn.txn)?;
+                while let Some((class_hash, location)) = cursor.next()? {
+                    if location.next_offset() > offset {
+                        return Err(StorageError::PrebuiltFileOffsetMismatch {
+                            offset_kind,
+                            offset,
+                            class_hash,
+                            location_end: location.next_offset(),
+                        });
+                    }
+                }
+            }
+            let contract_class_handlers = open_prebuilt_file(
+                &mmap_file_config,
+                contract_class_path,
+                OffsetKind::ContractClass,
+                offsets.contract_class,
+            )?;
+            let casm_handlers =
+                open_prebuilt_file(&mmap_file_config, casm_path, OffsetKind::Casm, offsets.casm)?;
+            // The opened files stay mapped after they're moved.
+            fs::rename(contract_class_path, files_path.join("contract_class.dat"))?;
+            fs::rename(casm_path, files_path.join("casm.dat"))?;
+
+            let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+            file_offsets_table.upsert(
+                &txn.txn,
+                &OffsetKind::ContractClass,
+                &offsets.contract_class,
+            )?;
+            file_offsets_table.upsert(&txn.txn, &OffsetKind::Casm, &offsets.casm)?;
+            (contract_class_handlers, casm_handlers)
+        };
+        // No transaction can begin between the commit and the switch to the new files.
+        // Transactions that already began keep reading from the old files, which stay mapped
+        // until these transactions end.
+        let mut file_readers = file_readers.write().expect("Lock should not be poisoned");
+        txn.commit()?;
+        self.file_writers.contract_class = contract_class_writer;
+        self.file_writers.casm = casm_writer;
+        file_readers.contract_class = contract_class_reader;
+        file_readers.casm = casm_reader;
+        Ok(())
+    }
+}
+
+/// The offsets where the data ends in prebuilt contract class and CASM files. See
+/// [`StorageWriter::attach_prebuilt_class_files`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClassFilesOffsets {
+    /// The offset in the contract class file.
+    pub contract_class: usize,
+    /// The offset in the CASM file.
+    pub casm: usize,
+}
+
+// Opens a prebuilt file whose data ends at the given offset.
+fn open_prebuilt_file<V: ValueSerde>(
+    mmap_file_config: &MmapFileConfig,
+    path: &Path,
+    offset_kind: OffsetKind,
+    offset: usize,
+) -> StorageResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
+    let file_size = usize::try_from(fs::metadata(path)?.len()).expect("u64 should fit in usize");
+    if file_size < offset {
+        return Err(StorageError::PrebuiltFileTooShort { offset_kind, offset, file_size });
+    }
+    Ok(open_file(
+        mmap_file_config.clone(),
+        mmap_file_config.compression_levels.get(offset_kind),
+        path.to_path_buf(),
+        offset,
+    )?)
 }
 
 /// A struct for interacting with the storage.
@@ -531,6 +867,7 @@ struct_field_names! {
         events: TableIdentifier<(ContractAddress, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
         headers: TableIdentifier<BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
+        node_metadata: TableIdentifier<String, NoVersionValueWrapper<Vec<u8>>, SimpleTable>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, CommonPrefix>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
         state_diffs: TableIdentifier<BlockNumber, VersionZeroWrapper<LocationInFile>, SimpleTable>,
@@ -610,6 +947,30 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error(
+        "Reached the maximum number of concurrent read transactions. Consider increasing \
+         `db_config.max_readers`."
+    )]
+    TooManyReaders,
+    #[error("The markers {markers:?} violate the invariants between the markers.")]
+    InconsistentMarkers { markers: BTreeMap<MarkerKind, BlockNumber> },
+    #[error(
+        "The offset {offset} of the prebuilt {offset_kind:?} file doesn't cover the location of \
+         class {class_hash:?}, which ends at {location_end}."
+    )]
+    PrebuiltFileOffsetMismatch {
+        offset_kind: OffsetKind,
+        offset: usize,
+        class_hash: ClassHash,
+        location_end: usize,
+    },
+    #[error(
+        "The prebuilt {offset_kind:?} file is of size {file_size}, smaller than its offset \
+         {offset}."
+    )]
+    PrebuiltFileTooShort { offset_kind: OffsetKind, offset: usize, file_size: usize },
+    #[error("Block {block_number} already has different {marker_kind:?} data stored.")]
+    ConflictingBlockData { block_number: BlockNumber, marker_kind: MarkerKind },
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -650,20 +1011,38 @@ pub struct DbStats {
     pub tables_stats: BTreeMap<String, DbTableStats>,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
-// A marker is the first block number for which the corresponding data doesn't exist yet.
+/// The disk space used by the storage, in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DiskUsage {
+    /// The used part of the database map.
+    pub db_size: u64,
+    /// A mapping from a memory mapped file name to its size on disk.
+    pub mmap_files_sizes: BTreeMap<String, u64>,
+    /// The sum of all the sizes above.
+    pub total: u64,
+}
+
+/// A marker is the first block number for which the corresponding data doesn't exist yet.
 // Invariants:
 // - CompiledClass <= Class <= State <= Header
 // - Body <= Header
 // - BaseLayerBlock <= Header
 // Event is currently unsupported.
-pub(crate) enum MarkerKind {
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
+pub enum MarkerKind {
+    /// The marker of the block headers and signatures.
     Header,
+    /// The marker of the block bodies, i.e. the transactions and their outputs.
     Body,
+    /// The marker of the events index. Currently unsupported and never set.
     Event,
+    /// The marker of the state diffs.
     State,
+    /// The marker of the classes declared in the state diffs.
     Class,
+    /// The marker of the compiled classes of the Cairo 1 classes declared in the state diffs.
     CompiledClass,
+    /// The first block that wasn't proved on the base layer yet.
     BaseLayerBlock,
 }
 
@@ -715,6 +1094,16 @@ impl FileHandlers<RW> {
         self.clone().transaction.append(transaction)
     }
 
+    // Flushes all the files and syncs them to the disk.
+    fn sync_all(&self) -> Result<(), MMapFileError> {
+        self.thin_state_diff.sync_all()?;
+        self.contract_class.sync_all()?;
+        self.casm.sync_all()?;
+        self.deprecated_contract_class.sync_all()?;
+        self.transaction_output.sync_all()?;
+        self.transaction.sync_all()
+    }
+
     // TODO(dan): Consider 1. flushing only the relevant files, 2. flushing concurrently.
     #[latency_histogram("storage_file_handler_flush_latency_seconds", false)]
     fn flush(&self) {
@@ -761,6 +1150,14 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
         })
     }
 
+    // Returns the serialized bytes of the contract class at the given location.
+    fn get_contract_class_bytes_unchecked(
+        &self,
+        location: LocationInFile,
+    ) -> StorageResult<Vec<u8>> {
+        Ok(self.contract_class.get_raw(location)?)
+    }
+
     // Returns the CASM at the given location or an error in case it doesn't exist.
     fn get_casm_unchecked(&self, location: LocationInFile) -> StorageResult<CasmContractClass> {
         self.casm.get(location)?.ok_or(StorageError::DBInconsistency {
@@ -806,12 +1203,14 @@ fn open_storage_files(
 ) -> StorageResult<(FileHandlers<RW>, FileHandlers<RO>)> {
     let db_transaction = db_reader.begin_ro_txn()?;
     let table = db_transaction.open_table(file_offsets_table)?;
+    let compression_levels = mmap_file_config.compression_levels;
 
     // TODO(dvir): consider using a loop here to avoid code duplication.
     let thin_state_diff_offset =
         table.get(&db_transaction, &OffsetKind::ThinStateDiff)?.unwrap_or_default();
     let (thin_state_diff_writer, thin_state_diff_reader) = open_file(
         mmap_file_config.clone(),
+        compression_levels.get(OffsetKind::ThinStateDiff),
         db_config.path().join("thin_state_diff.dat"),
         thin_state_diff_offset,
     )?;
@@ -820,18 +1219,24 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::ContractClass)?.unwrap_or_default();
     let (contract_class_writer, contract_class_reader) = open_file(
         mmap_file_config.clone(),
+        compression_levels.get(OffsetKind::ContractClass),
         db_config.path().join("contract_class.dat"),
         contract_class_offset,
     )?;
 
     let casm_offset = table.get(&db_transaction, &OffsetKind::Casm)?.unwrap_or_default();
-    let (casm_writer, casm_reader) =
-        open_file(mmap_file_config.clone(), db_config.path().join("casm.dat"), casm_offset)?;
+    let (casm_writer, casm_reader) = open_file(
+        mmap_file_config.clone(),
+        compression_levels.get(OffsetKind::Casm),
+        db_config.path().join("casm.dat"),
+        casm_offset,
+    )?;
 
     let deprecated_contract_class_offset =
         table.get(&db_transaction, &OffsetKind::DeprecatedContractClass)?.unwrap_or_default();
     let (deprecated_contract_class_writer, deprecated_contract_class_reader) = open_file(
         mmap_file_config.clone(),
+        compression_levels.get(OffsetKind::DeprecatedContractClass),
         db_config.path().join("deprecated_contract_class.dat"),
         deprecated_contract_class_offset,
     )?;
@@ -840,14 +1245,19 @@ fn open_storage_files(
         table.get(&db_transaction, &OffsetKind::TransactionOutput)?.unwrap_or_default();
     let (transaction_output_writer, transaction_output_reader) = open_file(
         mmap_file_config.clone(),
+        compression_levels.get(OffsetKind::TransactionOutput),
         db_config.path().join("transaction_output.dat"),
         transaction_output_offset,
     )?;
 
     let transaction_offset =
         table.get(&db_transaction, &OffsetKind::Transaction)?.unwrap_or_default();
-    let (transaction_writer, transaction_reader) =
-        open_file(mmap_file_config, db_config.path().join("transaction.dat"), transaction_offset)?;
+    let (transaction_writer, transaction_reader) = open_file(
+        mmap_file_config,
+        compression_levels.get(OffsetKind::Transaction),
+        db_config.path().join("transaction.dat"),
+        transaction_offset,
+    )?;
 
     Ok((
         FileHandlers {
@@ -0,0 +1,74 @@
+//! Interface for handling operational metadata of the node.
+//!
+//! The metadata is a key-value store of small pieces of data that the node needs to persist
+//! alongside the chain data (e.g. the last known peer id). The values are opaque bytes, and it's
+//! up to the user to define their encoding.
+//!
+//! Import [`MetadataStorageReader`] and [`MetadataStorageWriter`] to read and write the metadata
+//! using a [`StorageTxn`].
+//! # Example
+//! ```
+//! use papyrus_storage::metadata::{MetadataStorageReader, MetadataStorageWriter};
+//! use papyrus_storage::open_storage;
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId::Mainnet,
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! writer
+//!     .begin_rw_txn()?                                 // Start a RW transaction.
+//!     .set_metadata("deployment_epoch", &[1, 2, 3])?   // Set a metadata value.
+//!     .commit()?; // Commit the transaction.
+//! let value = reader.begin_ro_txn()?.get_metadata("deployment_epoch")?;
+//! assert_eq!(value, Some(vec![1, 2, 3]));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "metadata_test.rs"]
+mod metadata_test;
+
+use crate::db::table_types::Table;
+use crate::db::{TransactionKind, RW};
+use crate::{StorageResult, StorageTxn};
+
+/// Interface for reading the node metadata.
+pub trait MetadataStorageReader {
+    /// Returns the metadata value stored under the given key.
+    fn get_metadata(&self, key: &str) -> StorageResult<Option<Vec<u8>>>;
+}
+
+/// Interface for writing the node metadata.
+pub trait MetadataStorageWriter
+where
+    Self: Sized,
+{
+    /// Sets the metadata value of the given key, overwriting the existing value if there is one.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn set_metadata(self, key: &str, value: &[u8]) -> StorageResult<Self>;
+}
+
+impl<Mode: TransactionKind> MetadataStorageReader for StorageTxn<'_, Mode> {
+    fn get_metadata(&self, key: &str) -> StorageResult<Option<Vec<u8>>> {
+        let node_metadata_table = self.open_table(&self.tables.node_metadata)?;
+        Ok(node_metadata_table.get(&self.txn, &key.to_string())?)
+    }
+}
+
+impl MetadataStorageWriter for StorageTxn<'_, RW> {
+    fn set_metadata(self, key: &str, value: &[u8]) -> StorageResult<Self> {
+        let node_metadata_table = self.open_table(&self.tables.node_metadata)?;
+        node_metadata_table.upsert(&self.txn, &key.to_string(), &value.to_vec())?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,45 @@
+use crate::metadata::{MetadataStorageReader, MetadataStorageWriter};
+use crate::test_utils::get_test_storage;
+
+#[test]
+fn set_and_get_metadata() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert!(reader.begin_ro_txn().unwrap().get_metadata("peer_id").unwrap().is_none());
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .set_metadata("peer_id", b"first peer")
+        .unwrap()
+        .set_metadata("config_fingerprint", &[0, 1, 2])
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_metadata("peer_id").unwrap(), Some(b"first peer".to_vec()));
+    assert_eq!(txn.get_metadata("config_fingerprint").unwrap(), Some(vec![0, 1, 2]));
+    assert!(txn.get_metadata("deployment_epoch").unwrap().is_none());
+}
+
+#[test]
+fn overwrite_metadata() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .set_metadata("peer_id", b"first peer")
+        .unwrap()
+        .commit()
+        .unwrap();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .set_metadata("peer_id", b"second peer")
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_metadata("peer_id").unwrap(),
+        Some(b"second peer".to_vec())
+    );
+}
@@ -3,11 +3,13 @@ use std::sync::Arc;
 use papyrus_test_utils::get_rng;
 use pretty_assertions::assert_eq;
 use rand::Rng;
+use starknet_api::deprecated_contract_class::{ContractClass as DeprecatedContractClass, Program};
+use starknet_api::test_utils::read_json_file;
 use tempfile::tempdir;
 use tokio::sync::{Barrier, RwLock};
 
 use super::*;
-use crate::db::serialization::NoVersionValueWrapper;
+use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::test_utils::get_mmap_file_test_config;
 
 #[test]
@@ -22,6 +24,11 @@ fn config_validation() {
     assert!(config.validate().is_err());
     config.growth_step = 1 << 20;
     assert_eq!(config.validate(), Ok(()));
+
+    config.compression_levels.casm = zstd::compression_level_range().end() + 1;
+    assert!(config.validate().is_err());
+    config.compression_levels.casm = *zstd::compression_level_range().end();
+    assert_eq!(config.validate(), Ok(()));
 }
 
 #[test]
@@ -30,6 +37,7 @@ fn write_read() {
     let offset = 0;
     let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
         get_mmap_file_test_config(),
+        DEFAULT_COMPRESSION_LEVEL,
         dir.path().to_path_buf().join("test_write_read"),
         offset,
     )
@@ -52,6 +60,7 @@ fn concurrent_reads() {
     let offset = 0;
     let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
         get_mmap_file_test_config(),
+        DEFAULT_COMPRESSION_LEVEL,
         dir.path().to_path_buf().join("test_concurrent_reads"),
         offset,
     )
@@ -83,6 +92,7 @@ fn concurrent_reads_single_write() {
     let offset = 0;
     let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
         get_mmap_file_test_config(),
+        DEFAULT_COMPRESSION_LEVEL,
         dir.path().to_path_buf().join("test_concurrent_reads_single_write"),
         offset,
     )
@@ -132,6 +142,7 @@ fn grow_file() {
         max_size: 10 * serialization_size,
         max_object_size: serialization_size, // 3 (len + data)
         growth_step: serialization_size + 1, // 4
+        compression_levels: CompressionLevels::default(),
     };
 
     let file_path = dir.path().to_path_buf().join("test_grow_file");
@@ -147,9 +158,13 @@ fn grow_file() {
         // file_size = 0, offset = 0
         assert_eq!(file.metadata().unwrap().len(), 0);
 
-        let (mut writer, _) =
-            open_file::<NoVersionValueWrapper<Vec<u8>>>(config.clone(), file_path.clone(), offset)
-                .unwrap();
+        let (mut writer, _) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+            config.clone(),
+            DEFAULT_COMPRESSION_LEVEL,
+            file_path.clone(),
+            offset,
+        )
+        .unwrap();
         // file_size = 4 (growth_step), offset = 0
         let mut file_size = usize::try_from(file.metadata().unwrap().len()).unwrap();
         assert_eq!(file_size, config.growth_step);
@@ -188,7 +203,13 @@ fn grow_file() {
         .open(file_path.clone())
         .unwrap();
     assert_eq!(usize::try_from(file.metadata().unwrap().len()).unwrap(), 4 * config.growth_step);
-    let _ = open_file::<NoVersionValueWrapper<Vec<u8>>>(config.clone(), file_path, offset).unwrap();
+    let _ = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+        config.clone(),
+        DEFAULT_COMPRESSION_LEVEL,
+        file_path,
+        offset,
+    )
+    .unwrap();
     assert_eq!(usize::try_from(file.metadata().unwrap().len()).unwrap(), 4 * config.growth_step);
 
     dir.close().unwrap();
@@ -200,6 +221,7 @@ async fn write_read_different_locations() {
     let offset = 0;
     let (mut writer, reader) = open_file(
         get_mmap_file_test_config(),
+        DEFAULT_COMPRESSION_LEVEL,
         dir.path().to_path_buf().join("test_write_read_different_locations"),
         offset,
     )
@@ -257,6 +279,7 @@ fn reader_when_writer_is_out_of_scope() {
     let offset = 0;
     let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
         get_mmap_file_test_config(),
+        DEFAULT_COMPRESSION_LEVEL,
         dir.path().to_path_buf().join("test_reader_when_writer_is_out_of_scope"),
         offset,
     )
@@ -274,6 +297,42 @@ fn reader_when_writer_is_out_of_scope() {
     dir.close().unwrap();
 }
 
+#[test]
+fn different_compression_levels() {
+    let dir = tempdir().unwrap();
+    let program_json = read_json_file("program.json");
+    let program = serde_json::from_value::<Program>(program_json).unwrap();
+    let contract_class = DeprecatedContractClass { program, ..Default::default() };
+
+    let (mut fast_writer, fast_reader) = open_file::<VersionZeroWrapper<DeprecatedContractClass>>(
+        get_mmap_file_test_config(),
+        1,
+        dir.path().to_path_buf().join("test_fast_compression"),
+        0,
+    )
+    .unwrap();
+    let (mut strong_writer, strong_reader) =
+        open_file::<VersionZeroWrapper<DeprecatedContractClass>>(
+            get_mmap_file_test_config(),
+            19,
+            dir.path().to_path_buf().join("test_strong_compression"),
+            0,
+        )
+        .unwrap();
+
+    let fast_location = fast_writer.append(&contract_class);
+    fast_writer.flush();
+    let strong_location = strong_writer.append(&contract_class);
+    strong_writer.flush();
+
+    assert!(strong_location.len < fast_location.len);
+    assert!(strong_reader.stats().offset < fast_reader.stats().offset);
+    assert_eq!(fast_reader.get(fast_location).unwrap().unwrap(), contract_class);
+    assert_eq!(strong_reader.get(strong_location).unwrap().unwrap(), contract_class);
+
+    dir.close().unwrap();
+}
+
 #[test]
 fn storage_serde_test_location_in_file() {
     let item = LocationInFile::get_test_instance(&mut get_rng());
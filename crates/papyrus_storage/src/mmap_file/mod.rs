@@ -16,7 +16,7 @@ use std::result;
 use std::sync::{Arc, Mutex};
 
 use memmap2::{MmapMut, MmapOptions};
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 #[cfg(test)]
 use papyrus_test_utils::GetTestInstance;
@@ -27,8 +27,10 @@ use thiserror::Error;
 use tracing::{debug, instrument, trace};
 use validator::{Validate, ValidationError};
 
+use crate::compression_utils::DEFAULT_COMPRESSION_LEVEL;
 use crate::db::serialization::{StorageSerde, StorageSerdeError, ValueSerde};
 use crate::db::{TransactionKind, RO, RW};
+use crate::OffsetKind;
 
 type MmapFileResult<V> = result::Result<V, MMapFileError>;
 
@@ -42,11 +44,13 @@ pub struct MmapFileConfig {
     pub growth_step: usize,
     /// The maximum size of an object in bytes.
     pub max_object_size: usize,
+    /// The compression levels of the objects written to each of the files.
+    pub compression_levels: CompressionLevels,
 }
 
 impl SerializeConfig for MmapFileConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "max_size",
                 &self.max_size,
@@ -66,7 +70,9 @@ impl SerializeConfig for MmapFileConfig {
                 "The maximum size of a single object in the file in bytes",
                 ParamPrivacyInput::Public,
             ),
-        ])
+        ]);
+        config.extend(append_sub_config_name(self.compression_levels.dump(), "compression_levels"));
+        config
     }
 }
 
@@ -76,6 +82,7 @@ impl Default for MmapFileConfig {
             max_size: 1 << 40,        // 1TB
             growth_step: 1 << 30,     // 1GB
             max_object_size: 1 << 28, // 256MB
+            compression_levels: CompressionLevels::default(),
         }
     }
 }
@@ -87,9 +94,114 @@ fn validate_config(config: &MmapFileConfig) -> result::Result<(), ValidationErro
     if config.growth_step < config.max_object_size {
         return Err(ValidationError::new("growth_step should be larger than max_object_size"));
     }
+    if !config.compression_levels.is_valid() {
+        return Err(ValidationError::new("compression levels should be supported by zstd"));
+    }
     Ok(())
 }
 
+/// The zstd compression levels of the objects in each of the storage files. Higher levels are
+/// slower but compress better.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CompressionLevels {
+    /// The compression level of the thin state diffs file.
+    pub thin_state_diff: i32,
+    /// The compression level of the contract classes file.
+    pub contract_class: i32,
+    /// The compression level of the CASMs file.
+    pub casm: i32,
+    /// The compression level of the deprecated contract classes file.
+    pub deprecated_contract_class: i32,
+    /// The compression level of the transaction outputs file.
+    pub transaction_output: i32,
+    /// The compression level of the transactions file.
+    pub transaction: i32,
+}
+
+impl CompressionLevels {
+    /// Returns the compression level of the file of the given kind.
+    pub fn get(&self, offset_kind: OffsetKind) -> i32 {
+        match offset_kind {
+            OffsetKind::ThinStateDiff => self.thin_state_diff,
+            OffsetKind::ContractClass => self.contract_class,
+            OffsetKind::Casm => self.casm,
+            OffsetKind::DeprecatedContractClass => self.deprecated_contract_class,
+            OffsetKind::TransactionOutput => self.transaction_output,
+            OffsetKind::Transaction => self.transaction,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let supported_levels = zstd::compression_level_range();
+        [
+            self.thin_state_diff,
+            self.contract_class,
+            self.casm,
+            self.deprecated_contract_class,
+            self.transaction_output,
+            self.transaction,
+        ]
+        .iter()
+        .all(|level| supported_levels.contains(level))
+    }
+}
+
+impl Default for CompressionLevels {
+    fn default() -> Self {
+        Self {
+            thin_state_diff: DEFAULT_COMPRESSION_LEVEL,
+            contract_class: DEFAULT_COMPRESSION_LEVEL,
+            casm: DEFAULT_COMPRESSION_LEVEL,
+            deprecated_contract_class: DEFAULT_COMPRESSION_LEVEL,
+            transaction_output: DEFAULT_COMPRESSION_LEVEL,
+            transaction: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl SerializeConfig for CompressionLevels {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "thin_state_diff",
+                &self.thin_state_diff,
+                "The zstd compression level of the thin state diffs file.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "contract_class",
+                &self.contract_class,
+                "The zstd compression level of the contract classes file.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "casm",
+                &self.casm,
+                "The zstd compression level of the CASMs file.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "deprecated_contract_class",
+                &self.deprecated_contract_class,
+                "The zstd compression level of the deprecated contract classes file.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "transaction_output",
+                &self.transaction_output,
+                "The zstd compression level of the transaction outputs file.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "transaction",
+                &self.transaction,
+                "The zstd compression level of the transactions file.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
 /// Errors associated with memory mapped files.
 #[derive(Debug, Error)]
 pub enum MMapFileError {
@@ -109,12 +221,19 @@ pub(crate) trait Writer<V: ValueSerde> {
 
     /// Flushes the mmap to the file.
     fn flush(&self);
+
+    /// Flushes the mmap to the file and syncs the file to the disk, even if nothing was written
+    /// since the last flush.
+    fn sync_all(&self) -> MmapFileResult<()>;
 }
 
 /// A trait for reading from a memory mapped file.
 pub(crate) trait Reader<V: ValueSerde> {
     /// Returns an object from the file.
     fn get(&self, location: LocationInFile) -> MmapFileResult<Option<V::Value>>;
+
+    /// Returns the serialized bytes of an object from the file, as they are stored.
+    fn get_raw(&self, location: LocationInFile) -> MmapFileResult<Vec<u8>>;
 }
 
 /// Represents a location in the file.
@@ -137,6 +256,7 @@ impl LocationInFile {
 #[derive(Debug)]
 struct MMapFile<V: ValueSerde> {
     config: MmapFileConfig,
+    compression_level: i32,
     file: File,
     size: usize,
     mmap: MmapMut,
@@ -168,6 +288,7 @@ impl<V: ValueSerde> MMapFile<V> {
 #[instrument(level = "debug", err)]
 pub(crate) fn open_file<V: ValueSerde>(
     config: MmapFileConfig,
+    compression_level: i32,
     path: PathBuf,
     offset: usize,
 ) -> MmapFileResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
@@ -177,6 +298,7 @@ pub(crate) fn open_file<V: ValueSerde>(
     let mmap_ptr = mmap.as_ptr();
     let mmap_file = MMapFile {
         config,
+        compression_level,
         file,
         mmap,
         size: size.try_into().expect("size should fit in usize"),
@@ -226,7 +348,10 @@ impl<V: ValueSerde> FileHandler<V, RW> {
 impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
     fn append(&mut self, val: &V::Value) -> LocationInFile {
         trace!("Inserting object: {:?}", val);
-        let serialized = V::serialize(val).expect("Should be able to serialize");
+        let compression_level =
+            self.mmap_file.lock().expect("Lock should not be poisoned").compression_level;
+        let serialized = V::serialize_with_compression_level(val, compression_level)
+            .expect("Should be able to serialize");
         let len = serialized.len();
         let offset;
         {
@@ -253,20 +378,40 @@ impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
             mmap_file.flush();
         }
     }
+
+    fn sync_all(&self) -> MmapFileResult<()> {
+        let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
+        mmap_file.flush();
+        mmap_file.file.sync_all()?;
+        Ok(())
+    }
 }
 
 impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
     /// Returns an object from the file.
     fn get(&self, location: LocationInFile) -> MmapFileResult<Option<V::Value>> {
         trace!("Reading object at location: {:?}", location);
-        let mut bytes = unsafe {
+        let mut bytes = self.bytes_at(location)?;
+        trace!("Deserializing object: {:?}", bytes);
+        Ok(V::deserialize(&mut bytes))
+    }
+
+    /// Returns the serialized bytes of an object from the file.
+    fn get_raw(&self, location: LocationInFile) -> MmapFileResult<Vec<u8>> {
+        trace!("Reading raw object at location: {:?}", location);
+        Ok(self.bytes_at(location)?.to_vec())
+    }
+}
+
+impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
+    // Returns a slice of the memory mapped file at the given location.
+    fn bytes_at(&self, location: LocationInFile) -> MmapFileResult<&[u8]> {
+        Ok(unsafe {
             std::slice::from_raw_parts(
                 self.memory_ptr.offset(location.offset.try_into()?),
                 location.len,
             )
-        };
-        trace!("Deserializing object: {:?}", bytes);
-        Ok(V::deserialize(&mut bytes))
+        })
     }
 }
 
@@ -279,6 +424,13 @@ pub struct MMapFileStats {
     offset: usize,
 }
 
+impl MMapFileStats {
+    /// Returns the current size of the file on disk.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
 impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
     pub fn stats(&self) -> MMapFileStats {
         let mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
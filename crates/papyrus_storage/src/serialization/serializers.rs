@@ -134,6 +134,7 @@ use crate::compression_utils::{
     decompress_from_reader,
     serialize_and_compress,
     IsCompressed,
+    DEFAULT_COMPRESSION_LEVEL,
 };
 use crate::db::serialization::{StorageSerde, StorageSerdeError};
 use crate::db::table_types::NoValue;
@@ -621,6 +622,27 @@ macro_rules! auto_storage_serde {
                     )*
                 }
             }
+            fn serialize_into_with_compression_level(
+                &self,
+                res: &mut impl std::io::Write,
+                compression_level: i32,
+            ) -> Result<(), StorageSerdeError> {
+                #[allow(clippy::as_conversions)]
+                match self {
+                    $(
+                        variant!( value, $variant $( ($ty) )?) => {
+                            res.write_all(&[$num as u8])?;
+                            $(
+                                (value as &$ty).serialize_into_with_compression_level(
+                                    res,
+                                    compression_level,
+                                )?;
+                            )?
+                            Ok(())
+                        }
+                    )*
+                }
+            }
             fn deserialize_from(bytes: &mut impl std::io::Read) -> Option<Self> {
                 let mut kind = [0u8; 1];
                 bytes.read_exact(&mut kind).ok()?;
@@ -974,10 +996,18 @@ impl StorageSerde for TransactionOffsetInBlock {
 ////////////////////////////////////////////////////////////////////////
 impl StorageSerde for SierraContractClass {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
-        serialize_and_compress(&self.sierra_program)?.serialize_into(res)?;
+        self.serialize_into_with_compression_level(res, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn serialize_into_with_compression_level(
+        &self,
+        res: &mut impl std::io::Write,
+        compression_level: i32,
+    ) -> Result<(), StorageSerdeError> {
+        serialize_and_compress(&self.sierra_program, compression_level)?.serialize_into(res)?;
         self.contract_class_version.serialize_into(res)?;
         self.entry_points_by_type.serialize_into(res)?;
-        serialize_and_compress(&self.abi)?.serialize_into(res)?;
+        serialize_and_compress(&self.abi, compression_level)?.serialize_into(res)?;
         Ok(())
     }
 
@@ -997,6 +1027,14 @@ create_storage_serde_test!(SierraContractClass);
 
 impl StorageSerde for DeprecatedContractClass {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
+        self.serialize_into_with_compression_level(res, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn serialize_into_with_compression_level(
+        &self,
+        res: &mut impl std::io::Write,
+        compression_level: i32,
+    ) -> Result<(), StorageSerdeError> {
         // Compress together the program and abi for better compression results.
         let mut to_compress: Vec<u8> = Vec::new();
         self.abi.serialize_into(&mut to_compress)?;
@@ -1008,7 +1046,7 @@ impl StorageSerde for DeprecatedContractClass {
                 to_compress.len()
             );
         }
-        let compressed = compress(to_compress.as_slice())?;
+        let compressed = compress(to_compress.as_slice(), compression_level)?;
         compressed.serialize_into(res)?;
         self.entry_points_by_type.serialize_into(res)?;
         Ok(())
@@ -1052,6 +1090,14 @@ impl<TYPE: Default> StorageSerde for FunctionAbiEntry<TYPE> {
 
 impl StorageSerde for CasmContractClass {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
+        self.serialize_into_with_compression_level(res, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn serialize_into_with_compression_level(
+        &self,
+        res: &mut impl std::io::Write,
+        compression_level: i32,
+    ) -> Result<(), StorageSerdeError> {
         let mut to_compress: Vec<u8> = Vec::new();
         self.prime.serialize_into(&mut to_compress)?;
         self.compiler_version.serialize_into(&mut to_compress)?;
@@ -1067,7 +1113,7 @@ impl StorageSerde for CasmContractClass {
                 to_compress.len()
             );
         }
-        let compressed = compress(to_compress.as_slice())?;
+        let compressed = compress(to_compress.as_slice(), compression_level)?;
         compressed.serialize_into(res)?;
 
         Ok(())
@@ -1095,6 +1141,14 @@ create_storage_serde_test!(CasmContractClass);
 
 impl StorageSerde for ThinStateDiff {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
+        self.serialize_into_with_compression_level(res, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn serialize_into_with_compression_level(
+        &self,
+        res: &mut impl std::io::Write,
+        compression_level: i32,
+    ) -> Result<(), StorageSerdeError> {
         let mut to_compress: Vec<u8> = Vec::new();
         self.deployed_contracts.serialize_into(&mut to_compress)?;
         self.storage_diffs.serialize_into(&mut to_compress)?;
@@ -1109,7 +1163,7 @@ impl StorageSerde for ThinStateDiff {
                 to_compress.len()
             );
         }
-        let compressed = compress(to_compress.as_slice())?;
+        let compressed = compress(to_compress.as_slice(), compression_level)?;
         compressed.serialize_into(res)?;
         Ok(())
     }
@@ -1139,6 +1193,13 @@ macro_rules! auto_storage_serde_conditionally_compressed {
     ($(pub)? struct $name:ident { $(pub $field:ident : $ty:ty ,)* } $($rest:tt)*) => {
         impl StorageSerde for $name {
             fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
+                self.serialize_into_with_compression_level(res, DEFAULT_COMPRESSION_LEVEL)
+            }
+            fn serialize_into_with_compression_level(
+                &self,
+                res: &mut impl std::io::Write,
+                compression_level: i32,
+            ) -> Result<(), StorageSerdeError> {
                 let mut to_compress: Vec<u8> = Vec::new();
                 $(
                     self.$field.serialize_into(&mut to_compress)?;
@@ -1153,7 +1214,7 @@ macro_rules! auto_storage_serde_conditionally_compressed {
                             to_compress.len()
                         );
                     }
-                    let compressed = compress(to_compress.as_slice())?;
+                    let compressed = compress(to_compress.as_slice(), compression_level)?;
                     compressed.serialize_into(res)?;
                 } else {
                     IsCompressed::No.serialize_into(res)?;
@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     max_readers: 1 << 13, // 8K readers
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let state_diff = ThinStateDiff::default();
@@ -55,17 +56,20 @@ pub mod data;
 mod state_test;
 
 use std::collections::HashSet;
+use std::ops::Range;
 
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::IndexMap;
 use papyrus_proc_macros::latency_histogram;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::block_hash::state_diff_hash::calculate_state_diff_hash;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, BLOCK_HASH_TABLE_ADDRESS};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::{SierraContractClass, StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::state::{SierraContractClass, StateDiff, StateNumber, StorageKey, ThinStateDiff};
 use starknet_types_core::felt::Felt;
 use tracing::debug;
 
+use crate::class::ClassStorageReader;
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
@@ -80,6 +84,7 @@ use crate::{
     OffsetKind,
     StorageError,
     StorageResult,
+    StorageScope,
     StorageTxn,
 };
 
@@ -128,10 +133,56 @@ pub trait StateStorageReader<Mode: TransactionKind> {
     fn get_state_marker(&self) -> StorageResult<BlockNumber>;
     /// Returns the state diff at a given block number.
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>>;
+    /// Returns the state diff at a given block number, with the definitions of the classes it
+    /// declares.
+    ///
+    /// Returns [`StorageError`]::ScopeError under [`StorageScope::StateOnly`], where classes
+    /// aren't necessarily stored, and [`StorageError`]::MarkerMismatch if the classes of the block
+    /// weren't synced yet. Returns [`StorageError`]::DBInconsistency if a declared class of a block
+    /// below the class marker isn't stored.
+    fn get_full_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<StateDiff>>;
+    /// Returns a hash of the state diff at a given block number, for comparing state diffs
+    /// between nodes without transferring them. The hash doesn't depend on the order of the
+    /// entries in the state diff.
+    fn state_diff_hash(&self, block_number: BlockNumber) -> StorageResult<Option<Felt>>;
+    /// Returns the contracts deployed in the given block range, ordered by block number and then
+    /// by their order in the state diff. Class replacements aren't included. Blocks in the range
+    /// that don't have a state diff yet are ignored.
+    fn deployed_contracts_in_range(
+        &self,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<Vec<(ContractAddress, BlockNumber)>>;
+    /// Checks whether an untrusted state diff can be applied as the state diff of the given block,
+    /// on top of the stored state before that block. Returns the conflicts found, in the order of
+    /// the entries in the state diff. An empty result means the state diff applies cleanly.
+    ///
+    /// Returns [`StorageError`]::MarkerMismatch if the block is after the state marker.
+    fn validate_state_diff_applies(
+        &self,
+        block_number: BlockNumber,
+        thin_state_diff: &ThinStateDiff,
+    ) -> StorageResult<Vec<ApplyConflict>>;
     /// Returns a state reader.
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>>;
 }
 
+/// A reason a state diff can't be applied on top of the stored state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ApplyConflict {
+    /// The state diff deploys a contract that is already deployed.
+    ContractAlreadyDeployed(ContractAddress),
+    /// The state diff replaces the class of a contract that isn't deployed.
+    ReplacedClassOfUndeployedContract(ContractAddress),
+    /// The state diff writes to the storage of a contract that isn't deployed.
+    StorageOfUndeployedContract(ContractAddress),
+    /// The state diff sets the nonce of a contract that isn't deployed.
+    NonceOfUndeployedContract(ContractAddress),
+    /// The state diff doesn't advance the nonce of a contract.
+    NonceNotIncreasing { contract_address: ContractAddress, prior_nonce: Nonce, new_nonce: Nonce },
+    /// The state diff declares a class that is already declared.
+    ClassAlreadyDeclared(ClassHash),
+}
+
 type RevertedStateDiff = (
     ThinStateDiff,
     IndexMap<ClassHash, SierraContractClass>,
@@ -151,6 +202,15 @@ where
         thin_state_diff: ThinStateDiff,
     ) -> StorageResult<Self>;
 
+    /// Same as [`append_state_diff`](Self::append_state_diff), but if the block already has a
+    /// state diff, succeeds without writing when it's identical to the given one, and fails with
+    /// [`StorageError::ConflictingBlockData`] otherwise. Makes re-syncing stored blocks safe.
+    fn append_state_diff_if_absent(
+        self,
+        block_number: BlockNumber,
+        thin_state_diff: ThinStateDiff,
+    ) -> StorageResult<Self>;
+
     /// Removes a state diff from the storage and returns the removed data.
     fn revert_state_diff(
         self,
@@ -177,6 +237,145 @@ impl<Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'_, Mode> {
         }
     }
 
+    fn get_full_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<StateDiff>> {
+        if self.scope == StorageScope::StateOnly {
+            return Err(StorageError::ScopeError {
+                table_name: self.tables.declared_classes.name.to_owned(),
+                storage_scope: self.scope,
+            });
+        }
+        let Some(thin_state_diff) = self.get_state_diff(block_number)? else {
+            return Ok(None);
+        };
+        let class_marker = self.get_class_marker()?;
+        if block_number >= class_marker {
+            return Err(StorageError::MarkerMismatch {
+                expected: class_marker,
+                found: block_number,
+            });
+        }
+        let missing_class = |class_hash: &ClassHash| StorageError::DBInconsistency {
+            msg: format!(
+                "Class {class_hash:?} declared in block {block_number} is missing from the \
+                 storage."
+            ),
+        };
+        let declared_classes: IndexMap<_, _> = thin_state_diff
+            .declared_classes
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                let class =
+                    self.get_class(&class_hash)?.ok_or_else(|| missing_class(&class_hash))?;
+                Ok((class_hash, (compiled_class_hash, class)))
+            })
+            .collect::<StorageResult<_>>()?;
+        let deprecated_declared_classes: IndexMap<_, _> = thin_state_diff
+            .deprecated_declared_classes
+            .into_iter()
+            .map(|class_hash| {
+                let class = self
+                    .get_deprecated_class(&class_hash)?
+                    .ok_or_else(|| missing_class(&class_hash))?;
+                Ok((class_hash, class))
+            })
+            .collect::<StorageResult<_>>()?;
+        Ok(Some(StateDiff {
+            deployed_contracts: thin_state_diff.deployed_contracts,
+            storage_diffs: thin_state_diff.storage_diffs,
+            declared_classes,
+            deprecated_declared_classes,
+            nonces: thin_state_diff.nonces,
+            replaced_classes: thin_state_diff.replaced_classes,
+        }))
+    }
+
+    fn state_diff_hash(&self, block_number: BlockNumber) -> StorageResult<Option<Felt>> {
+        Ok(self
+            .get_state_diff(block_number)?
+            .map(|state_diff| calculate_state_diff_hash(&state_diff).0.0))
+    }
+
+    fn deployed_contracts_in_range(
+        &self,
+        block_range: Range<BlockNumber>,
+    ) -> StorageResult<Vec<(ContractAddress, BlockNumber)>> {
+        let up_to = block_range.end.min(self.get_state_marker()?);
+        let mut deployed_contracts = Vec::new();
+        for block_number in block_range.start.iter_up_to(up_to) {
+            let state_diff = self.get_state_diff(block_number)?.ok_or_else(|| {
+                StorageError::DBInconsistency {
+                    msg: format!("Missing state diff of block {block_number} below the marker."),
+                }
+            })?;
+            deployed_contracts.extend(
+                state_diff.deployed_contracts.into_keys().map(|address| (address, block_number)),
+            );
+        }
+        Ok(deployed_contracts)
+    }
+
+    fn validate_state_diff_applies(
+        &self,
+        block_number: BlockNumber,
+        thin_state_diff: &ThinStateDiff,
+    ) -> StorageResult<Vec<ApplyConflict>> {
+        let state_marker = self.get_state_marker()?;
+        if block_number > state_marker {
+            return Err(StorageError::MarkerMismatch {
+                expected: state_marker,
+                found: block_number,
+            });
+        }
+        let state_number = StateNumber::right_before_block(block_number);
+        let state_reader = self.get_state_reader()?;
+        let is_deployed = |address: &ContractAddress| -> StorageResult<bool> {
+            Ok(thin_state_diff.deployed_contracts.contains_key(address)
+                || state_reader.is_contract_deployed(state_number, address)?)
+        };
+
+        let mut conflicts = Vec::new();
+        for address in thin_state_diff.deployed_contracts.keys() {
+            if state_reader.is_contract_deployed(state_number, address)? {
+                conflicts.push(ApplyConflict::ContractAlreadyDeployed(*address));
+            }
+        }
+        for address in thin_state_diff.replaced_classes.keys() {
+            if !is_deployed(address)? {
+                conflicts.push(ApplyConflict::ReplacedClassOfUndeployedContract(*address));
+            }
+        }
+        for address in thin_state_diff.storage_diffs.keys() {
+            if !is_deployed(address)? {
+                conflicts.push(ApplyConflict::StorageOfUndeployedContract(*address));
+            }
+        }
+        for (address, new_nonce) in &thin_state_diff.nonces {
+            if !is_deployed(address)? {
+                conflicts.push(ApplyConflict::NonceOfUndeployedContract(*address));
+                continue;
+            }
+            // A nonce that was never set can be set to any value, including its default.
+            if let Some(prior_nonce) = state_reader.get_nonce_at(state_number, address)? {
+                if *new_nonce <= prior_nonce {
+                    conflicts.push(ApplyConflict::NonceNotIncreasing {
+                        contract_address: *address,
+                        prior_nonce,
+                        new_nonce: *new_nonce,
+                    });
+                }
+            }
+        }
+        for class_hash in thin_state_diff.declared_classes.keys() {
+            if state_reader
+                .get_class_definition_block_number(class_hash)?
+                .is_some_and(|declared_block_number| declared_block_number < block_number)
+            {
+                conflicts.push(ApplyConflict::ClassAlreadyDeclared(*class_hash));
+            }
+        }
+        Ok(conflicts)
+    }
+
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>> {
         StateReader::new(self)
     }
@@ -259,6 +458,27 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         }
     }
 
+    /// Returns whether a contract is deployed at a given state number.
+    /// The block hash table address ([`BLOCK_HASH_TABLE_ADDRESS`]) is always considered deployed,
+    /// since it stores the block hashes even though no contract is deployed to it.
+    ///
+    /// # Arguments
+    /// * state_number - state number to search before.
+    /// * address - contract address to search for.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn is_contract_deployed(
+        &self,
+        state_number: StateNumber,
+        address: &ContractAddress,
+    ) -> StorageResult<bool> {
+        if *address == BLOCK_HASH_TABLE_ADDRESS {
+            return Ok(true);
+        }
+        Ok(self.get_class_hash_at(state_number, address)?.is_some())
+    }
+
     /// Returns the nonce at a given state number.
     /// If there is no nonce at the given state number, returns `None`.
     ///
@@ -484,6 +704,23 @@ impl StateStorageWriter for StorageTxn<'_, RW> {
         Ok(self)
     }
 
+    fn append_state_diff_if_absent(
+        self,
+        block_number: BlockNumber,
+        thin_state_diff: ThinStateDiff,
+    ) -> StorageResult<Self> {
+        if block_number >= self.get_state_marker()? {
+            return self.append_state_diff(block_number, thin_state_diff);
+        }
+        if self.get_state_diff(block_number)? != Some(thin_state_diff) {
+            return Err(StorageError::ConflictingBlockData {
+                block_number,
+                marker_kind: MarkerKind::State,
+            });
+        }
+        Ok(self)
+    }
+
     #[latency_histogram("storage_revert_state_diff_latency_seconds", false)]
     fn revert_state_diff(
         self,
@@ -4,18 +4,25 @@ use indexmap::{indexmap, IndexMap};
 use papyrus_test_utils::get_test_state_diff;
 use pretty_assertions::assert_eq;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    Nonce,
+    BLOCK_HASH_TABLE_ADDRESS,
+};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkHash;
 use starknet_api::state::{SierraContractClass, StateNumber, ThinStateDiff};
 use starknet_api::{class_hash, contract_address, felt, storage_key};
 use starknet_types_core::felt::Felt;
+use tempfile::TempDir;
 
 use crate::class::{ClassStorageReader, ClassStorageWriter};
 use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
-use crate::state::{StateStorageReader, StateStorageWriter};
-use crate::test_utils::get_test_storage;
-use crate::StorageWriter;
+use crate::state::{ApplyConflict, StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
+use crate::{MarkerKind, StorageError, StorageReader, StorageScope, StorageWriter};
 
 #[test]
 fn get_class_definition_at() {
@@ -146,6 +153,291 @@ fn append_state_diff_replaced_classes() {
     assert_eq!(statetxn.get_class_hash_at(state3, &contract_1).unwrap(), Some(hash_2));
 }
 
+#[test]
+fn is_contract_deployed() {
+    let contract = contract_address!("0x100");
+    let not_deployed_contract = contract_address!("0x101");
+    let diff = ThinStateDiff {
+        deployed_contracts: IndexMap::from([(contract, class_hash!("0x10"))]),
+        ..Default::default()
+    };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let before_deployment = StateNumber::right_before_block(BlockNumber(0));
+    let after_deployment = StateNumber::right_after_block(BlockNumber(0)).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+
+    assert!(!state_reader.is_contract_deployed(before_deployment, &contract).unwrap());
+    assert!(state_reader.is_contract_deployed(after_deployment, &contract).unwrap());
+    assert!(!state_reader.is_contract_deployed(after_deployment, &not_deployed_contract).unwrap());
+    // The block hash table is never deployed but it always exists.
+    assert!(
+        state_reader.is_contract_deployed(before_deployment, &BLOCK_HASH_TABLE_ADDRESS).unwrap()
+    );
+    assert!(
+        state_reader.is_contract_deployed(after_deployment, &BLOCK_HASH_TABLE_ADDRESS).unwrap()
+    );
+}
+
+#[test]
+fn get_full_state_diff() {
+    let state_diff = get_test_state_diff();
+    let (thin_state_diff, classes, deprecated_classes) =
+        ThinStateDiff::from_state_diff(state_diff.clone());
+    let classes =
+        classes.iter().map(|(class_hash, class)| (*class_hash, class)).collect::<Vec<_>>();
+    let deprecated_classes = deprecated_classes
+        .iter()
+        .map(|(class_hash, class)| (*class_hash, class))
+        .collect::<Vec<_>>();
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), thin_state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The classes of the state diff weren't synced yet.
+    let result = reader.begin_ro_txn().unwrap().get_full_state_diff(BlockNumber(0));
+    assert_matches!(
+        result,
+        Err(StorageError::MarkerMismatch { expected: BlockNumber(0), found: BlockNumber(0) })
+    );
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_classes(BlockNumber(0), &classes, &deprecated_classes)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_full_state_diff(BlockNumber(0)).unwrap(), Some(state_diff));
+    assert_eq!(txn.get_full_state_diff(BlockNumber(1)).unwrap(), None);
+}
+
+#[test]
+fn get_full_state_diff_state_only() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::from(get_test_state_diff()))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let result = reader.begin_ro_txn().unwrap().get_full_state_diff(BlockNumber(0));
+    assert_matches!(
+        result,
+        Err(StorageError::ScopeError { storage_scope: StorageScope::StateOnly, .. })
+    );
+}
+
+#[test]
+fn state_diff_hash() {
+    let state_diff = ThinStateDiff::from(get_test_state_diff());
+    let mut reordered_state_diff = state_diff.clone();
+    reordered_state_diff.deployed_contracts.reverse();
+    reordered_state_diff.nonces.reverse();
+    let mut modified_state_diff = state_diff.clone();
+    modified_state_diff.nonces.insert(contract_address!("0x1234"), Nonce(felt!("0x1")));
+
+    let mut hashes = Vec::new();
+    for diff in [state_diff, reordered_state_diff, modified_state_diff] {
+        let ((reader, mut writer), _temp_dir) = get_test_storage();
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(0), diff)
+            .unwrap()
+            .commit()
+            .unwrap();
+        let txn = reader.begin_ro_txn().unwrap();
+        assert_eq!(txn.state_diff_hash(BlockNumber(1)).unwrap(), None);
+        hashes.push(txn.state_diff_hash(BlockNumber(0)).unwrap().unwrap());
+    }
+
+    assert_eq!(hashes[0], hashes[1]);
+    assert_ne!(hashes[0], hashes[2]);
+}
+
+#[test]
+fn deployed_contracts_in_range() {
+    let c0 = contract_address!("0x100");
+    let c1 = contract_address!("0x101");
+    let c2 = contract_address!("0x102");
+    let c3 = contract_address!("0x103");
+    let diffs = [
+        ThinStateDiff {
+            deployed_contracts: IndexMap::from([
+                (c0, class_hash!("0x10")),
+                (c1, class_hash!("0x11")),
+            ]),
+            ..Default::default()
+        },
+        ThinStateDiff::default(),
+        ThinStateDiff {
+            deployed_contracts: IndexMap::from([(c2, class_hash!("0x10"))]),
+            // Class replacements aren't deployments.
+            replaced_classes: IndexMap::from([(c0, class_hash!("0x11"))]),
+            ..Default::default()
+        },
+        ThinStateDiff {
+            deployed_contracts: IndexMap::from([(c3, class_hash!("0x11"))]),
+            ..Default::default()
+        },
+    ];
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (block_number, diff) in diffs.into_iter().enumerate() {
+        txn = txn.append_state_diff(BlockNumber(block_number.try_into().unwrap()), diff).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.deployed_contracts_in_range(BlockNumber(0)..BlockNumber(4)).unwrap(),
+        vec![
+            (c0, BlockNumber(0)),
+            (c1, BlockNumber(0)),
+            (c2, BlockNumber(2)),
+            (c3, BlockNumber(3))
+        ]
+    );
+    assert_eq!(
+        txn.deployed_contracts_in_range(BlockNumber(1)..BlockNumber(3)).unwrap(),
+        vec![(c2, BlockNumber(2))]
+    );
+    assert_eq!(
+        txn.deployed_contracts_in_range(BlockNumber(3)..BlockNumber(10)).unwrap(),
+        vec![(c3, BlockNumber(3))]
+    );
+    assert!(txn.deployed_contracts_in_range(BlockNumber(1)..BlockNumber(2)).unwrap().is_empty());
+    assert!(txn.deployed_contracts_in_range(BlockNumber(4)..BlockNumber(10)).unwrap().is_empty());
+}
+
+fn get_storage_with_deployed_contract(
+    contract: ContractAddress,
+    nonce: Nonce,
+) -> ((StorageReader, StorageWriter), TempDir) {
+    let ((reader, mut writer), temp_dir) = get_test_storage();
+    let diff = ThinStateDiff {
+        deployed_contracts: IndexMap::from([(contract, class_hash!("0x10"))]),
+        declared_classes: IndexMap::from([(class_hash!("0x10"), CompiledClassHash::default())]),
+        nonces: IndexMap::from([(contract, nonce)]),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+    ((reader, writer), temp_dir)
+}
+
+#[test]
+fn validate_state_diff_applies_clean_diff() {
+    let contract = contract_address!("0x100");
+    let new_contract = contract_address!("0x101");
+    let ((reader, _writer), _temp_dir) =
+        get_storage_with_deployed_contract(contract, Nonce(felt!("0x2")));
+
+    let diff = ThinStateDiff {
+        deployed_contracts: IndexMap::from([(new_contract, class_hash!("0x11"))]),
+        declared_classes: IndexMap::from([(class_hash!("0x11"), CompiledClassHash::default())]),
+        storage_diffs: IndexMap::from([
+            (contract, IndexMap::from([(storage_key!("0x1"), felt!("0x1"))])),
+            (new_contract, IndexMap::from([(storage_key!("0x1"), felt!("0x2"))])),
+        ]),
+        nonces: IndexMap::from([
+            (contract, Nonce(felt!("0x4"))),
+            (new_contract, Nonce(felt!("0x1"))),
+        ]),
+        replaced_classes: IndexMap::from([(contract, class_hash!("0x11"))]),
+        ..Default::default()
+    };
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.validate_state_diff_applies(BlockNumber(1), &diff).unwrap(), vec![]);
+    // The state diff can't be validated on top of a state we don't have yet.
+    assert_matches!(
+        txn.validate_state_diff_applies(BlockNumber(2), &diff),
+        Err(StorageError::MarkerMismatch { expected: BlockNumber(1), found: BlockNumber(2) })
+    );
+}
+
+#[test]
+fn validate_state_diff_applies_conflicts() {
+    let contract = contract_address!("0x100");
+    let undeployed_contract = contract_address!("0x101");
+    let prior_nonce = Nonce(felt!("0x2"));
+    let ((reader, _writer), _temp_dir) = get_storage_with_deployed_contract(contract, prior_nonce);
+
+    let nonce_regression_diff = ThinStateDiff {
+        nonces: IndexMap::from([(contract, Nonce(felt!("0x1")))]),
+        ..Default::default()
+    };
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.validate_state_diff_applies(BlockNumber(1), &nonce_regression_diff).unwrap(),
+        vec![ApplyConflict::NonceNotIncreasing {
+            contract_address: contract,
+            prior_nonce,
+            new_nonce: Nonce(felt!("0x1")),
+        }]
+    );
+
+    let conflicting_diff = ThinStateDiff {
+        deployed_contracts: IndexMap::from([(contract, class_hash!("0x10"))]),
+        declared_classes: IndexMap::from([(class_hash!("0x10"), CompiledClassHash::default())]),
+        storage_diffs: IndexMap::from([(
+            undeployed_contract,
+            IndexMap::from([(storage_key!("0x1"), felt!("0x1"))]),
+        )]),
+        nonces: IndexMap::from([
+            (contract, prior_nonce),
+            (undeployed_contract, Nonce(felt!("0x1"))),
+        ]),
+        replaced_classes: IndexMap::from([(undeployed_contract, class_hash!("0x10"))]),
+        ..Default::default()
+    };
+    assert_eq!(
+        txn.validate_state_diff_applies(BlockNumber(1), &conflicting_diff).unwrap(),
+        vec![
+            ApplyConflict::ContractAlreadyDeployed(contract),
+            ApplyConflict::ReplacedClassOfUndeployedContract(undeployed_contract),
+            ApplyConflict::StorageOfUndeployedContract(undeployed_contract),
+            ApplyConflict::NonceNotIncreasing {
+                contract_address: contract,
+                prior_nonce,
+                new_nonce: prior_nonce,
+            },
+            ApplyConflict::NonceOfUndeployedContract(undeployed_contract),
+            ApplyConflict::ClassAlreadyDeclared(class_hash!("0x10")),
+        ]
+    );
+    // Validating the stored state diff against the state before it finds no conflicts.
+    let stored_diff = txn.get_state_diff(BlockNumber(0)).unwrap().unwrap();
+    assert_eq!(txn.validate_state_diff_applies(BlockNumber(0), &stored_diff).unwrap(), vec![]);
+}
+
 #[test]
 fn append_state_diff() {
     let c0 = contract_address!("0x11");
@@ -789,3 +1081,40 @@ fn declare_revert_declare_scenario() {
             .is_some()
     );
 }
+
+#[test]
+fn append_state_diff_if_absent() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let state_diff = ThinStateDiff::from(get_test_state_diff());
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff_if_absent(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Re-appending an identical state diff is skipped.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff_if_absent(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff));
+
+    let result = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff_if_absent(BlockNumber(0), ThinStateDiff::default());
+    assert_matches!(
+        result,
+        Err(StorageError::ConflictingBlockData {
+            block_number: BlockNumber(0),
+            marker_kind: MarkerKind::State
+        })
+    );
+}
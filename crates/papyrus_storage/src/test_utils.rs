@@ -7,7 +7,7 @@ use starknet_api::core::ChainId;
 use tempfile::{tempdir, TempDir};
 
 use crate::db::DbConfig;
-use crate::mmap_file::MmapFileConfig;
+use crate::mmap_file::{CompressionLevels, MmapFileConfig};
 use crate::{open_storage, StorageConfig, StorageReader, StorageScope, StorageWriter};
 
 /// A chain id for tests.
@@ -32,6 +32,7 @@ pub(crate) fn get_test_config(storage_scope: Option<StorageScope>) -> (StorageCo
                 min_size: 1 << 20,    // 1MB
                 max_size: 1 << 35,    // 32GB
                 growth_step: 1 << 26, // 64MB
+                max_readers: 1 << 13, // 8K readers
             },
             scope: storage_scope,
             mmap_file_config: get_mmap_file_test_config(),
@@ -53,6 +54,7 @@ pub fn get_mmap_file_test_config() -> MmapFileConfig {
         max_size: 1 << 24,        // 16MB
         growth_step: 1 << 20,     // 1MB
         max_object_size: 1 << 16, // 64KB
+        compression_levels: CompressionLevels::default(),
     }
 }
 
@@ -6,8 +6,8 @@ use crate::db::table_types::Table;
 use crate::db::{TransactionKind, RW};
 use crate::{StorageError, StorageResult, StorageTxn};
 
-const VERSION_STATE_KEY: &str = "storage_version_state";
-const VERSION_BLOCKS_KEY: &str = "storage_version_blocks";
+pub(crate) const VERSION_STATE_KEY: &str = "storage_version_state";
+pub(crate) const VERSION_BLOCKS_KEY: &str = "storage_version_blocks";
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Version {
@@ -17,12 +17,15 @@ use crate::version::{
     VERSION_STATE_KEY,
 };
 use crate::{
+    check_storage_compatibility,
+    check_storage_compatibility_with_crate_versions,
     open_storage,
     set_version_if_needed,
     verify_storage_version,
     StorageError,
     StorageScope,
     StorageWriter,
+    VersionCompatibility,
     STORAGE_VERSION_BLOCKS,
     STORAGE_VERSION_STATE,
 };
@@ -201,6 +204,88 @@ fn open_storage_state_only_different_blocks_major_versions() {
     assert!(open_storage(config.clone()).is_ok());
 }
 
+#[test]
+fn check_storage_compatibility_compatible() {
+    let ((reader, writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    drop(reader);
+    drop(writer);
+
+    let report = check_storage_compatibility(&config).unwrap();
+    assert_eq!(report.state_version, Some(STORAGE_VERSION_STATE));
+    assert_eq!(report.blocks_version, Some(STORAGE_VERSION_BLOCKS));
+    assert_eq!(report.compatibility, VersionCompatibility::Compatible);
+
+    // A storage that doesn't exist yet will be initialized with the crate version.
+    let mut missing_storage_config = config.clone();
+    missing_storage_config.db_config.path_prefix =
+        config.db_config.path_prefix.join("missing_storage");
+    let report = check_storage_compatibility(&missing_storage_config).unwrap();
+    assert_eq!(report.state_version, None);
+    assert_eq!(report.compatibility, VersionCompatibility::Compatible);
+    assert!(!missing_storage_config.db_config.path().exists());
+}
+
+#[test]
+fn check_storage_compatibility_minor_migratable() {
+    let ((reader, writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    drop(reader);
+    drop(writer);
+
+    // Check the storage against crate versions with a higher minor.
+    let higher_minor_state_version =
+        Version { major: STORAGE_VERSION_STATE.major, minor: STORAGE_VERSION_STATE.minor + 1 };
+    let higher_minor_blocks_version =
+        Version { major: STORAGE_VERSION_BLOCKS.major, minor: STORAGE_VERSION_BLOCKS.minor + 1 };
+    let report = check_storage_compatibility_with_crate_versions(
+        &config,
+        &higher_minor_state_version,
+        &higher_minor_blocks_version,
+    )
+    .unwrap();
+    assert_eq!(report.state_version, Some(STORAGE_VERSION_STATE));
+    assert_eq!(report.blocks_version, Some(STORAGE_VERSION_BLOCKS));
+    assert_eq!(report.compatibility, VersionCompatibility::Migratable);
+
+    // The check doesn't migrate the storage.
+    let (reader, _) = open_storage(config).unwrap();
+    let version_state = reader.begin_ro_txn().unwrap().get_state_version().unwrap();
+    assert_eq!(version_state.unwrap(), STORAGE_VERSION_STATE);
+    let version_blocks = reader.begin_ro_txn().unwrap().get_blocks_version().unwrap();
+    assert_eq!(version_blocks.unwrap(), STORAGE_VERSION_BLOCKS);
+}
+
+#[test]
+fn check_storage_compatibility_major_incompatible() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let different_blocks_major_version = get_different_major_version(STORAGE_VERSION_BLOCKS);
+    change_storage_version(&mut writer, VERSION_BLOCKS_KEY, &different_blocks_major_version);
+    drop(reader);
+    drop(writer);
+
+    let report = check_storage_compatibility(&config).unwrap();
+    assert_eq!(report.state_version, Some(STORAGE_VERSION_STATE));
+    assert_eq!(report.blocks_version, Some(different_blocks_major_version.clone()));
+    assert_eq!(report.compatibility, VersionCompatibility::RequiresResync);
+
+    // The blocks version is irrelevant in state-only mode.
+    let mut state_only_config = config.clone();
+    state_only_config.scope = StorageScope::StateOnly;
+    let report = check_storage_compatibility(&state_only_config).unwrap();
+    assert_eq!(report.compatibility, VersionCompatibility::Compatible);
+
+    // The check doesn't modify the storage.
+    assert_matches!(
+        open_storage(config),
+        Err(StorageError::StorageVersionInconsistency(
+            StorageVersionError::InconsistentStorageVersion { crate_version, storage_version }
+        ))
+        if crate_version == STORAGE_VERSION_BLOCKS && storage_version == different_blocks_major_version
+    );
+}
+
 // Changes the storage version with version_key to the given version.
 fn change_storage_version(writer: &mut StorageWriter, version_key: &str, version: &Version) {
     let wtxn = writer.begin_rw_txn().unwrap();
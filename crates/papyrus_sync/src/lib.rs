@@ -10,6 +10,7 @@ pub mod sources;
 
 use std::cmp::min;
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -18,29 +19,43 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use chrono::{TimeZone, Utc};
 use futures_util::{pin_mut, select, Stream, StreamExt};
 use indexmap::IndexMap;
+use papyrus_common::class_hash::calculate_class_hash;
 use papyrus_common::metrics as papyrus_metrics;
 use papyrus_common::pending_classes::PendingClasses;
-use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::converters::{
+    deserialize_optional_seconds_to_duration,
+    deserialize_seconds_to_duration,
+};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
-use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
-use papyrus_storage::db::DbError;
+use papyrus_storage::db::{DbError, RW};
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::metadata::{MetadataStorageReader, MetadataStorageWriter};
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
-use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_storage::{StorageError, StorageReader, StorageResult, StorageTxn, StorageWriter};
 use serde::{Deserialize, Serialize};
 use sources::base_layer::BaseLayerSourceError;
-use starknet_api::block::{Block, BlockHash, BlockHashAndNumber, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
+use starknet_api::block::{
+    verify_block_signature,
+    Block,
+    BlockHash,
+    BlockHashAndNumber,
+    BlockNumber,
+    BlockSignature,
+    BlockTimestamp,
+};
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot, SequencerPublicKey};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{StateDiff, ThinStateDiff};
 use starknet_client::reader::PendingData;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, trace, warn};
+use validator::{Validate, ValidationError};
 
 use crate::pending_sync::sync_pending_data;
 use crate::sources::base_layer::{BaseLayerSourceTrait, EthereumBaseLayerSource};
@@ -58,24 +73,44 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
+// Sleep duration between checks of whether a paused sync was resumed.
+const PAUSED_SLEEP_DURATION: Duration = Duration::from_millis(100);
+const DEFAULT_BLOCK_PROPAGATION_SLEEP_DURATION: Duration = Duration::from_secs(2);
+// The metadata key of the block from which the last forced resync reverted the storage, so that
+// it isn't reverted again on every restart.
+const FORCED_RESYNC_FROM_METADATA_KEY: &str = "sync_forced_resync_from";
+// The value dumped for checkpoint_interval when it's unset.
+const DEFAULT_CHECKPOINT_INTERVAL: u64 = 1000;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Validate)]
+#[validate(schema(function = "validate_sync_config"))]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub block_propagation_sleep_duration: Duration,
+    #[serde(deserialize_with = "deserialize_optional_seconds_to_duration")]
+    pub state_diff_propagation_sleep_duration: Option<Duration>,
+    #[serde(deserialize_with = "deserialize_optional_seconds_to_duration")]
+    pub compiled_class_propagation_sleep_duration: Option<Duration>,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub base_layer_propagation_sleep_duration: Duration,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub recoverable_error_sleep_duration: Duration,
+    #[validate(range(min = 1))]
     pub blocks_max_stream_size: u32,
+    #[validate(range(min = 1))]
     pub state_updates_max_stream_size: u32,
     pub verify_blocks: bool,
     pub collect_pending_data: bool,
+    pub force_resync_from: Option<BlockNumber>,
+    #[validate(range(min = 1))]
+    pub checkpoint_interval: Option<u64>,
+    pub allow_reverts: bool,
+    pub atomic_block_and_state: bool,
 }
 
 impl SerializeConfig for SyncConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_param(
                 "block_propagation_sleep_duration",
                 &self.block_propagation_sleep_duration.as_secs(),
@@ -119,20 +154,87 @@ impl SerializeConfig for SyncConfig {
                 "Whether to collect data on pending blocks.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "allow_reverts",
+                &self.allow_reverts,
+                "Whether to revert blocks that are no longer in the central chain. If false, sync \
+                 fails instead of deleting their data.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "atomic_block_and_state",
+                &self.atomic_block_and_state,
+                "Whether to store each block together with its state diff in a single storage \
+                 transaction, so a crash never leaves a block without its state diff.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_param(
+            &self.state_diff_propagation_sleep_duration.map(|duration| duration.as_secs()),
+            DEFAULT_BLOCK_PROPAGATION_SLEEP_DURATION.as_secs(),
+            "state_diff_propagation_sleep_duration",
+            "Time in seconds before checking for a new state diff after the state diffs are \
+             synchronized with the downloaded blocks. If unset, block_propagation_sleep_duration \
+             is used.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_param(
+            &self.compiled_class_propagation_sleep_duration.map(|duration| duration.as_secs()),
+            DEFAULT_BLOCK_PROPAGATION_SLEEP_DURATION.as_secs(),
+            "compiled_class_propagation_sleep_duration",
+            "Time in seconds before checking for new compiled classes after the compiled classes \
+             are synchronized with the downloaded state diffs. If unset, \
+             block_propagation_sleep_duration is used.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_param(
+            &self.force_resync_from,
+            BlockNumber(0),
+            "force_resync_from",
+            "If set, all the blocks starting from this block are reverted on startup and synced \
+             again. The blocks are reverted only once for each value, so restarting with the same \
+             value doesn't revert them again. Intended for debugging and reprocessing a range of \
+             blocks.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(ser_optional_param(
+            &self.checkpoint_interval,
+            DEFAULT_CHECKPOINT_INTERVAL,
+            "checkpoint_interval",
+            "If set, whenever both the block and the state diff of a block whose number is a \
+             multiple of this interval are stored, all the synced data is flushed to the disk and \
+             a checkpoint is reported for that block.",
+            ParamPrivacyInput::Public,
+        ));
+        config
     }
 }
 
+fn validate_sync_config(config: &SyncConfig) -> Result<(), ValidationError> {
+    if config.force_resync_from.is_some() && !config.allow_reverts {
+        return Err(ValidationError::new(
+            "force_resync_from reverts blocks, so it can't be set when allow_reverts is false",
+        ));
+    }
+    Ok(())
+}
+
 impl Default for SyncConfig {
     fn default() -> Self {
         SyncConfig {
-            block_propagation_sleep_duration: Duration::from_secs(2),
+            block_propagation_sleep_duration: DEFAULT_BLOCK_PROPAGATION_SLEEP_DURATION,
+            state_diff_propagation_sleep_duration: None,
+            compiled_class_propagation_sleep_duration: None,
             base_layer_propagation_sleep_duration: Duration::from_secs(10),
             recoverable_error_sleep_duration: Duration::from_secs(3),
             blocks_max_stream_size: 1000,
             state_updates_max_stream_size: 1000,
             verify_blocks: true,
             collect_pending_data: false,
+            force_resync_from: None,
+            checkpoint_interval: None,
+            allow_reverts: true,
+            atomic_block_and_state: false,
         }
     }
 }
@@ -154,6 +256,56 @@ pub struct GenericStateSync<
     reader: StorageReader,
     writer: StorageWriter,
     sequencer_pub_key: Option<SequencerPublicKey>,
+    pause_control: SyncPauseControl,
+    checkpoint_callback: Option<CheckpointCallback>,
+}
+
+/// Called with the block number of each checkpoint the sync reaches. See
+/// [`SyncConfig::checkpoint_interval`].
+pub type CheckpointCallback = Box<dyn Fn(BlockNumber) + Send + Sync>;
+
+/// Pauses and resumes the sync from outside of it. While paused, the sync stops storing new data
+/// but keeps its streams open, so it continues from the same point once resumed.
+#[derive(Clone, Debug, Default)]
+pub struct SyncPauseControl {
+    paused: Arc<AtomicBool>,
+    // The number of times the sync was paused. Used to tell whether the sync was paused during a
+    // period of time.
+    n_pauses: Arc<AtomicU64>,
+}
+
+impl SyncPauseControl {
+    /// Pauses the sync. The sync stops after it finishes handling the current event.
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::SeqCst) {
+            self.n_pauses.fetch_add(1, Ordering::SeqCst);
+            metrics::gauge!(papyrus_metrics::PAPYRUS_SYNC_PAUSED, 1.0);
+            info!("Sync paused.");
+        }
+    }
+
+    /// Resumes a paused sync.
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            metrics::gauge!(papyrus_metrics::PAPYRUS_SYNC_PAUSED, 0.0);
+            info!("Sync resumed.");
+        }
+    }
+
+    /// Returns whether the sync is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    fn n_pauses(&self) -> u64 {
+        self.n_pauses.load(Ordering::SeqCst)
+    }
+
+    async fn wait_while_paused(&self) {
+        while self.is_paused() {
+            tokio::time::sleep(PAUSED_SLEEP_DURATION).await;
+        }
+    }
 }
 
 pub type StateSyncResult = Result<(), StateSyncError>;
@@ -194,6 +346,42 @@ pub enum StateSyncError {
     },
     #[error("Sequencer public key changed from {old:?} to {new:?}.")]
     SequencerPubKeyChanged { old: SequencerPublicKey, new: SequencerPublicKey },
+    #[error(
+        "Compiled class hash of class {class_hash} doesn't match its CASM. Expected {expected:?}, \
+         calculated {calculated:?}."
+    )]
+    CasmHashMismatch {
+        class_hash: ClassHash,
+        expected: CompiledClassHash,
+        calculated: CompiledClassHash,
+    },
+    #[error(
+        "Block {block_number} is no longer in the central chain and needs to be reverted, but \
+         reverts are not allowed."
+    )]
+    RevertNotAllowed { block_number: BlockNumber },
+    #[error(
+        "Signature {signature:?} of block {block_number} wasn't signed by the sequencer public \
+         key {sequencer_pub_key:?}."
+    )]
+    InvalidBlockSignature {
+        block_number: BlockNumber,
+        signature: BlockSignature,
+        sequencer_pub_key: SequencerPublicKey,
+    },
+    #[error(
+        "State diff of block {block_number} belongs to block hash {state_diff_block_hash}, while \
+         the downloaded block hash is {block_hash}."
+    )]
+    StateDiffBlockHashMismatch {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        state_diff_block_hash: BlockHash,
+    },
+    #[error(
+        "Class {class_hash} is declared inconsistently in the state diff of block {block_number}."
+    )]
+    StateDiffClassMismatch { block_number: BlockNumber, class_hash: ClassHash },
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -232,8 +420,22 @@ impl<
     TBaseLayerSource: BaseLayerSourceTrait + Sync + Send,
 > GenericStateSync<TCentralSource, TPendingSource, TBaseLayerSource>
 {
+    /// Sets a callback that is called every time the sync reaches a checkpoint, with the block
+    /// number of the checkpoint. When it's called, the headers, bodies and state diffs of all the
+    /// blocks up to and including that block are flushed to the disk, so it's safe to snapshot
+    /// the storage. Has no effect unless `checkpoint_interval` is set.
+    pub fn set_checkpoint_callback(
+        &mut self,
+        callback: impl Fn(BlockNumber) + Send + Sync + 'static,
+    ) {
+        self.checkpoint_callback = Some(Box::new(callback));
+    }
+
     pub async fn run(mut self) -> StateSyncResult {
         info!("State sync started.");
+        if let Some(block_number) = self.config.force_resync_from {
+            self.revert_blocks_from(block_number)?;
+        }
         loop {
             match self.sync_while_ok().await {
                 // A recoverable error occurred. Sleep and try syncing again.
@@ -267,8 +469,13 @@ impl<
                 | StateSyncError::BaseLayerSourceError(_)
                 | StateSyncError::ParentBlockHashMismatch { .. }
                 | StateSyncError::BaseLayerHashMismatch { .. }
-                | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
-                StateSyncError::SequencerPubKeyChanged { .. } => false,
+                | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. }
+                | StateSyncError::InvalidBlockSignature { .. }
+                | StateSyncError::StateDiffBlockHashMismatch { .. }
+                | StateSyncError::StateDiffClassMismatch { .. } => true,
+                StateSyncError::SequencerPubKeyChanged { .. }
+                | StateSyncError::CasmHashMismatch { .. }
+                | StateSyncError::RevertNotAllowed { .. } => false,
             }
         }
     }
@@ -320,21 +527,8 @@ impl<
             self.config.blocks_max_stream_size,
         )
         .fuse();
-        let state_diff_stream = stream_new_state_diffs(
-            self.reader.clone(),
-            self.central_source.clone(),
-            self.config.block_propagation_sleep_duration,
-            self.config.state_updates_max_stream_size,
-        )
-        .fuse();
-        let compiled_class_stream = stream_new_compiled_classes(
-            self.reader.clone(),
-            self.central_source.clone(),
-            self.config.block_propagation_sleep_duration,
-            // TODO(yair): separate config param.
-            self.config.state_updates_max_stream_size,
-        )
-        .fuse();
+        let state_diff_stream = self.state_diff_stream().fuse();
+        let compiled_class_stream = self.compiled_class_stream().fuse();
         let base_layer_block_stream = stream_new_base_layer_block(
             self.reader.clone(),
             self.base_layer_source.clone(),
@@ -343,7 +537,8 @@ impl<
         .fuse();
         // TODO(dvir): try use interval instead of stream.
         // TODO: fix the bug and remove this check.
-        let check_sync_progress = check_sync_progress(self.reader.clone()).fuse();
+        let check_sync_progress =
+            check_sync_progress(self.reader.clone(), self.pause_control.clone()).fuse();
         pin_mut!(
             block_stream,
             state_diff_stream,
@@ -353,6 +548,7 @@ impl<
         );
 
         loop {
+            self.pause_control.wait_while_paused().await;
             debug!("Selecting between block sync and state diff sync.");
             let sync_event = select! {
               res = block_stream.next() => res,
@@ -369,9 +565,37 @@ impl<
         unreachable!("Fetching data loop should never return.");
     }
 
+    fn state_diff_stream(&self) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+        stream_new_state_diffs(
+            self.reader.clone(),
+            self.central_source.clone(),
+            self.config
+                .state_diff_propagation_sleep_duration
+                .unwrap_or(self.config.block_propagation_sleep_duration),
+            self.config.state_updates_max_stream_size,
+        )
+    }
+
+    fn compiled_class_stream(&self) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+        stream_new_compiled_classes(
+            self.reader.clone(),
+            self.central_source.clone(),
+            self.config
+                .compiled_class_propagation_sleep_duration
+                .unwrap_or(self.config.block_propagation_sleep_duration),
+            // TODO(yair): separate config param.
+            self.config.state_updates_max_stream_size,
+        )
+    }
+
     // Tries to store the incoming data.
     async fn process_sync_event(&mut self, sync_event: SyncEvent) -> StateSyncResult {
         match sync_event {
+            SyncEvent::BlockAvailable { block_number, block, signature }
+                if self.config.atomic_block_and_state =>
+            {
+                self.store_block_with_state_diff(block_number, block, &signature).await
+            }
             SyncEvent::BlockAvailable { block_number, block, signature } => {
                 self.store_block(block_number, block, &signature)
             }
@@ -405,78 +629,96 @@ impl<
         fields(block_hash = format_args!("{:#064x}", block.header.block_hash.0)),
         err
     )]
-    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
     fn store_block(
         &mut self,
         block_number: BlockNumber,
         block: Block,
         signature: &BlockSignature,
     ) -> StateSyncResult {
-        // Assuming the central source is trusted, detect reverts by comparing the incoming block's
-        // parent hash to the current hash.
-        self.verify_parent_block_hash(block_number, &block)?;
+        self.verify_block(block_number, &block, signature)?;
 
         debug!("Storing block.");
         trace!("Block data: {block:#?}, signature: {signature:?}");
-        self.writer
-            .begin_rw_txn()?
-            .append_header(block_number, &block.header)?
-            .append_block_signature(block_number, signature)?
-            .append_body(block_number, block.body)?
-            .commit()?;
-        metrics::gauge!(
-            papyrus_metrics::PAPYRUS_HEADER_MARKER,
-            block_number.unchecked_next().0 as f64
-        );
-        metrics::gauge!(
-            papyrus_metrics::PAPYRUS_BODY_MARKER,
-            block_number.unchecked_next().0 as f64
-        );
-        let time_delta = Utc::now()
-            - Utc
-                .timestamp_opt(block.header.block_header_without_hash.timestamp.0 as i64, 0)
-                .single()
-                .expect("block timestamp should be valid");
-        let header_latency = time_delta.num_seconds();
-        debug!("Header latency: {}.", header_latency);
-        if header_latency >= 0 {
-            metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_LATENCY_SEC, header_latency as f64);
-        }
-        Ok(())
+        let timestamp = block.header.block_header_without_hash.timestamp;
+        append_block(self.writer.begin_rw_txn()?, block_number, block, signature)?.commit()?;
+        report_stored_block(block_number, timestamp);
+        self.report_checkpoint_if_reached(block_number)
     }
 
-    #[latency_histogram("sync_store_state_diff_latency_seconds", false)]
-    #[instrument(skip(self, state_diff, deployed_contract_class_definitions), level = "debug", err)]
-    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
-    fn store_state_diff(
+    // Stores the block together with its state diff in a single transaction. The state diff is
+    // downloaded right away instead of waiting for the state diff stream, which only handles state
+    // diffs of stored blocks.
+    #[instrument(
+        skip(self, block),
+        level = "debug",
+        fields(block_hash = format_args!("{:#064x}", block.header.block_hash.0)),
+        err
+    )]
+    async fn store_block_with_state_diff(
         &mut self,
         block_number: BlockNumber,
-        block_hash: BlockHash,
-        state_diff: StateDiff,
-        deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+        block: Block,
+        signature: &BlockSignature,
     ) -> StateSyncResult {
-        // TODO(dan): verifications - verify state diff against stored header.
-        debug!("Storing state diff.");
-        trace!("StateDiff data: {state_diff:#?}");
+        // The state diffs of blocks that were stored before this mode was turned on are still
+        // synced by the state diff stream.
+        if self.reader.begin_ro_txn()?.get_state_marker()? != block_number {
+            return self.store_block(block_number, block, signature);
+        }
+        self.verify_block(block_number, &block, signature)?;
 
-        // TODO(shahak): split the state diff stream to 2 separate streams for blocks and for
-        // classes.
-        let (thin_state_diff, classes, deprecated_classes) =
-            ThinStateDiff::from_state_diff(state_diff);
-        self.writer
-            .begin_rw_txn()?
-            .append_state_diff(block_number, thin_state_diff)?
-            .append_classes(
+        let (state_diff_block_number, state_diff_block_hash, mut state_diff, deployed_classes) =
+            self.central_source
+                .stream_state_updates(block_number, block_number.unchecked_next())
+                .next()
+                .await
+                .ok_or(CentralError::StateUpdateNotFound)??;
+        if state_diff_block_number != block_number {
+            return Err(CentralError::StateUpdateNotFound.into());
+        }
+        let block_hash = block.header.block_hash;
+        if state_diff_block_hash != block_hash {
+            return Err(StateSyncError::StateDiffBlockHashMismatch {
                 block_number,
-                &classes.iter().map(|(class_hash, class)| (*class_hash, class)).collect::<Vec<_>>(),
-                &deprecated_classes
-                    .iter()
-                    .chain(deployed_contract_class_definitions.iter())
-                    .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
-                    .collect::<Vec<_>>(),
-            )?
-            .commit()?;
+                block_hash,
+                state_diff_block_hash,
+            });
+        }
+        if self.config.verify_blocks {
+            verify_state_diff_declared_classes(&self.reader, block_number, &state_diff)?;
+        }
+        sort_state_diff(&mut state_diff);
+
+        debug!("Storing block and state diff.");
+        trace!("Block data: {block:#?}, signature: {signature:?}, StateDiff data: {state_diff:#?}");
+        let timestamp = block.header.block_header_without_hash.timestamp;
+        let txn = append_block(self.writer.begin_rw_txn()?, block_number, block, signature)?;
+        append_state_diff(txn, block_number, state_diff, deployed_classes)?.commit()?;
+        report_stored_block(block_number, timestamp);
+        self.report_stored_state_diff(block_number, block_hash)
+    }
 
+    // Assuming the central source is trusted, detect reverts by comparing the incoming block's
+    // parent hash to the current hash.
+    fn verify_block(
+        &self,
+        block_number: BlockNumber,
+        block: &Block,
+        signature: &BlockSignature,
+    ) -> StateSyncResult {
+        self.verify_parent_block_hash(block_number, block)?;
+        if self.config.verify_blocks {
+            self.verify_block_signature(block_number, block, signature)?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+    fn report_stored_state_diff(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    ) -> StateSyncResult {
         metrics::gauge!(
             papyrus_metrics::PAPYRUS_STATE_MARKER,
             block_number.unchecked_next().0 as f64
@@ -490,9 +732,55 @@ impl<
         // Info the user on syncing the block once all the data is stored.
         info!("Added block {} with hash {:#064x}.", block_number, block_hash.0);
 
+        self.report_checkpoint_if_reached(block_number)
+    }
+
+    // Called after a part of the given block is stored. If it's a checkpoint block and that part
+    // was the last one missing, flushes the storage and reports the checkpoint. The block and the
+    // state diff are stored by different streams, and a block is complete once both the body
+    // marker and the state marker are past it, so each checkpoint is reported exactly once.
+    fn report_checkpoint_if_reached(&self, block_number: BlockNumber) -> StateSyncResult {
+        if !self.config.checkpoint_interval.is_some_and(|interval| block_number.0 % interval == 0) {
+            return Ok(());
+        }
+        let txn = self.reader.begin_ro_txn()?;
+        if txn.get_body_marker()? <= block_number || txn.get_state_marker()? <= block_number {
+            return Ok(());
+        }
+        self.writer.sync_all()?;
+        info!("Checkpoint reached at block {block_number}.");
+        if let Some(checkpoint_callback) = &self.checkpoint_callback {
+            checkpoint_callback(block_number);
+        }
         Ok(())
     }
 
+    #[latency_histogram("sync_store_state_diff_latency_seconds", false)]
+    #[instrument(skip(self, state_diff, deployed_contract_class_definitions), level = "debug", err)]
+    fn store_state_diff(
+        &mut self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        state_diff: StateDiff,
+        deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+    ) -> StateSyncResult {
+        // TODO(dan): verifications - verify state diff against stored header.
+        if self.config.verify_blocks {
+            verify_state_diff_declared_classes(&self.reader, block_number, &state_diff)?;
+        }
+        debug!("Storing state diff.");
+        trace!("StateDiff data: {state_diff:#?}");
+
+        append_state_diff(
+            self.writer.begin_rw_txn()?,
+            block_number,
+            state_diff,
+            deployed_contract_class_definitions,
+        )?
+        .commit()?;
+        self.report_stored_state_diff(block_number, block_hash)
+    }
+
     #[latency_histogram("sync_store_compiled_class_latency_seconds", false)]
     #[instrument(skip(self, compiled_class), level = "debug", err)]
     fn store_compiled_class(
@@ -501,8 +789,17 @@ impl<
         compiled_class_hash: CompiledClassHash,
         compiled_class: CasmContractClass,
     ) -> StateSyncResult {
+        if self.config.verify_blocks {
+            let calculated = CompiledClassHash(compiled_class.compiled_class_hash());
+            if calculated != compiled_class_hash {
+                return Err(StateSyncError::CasmHashMismatch {
+                    class_hash,
+                    expected: compiled_class_hash,
+                    calculated,
+                });
+            }
+        }
         let txn = self.writer.begin_rw_txn()?;
-        // TODO: verifications - verify casm corresponds to a class on storage.
         match txn.append_casm(&class_hash, &compiled_class) {
             #[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
             Ok(txn) => {
@@ -562,6 +859,35 @@ impl<
         Ok(())
     }
 
+    // Verifies the block was signed by the sequencer public key tracked by the sync. Skipped if the
+    // key isn't known yet or if the block has no state diff commitment to verify against.
+    fn verify_block_signature(
+        &self,
+        block_number: BlockNumber,
+        block: &Block,
+        signature: &BlockSignature,
+    ) -> StateSyncResult {
+        let (Some(sequencer_pub_key), Some(state_diff_commitment)) =
+            (self.sequencer_pub_key, block.header.state_diff_commitment)
+        else {
+            return Ok(());
+        };
+        let is_valid = verify_block_signature(
+            &sequencer_pub_key,
+            signature,
+            &GlobalRoot(state_diff_commitment.0.0),
+            &block.header.block_hash,
+        );
+        if !matches!(is_valid, Ok(true)) {
+            return Err(StateSyncError::InvalidBlockSignature {
+                block_number,
+                signature: *signature,
+                sequencer_pub_key,
+            });
+        }
+        Ok(())
+    }
+
     // Compares the block's parent hash to the stored block.
     fn verify_parent_block_hash(
         &self,
@@ -610,6 +936,9 @@ impl<
         let mut last_block_in_storage = header_marker.prev();
         while let Some(block_number) = last_block_in_storage {
             if self.should_revert_block(block_number).await? {
+                if !self.config.allow_reverts {
+                    return Err(StateSyncError::RevertNotAllowed { block_number });
+                }
                 self.revert_block(block_number)?;
                 last_block_in_storage = block_number.prev();
             } else {
@@ -619,6 +948,41 @@ impl<
         Ok(())
     }
 
+    // Reverts all the blocks starting from the given block, so that they are synced again. Does
+    // nothing if the storage was already reverted from this block, e.g. before a restart.
+    fn revert_blocks_from(&mut self, block_number: BlockNumber) -> StateSyncResult {
+        let txn = self.reader.begin_ro_txn()?;
+        let already_reverted = txn.get_metadata(FORCED_RESYNC_FROM_METADATA_KEY)?
+            == Some(block_number.0.to_be_bytes().to_vec());
+        let header_marker = txn.get_header_marker()?;
+        drop(txn);
+        if already_reverted {
+            info!("Already resynced from block {block_number}, not reverting again.");
+            return Ok(());
+        }
+
+        // The header marker is the highest marker, so there's nothing to revert if it isn't
+        // beyond the block.
+        if header_marker > block_number {
+            warn!(
+                "Forcing a resync from block {block_number}. Reverting blocks [{block_number}, \
+                 {header_marker})."
+            );
+            let mut last_block_in_storage = header_marker.prev();
+            while let Some(current_block_number) = last_block_in_storage
+                .filter(|current_block_number| *current_block_number >= block_number)
+            {
+                self.revert_block(current_block_number)?;
+                last_block_in_storage = current_block_number.prev();
+            }
+        }
+        self.writer
+            .begin_rw_txn()?
+            .set_metadata(FORCED_RESYNC_FROM_METADATA_KEY, &block_number.0.to_be_bytes())?
+            .commit()?;
+        Ok(())
+    }
+
     // TODO(dan): update necessary metrics.
     // Deletes the block data from the storage.
     #[allow(clippy::expect_fun_call)]
@@ -665,6 +1029,54 @@ impl<
         }
     }
 }
+
+fn append_block<'env>(
+    txn: StorageTxn<'env, RW>,
+    block_number: BlockNumber,
+    block: Block,
+    signature: &BlockSignature,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    txn.append_header(block_number, &block.header)?
+        .append_block_signature(block_number, signature)?
+        .append_body(block_number, block.body)
+}
+
+fn append_state_diff<'env>(
+    txn: StorageTxn<'env, RW>,
+    block_number: BlockNumber,
+    state_diff: StateDiff,
+    deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    // TODO(shahak): split the state diff stream to 2 separate streams for blocks and for
+    // classes.
+    let (thin_state_diff, classes, deprecated_classes) = ThinStateDiff::from_state_diff(state_diff);
+    txn.append_state_diff(block_number, thin_state_diff)?.append_classes(
+        block_number,
+        &classes.iter().map(|(class_hash, class)| (*class_hash, class)).collect::<Vec<_>>(),
+        &deprecated_classes
+            .iter()
+            .chain(deployed_contract_class_definitions.iter())
+            .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[allow(clippy::as_conversions)] // FIXME: use int metrics so `as f64` may be removed.
+fn report_stored_block(block_number: BlockNumber, timestamp: BlockTimestamp) {
+    metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_MARKER, block_number.unchecked_next().0 as f64);
+    metrics::gauge!(papyrus_metrics::PAPYRUS_BODY_MARKER, block_number.unchecked_next().0 as f64);
+    let time_delta = Utc::now()
+        - Utc
+            .timestamp_opt(timestamp.0 as i64, 0)
+            .single()
+            .expect("block timestamp should be valid");
+    let header_latency = time_delta.num_seconds();
+    debug!("Header latency: {}.", header_latency);
+    if header_latency >= 0 {
+        metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_LATENCY_SEC, header_latency as f64);
+    }
+}
+
 // TODO(dvir): consider gathering in a single pending argument instead.
 #[allow(clippy::too_many_arguments)]
 fn stream_new_blocks<
@@ -694,6 +1106,11 @@ fn stream_new_blocks<
             metrics::gauge!(
                 papyrus_metrics::PAPYRUS_CENTRAL_BLOCK_MARKER, central_block_marker.0 as f64
             );
+            // Central may be behind us right after it reverted blocks.
+            metrics::gauge!(
+                papyrus_metrics::PAPYRUS_BLOCKS_BEHIND_CENTRAL,
+                central_block_marker.0.saturating_sub(header_marker.0) as f64
+            );
             if header_marker == central_block_marker {
                 // Only if the node have the last block and state (without casms), sync pending data.
                 if collect_pending_data && reader.begin_ro_txn()?.get_state_marker()? == header_marker{
@@ -730,7 +1147,7 @@ fn stream_new_blocks<
 fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
     reader: StorageReader,
     central_source: Arc<TCentralSource>,
-    block_propagation_sleep_duration: Duration,
+    state_diff_propagation_sleep_duration: Duration,
     max_stream_size: u32,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
@@ -741,7 +1158,7 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
             drop(txn);
             if state_marker == last_block_number {
                 debug!("State updates syncing reached the last downloaded block, waiting for more blocks.");
-                tokio::time::sleep(block_propagation_sleep_duration).await;
+                tokio::time::sleep(state_diff_propagation_sleep_duration).await;
                 continue;
             }
             let up_to = min(last_block_number, BlockNumber(state_marker.0 + u64::from(max_stream_size)));
@@ -769,6 +1186,54 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
     }
 }
 
+// Verifies that each declared Sierra class isn't also declared as a deprecated class, hashes to the
+// hash it's keyed by, and has the compiled class hash of its stored declaration and CASM, if any.
+fn verify_state_diff_declared_classes(
+    reader: &StorageReader,
+    block_number: BlockNumber,
+    state_diff: &StateDiff,
+) -> StateSyncResult {
+    let txn = reader.begin_ro_txn()?;
+    let state_reader = txn.get_state_reader()?;
+    for (class_hash, (compiled_class_hash, class)) in &state_diff.declared_classes {
+        let mismatch =
+            || StateSyncError::StateDiffClassMismatch { block_number, class_hash: *class_hash };
+        if state_diff.deprecated_declared_classes.contains_key(class_hash)
+            || calculate_class_hash(class) != *class_hash
+        {
+            return Err(mismatch());
+        }
+        if let Some(declaration_block_number) =
+            state_reader.get_class_definition_block_number(class_hash)?
+        {
+            let declared_compiled_class_hash =
+                txn.get_state_diff(declaration_block_number)?.and_then(|declaration_diff| {
+                    declaration_diff.declared_classes.get(class_hash).copied()
+                });
+            if declared_compiled_class_hash.is_some_and(|hash| hash != *compiled_class_hash) {
+                return Err(mismatch());
+            }
+        }
+        if let Some(casm) = txn.get_casm(class_hash)? {
+            if CompiledClassHash(casm.compiled_class_hash()) != *compiled_class_hash {
+                return Err(mismatch());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the given state diff is ordered the same way [`sort_state_diff`] orders it.
+pub fn is_state_diff_sorted(diff: &StateDiff) -> bool {
+    diff.declared_classes.keys().is_sorted()
+        && diff.deprecated_declared_classes.keys().is_sorted()
+        && diff.deployed_contracts.keys().is_sorted()
+        && diff.nonces.keys().is_sorted()
+        && diff.replaced_classes.keys().is_sorted()
+        && diff.storage_diffs.keys().is_sorted()
+        && diff.storage_diffs.values().all(|storage_entries| storage_entries.keys().is_sorted())
+}
+
 pub fn sort_state_diff(diff: &mut StateDiff) {
     diff.declared_classes.sort_unstable_keys();
     diff.deprecated_declared_classes.sort_unstable_keys();
@@ -795,6 +1260,7 @@ impl StateSync {
         base_layer_source: EthereumBaseLayerSource,
         reader: StorageReader,
         writer: StorageWriter,
+        pause_control: SyncPauseControl,
     ) -> Self {
         Self {
             config,
@@ -807,6 +1273,8 @@ impl StateSync {
             reader,
             writer,
             sequencer_pub_key: None,
+            pause_control,
+            checkpoint_callback: None,
         }
     }
 }
@@ -814,7 +1282,7 @@ impl StateSync {
 fn stream_new_compiled_classes<TCentralSource: CentralSourceTrait + Sync + Send>(
     reader: StorageReader,
     central_source: Arc<TCentralSource>,
-    block_propagation_sleep_duration: Duration,
+    compiled_class_propagation_sleep_duration: Duration,
     max_stream_size: u32,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
@@ -838,7 +1306,7 @@ fn stream_new_compiled_classes<TCentralSource: CentralSourceTrait + Sync + Send>
                     "Compiled classes syncing reached the last downloaded state update, waiting \
                      for more state updates."
                 );
-                tokio::time::sleep(block_propagation_sleep_duration).await;
+                tokio::time::sleep(compiled_class_propagation_sleep_duration).await;
                 continue;
             }
             let up_to = min(state_marker, BlockNumber(from.0 + u64::from(max_stream_size)));
@@ -897,6 +1365,7 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
 // TODO(dvir): add a test for this scenario.
 fn check_sync_progress(
     reader: StorageReader,
+    pause_control: SyncPauseControl,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         let mut txn=reader.begin_ro_txn()?;
@@ -904,13 +1373,16 @@ fn check_sync_progress(
         let mut state_marker=txn.get_state_marker()?;
         let mut casm_marker=txn.get_compiled_class_marker()?;
         loop{
+            let n_pauses=pause_control.n_pauses();
             tokio::time::sleep(SLEEP_TIME_SYNC_PROGRESS).await;
             debug!("Checking if sync stopped progress.");
             txn=reader.begin_ro_txn()?;
             let new_header_marker=txn.get_header_marker()?;
             let new_state_marker=txn.get_state_marker()?;
             let new_casm_marker=txn.get_compiled_class_marker()?;
-            if header_marker==new_header_marker || state_marker==new_state_marker || casm_marker==new_casm_marker{
+            // A paused sync isn't expected to progress.
+            let was_paused=pause_control.is_paused() || pause_control.n_pauses()!=n_pauses;
+            if !was_paused && (header_marker==new_header_marker || state_marker==new_state_marker || casm_marker==new_casm_marker){
                 debug!("No progress in the sync. Return NoProgress event.");
                 yield SyncEvent::NoProgress;
             }
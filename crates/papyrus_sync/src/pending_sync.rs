@@ -12,7 +12,7 @@ use starknet_api::core::ClassHash;
 use starknet_client::reader::{DeclaredClassHashEntry, PendingData};
 use starknet_types_core::felt::Felt;
 use tokio::sync::RwLock;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::sources::central::CentralSourceTrait;
 use crate::sources::pending::PendingSourceTrait;
@@ -30,24 +30,11 @@ pub(crate) async fn sync_pending_data<
     pending_classes: Arc<RwLock<PendingClasses>>,
     sleep_duration: Duration,
 ) -> Result<(), StateSyncError> {
-    let txn = reader.begin_ro_txn()?;
-    let header_marker = txn.get_header_marker()?;
-    // TODO: Consider extracting this functionality to different а function.
-    let latest_block_hash = match header_marker {
-        BlockNumber(0) => BlockHash(Felt::from_hex_unchecked(crate::GENESIS_HASH)),
-        _ => {
-            txn.get_block_header(
-                header_marker
-                    .prev()
-                    .expect("All blocks other than the first block should have a predecessor."),
-            )?
-            .expect("Block before the header marker must have header in the database.")
-            .block_hash
-        }
-    };
+    let latest_block_hash = get_latest_stored_block_hash(&reader)?;
     let mut tasks = FuturesUnordered::new();
     tasks.push(
         get_pending_data(
+            reader.clone(),
             latest_block_hash,
             pending_source.clone(),
             pending_data.clone(),
@@ -107,6 +94,7 @@ pub(crate) async fn sync_pending_data<
                 }
                 tasks.push(
                     get_pending_data(
+                        reader.clone(),
                         latest_block_hash,
                         pending_source.clone(),
                         pending_data.clone(),
@@ -118,6 +106,7 @@ pub(crate) async fn sync_pending_data<
             }
             PendingSyncTaskResult::DownloadedOldPendingData => tasks.push(
                 get_pending_data(
+                    reader.clone(),
                     latest_block_hash,
                     pending_source.clone(),
                     pending_data.clone(),
@@ -131,6 +120,25 @@ pub(crate) async fn sync_pending_data<
     }
 }
 
+// Returns the hash of the latest block in the storage, or the genesis parent hash if there are no
+// blocks.
+fn get_latest_stored_block_hash(reader: &StorageReader) -> Result<BlockHash, StateSyncError> {
+    let txn = reader.begin_ro_txn()?;
+    let header_marker = txn.get_header_marker()?;
+    Ok(match header_marker {
+        BlockNumber(0) => BlockHash(Felt::from_hex_unchecked(crate::GENESIS_HASH)),
+        _ => {
+            txn.get_block_header(
+                header_marker
+                    .prev()
+                    .expect("All blocks other than the first block should have a predecessor."),
+            )?
+            .expect("Block before the header marker must have header in the database.")
+            .block_hash
+        }
+    })
+}
+
 enum PendingSyncTaskResult {
     DownloadedNewPendingData,
     DownloadedOldPendingData,
@@ -139,6 +147,7 @@ enum PendingSyncTaskResult {
 }
 
 async fn get_pending_data<TPendingSource: PendingSourceTrait + Sync + Send + 'static>(
+    reader: StorageReader,
     latest_block_hash: BlockHash,
     pending_source: Arc<TPendingSource>,
     pending_data: Arc<RwLock<PendingData>>,
@@ -160,6 +169,16 @@ async fn get_pending_data<TPendingSource: PendingSourceTrait + Sync + Send + 'st
         debug!("A new block was found. Stopping pending sync.");
         return Ok(PendingSyncTaskResult::PendingSyncFinished);
     };
+    // The stored blocks may have been reverted while we were polling. Exposing pending data that
+    // isn't on top of the latest stored block would make RPC serve an inconsistent state.
+    let latest_stored_block_hash = get_latest_stored_block_hash(&reader)?;
+    if new_pending_parent_hash != latest_stored_block_hash {
+        warn!(
+            "Discarding pending data on top of block {new_pending_parent_hash:?} since the latest \
+             stored block is {latest_stored_block_hash:?}. Stopping pending sync."
+        );
+        return Ok(PendingSyncTaskResult::PendingSyncFinished);
+    }
 
     let (current_pending_num_transactions, current_pending_parent_hash) = {
         let pending_block = &pending_data.read().await.block;
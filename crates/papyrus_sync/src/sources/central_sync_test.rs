@@ -9,8 +9,9 @@ use futures::StreamExt;
 use indexmap::IndexMap;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses};
 use papyrus_storage::base_layer::BaseLayerStorageReader;
-use papyrus_storage::header::HeaderStorageReader;
-use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use starknet_api::block::{
@@ -26,7 +27,7 @@ use starknet_api::block::{
 use starknet_api::core::{ClassHash, SequencerPublicKey};
 use starknet_api::crypto::utils::PublicKey;
 use starknet_api::felt;
-use starknet_api::state::StateDiff;
+use starknet_api::state::{StateDiff, ThinStateDiff};
 use starknet_client::reader::PendingData;
 use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error};
@@ -39,6 +40,7 @@ use crate::sources::central::{
     MockCentralSourceTrait,
     StateUpdatesStream,
 };
+use crate::sync_test::BLOCKS_BEHIND_CENTRAL_LOCK;
 use crate::{
     CentralError,
     CentralSourceTrait,
@@ -46,6 +48,7 @@ use crate::{
     StateSyncError,
     StateSyncResult,
     SyncConfig,
+    SyncPauseControl,
 };
 
 const SYNC_SLEEP_DURATION: Duration = Duration::from_millis(100); // 100ms
@@ -97,12 +100,18 @@ async fn check_storage(
 fn get_test_sync_config(verify_blocks: bool) -> SyncConfig {
     SyncConfig {
         block_propagation_sleep_duration: SYNC_SLEEP_DURATION,
+        state_diff_propagation_sleep_duration: None,
+        compiled_class_propagation_sleep_duration: None,
         base_layer_propagation_sleep_duration: BASE_LAYER_SLEEP_DURATION,
         recoverable_error_sleep_duration: SYNC_SLEEP_DURATION,
         blocks_max_stream_size: STREAM_SIZE,
         state_updates_max_stream_size: STREAM_SIZE,
         verify_blocks,
         collect_pending_data: false,
+        force_resync_from: None,
+        checkpoint_interval: None,
+        allow_reverts: true,
+        atomic_block_and_state: false,
     }
 }
 
@@ -114,6 +123,27 @@ async fn run_sync(
     base_layer: impl BaseLayerSourceTrait + Send + Sync,
     config: SyncConfig,
 ) -> StateSyncResult {
+    run_sync_with_pause_control(
+        reader,
+        writer,
+        central,
+        base_layer,
+        config,
+        SyncPauseControl::default(),
+    )
+    .await
+}
+
+// Same as run_sync, but the sync can be paused and resumed with the given pause control.
+async fn run_sync_with_pause_control(
+    reader: StorageReader,
+    writer: StorageWriter,
+    central: impl CentralSourceTrait + Send + Sync + 'static,
+    base_layer: impl BaseLayerSourceTrait + Send + Sync,
+    config: SyncConfig,
+    pause_control: SyncPauseControl,
+) -> StateSyncResult {
+    let _blocks_behind_central_guard = BLOCKS_BEHIND_CENTRAL_LOCK.lock().await;
     // Mock to the pending source that always returns the default pending data.
     let mut pending_source = MockPendingSourceTrait::new();
     pending_source.expect_get_pending_data().returning(|| Ok(PendingData::default()));
@@ -129,6 +159,8 @@ async fn run_sync(
         reader,
         writer,
         sequencer_pub_key: None,
+        pause_control,
+        checkpoint_callback: None,
     };
 
     state_sync.run().await?;
@@ -707,6 +739,285 @@ async fn sequencer_pub_key_management() {
     );
 }
 
+#[tokio::test]
+async fn sync_force_resync_from() {
+    const N_BLOCKS: u64 = 5;
+    const LATEST_BLOCK_NUMBER: BlockNumber = BlockNumber(N_BLOCKS - 1);
+    const RESYNC_FROM: BlockNumber = BlockNumber(2);
+    const MAX_TIME_TO_SYNC_MS: u64 = 800;
+    let _ = simple_logger::init_with_env();
+
+    // Fill the storage with N_BLOCKS blocks that are already synced.
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    for block_number in BlockNumber(0).iter_up_to(BlockNumber(N_BLOCKS)) {
+        let header = BlockHeader {
+            block_hash: create_block_hash(block_number, false),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                parent_hash: create_block_hash(block_number.prev().unwrap_or_default(), false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, BlockBody::default())
+            .unwrap()
+            .append_state_diff(block_number, ThinStateDiff::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // Mock having N_BLOCKS chain in central and record the first block that is requested.
+    let first_requested_block = Arc::new(std::sync::Mutex::new(None));
+    let first_requested_block_clone = first_requested_block.clone();
+    let mut central_mock = MockCentralSourceTrait::new();
+    central_mock.expect_get_latest_block().returning(|| {
+        Ok(Some(BlockHashAndNumber {
+            number: LATEST_BLOCK_NUMBER,
+            hash: create_block_hash(LATEST_BLOCK_NUMBER, false),
+        }))
+    });
+    central_mock.expect_stream_new_blocks().returning(move |initial, up_to| {
+        first_requested_block_clone.lock().unwrap().get_or_insert(initial);
+        let blocks_stream: BlocksStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                let header = BlockHeader {
+                    block_hash: create_block_hash(block_number, false),
+                    block_header_without_hash: BlockHeaderWithoutHash {
+                        block_number,
+                        parent_hash: create_block_hash(block_number.prev().unwrap_or_default(), false),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                yield Ok((
+                    block_number,
+                    Block { header, body: BlockBody::default() },
+                    BlockSignature::default(),
+                ));
+            }
+        }
+        .boxed();
+        blocks_stream
+    });
+    central_mock.expect_stream_state_updates().returning(move |initial, up_to| {
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                yield Ok((
+                    block_number,
+                    create_block_hash(block_number, false),
+                    StateDiff::default(),
+                    IndexMap::new(),
+                ));
+            }
+        }
+        .boxed();
+        state_stream
+    });
+    central_mock.expect_get_block_hash().returning(|bn| Ok(Some(create_block_hash(bn, false))));
+
+    let mut base_layer_mock = MockBaseLayerSourceTrait::new();
+    base_layer_mock.expect_latest_proved_block().returning(|| Ok(None));
+
+    let config = SyncConfig { force_resync_from: Some(RESYNC_FROM), ..get_test_sync_config(false) };
+    let sync_future = run_sync(reader.clone(), writer, central_mock, base_layer_mock, config);
+
+    // Check that the reverted blocks were synced again.
+    let check_storage_future =
+        check_storage(reader, Duration::from_millis(MAX_TIME_TO_SYNC_MS), |reader| {
+            let txn = reader.begin_ro_txn().unwrap();
+            let header_marker = txn.get_header_marker().unwrap();
+            let state_marker = txn.get_state_marker().unwrap();
+            debug!("Header marker at {header_marker}, state marker at {state_marker}");
+            if header_marker < BlockNumber(N_BLOCKS) || state_marker < BlockNumber(N_BLOCKS) {
+                return CheckStoragePredicateResult::InProgress;
+            }
+            if header_marker > BlockNumber(N_BLOCKS) || state_marker > BlockNumber(N_BLOCKS) {
+                return CheckStoragePredicateResult::Error;
+            }
+            CheckStoragePredicateResult::Passed
+        });
+
+    tokio::select! {
+        sync_result = sync_future => sync_result.unwrap(),
+        storage_check_result = check_storage_future => assert!(storage_check_result),
+    }
+    assert_eq!(*first_requested_block.lock().unwrap(), Some(RESYNC_FROM));
+}
+
+#[tokio::test]
+async fn sync_with_revert_fails_when_reverts_are_not_allowed() {
+    const N_BLOCKS: u64 = 5;
+    const LATEST_BLOCK_NUMBER: BlockNumber = BlockNumber(N_BLOCKS - 1);
+    let _ = simple_logger::init_with_env();
+
+    // Fill the storage with N_BLOCKS blocks that are already synced.
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    for block_number in BlockNumber(0).iter_up_to(BlockNumber(N_BLOCKS)) {
+        let header = BlockHeader {
+            block_hash: create_block_hash(block_number, false),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                parent_hash: create_block_hash(block_number.prev().unwrap_or_default(), false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, BlockBody::default())
+            .unwrap()
+            .append_state_diff(block_number, ThinStateDiff::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // Mock a central in which the latest stored block was reorged.
+    let mut central_mock = MockCentralSourceTrait::new();
+    central_mock.expect_get_block_hash().returning(|block_number| {
+        Ok(Some(create_block_hash(block_number, block_number == LATEST_BLOCK_NUMBER)))
+    });
+
+    let config = SyncConfig { allow_reverts: false, ..get_test_sync_config(false) };
+    let sync_result = tokio::time::timeout(
+        config.block_propagation_sleep_duration * 4,
+        run_sync(reader.clone(), writer, central_mock, MockBaseLayerSourceTrait::new(), config),
+    )
+    .await
+    .unwrap()
+    .expect_err("Expecting sync to fail due to a revert while reverts are not allowed.");
+
+    assert_matches!(
+        sync_result,
+        StateSyncError::RevertNotAllowed { block_number } if block_number == LATEST_BLOCK_NUMBER
+    );
+    // The block that needed to be reverted is still stored.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(N_BLOCKS));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(N_BLOCKS));
+    assert_eq!(
+        txn.get_block_header(LATEST_BLOCK_NUMBER).unwrap().unwrap().block_hash,
+        create_block_hash(LATEST_BLOCK_NUMBER, false)
+    );
+}
+
+#[tokio::test]
+async fn sync_pause_and_resume() {
+    const N_BLOCKS: u64 = 5;
+    const LATEST_BLOCK_NUMBER: BlockNumber = BlockNumber(N_BLOCKS - 1);
+    const MAX_TIME_TO_SYNC_MS: u64 = 800;
+    let _ = simple_logger::init_with_env();
+
+    // Mock having N_BLOCKS chain in central.
+    let mut central_mock = MockCentralSourceTrait::new();
+    central_mock.expect_get_latest_block().returning(|| {
+        Ok(Some(BlockHashAndNumber {
+            number: LATEST_BLOCK_NUMBER,
+            hash: create_block_hash(LATEST_BLOCK_NUMBER, false),
+        }))
+    });
+    central_mock.expect_stream_new_blocks().returning(move |initial, up_to| {
+        let blocks_stream: BlocksStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                if block_number.0 >= N_BLOCKS {
+                    yield Err(CentralError::BlockNotFound { block_number });
+                }
+                let header = BlockHeader {
+                    block_hash: create_block_hash(block_number, false),
+                    block_header_without_hash: BlockHeaderWithoutHash {
+                        block_number,
+                        parent_hash: create_block_hash(block_number.prev().unwrap_or_default(), false),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                yield Ok((
+                    block_number,
+                    Block { header, body: BlockBody::default() },
+                    BlockSignature::default(),
+                ));
+            }
+        }
+        .boxed();
+        blocks_stream
+    });
+    central_mock.expect_stream_state_updates().returning(move |initial, up_to| {
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                if block_number.0 >= N_BLOCKS {
+                    yield Err(CentralError::BlockNotFound { block_number })
+                }
+                yield Ok((
+                    block_number,
+                    create_block_hash(block_number, false),
+                    StateDiff::default(),
+                    IndexMap::new(),
+                ));
+            }
+        }
+        .boxed();
+        state_stream
+    });
+    central_mock.expect_get_block_hash().returning(|bn| Ok(Some(create_block_hash(bn, false))));
+
+    let mut base_layer_mock = MockBaseLayerSourceTrait::new();
+    base_layer_mock.expect_latest_proved_block().returning(|| Ok(None));
+
+    // Start the sync paused.
+    let pause_control = SyncPauseControl::default();
+    pause_control.pause();
+    assert!(pause_control.is_paused());
+
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let sync_future = run_sync_with_pause_control(
+        reader.clone(),
+        writer,
+        central_mock,
+        base_layer_mock,
+        get_test_sync_config(false),
+        pause_control.clone(),
+    );
+
+    let check_storage_future = async {
+        // The markers shouldn't advance while the sync is paused.
+        tokio::time::sleep(SYNC_SLEEP_DURATION.saturating_mul(5)).await;
+        assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(0));
+        assert_eq!(reader.begin_ro_txn().unwrap().get_state_marker().unwrap(), BlockNumber(0));
+
+        // Once resumed, the sync should reach N_BLOCKS.
+        pause_control.resume();
+        assert!(!pause_control.is_paused());
+        check_storage(reader.clone(), Duration::from_millis(MAX_TIME_TO_SYNC_MS), |reader| {
+            let txn = reader.begin_ro_txn().unwrap();
+            let header_marker = txn.get_header_marker().unwrap();
+            let state_marker = txn.get_state_marker().unwrap();
+            debug!("Header marker at {header_marker}, state marker at {state_marker}");
+            if header_marker > BlockNumber(N_BLOCKS) || state_marker > BlockNumber(N_BLOCKS) {
+                return CheckStoragePredicateResult::Error;
+            }
+            if header_marker < BlockNumber(N_BLOCKS) || state_marker < BlockNumber(N_BLOCKS) {
+                return CheckStoragePredicateResult::InProgress;
+            }
+            CheckStoragePredicateResult::Passed
+        })
+        .await
+    };
+
+    tokio::select! {
+        sync_result = sync_future => sync_result.unwrap(),
+        storage_check_result = check_storage_future => assert!(storage_check_result),
+    }
+}
+
 fn create_block_hash(bn: BlockNumber, is_reverted_block: bool) -> BlockHash {
     if is_reverted_block {
         BlockHash(felt!(format!("0x{}10", bn.0).as_str()))
@@ -1,22 +1,48 @@
+use std::hash::Hash;
 use std::sync::Arc;
 use std::time::Duration;
 
 use assert_matches::assert_matches;
+use async_stream::stream;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use futures::poll;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_common::class_hash::calculate_class_hash;
+use papyrus_common::metrics as papyrus_metrics;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
 use papyrus_storage::base_layer::BaseLayerStorageReader;
-use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageReader, StorageWriter};
-use papyrus_test_utils::{get_rng, GetTestInstance};
+use papyrus_test_utils::{get_rng, prometheus_is_contained, GetTestInstance};
 use pretty_assertions::assert_eq;
-use starknet_api::block::{BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
-use starknet_api::core::{ClassHash, CompiledClassHash, Nonce};
+use prometheus_parse::Value::Gauge;
+use starknet_api::block::{
+    Block,
+    BlockBody,
+    BlockHash,
+    BlockHashAndNumber,
+    BlockHeader,
+    BlockHeaderWithoutHash,
+    BlockNumber,
+    BlockSignature,
+};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    Nonce,
+    SequencerPublicKey,
+    StateDiffCommitment,
+};
+use starknet_api::crypto::utils::{PublicKey, Signature};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::hash::StarkHash;
-use starknet_api::state::{SierraContractClass, StateDiff};
+use starknet_api::hash::{PoseidonHash, StarkHash};
+use starknet_api::state::{SierraContractClass, StateDiff, ThinStateDiff};
 use starknet_api::{contract_address, felt, storage_key};
 use starknet_client::reader::objects::pending_data::{
     AcceptedOnL2ExtraData,
@@ -27,22 +53,60 @@ use starknet_client::reader::objects::pending_data::{
 use starknet_client::reader::objects::state::StateDiff as ClientStateDiff;
 use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
 use starknet_client::reader::{DeclaredClassHashEntry, PendingData};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
+use validator::Validate;
 
 use crate::sources::base_layer::MockBaseLayerSourceTrait;
-use crate::sources::central::MockCentralSourceTrait;
+use crate::sources::central::{
+    CentralError,
+    CompiledClassesStream,
+    MockCentralSourceTrait,
+    StateUpdatesStream,
+};
 use crate::sources::pending::MockPendingSourceTrait;
 use crate::{
+    is_state_diff_sorted,
     sort_state_diff,
     stream_new_base_layer_block,
+    stream_new_blocks,
     sync_pending_data,
     GenericStateSync,
     StateSyncError,
+    StateSyncResult,
     SyncConfig,
     SyncEvent,
+    SyncPauseControl,
     GENESIS_HASH,
 };
 
+#[test]
+fn config_validation() {
+    let mut config = SyncConfig::default();
+    assert_eq!(config.validate(), Ok(()));
+
+    config.blocks_max_stream_size = 0;
+    assert!(config.validate().is_err());
+    config.blocks_max_stream_size = 1;
+    assert_eq!(config.validate(), Ok(()));
+
+    config.state_updates_max_stream_size = 0;
+    assert!(config.validate().is_err());
+    config.state_updates_max_stream_size = 1;
+    assert_eq!(config.validate(), Ok(()));
+
+    config.force_resync_from = Some(BlockNumber(1));
+    config.allow_reverts = false;
+    assert!(config.validate().is_err());
+    config.allow_reverts = true;
+    assert_eq!(config.validate(), Ok(()));
+
+    config.checkpoint_interval = Some(0);
+    assert!(config.validate().is_err());
+    config.checkpoint_interval = Some(1);
+    assert_eq!(config.validate(), Ok(()));
+}
+
 // TODO(anatg): Add a test to check that the sync calls the sort_state_diff function
 // before writing to the storage.
 #[test]
@@ -128,6 +192,76 @@ fn state_sorted() {
     );
 }
 
+#[test]
+fn state_diff_sorted_check() {
+    let address_0 = contract_address!("0x0");
+    let address_1 = contract_address!("0x1");
+    let class_hash_0 = ClassHash(felt!("0x0"));
+    let class_hash_1 = ClassHash(felt!("0x1"));
+    let storage_entries =
+        IndexMap::from([(storage_key!("0x0"), felt!("0x0")), (storage_key!("0x1"), felt!("0x1"))]);
+    let sorted_state_diff = StateDiff {
+        deployed_contracts: IndexMap::from([(address_0, class_hash_0), (address_1, class_hash_1)]),
+        storage_diffs: IndexMap::from([
+            (address_0, storage_entries.clone()),
+            (address_1, storage_entries.clone()),
+        ]),
+        declared_classes: IndexMap::from([
+            (class_hash_0, (CompiledClassHash::default(), SierraContractClass::default())),
+            (class_hash_1, (CompiledClassHash::default(), SierraContractClass::default())),
+        ]),
+        deprecated_declared_classes: IndexMap::from([
+            (class_hash_0, DeprecatedContractClass::default()),
+            (class_hash_1, DeprecatedContractClass::default()),
+        ]),
+        nonces: IndexMap::from([(address_0, Nonce::default()), (address_1, Nonce::default())]),
+        replaced_classes: IndexMap::from([(address_0, class_hash_0), (address_1, class_hash_1)]),
+    };
+    assert!(is_state_diff_sorted(&sorted_state_diff));
+    assert!(is_state_diff_sorted(&StateDiff::default()));
+
+    let unsorted_state_diffs = [
+        StateDiff {
+            deployed_contracts: reversed(&sorted_state_diff.deployed_contracts),
+            ..sorted_state_diff.clone()
+        },
+        StateDiff {
+            storage_diffs: reversed(&sorted_state_diff.storage_diffs),
+            ..sorted_state_diff.clone()
+        },
+        StateDiff {
+            declared_classes: reversed(&sorted_state_diff.declared_classes),
+            ..sorted_state_diff.clone()
+        },
+        StateDiff {
+            deprecated_declared_classes: reversed(&sorted_state_diff.deprecated_declared_classes),
+            ..sorted_state_diff.clone()
+        },
+        StateDiff { nonces: reversed(&sorted_state_diff.nonces), ..sorted_state_diff.clone() },
+        StateDiff {
+            replaced_classes: reversed(&sorted_state_diff.replaced_classes),
+            ..sorted_state_diff.clone()
+        },
+        // Only the storage entries of the second contract are unsorted.
+        StateDiff {
+            storage_diffs: IndexMap::from([
+                (address_0, storage_entries.clone()),
+                (address_1, reversed(&storage_entries)),
+            ]),
+            ..sorted_state_diff.clone()
+        },
+    ];
+    for mut state_diff in unsorted_state_diffs {
+        assert!(!is_state_diff_sorted(&state_diff));
+        sort_state_diff(&mut state_diff);
+        assert!(is_state_diff_sorted(&state_diff));
+    }
+
+    fn reversed<K: Clone + Hash + Eq, V: Clone>(map: &IndexMap<K, V>) -> IndexMap<K, V> {
+        map.iter().rev().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+}
+
 #[tokio::test]
 async fn stream_new_base_layer_block_test_header_marker() {
     let (reader, mut writer) = get_test_storage().0;
@@ -171,6 +305,121 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
     assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
 }
 
+// Values that are far enough apart to tell which of them a stream waited for.
+const BLOCK_PROPAGATION_SLEEP_DURATION: Duration = Duration::from_secs(100);
+const STATE_DIFF_PROPAGATION_SLEEP_DURATION: Duration = Duration::from_secs(10);
+const COMPILED_CLASS_PROPAGATION_SLEEP_DURATION: Duration = Duration::from_secs(20);
+const STREAM_SIZE: u32 = 10;
+
+// Held by the tests that stream new blocks, so that they don't change the blocks behind central
+// gauge while another test measures it.
+pub(crate) static BLOCKS_BEHIND_CENTRAL_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn get_state_sync_with_propagation_sleep_durations(
+    reader: StorageReader,
+    writer: StorageWriter,
+    central_source: MockCentralSourceTrait,
+) -> GenericStateSync<MockCentralSourceTrait, MockPendingSourceTrait, MockBaseLayerSourceTrait> {
+    GenericStateSync {
+        config: SyncConfig {
+            block_propagation_sleep_duration: BLOCK_PROPAGATION_SLEEP_DURATION,
+            state_diff_propagation_sleep_duration: Some(STATE_DIFF_PROPAGATION_SLEEP_DURATION),
+            compiled_class_propagation_sleep_duration: Some(
+                COMPILED_CLASS_PROPAGATION_SLEEP_DURATION,
+            ),
+            ..SyncConfig::default()
+        },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(central_source),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn state_diff_stream_sleeps_for_state_diff_propagation_duration() {
+    let (reader, writer) = get_test_storage().0;
+
+    let mut mock = MockCentralSourceTrait::new();
+    mock.expect_stream_state_updates().times(1).returning(|initial, _| {
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            yield Ok((initial, BlockHash::default(), StateDiff::default(), IndexMap::new()));
+        }
+        .boxed();
+        state_stream
+    });
+
+    let mut state_sync = get_state_sync_with_propagation_sleep_durations(reader, writer, mock);
+    let start = Instant::now();
+    let mut stream = state_sync.state_diff_stream().boxed();
+    // There are no headers yet, so the stream goes to sleep.
+    assert!(poll!(stream.next()).is_pending());
+    add_headers(1, &mut state_sync.writer);
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::StateDiffAvailable { block_number: BlockNumber(0), .. });
+    let elapsed = start.elapsed();
+    assert!(elapsed >= STATE_DIFF_PROPAGATION_SLEEP_DURATION);
+    assert!(elapsed < BLOCK_PROPAGATION_SLEEP_DURATION);
+}
+
+#[tokio::test(start_paused = true)]
+async fn compiled_class_stream_sleeps_for_compiled_class_propagation_duration() {
+    let (reader, writer) = get_test_storage().0;
+    let mut rng = get_rng();
+    let class_hash = ClassHash(felt!("0x1"));
+    let compiled_class_hash = CompiledClassHash(felt!("0x2"));
+    let compiled_class = CasmContractClass::get_test_instance(&mut rng);
+
+    let mut mock = MockCentralSourceTrait::new();
+    let compiled_class_clone = compiled_class.clone();
+    mock.expect_stream_compiled_classes().times(1).returning(move |_, _| {
+        let compiled_class = compiled_class_clone.clone();
+        let compiled_classes_stream: CompiledClassesStream<'_> = stream! {
+            yield Ok((class_hash, compiled_class_hash, compiled_class));
+        }
+        .boxed();
+        compiled_classes_stream
+    });
+
+    let mut state_sync = get_state_sync_with_propagation_sleep_durations(reader, writer, mock);
+    let start = Instant::now();
+    let mut stream = state_sync.compiled_class_stream().boxed();
+    // There are no state diffs yet, so the stream goes to sleep.
+    assert!(poll!(stream.next()).is_pending());
+    state_sync
+        .writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                declared_classes: IndexMap::from([(class_hash, compiled_class_hash)]),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(
+        event,
+        SyncEvent::CompiledClassAvailable { class_hash: hash, compiled_class: class, .. }
+            if hash == class_hash && class == compiled_class
+    );
+    let elapsed = start.elapsed();
+    assert!(elapsed >= COMPILED_CLASS_PROPAGATION_SLEEP_DURATION);
+    assert!(elapsed < BLOCK_PROPAGATION_SLEEP_DURATION);
+}
+
 #[test]
 fn store_base_layer_block_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -203,6 +452,8 @@ fn store_base_layer_block_test() {
         reader,
         writer,
         sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
     };
 
     // Trying to store a block without a header in the storage.
@@ -221,6 +472,449 @@ fn store_base_layer_block_test() {
     assert_eq!(base_layer_marker, BlockNumber(1));
 }
 
+#[test]
+fn store_compiled_class_rejects_casm_hash_mismatch() {
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { verify_blocks: true, ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+    let class_hash = ClassHash(felt!("0x1"));
+    let casm = CasmContractClass {
+        prime: Default::default(),
+        compiler_version: Default::default(),
+        bytecode: Default::default(),
+        bytecode_segment_lengths: Default::default(),
+        hints: Default::default(),
+        pythonic_hints: Default::default(),
+        entry_points_by_type: Default::default(),
+    };
+    let compiled_class_hash = CompiledClassHash(casm.compiled_class_hash());
+    let wrong_compiled_class_hash = CompiledClassHash(felt!("0x2"));
+    assert_ne!(compiled_class_hash, wrong_compiled_class_hash);
+
+    let res =
+        gen_state_sync.store_compiled_class(class_hash, wrong_compiled_class_hash, casm.clone());
+    assert_matches!(
+        res,
+        Err(StateSyncError::CasmHashMismatch { class_hash: hash, expected, calculated })
+            if hash == class_hash
+                && expected == wrong_compiled_class_hash
+                && calculated == compiled_class_hash
+    );
+    assert!(gen_state_sync.reader.begin_ro_txn().unwrap().get_casm(&class_hash).unwrap().is_none());
+
+    gen_state_sync.store_compiled_class(class_hash, compiled_class_hash, casm.clone()).unwrap();
+    assert_eq!(
+        gen_state_sync.reader.begin_ro_txn().unwrap().get_casm(&class_hash).unwrap(),
+        Some(casm)
+    );
+}
+
+#[test]
+fn store_state_diff_rejects_declared_class_mismatch() {
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { verify_blocks: true, ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+    let class = SierraContractClass::default();
+    let class_hash = calculate_class_hash(&class);
+    let wrong_class_hash = ClassHash(felt!("0x1"));
+    assert_ne!(class_hash, wrong_class_hash);
+    let casm = CasmContractClass {
+        prime: Default::default(),
+        compiler_version: Default::default(),
+        bytecode: Default::default(),
+        bytecode_segment_lengths: Default::default(),
+        hints: Default::default(),
+        pythonic_hints: Default::default(),
+        entry_points_by_type: Default::default(),
+    };
+    let compiled_class_hash = CompiledClassHash(casm.compiled_class_hash());
+    let wrong_compiled_class_hash = CompiledClassHash(felt!("0x2"));
+    assert_ne!(compiled_class_hash, wrong_compiled_class_hash);
+    let state_diff_declaring = |class_hash, compiled_class_hash| StateDiff {
+        declared_classes: IndexMap::from([(class_hash, (compiled_class_hash, class.clone()))]),
+        ..Default::default()
+    };
+    let assert_mismatch = |res: StateSyncResult, expected_block_number, expected_class_hash| {
+        assert_matches!(
+            res,
+            Err(StateSyncError::StateDiffClassMismatch { block_number, class_hash: hash })
+                if block_number == expected_block_number && hash == expected_class_hash
+        );
+    };
+
+    // The compiled class hash is mapped to a class hash that isn't the hash of the declared class.
+    let res = gen_state_sync.store_state_diff(
+        BlockNumber(0),
+        BlockHash::default(),
+        state_diff_declaring(wrong_class_hash, compiled_class_hash),
+        IndexMap::new(),
+    );
+    assert_mismatch(res, BlockNumber(0), wrong_class_hash);
+
+    // The compiled class hash isn't the hash of the CASM that is already stored for the class.
+    gen_state_sync
+        .writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_casm(&class_hash, &casm)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let res = gen_state_sync.store_state_diff(
+        BlockNumber(0),
+        BlockHash::default(),
+        state_diff_declaring(class_hash, wrong_compiled_class_hash),
+        IndexMap::new(),
+    );
+    assert_mismatch(res, BlockNumber(0), class_hash);
+    assert_eq!(
+        gen_state_sync.reader.begin_ro_txn().unwrap().get_state_marker().unwrap(),
+        BlockNumber(0)
+    );
+
+    gen_state_sync
+        .store_state_diff(
+            BlockNumber(0),
+            BlockHash::default(),
+            state_diff_declaring(class_hash, compiled_class_hash),
+            IndexMap::new(),
+        )
+        .unwrap();
+    let state_reader = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(state_reader.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(
+        state_reader.get_state_diff(BlockNumber(0)).unwrap().unwrap().declared_classes,
+        IndexMap::from([(class_hash, compiled_class_hash)])
+    );
+    drop(state_reader);
+
+    // A later declaration of the class with another compiled class hash.
+    let res = gen_state_sync.store_state_diff(
+        BlockNumber(1),
+        BlockHash::default(),
+        state_diff_declaring(class_hash, wrong_compiled_class_hash),
+        IndexMap::new(),
+    );
+    assert_mismatch(res, BlockNumber(1), class_hash);
+}
+
+#[test]
+fn checkpoints_are_reported_at_the_checkpoint_interval() {
+    const CHECKPOINT_INTERVAL: u64 = 3;
+    const N_BLOCKS: u64 = 8;
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig {
+            checkpoint_interval: Some(CHECKPOINT_INTERVAL),
+            verify_blocks: false,
+            ..SyncConfig::default()
+        },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader: reader.clone(),
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+    let checkpoints = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let callback_checkpoints = checkpoints.clone();
+    gen_state_sync.set_checkpoint_callback(move |block_number| {
+        // The block and the state diff of the checkpoint block are stored when the callback is
+        // called.
+        let txn = reader.begin_ro_txn().unwrap();
+        assert!(txn.get_body_marker().unwrap() > block_number);
+        assert!(txn.get_state_marker().unwrap() > block_number);
+        callback_checkpoints.lock().unwrap().push(block_number);
+    });
+
+    let block = |i: u64| Block {
+        header: BlockHeader {
+            block_hash: BlockHash(felt!(i + 1)),
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number: BlockNumber(i),
+                parent_hash: BlockHash(felt!(i)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        body: BlockBody::default(),
+    };
+    let store_state_diff = |gen_state_sync: &mut GenericStateSync<_, _, _>, i: u64| {
+        gen_state_sync
+            .store_state_diff(
+                BlockNumber(i),
+                BlockHash(felt!(i + 1)),
+                StateDiff::default(),
+                IndexMap::new(),
+            )
+            .unwrap();
+    };
+    // Each block is stored before its state diff in the first half, and after it in the second
+    // half, so that the checkpoints are reached both when storing blocks and when storing state
+    // diffs.
+    for i in 0..N_BLOCKS / 2 {
+        gen_state_sync.store_block(BlockNumber(i), block(i), &BlockSignature::default()).unwrap();
+        store_state_diff(&mut gen_state_sync, i);
+    }
+    for i in N_BLOCKS / 2..N_BLOCKS {
+        store_state_diff(&mut gen_state_sync, i);
+    }
+    for i in N_BLOCKS / 2..N_BLOCKS {
+        gen_state_sync.store_block(BlockNumber(i), block(i), &BlockSignature::default()).unwrap();
+    }
+    assert_eq!(*checkpoints.lock().unwrap(), vec![BlockNumber(0), BlockNumber(3), BlockNumber(6)]);
+}
+
+#[test]
+fn store_block_rejects_invalid_signature() {
+    // Values taken from Mainnet.
+    let block_hash =
+        BlockHash(felt!("0x7d5db04c5ca2aea828180dc441afb1580e3cee7547a3567ced3aa5bb8b273c0"));
+    let state_diff_commitment = StateDiffCommitment(PoseidonHash(felt!(
+        "0x64689c12248e1110af4b3af0e2b43cd51ad13e8855f10e37669e2a4baf919c6"
+    )));
+    let signature = BlockSignature(Signature {
+        r: felt!("0x1b382bbfd693011c9b7692bc932b23ed9c288deb27c8e75772e172abbe5950c"),
+        s: felt!("0xbe4438085057e1a7c704a0da3b30f7b8340fe3d24c86772abfd24aa597e42"),
+    });
+    let sequencer_pub_key = SequencerPublicKey(PublicKey(felt!(
+        "0x48253ff2c3bed7af18bde0b611b083b39445959102d4947c51c4db6aa4f4e58"
+    )));
+    let bad_signature = BlockSignature(Signature { r: signature.0.s, s: signature.0.r });
+
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { verify_blocks: true, ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: Some(sequencer_pub_key),
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+    let block = Block {
+        header: BlockHeader {
+            block_hash,
+            state_diff_commitment: Some(state_diff_commitment),
+            ..Default::default()
+        },
+        body: BlockBody::default(),
+    };
+
+    let res = gen_state_sync.store_block(BlockNumber(0), block.clone(), &bad_signature);
+    assert_matches!(
+        res,
+        Err(StateSyncError::InvalidBlockSignature { block_number, signature, sequencer_pub_key: key })
+            if block_number == BlockNumber(0)
+                && signature == bad_signature
+                && key == sequencer_pub_key
+    );
+    assert_eq!(
+        gen_state_sync.reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+        BlockNumber(0)
+    );
+
+    gen_state_sync.store_block(BlockNumber(0), block, &signature).unwrap();
+    assert_eq!(
+        gen_state_sync.reader.begin_ro_txn().unwrap().get_block_signature(BlockNumber(0)).unwrap(),
+        Some(signature)
+    );
+}
+
+#[tokio::test]
+async fn store_block_with_state_diff_is_atomic() {
+    let block_hash = BlockHash(felt!("0x1"));
+    let mut central_mock = MockCentralSourceTrait::new();
+    let mut call_counter = 0;
+    central_mock.expect_stream_state_updates().returning(move |initial, _up_to| {
+        call_counter += 1;
+        // Simulates a failure between downloading the block and downloading its state diff.
+        let should_fail = call_counter == 1;
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            if should_fail {
+                yield Err(CentralError::StateUpdateNotFound);
+            } else {
+                yield Ok((initial, block_hash, StateDiff::default(), IndexMap::new()));
+            }
+        }
+        .boxed();
+        state_stream
+    });
+
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig {
+            verify_blocks: false,
+            atomic_block_and_state: true,
+            ..SyncConfig::default()
+        },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(central_mock),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+    let block = Block {
+        header: BlockHeader { block_hash, ..Default::default() },
+        body: BlockBody::default(),
+    };
+    let block_available = || SyncEvent::BlockAvailable {
+        block_number: BlockNumber(0),
+        block: block.clone(),
+        signature: BlockSignature::default(),
+    };
+
+    // No part of the block is stored if its state diff couldn't be stored.
+    let res = gen_state_sync.process_sync_event(block_available()).await;
+    assert_matches!(
+        res,
+        Err(StateSyncError::CentralSourceError(CentralError::StateUpdateNotFound))
+    );
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(0));
+    drop(txn);
+
+    gen_state_sync.process_sync_event(block_available()).await.unwrap();
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+}
+
+#[test]
+fn forced_resync_reverts_only_once() {
+    const N_BLOCKS: u64 = 5;
+    const RESYNC_FROM: BlockNumber = BlockNumber(2);
+    let (reader, mut writer) = get_test_storage().0;
+    add_headers(N_BLOCKS, &mut writer);
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { force_resync_from: Some(RESYNC_FROM), ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader: reader.clone(),
+        writer,
+        sequencer_pub_key: None,
+        pause_control: SyncPauseControl::default(),
+        checkpoint_callback: None,
+    };
+
+    gen_state_sync.revert_blocks_from(RESYNC_FROM).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), RESYNC_FROM);
+
+    // The reverted blocks are synced again, and then the node restarts with the same config.
+    for block_number in RESYNC_FROM.iter_up_to(BlockNumber(N_BLOCKS)) {
+        let header = BlockHeader {
+            block_header_without_hash: BlockHeaderWithoutHash {
+                block_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        gen_state_sync
+            .writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    gen_state_sync.revert_blocks_from(RESYNC_FROM).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(N_BLOCKS));
+}
+
+#[tokio::test]
+async fn stream_new_blocks_reports_blocks_behind_central() {
+    let _blocks_behind_central_guard = BLOCKS_BEHIND_CENTRAL_LOCK.lock().await;
+    let handle = PrometheusBuilder::new().install_recorder().unwrap();
+    let (reader, mut writer) = get_test_storage().0;
+
+    // The node has blocks [0, 3) and central has blocks [0, 10).
+    add_headers(3, &mut writer);
+    let mut mock = MockCentralSourceTrait::new();
+    mock.expect_get_latest_block().returning(|| {
+        Ok(Some(BlockHashAndNumber { hash: BlockHash::default(), number: BlockNumber(9) }))
+    });
+    mock.expect_stream_new_blocks().returning(|initial, _| {
+        stream! {
+            yield Ok((initial, Block::default(), BlockSignature::default()));
+        }
+        .boxed()
+    });
+
+    let mut stream = stream_new_blocks(
+        reader,
+        Arc::new(mock),
+        Arc::new(MockPendingSourceTrait::new()),
+        Arc::new(RwLock::new(None)),
+        Arc::new(RwLock::new(PendingData::default())),
+        Arc::new(RwLock::new(PendingClasses::default())),
+        BLOCK_PROPAGATION_SLEEP_DURATION,
+        false,
+        Duration::ZERO,
+        STREAM_SIZE,
+    )
+    .boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::BlockAvailable { block_number: BlockNumber(3), .. });
+    assert_eq!(
+        prometheus_is_contained(
+            handle.render(),
+            papyrus_metrics::PAPYRUS_BLOCKS_BEHIND_CENTRAL,
+            &[]
+        ),
+        Some(Gauge(7.0))
+    );
+}
+
 // Adds to the storage 'headers_num' headers.
 fn add_headers(headers_num: u64, writer: &mut StorageWriter) {
     for i in 0..headers_num {
@@ -789,3 +1483,73 @@ async fn pending_sync_classes_are_cleaned_on_first_pending_data_from_latest_bloc
     )
     .await
 }
+
+#[tokio::test]
+async fn pending_sync_discards_data_not_on_top_of_latest_stored_block() {
+    const FIRST_BLOCK_HASH: BlockHash = BlockHash(StarkHash::ONE);
+    const REORGED_FIRST_BLOCK_HASH: BlockHash = BlockHash(StarkHash::TWO);
+    let genesis_hash = BlockHash(felt!(GENESIS_HASH));
+    let first_block_header = move |block_hash| BlockHeader {
+        block_hash,
+        block_header_without_hash: BlockHeaderWithoutHash {
+            parent_hash: genesis_hash,
+            block_number: BlockNumber(0),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (reader, mut writer) = get_test_storage().0;
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &first_block_header(FIRST_BLOCK_HASH))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let mut rng = get_rng();
+
+    let old_pending_data = PendingData {
+        block: PendingBlockOrDeprecated::Deprecated(DeprecatedPendingBlock {
+            parent_block_hash: FIRST_BLOCK_HASH,
+            transactions: vec![ClientTransaction::get_test_instance(&mut rng)],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let stale_pending_data = PendingData {
+        block: PendingBlockOrDeprecated::Deprecated(DeprecatedPendingBlock {
+            parent_block_hash: FIRST_BLOCK_HASH,
+            transactions: vec![
+                ClientTransaction::get_test_instance(&mut rng),
+                ClientTransaction::get_test_instance(&mut rng),
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    // The first block is reorged while the pending data is being polled.
+    let mut mock_pending_source = MockPendingSourceTrait::new();
+    mock_pending_source.expect_get_pending_data().times(1).return_once(move || {
+        let (txn, _, _) = writer.begin_rw_txn().unwrap().revert_header(BlockNumber(0)).unwrap();
+        txn.append_header(BlockNumber(0), &first_block_header(REORGED_FIRST_BLOCK_HASH))
+            .unwrap()
+            .commit()
+            .unwrap();
+        Ok(stale_pending_data)
+    });
+    let pending_data_lock = Arc::new(RwLock::new(old_pending_data.clone()));
+
+    sync_pending_data(
+        reader,
+        Arc::new(MockCentralSourceTrait::new()),
+        Arc::new(mock_pending_source),
+        pending_data_lock.clone(),
+        Arc::new(RwLock::new(PendingClasses::default())),
+        Duration::ZERO,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(pending_data_lock.read().await.clone(), old_pending_data);
+}
@@ -20,7 +20,7 @@ use crate::core::{
 };
 use crate::crypto::utils::{verify_message_hash_signature, CryptoError, Signature};
 use crate::data_availability::L1DataAvailabilityMode;
-use crate::execution_resources::GasAmount;
+use crate::execution_resources::{GasAmount, GasVector};
 use crate::hash::StarkHash;
 use crate::serde_utils::{BytesAsHex, PrefixedBytesAsHex};
 use crate::transaction::fields::Fee;
@@ -36,6 +36,33 @@ pub struct Block {
     pub body: BlockBody,
 }
 
+impl Block {
+    /// Returns the total resources consumed by the transactions of the block, or None if the block
+    /// doesn't contain the outputs of all its transactions or the sum overflows.
+    pub fn resource_weight(&self) -> Option<BlockWeight> {
+        if self.body.transaction_outputs.len() != self.body.transactions.len() {
+            return None;
+        }
+        self.body.transaction_outputs.iter().try_fold(BlockWeight::default(), |weight, output| {
+            let resources = output.execution_resources();
+            Some(BlockWeight {
+                n_steps: weight.n_steps.checked_add(resources.steps)?,
+                gas_consumed: weight.gas_consumed.checked_add(resources.gas_consumed)?,
+                da_gas_consumed: weight.da_gas_consumed.checked_add(resources.da_gas_consumed)?,
+            })
+        })
+    }
+}
+
+/// The resources consumed by the transactions of a [Block](`crate::block::Block`), used to
+/// classify blocks by size.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockWeight {
+    pub n_steps: u64,
+    pub gas_consumed: GasVector,
+    pub da_gas_consumed: GasVector,
+}
+
 macro_rules! starknet_version_enum {
     (
         $(($variant:ident, $major:literal, $minor:literal, $patch:literal $(, $fourth:literal)?)),+,
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use serde::{Deserialize, Serialize};
@@ -118,7 +119,67 @@ pub fn calculate_block_hash(
     block_commitments: BlockHeaderCommitments,
 ) -> StarknetApiResult<BlockHash> {
     let block_hash_version: BlockHashVersion = header.starknet_version.try_into()?;
-    Ok(BlockHash(
+    let starknet_version_felt = starknet_version_as_felt(&header.starknet_version);
+    Ok(calculate_block_hash_inner(
+        header,
+        block_commitments,
+        block_hash_version,
+        starknet_version_felt,
+    ))
+}
+
+/// Calculates the hashes of many blocks, e.g. when verifying a range of synced blocks. Gives the
+/// same results as [calculate_block_hash], but the felts derived from each Starknet version are
+/// computed once instead of per block.
+#[derive(Debug, Default)]
+pub struct BlockHashComputer {
+    version_constants: HashMap<StarknetVersion, (BlockHashVersion, Felt)>,
+}
+
+impl BlockHashComputer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as [calculate_block_hash].
+    pub fn calculate_block_hash(
+        &mut self,
+        header: BlockHeaderWithoutHash,
+        block_commitments: BlockHeaderCommitments,
+    ) -> StarknetApiResult<BlockHash> {
+        let (block_hash_version, starknet_version_felt) =
+            match self.version_constants.get(&header.starknet_version) {
+                Some(version_constants) => version_constants.clone(),
+                None => {
+                    let version_constants = (
+                        header.starknet_version.try_into()?,
+                        starknet_version_as_felt(&header.starknet_version),
+                    );
+                    self.version_constants
+                        .insert(header.starknet_version, version_constants.clone());
+                    version_constants
+                }
+            };
+        Ok(calculate_block_hash_inner(
+            header,
+            block_commitments,
+            block_hash_version,
+            starknet_version_felt,
+        ))
+    }
+}
+
+fn starknet_version_as_felt(starknet_version: &StarknetVersion) -> Felt {
+    ascii_as_felt(&starknet_version.to_string()).expect("Expect ASCII version")
+}
+
+fn calculate_block_hash_inner(
+    header: BlockHeaderWithoutHash,
+    block_commitments: BlockHeaderCommitments,
+    block_hash_version: BlockHashVersion,
+    starknet_version_felt: Felt,
+) -> BlockHash {
+    BlockHash(
         HashChain::new()
             .chain(&block_hash_version.clone().into())
             .chain(&header.block_number.0.into())
@@ -139,13 +200,11 @@ pub fn calculate_block_hash(
                 )
                 .iter(),
             )
-            .chain(
-                &ascii_as_felt(&header.starknet_version.to_string()).expect("Expect ASCII version"),
-            )
+            .chain(&starknet_version_felt)
             .chain(&Felt::ZERO)
             .chain(&header.parent_hash.0)
             .get_poseidon_hash(),
-    ))
+    )
 }
 
 /// Calculates the commitments of the transactions data for the block hash.
@@ -12,6 +12,7 @@ use crate::block::{
 use crate::block_hash::block_hash_calculator::{
     calculate_block_commitments,
     calculate_block_hash,
+    BlockHashComputer,
     BlockHashVersion,
     BlockHeaderCommitments,
     TransactionHashingData,
@@ -175,3 +176,36 @@ fn change_field_of_hash_input() {
     );
     // TODO(Aviv, 10/06/2024): add tests that changes the first hash input, and the const zero.
 }
+
+#[test]
+fn block_hash_computer_matches_calculate_block_hash() {
+    let mut block_hash_computer = BlockHashComputer::new();
+    // Alternating versions, so that cached version constants are reused across blocks.
+    for block_number in 0..6_u8 {
+        let block_hash_version = if block_number % 2 == 0 {
+            BlockHashVersion::V0_13_2
+        } else {
+            BlockHashVersion::V0_13_4
+        };
+        let header = BlockHeaderWithoutHash {
+            block_number: BlockNumber(block_number.into()),
+            state_root: GlobalRoot(Felt::from(block_number)),
+            timestamp: BlockTimestamp(block_number.into()),
+            l2_gas_price: GasPricePerToken {
+                price_in_fri: block_number.into(),
+                price_in_wei: block_number.into(),
+            },
+            starknet_version: block_hash_version.into(),
+            parent_hash: BlockHash(Felt::from(block_number)),
+            ..Default::default()
+        };
+        let commitments = BlockHeaderCommitments {
+            concatenated_counts: Felt::from(block_number),
+            ..Default::default()
+        };
+        assert_eq!(
+            block_hash_computer.calculate_block_hash(header.clone(), commitments.clone()).unwrap(),
+            calculate_block_hash(header, commitments).unwrap()
+        );
+    }
+}
@@ -2,10 +2,18 @@ use serde_json::json;
 use strum::IntoEnumIterator;
 
 use super::{verify_block_signature, StarknetVersion};
-use crate::block::{BlockHash, BlockNumber, BlockSignature};
+use crate::block::{Block, BlockBody, BlockHash, BlockNumber, BlockSignature, BlockWeight};
 use crate::core::{GlobalRoot, SequencerPublicKey};
 use crate::crypto::utils::{PublicKey, Signature};
+use crate::execution_resources::{ExecutionResources, GasAmount, GasVector};
 use crate::felt;
+use crate::transaction::{
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    Transaction,
+    TransactionOutput,
+};
 
 #[test]
 fn test_block_number_iteration() {
@@ -80,3 +88,40 @@ fn test_latest_version() {
         assert!(version <= latest);
     }
 }
+
+#[test]
+fn block_resource_weight() {
+    let invoke_with_resources = |steps: u64, gas: u64, da_gas: u64| {
+        (
+            Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1::default())),
+            TransactionOutput::Invoke(InvokeTransactionOutput {
+                execution_resources: ExecutionResources {
+                    steps,
+                    gas_consumed: GasVector::from_l2_gas(GasAmount(gas)),
+                    da_gas_consumed: GasVector::from_l1_data_gas(GasAmount(da_gas)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+    };
+    let (transactions, transaction_outputs): (Vec<_>, Vec<_>) =
+        [invoke_with_resources(10, 100, 1), invoke_with_resources(20, 200, 2)].into_iter().unzip();
+    let mut block = Block {
+        body: BlockBody { transactions, transaction_outputs, ..Default::default() },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        block.resource_weight(),
+        Some(BlockWeight {
+            n_steps: 30,
+            gas_consumed: GasVector::from_l2_gas(GasAmount(300)),
+            da_gas_consumed: GasVector::from_l1_data_gas(GasAmount(3)),
+        })
+    );
+
+    // A block without the outputs of its transactions has no weight.
+    block.body.transaction_outputs.clear();
+    assert_eq!(block.resource_weight(), None);
+}
@@ -83,6 +83,11 @@ impl ChainId {
         format!("0x{}", hex::encode(self.to_string()))
     }
 
+    /// Returns the chain id as a felt, as used in transaction hashes.
+    pub fn as_felt(&self) -> Result<Felt, StarknetApiError> {
+        ascii_as_felt(self.to_string().as_str())
+    }
+
     #[cfg(any(feature = "testing", test))]
     pub fn create_for_testing() -> Self {
         const CHAIN_ID_NAME: &str = "SN_GOERLI";
@@ -1,3 +1,7 @@
+#[cfg(test)]
+#[path = "execution_resources_test.rs"]
+mod execution_resources_test;
+
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
@@ -271,3 +275,62 @@ impl Builtin {
         }
     }
 }
+
+/// The execution resources of a transaction, shaped as in the RPC transaction receipt. Conversions
+/// to and from [`ExecutionResources`] are defined here so that RPC versions don't each map the
+/// fields on their own.
+///
+/// Builtins with no applications and zero memory holes are omitted, and only the data
+/// availability gas is kept, since these are all the receipt reports.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
+pub struct ExecutionResourcesRpc {
+    pub steps: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_holes: Option<u64>,
+    #[serde(flatten)]
+    pub builtin_instance_counter: HashMap<Builtin, u64>,
+    pub data_availability: DataAvailabilityResourcesRpc,
+}
+
+/// The gas a transaction consumed for data availability, shaped as in the RPC transaction receipt.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, Eq, PartialEq)]
+pub struct DataAvailabilityResourcesRpc {
+    pub l1_gas: GasAmount,
+    pub l1_data_gas: GasAmount,
+}
+
+impl From<ExecutionResources> for ExecutionResourcesRpc {
+    fn from(execution_resources: ExecutionResources) -> Self {
+        Self {
+            steps: execution_resources.steps,
+            memory_holes: (execution_resources.memory_holes > 0)
+                .then_some(execution_resources.memory_holes),
+            builtin_instance_counter: execution_resources
+                .builtin_instance_counter
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+            data_availability: DataAvailabilityResourcesRpc {
+                l1_gas: execution_resources.da_gas_consumed.l1_gas,
+                l1_data_gas: execution_resources.da_gas_consumed.l1_data_gas,
+            },
+        }
+    }
+}
+
+impl From<ExecutionResourcesRpc> for ExecutionResources {
+    fn from(execution_resources: ExecutionResourcesRpc) -> Self {
+        Self {
+            steps: execution_resources.steps,
+            builtin_instance_counter: execution_resources.builtin_instance_counter,
+            memory_holes: execution_resources.memory_holes.unwrap_or_default(),
+            da_gas_consumed: GasVector {
+                l1_gas: execution_resources.data_availability.l1_gas,
+                l1_data_gas: execution_resources.data_availability.l1_data_gas,
+                l2_gas: GasAmount::ZERO,
+            },
+            // The receipt doesn't report the gas consumed for computation.
+            gas_consumed: GasVector::ZERO,
+        }
+    }
+}
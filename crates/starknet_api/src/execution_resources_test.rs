@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use super::{
+    Builtin,
+    DataAvailabilityResourcesRpc,
+    ExecutionResources,
+    ExecutionResourcesRpc,
+    GasAmount,
+    GasVector,
+};
+
+#[test]
+fn execution_resources_rpc_round_trip() {
+    let execution_resources = ExecutionResources {
+        steps: 100,
+        builtin_instance_counter: HashMap::from([
+            (Builtin::RangeCheck, 4),
+            (Builtin::Pedersen, 2),
+            (Builtin::Poseidon, 1),
+            (Builtin::SegmentArena, 3),
+        ]),
+        memory_holes: 7,
+        da_gas_consumed: GasVector {
+            l1_gas: GasAmount(5),
+            l1_data_gas: GasAmount(6),
+            l2_gas: GasAmount::ZERO,
+        },
+        gas_consumed: GasVector::ZERO,
+    };
+
+    let execution_resources_rpc = ExecutionResourcesRpc::from(execution_resources.clone());
+    assert_eq!(
+        serde_json::to_value(&execution_resources_rpc).unwrap(),
+        json!({
+            "steps": 100,
+            "memory_holes": 7,
+            "range_check_builtin_applications": 4,
+            "pedersen_builtin_applications": 2,
+            "poseidon_builtin_applications": 1,
+            "segment_arena_builtin": 3,
+            "data_availability": {"l1_gas": 5, "l1_data_gas": 6},
+        })
+    );
+    let deserialized: ExecutionResourcesRpc =
+        serde_json::from_value(serde_json::to_value(&execution_resources_rpc).unwrap()).unwrap();
+    assert_eq!(deserialized, execution_resources_rpc);
+    assert_eq!(ExecutionResources::from(execution_resources_rpc), execution_resources);
+}
+
+#[test]
+fn execution_resources_rpc_omits_empty_fields() {
+    let execution_resources = ExecutionResources {
+        steps: 1,
+        builtin_instance_counter: HashMap::from([(Builtin::Bitwise, 0)]),
+        ..Default::default()
+    };
+    assert_eq!(
+        ExecutionResourcesRpc::from(execution_resources),
+        ExecutionResourcesRpc {
+            steps: 1,
+            memory_holes: None,
+            builtin_instance_counter: HashMap::new(),
+            data_availability: DataAvailabilityResourcesRpc::default(),
+        }
+    );
+}
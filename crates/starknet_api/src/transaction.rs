@@ -267,10 +267,18 @@ impl TransactionHasher for DeclareTransactionV0V1 {
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
         if *transaction_version == TransactionVersion::ZERO {
-            return get_declare_transaction_v0_hash(self, chain_id, transaction_version);
+            return get_declare_transaction_v0_hash(
+                self,
+                &chain_id.as_felt()?,
+                transaction_version,
+            );
         }
         if *transaction_version == TransactionVersion::ONE {
-            return get_declare_transaction_v1_hash(self, chain_id, transaction_version);
+            return get_declare_transaction_v1_hash(
+                self,
+                &chain_id.as_felt()?,
+                transaction_version,
+            );
         }
         panic!("Illegal transaction version.");
     }
@@ -293,7 +301,7 @@ impl TransactionHasher for DeclareTransactionV2 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_declare_transaction_v2_hash(self, chain_id, transaction_version)
+        get_declare_transaction_v2_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -319,7 +327,7 @@ impl TransactionHasher for DeclareTransactionV3 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_declare_transaction_v3_hash(self, chain_id, transaction_version)
+        get_declare_transaction_v3_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -421,7 +429,7 @@ impl TransactionHasher for DeployAccountTransactionV1 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_deploy_account_transaction_v1_hash(self, chain_id, transaction_version)
+        get_deploy_account_transaction_v1_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -446,7 +454,7 @@ impl TransactionHasher for DeployAccountTransactionV3 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_deploy_account_transaction_v3_hash(self, chain_id, transaction_version)
+        get_deploy_account_transaction_v3_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -528,7 +536,7 @@ impl TransactionHasher for DeployTransaction {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_deploy_transaction_hash(self, chain_id, transaction_version)
+        get_deploy_transaction_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -548,7 +556,7 @@ impl TransactionHasher for InvokeTransactionV0 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_invoke_transaction_v0_hash(self, chain_id, transaction_version)
+        get_invoke_transaction_v0_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -568,7 +576,7 @@ impl TransactionHasher for InvokeTransactionV1 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_invoke_transaction_v1_hash(self, chain_id, transaction_version)
+        get_invoke_transaction_v1_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -593,7 +601,7 @@ impl TransactionHasher for InvokeTransactionV3 {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_invoke_transaction_v3_hash(self, chain_id, transaction_version)
+        get_invoke_transaction_v3_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -697,7 +705,7 @@ impl TransactionHasher for L1HandlerTransaction {
         chain_id: &ChainId,
         transaction_version: &TransactionVersion,
     ) -> Result<TransactionHash, StarknetApiError> {
-        get_l1_handler_transaction_hash(self, chain_id, transaction_version)
+        get_l1_handler_transaction_hash(self, &chain_id.as_felt()?, transaction_version)
     }
 }
 
@@ -60,6 +60,17 @@ pub fn get_transaction_hash(
     chain_id: &ChainId,
     transaction_options: &TransactionOptions,
 ) -> Result<TransactionHash, StarknetApiError> {
+    get_transaction_hash_with_chain_felt(transaction, chain_id.as_felt()?, transaction_options)
+}
+
+/// Same as [get_transaction_hash], with the chain id given as a felt (see [ChainId::as_felt]).
+/// Saves converting the chain id when hashing many transactions of the same chain.
+pub fn get_transaction_hash_with_chain_felt(
+    transaction: &Transaction,
+    chain_id_felt: Felt,
+    transaction_options: &TransactionOptions,
+) -> Result<TransactionHash, StarknetApiError> {
+    let chain_id = &chain_id_felt;
     let transaction_version = &signed_tx_version_from_tx(transaction, transaction_options);
     match transaction {
         Transaction::Declare(declare) => match declare {
@@ -127,6 +138,7 @@ fn get_deprecated_transaction_hashes(
     Ok(if chain_id == &ChainId::Mainnet && block_number > &MAINNET_TRANSACTION_HASH_WITH_VERSION {
         vec![]
     } else {
+        let chain_id = &chain_id.as_felt()?;
         match transaction {
             Transaction::Declare(_) => vec![],
             Transaction::Deploy(deploy) => {
@@ -236,7 +248,7 @@ fn data_availability_mode_index(mode: &DataAvailabilityMode) -> u64 {
 
 pub(crate) fn get_deploy_transaction_hash(
     transaction: &DeployTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     get_common_deploy_transaction_hash(transaction, chain_id, false, transaction_version)
@@ -244,7 +256,7 @@ pub(crate) fn get_deploy_transaction_hash(
 
 fn get_deprecated_deploy_transaction_hash(
     transaction: &DeployTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     get_common_deploy_transaction_hash(transaction, chain_id, true, transaction_version)
@@ -252,7 +264,7 @@ fn get_deprecated_deploy_transaction_hash(
 
 fn get_common_deploy_transaction_hash(
     transaction: &DeployTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     is_deprecated: bool,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
@@ -288,14 +300,14 @@ fn get_common_deploy_transaction_hash(
                 None
             }
         })
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .get_pedersen_hash(),
     ))
 }
 
 pub(crate) fn get_invoke_transaction_v0_hash(
     transaction: &InvokeTransactionV0,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     get_common_invoke_transaction_v0_hash(transaction, chain_id, false, transaction_version)
@@ -303,7 +315,7 @@ pub(crate) fn get_invoke_transaction_v0_hash(
 
 fn get_deprecated_invoke_transaction_v0_hash(
     transaction: &InvokeTransactionV0,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     get_common_invoke_transaction_v0_hash(transaction, chain_id, true, transaction_version)
@@ -311,7 +323,7 @@ fn get_deprecated_invoke_transaction_v0_hash(
 
 fn get_common_invoke_transaction_v0_hash(
     transaction: &InvokeTransactionV0,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     is_deprecated: bool,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
@@ -323,14 +335,14 @@ fn get_common_invoke_transaction_v0_hash(
             .chain(&transaction.entry_point_selector.0)
             .chain(&HashChain::new().chain_iter(transaction.calldata.0.iter()).get_pedersen_hash())
             .chain_if_fn(|| if !is_deprecated { Some(transaction.max_fee.0.into()) } else { None })
-            .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+            .chain(chain_id)
             .get_pedersen_hash(),
     ))
 }
 
 pub(crate) fn get_invoke_transaction_v1_hash(
     transaction: &InvokeTransactionV1,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     Ok(TransactionHash(
@@ -341,7 +353,7 @@ pub(crate) fn get_invoke_transaction_v1_hash(
         .chain(&Felt::ZERO) // No entry point selector in invoke transaction.
         .chain(&HashChain::new().chain_iter(transaction.calldata.0.iter()).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -349,7 +361,7 @@ pub(crate) fn get_invoke_transaction_v1_hash(
 
 pub(crate) fn get_invoke_transaction_v3_hash(
     transaction: &InvokeTransactionV3,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     let tip_resource_bounds_hash =
@@ -373,7 +385,7 @@ pub(crate) fn get_invoke_transaction_v3_hash(
             .chain(transaction.sender_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+            .chain(chain_id)
             .chain(&transaction.nonce.0)
             .chain(&data_availability_mode)
             .chain(&account_deployment_data_hash)
@@ -391,7 +403,7 @@ enum L1HandlerVersions {
 
 pub(crate) fn get_l1_handler_transaction_hash(
     transaction: &L1HandlerTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     get_common_l1_handler_transaction_hash(
@@ -404,7 +416,7 @@ pub(crate) fn get_l1_handler_transaction_hash(
 
 fn get_deprecated_l1_handler_transaction_hashes(
     transaction: &L1HandlerTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<Vec<TransactionHash>, StarknetApiError> {
     Ok(vec![
@@ -425,7 +437,7 @@ fn get_deprecated_l1_handler_transaction_hashes(
 
 fn get_common_l1_handler_transaction_hash(
     transaction: &L1HandlerTransaction,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     version: L1HandlerVersions,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
@@ -456,7 +468,7 @@ fn get_common_l1_handler_transaction_hash(
                 None
             }
         })
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain_if_fn(|| {
             if version > L1HandlerVersions::AsInvoke {
                 Some(transaction.nonce.0)
@@ -470,7 +482,7 @@ fn get_common_l1_handler_transaction_hash(
 
 pub(crate) fn get_declare_transaction_v0_hash(
     transaction: &DeclareTransactionV0V1,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     Ok(TransactionHash(
@@ -481,7 +493,7 @@ pub(crate) fn get_declare_transaction_v0_hash(
         .chain(&Felt::ZERO) // No entry point selector in declare transaction.
         .chain(&HashChain::new().get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain(&transaction.class_hash.0)
         .get_pedersen_hash(),
     ))
@@ -489,7 +501,7 @@ pub(crate) fn get_declare_transaction_v0_hash(
 
 pub(crate) fn get_declare_transaction_v1_hash(
     transaction: &DeclareTransactionV0V1,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     Ok(TransactionHash(
@@ -500,7 +512,7 @@ pub(crate) fn get_declare_transaction_v1_hash(
         .chain(&Felt::ZERO) // No entry point selector in declare transaction.
         .chain(&HashChain::new().chain(&transaction.class_hash.0).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -508,7 +520,7 @@ pub(crate) fn get_declare_transaction_v1_hash(
 
 pub(crate) fn get_declare_transaction_v2_hash(
     transaction: &DeclareTransactionV2,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     Ok(TransactionHash(
@@ -519,7 +531,7 @@ pub(crate) fn get_declare_transaction_v2_hash(
         .chain(&Felt::ZERO) // No entry point selector in declare transaction.
         .chain(&HashChain::new().chain(&transaction.class_hash.0).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain(&transaction.nonce.0)
         .chain(&transaction.compiled_class_hash.0)
         .get_pedersen_hash(),
@@ -528,7 +540,7 @@ pub(crate) fn get_declare_transaction_v2_hash(
 
 pub(crate) fn get_declare_transaction_v3_hash(
     transaction: &DeclareTransactionV3,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     let tip_resource_bounds_hash =
@@ -550,7 +562,7 @@ pub(crate) fn get_declare_transaction_v3_hash(
             .chain(transaction.sender_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+            .chain(chain_id)
             .chain(&transaction.nonce.0)
             .chain(&data_availability_mode)
             .chain(&account_deployment_data_hash)
@@ -562,7 +574,7 @@ pub(crate) fn get_declare_transaction_v3_hash(
 
 pub(crate) fn get_deploy_account_transaction_v1_hash(
     transaction: &DeployAccountTransactionV1,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     let calldata_hash = HashChain::new()
@@ -586,7 +598,7 @@ pub(crate) fn get_deploy_account_transaction_v1_hash(
         .chain(&Felt::ZERO) // No entry point selector in deploy account transaction.
         .chain(&calldata_hash)
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+        .chain(chain_id)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -594,7 +606,7 @@ pub(crate) fn get_deploy_account_transaction_v1_hash(
 
 pub(crate) fn get_deploy_account_transaction_v3_hash(
     transaction: &DeployAccountTransactionV3,
-    chain_id: &ChainId,
+    chain_id: &Felt,
     transaction_version: &TransactionVersion,
 ) -> Result<TransactionHash, StarknetApiError> {
     let contract_address = calculate_contract_address(
@@ -621,7 +633,7 @@ pub(crate) fn get_deploy_account_transaction_v3_hash(
             .chain(contract_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.to_string().as_str())?)
+            .chain(chain_id)
             .chain(&data_availability_mode)
             .chain(&transaction.nonce.0)
             .chain(&constructor_calldata_hash)
@@ -2,7 +2,12 @@ use pretty_assertions::assert_eq;
 use sha3::{Digest, Keccak256};
 use starknet_types_core::felt::Felt;
 
-use super::{get_transaction_hash, validate_transaction_hash, CONSTRUCTOR_ENTRY_POINT_SELECTOR};
+use super::{
+    get_transaction_hash,
+    get_transaction_hash_with_chain_felt,
+    validate_transaction_hash,
+    CONSTRUCTOR_ENTRY_POINT_SELECTOR,
+};
 use crate::test_utils::{read_json_file, TransactionTestData};
 use crate::transaction::{Transaction, TransactionOptions};
 
@@ -97,3 +102,37 @@ fn test_only_query_transaction_hash() {
         );
     }
 }
+
+#[test]
+fn test_transaction_hash_with_chain_felt() {
+    let transactions_test_data_vec: Vec<TransactionTestData> =
+        serde_json::from_value(read_json_file("transaction_hash.json")).unwrap();
+
+    for transaction_test_data in transactions_test_data_vec {
+        let chain_id_felt = transaction_test_data.chain_id.as_felt().unwrap();
+        for transaction_options in
+            [TransactionOptions::default(), TransactionOptions { only_query: true }]
+        {
+            // L1Handler only-query transactions are not supported.
+            if transaction_options.only_query
+                && matches!(transaction_test_data.transaction, Transaction::L1Handler(_))
+            {
+                continue;
+            }
+            assert_eq!(
+                get_transaction_hash_with_chain_felt(
+                    &transaction_test_data.transaction,
+                    chain_id_felt,
+                    &transaction_options,
+                )
+                .unwrap(),
+                get_transaction_hash(
+                    &transaction_test_data.transaction,
+                    &transaction_test_data.chain_id,
+                    &transaction_options,
+                )
+                .unwrap(),
+            );
+        }
+    }
+}
@@ -122,7 +122,10 @@ impl StarknetClient {
                     Some(RetryErrorCode::Timeout)
                 }
                 StatusCode::TOO_MANY_REQUESTS => Some(RetryErrorCode::TooManyRequests),
-                StatusCode::SERVICE_UNAVAILABLE => Some(RetryErrorCode::ServiceUnavailable),
+                // Transient errors of the feeder gateway or of the proxies in front of it.
+                StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => {
+                    Some(RetryErrorCode::ServiceUnavailable)
+                }
                 _ => None,
             },
 
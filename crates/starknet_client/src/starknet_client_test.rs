@@ -82,6 +82,7 @@ async fn request_with_retry_max_retries_reached() {
         (StatusCode::TEMPORARY_REDIRECT, RetryErrorCode::Redirect),
         (StatusCode::REQUEST_TIMEOUT, RetryErrorCode::Timeout),
         (StatusCode::TOO_MANY_REQUESTS, RetryErrorCode::TooManyRequests),
+        (StatusCode::BAD_GATEWAY, RetryErrorCode::ServiceUnavailable),
         (StatusCode::SERVICE_UNAVAILABLE, RetryErrorCode::ServiceUnavailable),
         (StatusCode::GATEWAY_TIMEOUT, RetryErrorCode::Timeout),
     ] {
@@ -109,6 +110,7 @@ async fn request_with_retry_success_on_retry() {
         StatusCode::TEMPORARY_REDIRECT,
         StatusCode::REQUEST_TIMEOUT,
         StatusCode::TOO_MANY_REQUESTS,
+        StatusCode::BAD_GATEWAY,
         StatusCode::SERVICE_UNAVAILABLE,
         StatusCode::GATEWAY_TIMEOUT,
     ] {
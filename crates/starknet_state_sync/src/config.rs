@@ -1,8 +1,8 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use papyrus_config::dumping::{append_sub_config_name, SerializeConfig};
-use papyrus_config::{ParamPath, SerializedParam};
+use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_network::NetworkConfig;
 use papyrus_p2p_sync::client::P2PSyncClientConfig;
 use papyrus_storage::db::DbConfig;
@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 const STATE_SYNC_TCP_PORT: u16 = 12345;
+const DEFAULT_CLASS_HASH_CACHE_SIZE: usize = 1000;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Validate)]
 pub struct StateSyncConfig {
@@ -20,11 +21,19 @@ pub struct StateSyncConfig {
     pub p2p_sync_client_config: P2PSyncClientConfig,
     #[validate]
     pub network_config: NetworkConfig,
+    #[validate(range(min = 1))]
+    pub class_hash_cache_size: usize,
 }
 
 impl SerializeConfig for StateSyncConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
         vec![
+            BTreeMap::from([ser_param(
+                "class_hash_cache_size",
+                &self.class_hash_cache_size,
+                "Size of the cache of class hash lookups, must be a positive integer.",
+                ParamPrivacyInput::Public,
+            )]),
             append_sub_config_name(self.storage_config.dump(), "storage_config"),
             append_sub_config_name(self.p2p_sync_client_config.dump(), "p2p_sync_client_config"),
             append_sub_config_name(self.network_config.dump(), "network_config"),
@@ -47,6 +56,7 @@ impl Default for StateSyncConfig {
             },
             p2p_sync_client_config: Default::default(),
             network_config: NetworkConfig { tcp_port: STATE_SYNC_TCP_PORT, ..Default::default() },
+            class_hash_cache_size: DEFAULT_CLASS_HASH_CACHE_SIZE,
         }
     }
 }
@@ -3,40 +3,74 @@ pub mod runner;
 #[cfg(test)]
 mod test;
 
+use std::num::NonZeroUsize;
+
 use async_trait::async_trait;
 use futures::channel::mpsc::{channel, Sender};
 use futures::SinkExt;
+use lru::LruCache;
 use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::{StateReader, StateStorageReader};
 use papyrus_storage::{StorageReader, StorageTxn};
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::contract_class::{ContractClass, SierraVersion};
-use starknet_api::core::{ClassHash, ContractAddress, Nonce, BLOCK_HASH_TABLE_ADDRESS};
-use starknet_api::state::{StateNumber, StorageKey};
+use starknet_api::core::{ClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
 use starknet_sequencer_infra::component_definitions::{ComponentRequestHandler, ComponentStarter};
 use starknet_sequencer_infra::component_server::{LocalComponentServer, RemoteComponentServer};
 use starknet_state_sync_types::communication::{StateSyncRequest, StateSyncResponse};
 use starknet_state_sync_types::errors::StateSyncError;
-use starknet_state_sync_types::state_sync_types::{StateSyncResult, SyncBlock};
+use starknet_state_sync_types::state_sync_types::{
+    NewBlockSubscription,
+    StateSyncResult,
+    SyncBlock,
+};
 use starknet_types_core::felt::Felt;
+use tokio::sync::broadcast;
 
 use crate::config::StateSyncConfig;
 use crate::runner::StateSyncRunner;
 
 const BUFFER_SIZE: usize = 100000;
+// Subscribers that fall behind by more than this many blocks lose the oldest notifications.
+const NEW_BLOCK_NOTIFICATIONS_BUFFER_SIZE: usize = 1000;
 
 pub fn create_state_sync_and_runner(config: StateSyncConfig) -> (StateSync, StateSyncRunner) {
     let (new_block_sender, new_block_receiver) = channel(BUFFER_SIZE);
-    let (state_sync_runner, storage_reader) = StateSyncRunner::new(config, new_block_receiver);
-    (StateSync { storage_reader, new_block_sender }, state_sync_runner)
+    // The config validates that the size is positive.
+    let class_hash_cache =
+        LruCache::new(NonZeroUsize::new(config.class_hash_cache_size).unwrap_or(NonZeroUsize::MIN));
+    let (new_block_notification_sender, _) =
+        broadcast::channel(NEW_BLOCK_NOTIFICATIONS_BUFFER_SIZE);
+    let (state_sync_runner, storage_reader) =
+        StateSyncRunner::new(config, new_block_receiver, new_block_notification_sender.clone());
+    (
+        StateSync {
+            storage_reader,
+            new_block_sender,
+            new_block_notification_sender,
+            pending_state_diff: None,
+            class_hash_cache,
+        },
+        state_sync_runner,
+    )
 }
 
 pub struct StateSync {
     storage_reader: StorageReader,
     new_block_sender: Sender<(BlockNumber, SyncBlock)>,
+    // Fed by the P2P sync client with the number of every block whose state diff it commits.
+    new_block_notification_sender: broadcast::Sender<BlockNumber>,
+    // The number and state diff of the pending block. It's used only while the block right before
+    // it is the latest synced block, so that it's never applied on top of a different state.
+    pending_state_diff: Option<(BlockNumber, ThinStateDiff)>,
+    // The class hash of a contract at a given block never changes unless the block is reverted.
+    // Each entry holds the hash of the block it was read at, so that it isn't served after the
+    // block is reverted and a different block is synced in its place.
+    class_hash_cache: LruCache<(BlockNumber, ContractAddress), (BlockHash, ClassHash)>,
 }
 
 // TODO(shahak): Have StateSyncRunner call StateSync instead of the opposite once we stop supporting
@@ -79,6 +113,24 @@ impl ComponentRequestHandler<StateSyncRequest, StateSyncResponse> for StateSync
             StateSyncRequest::GetLatestBlockNumber() => {
                 StateSyncResponse::GetLatestBlockNumber(self.get_latest_block_number())
             }
+            StateSyncRequest::SetPendingStateDiff(block_number, state_diff) => {
+                self.pending_state_diff = Some((block_number, state_diff));
+                StateSyncResponse::SetPendingStateDiff(Ok(()))
+            }
+            StateSyncRequest::GetPendingStorageAt(contract_address, storage_key) => {
+                StateSyncResponse::GetPendingStorageAt(
+                    self.get_pending_storage_at(contract_address, storage_key),
+                )
+            }
+            StateSyncRequest::GetPendingNonceAt(contract_address) => {
+                StateSyncResponse::GetPendingNonceAt(self.get_pending_nonce_at(contract_address))
+            }
+            StateSyncRequest::GetStateCommitment(block_number) => {
+                StateSyncResponse::GetStateCommitment(self.get_state_commitment(block_number))
+            }
+            StateSyncRequest::SubscribeNewBlocks { is_local } => {
+                StateSyncResponse::SubscribeNewBlocks(self.subscribe_new_blocks(is_local))
+            }
         }
     }
 }
@@ -87,20 +139,39 @@ impl StateSync {
     fn get_block(&self, block_number: BlockNumber) -> StateSyncResult<Option<SyncBlock>> {
         let txn = self.storage_reader.begin_ro_txn()?;
         let block_header = txn.get_block_header(block_number)?;
-        let Some(block_transaction_hashes) = txn.get_block_transaction_hashes(block_number)? else {
-            return Ok(None);
-        };
-        let Some(thin_state_diff) = txn.get_state_diff(block_number)? else {
+        let block_transaction_hashes = txn.get_block_transaction_hashes(block_number)?;
+        let thin_state_diff = txn.get_state_diff(block_number)?;
+        let (block_header, block_transaction_hashes, thin_state_diff) =
+            match (block_header, block_transaction_hashes, thin_state_diff) {
+                (Some(block_header), Some(block_transaction_hashes), Some(thin_state_diff)) => {
+                    return Ok(Some(SyncBlock {
+                        state_diff: thin_state_diff,
+                        block_header_without_hash: block_header.block_header_without_hash,
+                        transaction_hashes: block_transaction_hashes,
+                    }));
+                }
+                parts => parts,
+            };
+
+        // The sync downloads the parts of a block at different times, so a block whose body or
+        // state diff wasn't downloaded yet is simply not synced. A part is missing only if its
+        // marker is past the block. The header is downloaded before the other parts.
+        let is_body_synced = block_number < txn.get_body_marker()?;
+        let is_state_diff_synced = block_number < txn.get_state_marker()?;
+        let is_header_synced =
+            block_number < txn.get_header_marker()? || is_body_synced || is_state_diff_synced;
+        let missing = [
+            (is_header_synced && block_header.is_none(), "header"),
+            (is_body_synced && block_transaction_hashes.is_none(), "transactions"),
+            (is_state_diff_synced && thin_state_diff.is_none(), "state diff"),
+        ]
+        .into_iter()
+        .filter_map(|(is_missing, component)| is_missing.then_some(component))
+        .collect::<Vec<_>>();
+        if missing.is_empty() {
             return Ok(None);
-        };
-        let Some(block_header) = block_header else {
-            return Ok(None);
-        };
-        Ok(Some(SyncBlock {
-            state_diff: thin_state_diff,
-            block_header_without_hash: block_header.block_header_without_hash,
-            transaction_hashes: block_transaction_hashes,
-        }))
+        }
+        Err(StateSyncError::StorageInconsistency { block_number, missing: missing.join(", ") })
     }
 
     fn get_storage_at(
@@ -143,18 +214,30 @@ impl StateSync {
     }
 
     fn get_class_hash_at(
-        &self,
+        &mut self,
         block_number: BlockNumber,
         contract_address: ContractAddress,
     ) -> StateSyncResult<ClassHash> {
         let txn = self.storage_reader.begin_ro_txn()?;
         verify_synced_up_to(&txn, block_number)?;
 
+        let block_hash = txn.get_block_header(block_number)?.map(|header| header.block_hash);
+        if let (Some(block_hash), Some((cached_block_hash, class_hash))) =
+            (block_hash, self.class_hash_cache.get(&(block_number, contract_address)))
+        {
+            if block_hash == *cached_block_hash {
+                return Ok(*class_hash);
+            }
+        }
+
         let state_number = StateNumber::unchecked_right_after_block(block_number);
         let state_reader = txn.get_state_reader()?;
         let class_hash = state_reader
             .get_class_hash_at(state_number, &contract_address)?
             .ok_or(StateSyncError::ContractNotFound(contract_address))?;
+        if let Some(block_hash) = block_hash {
+            self.class_hash_cache.put((block_number, contract_address), (block_hash, class_hash));
+        }
         Ok(class_hash)
     }
 
@@ -199,6 +282,89 @@ impl StateSync {
         let latest_block_number = txn.get_state_marker()?.prev();
         Ok(latest_block_number)
     }
+
+    // Returns the pending state diff if it's on top of the given state marker.
+    fn pending_state_diff_at(&self, state_marker: BlockNumber) -> Option<&ThinStateDiff> {
+        self.pending_state_diff
+            .as_ref()
+            .filter(|(pending_block_number, _)| *pending_block_number == state_marker)
+            .map(|(_, pending_state_diff)| pending_state_diff)
+    }
+
+    fn get_pending_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        storage_key: StorageKey,
+    ) -> StateSyncResult<Felt> {
+        let txn = self.storage_reader.begin_ro_txn()?;
+        let state_marker = txn.get_state_marker()?;
+        let pending_state_diff = self.pending_state_diff_at(state_marker);
+        if let Some(value) = pending_state_diff
+            .and_then(|pending_state_diff| pending_state_diff.storage_diffs.get(&contract_address))
+            .and_then(|storage_entries| storage_entries.get(&storage_key))
+        {
+            return Ok(*value);
+        }
+
+        let state_number = StateNumber(state_marker);
+        let state_reader = txn.get_state_reader()?;
+
+        let deployed_in_pending = pending_state_diff.is_some_and(|pending_state_diff| {
+            pending_state_diff.deployed_contracts.contains_key(&contract_address)
+        });
+        if !deployed_in_pending {
+            verify_contract_deployed(&state_reader, state_number, contract_address)?;
+        }
+
+        let res = state_reader.get_storage_at(state_number, &contract_address, &storage_key)?;
+
+        Ok(res)
+    }
+
+    fn get_pending_nonce_at(&self, contract_address: ContractAddress) -> StateSyncResult<Nonce> {
+        let txn = self.storage_reader.begin_ro_txn()?;
+        let state_marker = txn.get_state_marker()?;
+        if let Some(pending_state_diff) = self.pending_state_diff_at(state_marker) {
+            if let Some(nonce) = pending_state_diff.nonces.get(&contract_address) {
+                return Ok(*nonce);
+            }
+            // A contract deployed in the pending block without a nonce update has the default
+            // nonce.
+            if pending_state_diff.deployed_contracts.contains_key(&contract_address) {
+                return Ok(Nonce::default());
+            }
+        }
+
+        let state_number = StateNumber(state_marker);
+        let state_reader = txn.get_state_reader()?;
+
+        verify_contract_deployed(&state_reader, state_number, contract_address)?;
+
+        let res = state_reader
+            .get_nonce_at(state_number, &contract_address)?
+            .ok_or(StateSyncError::ContractNotFound(contract_address))?;
+
+        Ok(res)
+    }
+
+    fn get_state_commitment(&self, block_number: BlockNumber) -> StateSyncResult<GlobalRoot> {
+        let txn = self.storage_reader.begin_ro_txn()?;
+        verify_synced_up_to(&txn, block_number)?;
+
+        let block_header = txn
+            .get_block_header(block_number)?
+            .ok_or(StateSyncError::BlockNotFound(block_number))?;
+        Ok(block_header.block_header_without_hash.state_root)
+    }
+
+    // A channel can't be sent over the network, so requests from a remote client are rejected
+    // instead of failing to serialize the response.
+    fn subscribe_new_blocks(&self, is_local: bool) -> StateSyncResult<NewBlockSubscription> {
+        if !is_local {
+            return Err(StateSyncError::RemoteSubscription);
+        }
+        Ok(NewBlockSubscription(self.new_block_notification_sender.subscribe()))
+    }
 }
 
 fn verify_synced_up_to<Mode: TransactionKind>(
@@ -219,14 +385,9 @@ fn verify_contract_deployed<Mode: TransactionKind>(
     state_number: StateNumber,
     contract_address: ContractAddress,
 ) -> Result<(), StateSyncError> {
-    // Contract address 0x1 is a special address, it stores the block
-    // hashes. Contracts are not deployed to this address.
-    if contract_address != BLOCK_HASH_TABLE_ADDRESS {
-        // check if the contract is deployed
-        state_reader
-            .get_class_hash_at(state_number, &contract_address)?
-            .ok_or(StateSyncError::ContractNotFound(contract_address))?;
-    };
+    if !state_reader.is_contract_deployed(state_number, &contract_address)? {
+        return Err(StateSyncError::ContractNotFound(contract_address));
+    }
 
     Ok(())
 }
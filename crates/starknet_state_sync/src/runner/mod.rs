@@ -16,6 +16,7 @@ use starknet_sequencer_infra::component_definitions::ComponentStarter;
 use starknet_sequencer_infra::component_server::WrapperServer;
 use starknet_sequencer_infra::errors::ComponentError;
 use starknet_state_sync_types::state_sync_types::SyncBlock;
+use tokio::sync::broadcast;
 
 use crate::config::StateSyncConfig;
 
@@ -47,6 +48,7 @@ impl StateSyncRunner {
     pub fn new(
         config: StateSyncConfig,
         new_block_receiver: Receiver<(BlockNumber, SyncBlock)>,
+        new_block_notification_sender: broadcast::Sender<BlockNumber>,
     ) -> (Self, StorageReader) {
         let (storage_reader, storage_writer) =
             open_storage(config.storage_config).expect("StateSyncRunner failed opening storage");
@@ -76,6 +78,7 @@ impl StateSyncRunner {
             storage_writer,
             p2p_sync_client_channels,
             new_block_receiver.boxed(),
+            Some(new_block_notification_sender),
         );
 
         let header_server_receiver = network_manager
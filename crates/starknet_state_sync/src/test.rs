@@ -1,6 +1,9 @@
+use std::num::NonZeroUsize;
+
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures::channel::mpsc::channel;
 use indexmap::IndexMap;
+use lru::LruCache;
 use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::CasmStorageWriter;
@@ -10,24 +13,46 @@ use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::StorageWriter;
 use papyrus_test_utils::{get_rng, get_test_block, get_test_state_diff, GetTestInstance};
 use rand_chacha::rand_core::RngCore;
-use starknet_api::block::{Block, BlockHeader, BlockNumber};
+use starknet_api::block::{Block, BlockHash, BlockHeader, BlockHeaderWithoutHash, BlockNumber};
 use starknet_api::contract_class::{ContractClass, SierraVersion};
-use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{SierraContractClass, StorageKey, ThinStateDiff};
 use starknet_sequencer_infra::component_definitions::ComponentRequestHandler;
+use starknet_sequencer_infra::serde_utils::SerdeWrapper;
 use starknet_state_sync_types::communication::{StateSyncRequest, StateSyncResponse};
 use starknet_state_sync_types::errors::StateSyncError;
+use starknet_state_sync_types::state_sync_types::NewBlockSubscription;
 use starknet_types_core::felt::Felt;
+use tokio::sync::broadcast;
+use validator::Validate;
 
+use crate::config::StateSyncConfig;
 use crate::StateSync;
 
+const CLASS_HASH_CACHE_SIZE: usize = 10;
+
 fn setup() -> (StateSync, StorageWriter) {
     let ((storage_reader, storage_writer), _) = get_test_storage();
-    let state_sync = StateSync { storage_reader, new_block_sender: channel(0).0 };
+    let state_sync = StateSync {
+        storage_reader,
+        new_block_sender: channel(0).0,
+        new_block_notification_sender: broadcast::channel(1).0,
+        pending_state_diff: None,
+        class_hash_cache: LruCache::new(NonZeroUsize::new(CLASS_HASH_CACHE_SIZE).unwrap()),
+    };
     (state_sync, storage_writer)
 }
 
+#[test]
+fn config_validation() {
+    let mut config = StateSyncConfig::default();
+    assert!(config.validate().is_ok());
+
+    config.class_hash_cache_size = 0;
+    assert!(config.validate().is_err());
+}
+
 #[tokio::test]
 async fn test_get_block() {
     let (mut state_sync, mut storage_writer) = setup();
@@ -67,6 +92,53 @@ async fn test_get_block() {
     assert_eq!(block.transaction_hashes[0], expected_body.transaction_hashes[0]);
 }
 
+#[tokio::test]
+async fn test_get_block_partially_synced() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    let Block { header, .. } = get_test_block(1, None, None, None);
+    let block_number = header.block_header_without_hash.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The body wasn't synced yet, so the block isn't synced.
+    let response = state_sync.handle_request(StateSyncRequest::GetBlock(block_number)).await;
+    let StateSyncResponse::GetBlock(Ok(None)) = response else {
+        panic!("Expected StateSyncResponse::GetBlock::Ok(None), but got {:?}", response);
+    };
+}
+
+#[tokio::test]
+async fn test_get_block_storage_inconsistency() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    // The state marker is past the block while its header is missing.
+    let block_number = BlockNumber(0);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let response = state_sync.handle_request(StateSyncRequest::GetBlock(block_number)).await;
+    let StateSyncResponse::GetBlock(Err(error)) = response else {
+        panic!("Expected StateSyncResponse::GetBlock::Err(_), but got {:?}", response);
+    };
+    assert_eq!(
+        error,
+        StateSyncError::StorageInconsistency { block_number, missing: "header".to_string() }
+    );
+}
+
 #[tokio::test]
 async fn test_get_storage_at() {
     let (mut state_sync, mut storage_writer) = setup();
@@ -143,6 +215,152 @@ async fn test_get_nonce_at() {
     assert_eq!(nonce, expected_nonce);
 }
 
+#[tokio::test]
+async fn test_get_pending_storage_at() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    let mut rng = get_rng();
+    let address = ContractAddress::from(rng.next_u64());
+    let overridden_key = StorageKey::from(rng.next_u64());
+    let committed_key = StorageKey::from(rng.next_u64());
+    let committed_value = Felt::from(rng.next_u64());
+    let pending_value = Felt::from(rng.next_u64());
+    let mut diff = ThinStateDiff::from(get_test_state_diff());
+    diff.storage_diffs.insert(
+        address,
+        IndexMap::from([(overridden_key, committed_value), (committed_key, committed_value)]),
+    );
+    diff.deployed_contracts.insert(address, Default::default());
+    let header = BlockHeader::default();
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_header_without_hash.block_number, &header)
+        .unwrap()
+        .append_state_diff(header.block_header_without_hash.block_number, diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let pending_state_diff = ThinStateDiff {
+        storage_diffs: IndexMap::from([(
+            address,
+            IndexMap::from([(overridden_key, pending_value)]),
+        )]),
+        ..Default::default()
+    };
+    let response = state_sync
+        .handle_request(StateSyncRequest::SetPendingStateDiff(BlockNumber(1), pending_state_diff))
+        .await;
+    let StateSyncResponse::SetPendingStateDiff(Ok(())) = response else {
+        panic!("Expected StateSyncResponse::SetPendingStateDiff::Ok(()), but got {:?}", response);
+    };
+
+    // Verify that the pending value overrides the committed one.
+    let response = state_sync
+        .handle_request(StateSyncRequest::GetPendingStorageAt(address, overridden_key))
+        .await;
+    let StateSyncResponse::GetPendingStorageAt(Ok(value)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingStorageAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(value, pending_value);
+
+    // Verify that a slot the pending block didn't modify falls back to the committed value.
+    let response = state_sync
+        .handle_request(StateSyncRequest::GetPendingStorageAt(address, committed_key))
+        .await;
+    let StateSyncResponse::GetPendingStorageAt(Ok(value)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingStorageAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(value, committed_value);
+
+    // Verify that the pending state diff is ignored once its block is synced, since it's no longer
+    // on top of the latest synced block.
+    let header = BlockHeader {
+        block_header_without_hash: BlockHeaderWithoutHash {
+            block_number: BlockNumber(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(1), &header)
+        .unwrap()
+        .append_state_diff(BlockNumber(1), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let response = state_sync
+        .handle_request(StateSyncRequest::GetPendingStorageAt(address, overridden_key))
+        .await;
+    let StateSyncResponse::GetPendingStorageAt(Ok(value)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingStorageAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(value, committed_value);
+}
+
+#[tokio::test]
+async fn test_get_pending_nonce_at() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    let mut rng = get_rng();
+    let address = ContractAddress::from(rng.next_u64());
+    let pending_address = ContractAddress::from(rng.next_u64());
+    let committed_nonce = Nonce::get_test_instance(&mut rng);
+    let pending_nonce = Nonce(committed_nonce.0 + Felt::ONE);
+    let mut diff = ThinStateDiff::from(get_test_state_diff());
+    diff.nonces.insert(address, committed_nonce);
+    diff.deployed_contracts.insert(address, Default::default());
+    let header = BlockHeader::default();
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_header_without_hash.block_number, &header)
+        .unwrap()
+        .append_state_diff(header.block_header_without_hash.block_number, diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Verify that the committed nonce is returned while there's no pending data.
+    let response = state_sync.handle_request(StateSyncRequest::GetPendingNonceAt(address)).await;
+    let StateSyncResponse::GetPendingNonceAt(Ok(nonce)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingNonceAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(nonce, committed_nonce);
+
+    let pending_state_diff = ThinStateDiff {
+        nonces: IndexMap::from([(address, pending_nonce)]),
+        deployed_contracts: IndexMap::from([(pending_address, Default::default())]),
+        ..Default::default()
+    };
+    let response = state_sync
+        .handle_request(StateSyncRequest::SetPendingStateDiff(BlockNumber(1), pending_state_diff))
+        .await;
+    let StateSyncResponse::SetPendingStateDiff(Ok(())) = response else {
+        panic!("Expected StateSyncResponse::SetPendingStateDiff::Ok(()), but got {:?}", response);
+    };
+
+    // Verify that the pending nonce overrides the committed one.
+    let response = state_sync.handle_request(StateSyncRequest::GetPendingNonceAt(address)).await;
+    let StateSyncResponse::GetPendingNonceAt(Ok(nonce)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingNonceAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(nonce, pending_nonce);
+
+    // Verify that a contract deployed in the pending block is found.
+    let response =
+        state_sync.handle_request(StateSyncRequest::GetPendingNonceAt(pending_address)).await;
+    let StateSyncResponse::GetPendingNonceAt(Ok(nonce)) = response else {
+        panic!("Expected StateSyncResponse::GetPendingNonceAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(nonce, Nonce::default());
+}
+
 #[tokio::test]
 async fn get_class_hash_at() {
     let (mut state_sync, mut storage_writer) = setup();
@@ -179,6 +397,86 @@ async fn get_class_hash_at() {
     assert_eq!(class_hash, expected_class_hash);
 }
 
+#[tokio::test]
+async fn get_class_hash_at_is_cached_until_revert() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    let mut rng = get_rng();
+    let address = ContractAddress::from(rng.next_u64());
+    let expected_class_hash = ClassHash::get_test_instance(&mut rng);
+    let mut diff = ThinStateDiff::from(get_test_state_diff());
+    diff.deployed_contracts.insert(address, expected_class_hash);
+    let header = BlockHeader::default();
+    let block_number = header.block_header_without_hash.block_number;
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_state_diff(block_number, diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let response =
+        state_sync.handle_request(StateSyncRequest::GetClassHashAt(block_number, address)).await;
+    let StateSyncResponse::GetClassHashAt(Ok(class_hash)) = response else {
+        panic!("Expected StateSyncResponse::GetClassHashAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(class_hash, expected_class_hash);
+    assert_eq!(
+        state_sync.class_hash_cache.peek(&(block_number, address)),
+        Some(&(header.block_hash, class_hash))
+    );
+
+    // Revert the block and sync a different block in its place. The cached value belongs to the
+    // reverted block, so it isn't served anymore.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .revert_state_diff(block_number)
+        .unwrap()
+        .0
+        .revert_header(block_number)
+        .unwrap()
+        .0
+        .commit()
+        .unwrap();
+
+    let response =
+        state_sync.handle_request(StateSyncRequest::GetClassHashAt(block_number, address)).await;
+    let StateSyncResponse::GetClassHashAt(get_class_hash_at_result) = response else {
+        panic!("Expected StateSyncResponse::GetClassHashAt(_), but got {:?}", response);
+    };
+    assert_eq!(get_class_hash_at_result, Err(StateSyncError::BlockNotFound(block_number)));
+
+    let new_class_hash = ClassHash::get_test_instance(&mut rng);
+    let mut new_diff = ThinStateDiff::from(get_test_state_diff());
+    new_diff.deployed_contracts.insert(address, new_class_hash);
+    let new_header = BlockHeader { block_hash: BlockHash(Felt::ONE), ..header };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &new_header)
+        .unwrap()
+        .append_state_diff(block_number, new_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let response =
+        state_sync.handle_request(StateSyncRequest::GetClassHashAt(block_number, address)).await;
+    let StateSyncResponse::GetClassHashAt(Ok(class_hash)) = response else {
+        panic!("Expected StateSyncResponse::GetClassHashAt::Ok(_), but got {:?}", response);
+    };
+    assert_eq!(class_hash, new_class_hash);
+    assert_eq!(
+        state_sync.class_hash_cache.peek(&(block_number, address)),
+        Some(&(new_header.block_hash, new_class_hash))
+    );
+}
+
 #[tokio::test]
 async fn test_get_compiled_class_deprecated() {
     let (state_sync, mut storage_writer) = setup();
@@ -234,6 +532,34 @@ async fn test_get_compiled_class_deprecated() {
     assert_eq!(result, Err(StateSyncError::ClassNotFound(other_class_hash)));
 }
 
+#[tokio::test]
+async fn test_get_state_commitment() {
+    let (mut state_sync, mut storage_writer) = setup();
+
+    let mut rng = get_rng();
+    let mut header = BlockHeader::default();
+    header.block_header_without_hash.state_root = GlobalRoot(Felt::from(rng.next_u64()));
+    let block_number = header.block_header_without_hash.block_number;
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_state_diff(block_number, ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let response =
+        state_sync.handle_request(StateSyncRequest::GetStateCommitment(block_number)).await;
+    let StateSyncResponse::GetStateCommitment(Ok(state_commitment)) = response else {
+        panic!("Expected StateSyncResponse::GetStateCommitment::Ok(_), but got {:?}", response);
+    };
+
+    assert_eq!(state_commitment, header.block_header_without_hash.state_root);
+}
+
 // Verify we get None/BlockNotFound when trying to call read methods with a block number that does
 // not exist.
 #[tokio::test]
@@ -303,6 +629,18 @@ async fn test_block_not_found() {
         get_compiled_class_result,
         Err(StateSyncError::BlockNotFound(non_existing_block_number))
     );
+
+    let response = state_sync
+        .handle_request(StateSyncRequest::GetStateCommitment(non_existing_block_number))
+        .await;
+    let StateSyncResponse::GetStateCommitment(get_state_commitment_result) = response else {
+        panic!("Expected StateSyncResponse::GetStateCommitment(_), but got {:?}", response);
+    };
+
+    assert_eq!(
+        get_state_commitment_result,
+        Err(StateSyncError::BlockNotFound(non_existing_block_number))
+    );
 }
 
 #[tokio::test]
@@ -365,3 +703,53 @@ async fn test_contract_not_found() {
 
     assert_eq!(get_class_hash_at_result, Err(StateSyncError::ContractNotFound(address)));
 }
+
+#[tokio::test]
+async fn test_subscribe_new_blocks() {
+    let (mut state_sync, _) = setup();
+
+    let response =
+        state_sync.handle_request(StateSyncRequest::SubscribeNewBlocks { is_local: true }).await;
+    let StateSyncResponse::SubscribeNewBlocks(Ok(NewBlockSubscription(mut subscriber))) = response
+    else {
+        panic!("Expected StateSyncResponse::SubscribeNewBlocks::Ok(_), but got {:?}", response);
+    };
+
+    // Notify as the P2P sync client does after committing a state diff.
+    for block_number in [BlockNumber(0), BlockNumber(1)] {
+        state_sync.new_block_notification_sender.send(block_number).unwrap();
+        assert_eq!(subscriber.recv().await.unwrap(), block_number);
+    }
+
+    // The channel in the setup holds a single notification, so a subscriber that falls behind
+    // skips the oldest ones instead of blocking the sync.
+    state_sync.new_block_notification_sender.send(BlockNumber(2)).unwrap();
+    state_sync.new_block_notification_sender.send(BlockNumber(3)).unwrap();
+    assert_eq!(subscriber.recv().await, Err(broadcast::error::RecvError::Lagged(1)));
+    assert_eq!(subscriber.recv().await.unwrap(), BlockNumber(3));
+}
+
+#[tokio::test]
+async fn test_subscribe_new_blocks_from_remote_client() {
+    let (mut state_sync, _) = setup();
+
+    // A request that went through a remote client, as the remote server deserializes it.
+    let request = StateSyncRequest::SubscribeNewBlocks { is_local: true };
+    let request = SerdeWrapper::<StateSyncRequest>::wrapper_deserialize(
+        &SerdeWrapper::new(request).wrapper_serialize().unwrap(),
+    )
+    .unwrap();
+
+    let response = state_sync.handle_request(request).await;
+    let StateSyncResponse::SubscribeNewBlocks(subscribe_result) = response else {
+        panic!("Expected StateSyncResponse::SubscribeNewBlocks(_), but got {:?}", response);
+    };
+    assert_eq!(subscribe_result.unwrap_err(), StateSyncError::RemoteSubscription);
+
+    // The error can be sent back to the remote client.
+    SerdeWrapper::new(StateSyncResponse::SubscribeNewBlocks(Err(
+        StateSyncError::RemoteSubscription,
+    )))
+    .wrapper_serialize()
+    .unwrap();
+}
@@ -7,8 +7,8 @@ use papyrus_proc_macros::handle_response_variants;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
 use starknet_api::contract_class::ContractClass;
-use starknet_api::core::{ClassHash, ContractAddress, Nonce};
-use starknet_api::state::StorageKey;
+use starknet_api::core::{ClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::state::{StorageKey, ThinStateDiff};
 use starknet_sequencer_infra::component_client::{
     ClientError,
     LocalComponentClient,
@@ -22,13 +22,15 @@ use starknet_types_core::felt::Felt;
 use thiserror::Error;
 
 use crate::errors::StateSyncError;
-use crate::state_sync_types::{StateSyncResult, SyncBlock};
+use crate::state_sync_types::{NewBlockSubscription, StateSyncResult, SyncBlock};
 
 #[cfg_attr(any(test, feature = "testing"), automock)]
 #[async_trait]
 pub trait StateSyncClient: Send + Sync {
     /// Request for a block at a specific height.
     /// If the block doesn't exist, or if the sync didn't download it yet, returns None.
+    /// If the storage says a part of the block was synced but that part is missing, returns
+    /// StorageInconsistency.
     async fn get_block(
         &self,
         block_number: BlockNumber,
@@ -70,6 +72,43 @@ pub trait StateSyncClient: Send + Sync {
 
     async fn get_latest_block_number(&self) -> StateSyncClientResult<Option<BlockNumber>>;
 
+    /// Notify the sync of the state diff of the pending block, i.e. the block that is built on
+    /// top of the latest block. Replaces the previous pending state diff. The pending requests
+    /// use it only while `block_number` is right after the latest synced block, and fall back to
+    /// the latest synced block otherwise.
+    async fn set_pending_state_diff(
+        &self,
+        block_number: BlockNumber,
+        state_diff: ThinStateDiff,
+    ) -> StateSyncClientResult<()>;
+
+    /// Request for the storage value of a contract in the pending block. If the pending block
+    /// didn't modify this storage slot, returns its value in the latest synced block.
+    async fn get_pending_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        storage_key: StorageKey,
+    ) -> StateSyncClientResult<Felt>;
+
+    /// Request for the nonce of a contract in the pending block. If the pending block didn't
+    /// modify this nonce, returns its value in the latest synced block.
+    async fn get_pending_nonce_at(
+        &self,
+        contract_address: ContractAddress,
+    ) -> StateSyncClientResult<Nonce>;
+
+    /// Request for the global state root (the state commitment) at the end of the given block,
+    /// as stored in its header. Returns BlockNotFound if the sync didn't download the block yet.
+    async fn get_state_commitment(
+        &self,
+        block_number: BlockNumber,
+    ) -> StateSyncClientResult<GlobalRoot>;
+
+    /// Subscribe to the blocks that the sync commits from now on. See [`NewBlockSubscription`].
+    /// Available only through a local client, a remote client gets
+    /// [`StateSyncError::RemoteSubscription`].
+    async fn subscribe_new_blocks(&self) -> StateSyncClientResult<NewBlockSubscription>;
+
     // TODO: Add get_compiled_class_hash for StateSyncReader
 }
 
@@ -98,6 +137,15 @@ pub enum StateSyncRequest {
     GetClassHashAt(BlockNumber, ContractAddress),
     GetCompiledClassDeprecated(BlockNumber, ClassHash),
     GetLatestBlockNumber(),
+    SetPendingStateDiff(BlockNumber, ThinStateDiff),
+    GetPendingStorageAt(ContractAddress, StorageKey),
+    GetPendingNonceAt(ContractAddress),
+    GetStateCommitment(BlockNumber),
+    SubscribeNewBlocks {
+        // Not serialized, so it's false on requests that went through a remote client.
+        #[serde(skip)]
+        is_local: bool,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -110,6 +158,11 @@ pub enum StateSyncResponse {
     GetClassHashAt(StateSyncResult<ClassHash>),
     GetCompiledClassDeprecated(StateSyncResult<ContractClass>),
     GetLatestBlockNumber(StateSyncResult<Option<BlockNumber>>),
+    SetPendingStateDiff(StateSyncResult<()>),
+    GetPendingStorageAt(StateSyncResult<Felt>),
+    GetPendingNonceAt(StateSyncResult<Nonce>),
+    GetStateCommitment(StateSyncResult<GlobalRoot>),
+    SubscribeNewBlocks(StateSyncResult<NewBlockSubscription>),
 }
 
 #[async_trait]
@@ -212,4 +265,73 @@ where
             StateSyncError
         )
     }
+
+    async fn set_pending_state_diff(
+        &self,
+        block_number: BlockNumber,
+        state_diff: ThinStateDiff,
+    ) -> StateSyncClientResult<()> {
+        let request = StateSyncRequest::SetPendingStateDiff(block_number, state_diff);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            StateSyncResponse,
+            SetPendingStateDiff,
+            StateSyncClientError,
+            StateSyncError
+        )
+    }
+
+    async fn get_pending_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        storage_key: StorageKey,
+    ) -> StateSyncClientResult<Felt> {
+        let request = StateSyncRequest::GetPendingStorageAt(contract_address, storage_key);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            StateSyncResponse,
+            GetPendingStorageAt,
+            StateSyncClientError,
+            StateSyncError
+        )
+    }
+
+    async fn get_pending_nonce_at(
+        &self,
+        contract_address: ContractAddress,
+    ) -> StateSyncClientResult<Nonce> {
+        let request = StateSyncRequest::GetPendingNonceAt(contract_address);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            StateSyncResponse,
+            GetPendingNonceAt,
+            StateSyncClientError,
+            StateSyncError
+        )
+    }
+
+    async fn get_state_commitment(
+        &self,
+        block_number: BlockNumber,
+    ) -> StateSyncClientResult<GlobalRoot> {
+        let request = StateSyncRequest::GetStateCommitment(block_number);
+        let response = self.send(request).await;
+        handle_response_variants!(
+            StateSyncResponse,
+            GetStateCommitment,
+            StateSyncClientError,
+            StateSyncError
+        )
+    }
+
+    async fn subscribe_new_blocks(&self) -> StateSyncClientResult<NewBlockSubscription> {
+        let request = StateSyncRequest::SubscribeNewBlocks { is_local: true };
+        let response = self.send(request).await;
+        handle_response_variants!(
+            StateSyncResponse,
+            SubscribeNewBlocks,
+            StateSyncClientError,
+            StateSyncError
+        )
+    }
 }
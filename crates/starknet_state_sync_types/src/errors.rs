@@ -28,6 +28,10 @@ pub enum StateSyncError {
     StarknetApiError(String),
     #[error("State is empty, latest block returned None")]
     EmptyState,
+    #[error("Block number {block_number} is only partially stored, missing its {missing}")]
+    StorageInconsistency { block_number: BlockNumber, missing: String },
+    #[error("Subscribing to new blocks is only available through a local client")]
+    RemoteSubscription,
 }
 
 impl From<StorageError> for StateSyncError {
@@ -1,7 +1,8 @@
-use serde::{Deserialize, Serialize};
-use starknet_api::block::BlockHeaderWithoutHash;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use starknet_api::block::{BlockHeaderWithoutHash, BlockNumber};
 use starknet_api::state::ThinStateDiff;
 use starknet_api::transaction::TransactionHash;
+use tokio::sync::broadcast;
 
 use crate::errors::StateSyncError;
 
@@ -19,3 +20,34 @@ pub struct SyncBlock {
     pub transaction_hashes: Vec<TransactionHash>,
     pub block_header_without_hash: BlockHeaderWithoutHash,
 }
+
+/// A subscription to the blocks that the state sync commits. The receiver gets the number of every
+/// block that is synced from the moment of subscribing, in increasing order.
+///
+/// The channel is bounded so that a slow subscriber never blocks sync: once it falls behind, the
+/// oldest notifications are dropped and the next `recv` returns
+/// [`broadcast::error::RecvError::Lagged`] with the number of skipped blocks.
+///
+/// A channel can't be sent over the network, so the subscription is only available through a local
+/// client.
+#[derive(Debug)]
+pub struct NewBlockSubscription(pub broadcast::Receiver<BlockNumber>);
+
+impl Clone for NewBlockSubscription {
+    // The clone gets only the notifications sent after it was created.
+    fn clone(&self) -> Self {
+        Self(self.0.resubscribe())
+    }
+}
+
+impl Serialize for NewBlockSubscription {
+    fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("NewBlockSubscription can't be sent to a remote client"))
+    }
+}
+
+impl<'de> Deserialize<'de> for NewBlockSubscription {
+    fn deserialize<D: Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(serde::de::Error::custom("NewBlockSubscription can't be received from a remote server"))
+    }
+}